@@ -30,10 +30,11 @@ use crate::config::{AuthInfo, Config};
 use crate::types::{
     AddOrgUserRequest, AnalysisPackageDescriptor, ApiOrgGroup, CreateProjectRequest,
     FirewallLogFilter, FirewallLogResponse, FirewallPaginated, GetProjectResponse, HistoryJob,
-    ListUserGroupsResponse, OrgGroupsResponse, OrgMembersResponse, OrgsResponse, PackageSpecifier,
-    PackageSubmitResponse, Paginated, PingResponse, PolicyEvaluationRequest,
-    PolicyEvaluationResponse, PolicyEvaluationResponseRaw, Preferences, ProjectListEntry,
-    RevokeTokenRequest, SubmitPackageRequest, Suppression, UpdateProjectRequest, UserToken,
+    ListUserGroupsResponse, LockfileProvenance, OrgGroupsResponse, OrgMembersResponse,
+    OrgsResponse, PackageSpecifier, PackageSubmitResponse, Paginated, PingResponse,
+    PolicyEvaluationRequest, PolicyEvaluationResponse, PolicyEvaluationResponseRaw, Preferences,
+    ProjectListEntry, RevokeTokenRequest, SubmitPackageRequest, Suppression, UpdateProjectRequest,
+    UserToken,
 };
 
 pub mod endpoints;
@@ -368,6 +369,7 @@ impl PhylumApi {
         project: ProjectId,
         label: Option<String>,
         group_name: Option<String>,
+        provenance: &[LockfileProvenance],
     ) -> Result<JobId> {
         let req = SubmitPackageRequest {
             packages: package_list.to_vec(),
@@ -375,6 +377,7 @@ impl PhylumApi {
             project,
             label: label.unwrap_or_else(|| "uncategorized".to_string()),
             group_name,
+            provenance: provenance.to_vec(),
         };
         log::debug!("==> Sending package submission: {req:?}");
         let resp: SubmitPackageResponse =
@@ -780,7 +783,7 @@ mod tests {
 
         let project_id = ProjectId::new_v4();
         let label = Some("mylabel".to_string());
-        client.submit_request(&[pkg], project_id, label, None).await?;
+        client.submit_request(&[pkg], project_id, label, None, &[]).await?;
 
         // Request should have been submitted with a bearer token
         let bearer_token = token_holder.lock().unwrap().take();
@@ -816,7 +819,7 @@ mod tests {
 
         let project_id = ProjectId::new_v4();
         let label = Some("mylabel".to_string());
-        client.submit_request(&[pkg], project_id, label, None).await?;
+        client.submit_request(&[pkg], project_id, label, None, &[]).await?;
         Ok(())
     }
 
@@ -1,7 +1,10 @@
+use std::num::NonZeroUsize;
+
 use clap::builder::PossibleValuesParser;
 use clap::{Arg, ArgAction, ArgGroup, Command, ValueHint};
 use git_version::git_version;
 use lazy_static::lazy_static;
+use phylum_project::MaxDepth;
 
 #[cfg(feature = "extensions")]
 use crate::commands::extensions;
@@ -354,9 +357,15 @@ pub fn add_subcommands(command: Command) -> Command {
                 .args(&[
                     Arg::new("depfile")
                         .value_name("DEPENDENCY_FILE")
-                        .value_hint(ValueHint::FilePath)
+                        .value_hint(ValueHint::AnyPath)
                         .help("Path to the dependency file to parse")
                         .action(ArgAction::Append),
+                    Arg::new("image")
+                        .long("image")
+                        .value_name("IMAGE")
+                        .value_hint(ValueHint::AnyPath)
+                        .help("Container image archive or OCI layout to scan for dependencies")
+                        .action(ArgAction::Append),
                     Arg::new("type")
                         .short('t')
                         .long("type")
@@ -372,7 +381,44 @@ pub fn add_subcommands(command: Command) -> Command {
                         .action(ArgAction::SetTrue)
                         .long("no-generation")
                         .help("Disable generation of lockfiles from manifests"),
-                ]),
+                    Arg::new("generate-in")
+                        .long("generate-in")
+                        .value_name("LOCATION")
+                        .help(
+                            "Where lockfiles are generated (default: scratch, or project with \
+                             --skip-sandbox or on Windows)",
+                        )
+                        .value_parser(["scratch", "project"]),
+                    Arg::new("offline").action(ArgAction::SetTrue).long("offline").help(
+                        "Generate lockfiles from local package caches, without network access",
+                    ),
+                    Arg::new("allow-domain")
+                        .long("allow-domain")
+                        .value_name("DOMAIN")
+                        .help("Only allow these domains through the lockfile generation HTTP proxy")
+                        .action(ArgAction::Append),
+                    Arg::new("dry-run")
+                        .action(ArgAction::SetTrue)
+                        .long("dry-run")
+                        .visible_alias("explain")
+                        .help("Explain how dependency files would be parsed, without parsing them"),
+                    Arg::new("provenance").action(ArgAction::SetTrue).long("provenance").help(
+                        "Output an object with packages and the provenance of generated lockfiles",
+                    ),
+                ])
+                .args(parallelism_args())
+                .args(generation_limit_args())
+                .args(discovery_args()),
+        )
+        .subcommand(
+            Command::new("recover")
+                .about("Restore dependency files left behind by interrupted lockfile generation")
+                .arg(
+                    Arg::new("force")
+                        .long("force")
+                        .help("Also restore files of lockfile generations which are still running")
+                        .action(ArgAction::SetTrue),
+                ),
         )
         .subcommand(
             Command::new("analyze")
@@ -401,9 +447,15 @@ pub fn add_subcommands(command: Command) -> Command {
                         .requires("project"),
                     Arg::new("depfile")
                         .value_name("DEPENDENCY_FILE")
-                        .value_hint(ValueHint::FilePath)
+                        .value_hint(ValueHint::AnyPath)
                         .help("Path to the dependency file to submit")
                         .action(ArgAction::Append),
+                    Arg::new("image")
+                        .long("image")
+                        .value_name("IMAGE")
+                        .value_hint(ValueHint::AnyPath)
+                        .help("Container image archive or OCI layout to scan for dependencies")
+                        .action(ArgAction::Append),
                     Arg::new("type")
                         .short('t')
                         .long("type")
@@ -426,7 +478,31 @@ pub fn add_subcommands(command: Command) -> Command {
                         .action(ArgAction::SetTrue)
                         .long("no-generation")
                         .help("Disable generation of lockfiles from manifests"),
-                ]),
+                    Arg::new("generate-in")
+                        .long("generate-in")
+                        .value_name("LOCATION")
+                        .help(
+                            "Where lockfiles are generated (default: scratch, or project with \
+                             --skip-sandbox or on Windows)",
+                        )
+                        .value_parser(["scratch", "project"]),
+                    Arg::new("offline").action(ArgAction::SetTrue).long("offline").help(
+                        "Generate lockfiles from local package caches, without network access",
+                    ),
+                    Arg::new("allow-domain")
+                        .long("allow-domain")
+                        .value_name("DOMAIN")
+                        .help("Only allow these domains through the lockfile generation HTTP proxy")
+                        .action(ArgAction::Append),
+                    Arg::new("dry-run")
+                        .action(ArgAction::SetTrue)
+                        .long("dry-run")
+                        .visible_alias("explain")
+                        .help("Explain how dependency files would be parsed, without parsing them"),
+                ])
+                .args(parallelism_args())
+                .args(generation_limit_args())
+                .args(discovery_args()),
         )
         .subcommand(Command::new("version").about("Display application version"))
         .subcommand(
@@ -501,37 +577,40 @@ pub fn add_subcommands(command: Command) -> Command {
                 ),
         )
         .subcommand(
-            Command::new("init").about("Setup a new Phylum project").args(&[
-                Arg::new("project").value_name("PROJECT_NAME").help("Phylum project name"),
-                Arg::new("group")
-                    .short('g')
-                    .long("group")
-                    .value_name("GROUP_NAME")
-                    .help("Group which will be the owner of the project"),
-                Arg::new("depfile")
-                    .short('d')
-                    .long("dependency-file")
-                    .value_name("DEPENDENCY_FILE")
-                    .help("Project-relative dependency file path")
-                    .action(ArgAction::Append),
-                Arg::new("type")
-                    .short('t')
-                    .long("type")
-                    .value_name("TYPE")
-                    .requires("depfile")
-                    .help("Dependency file type used for all lockfiles (default: auto)")
-                    .value_parser(PossibleValuesParser::new(parse::lockfile_types(true))),
-                Arg::new("force")
-                    .short('f')
-                    .long("force")
-                    .help("Overwrite existing configurations without confirmation")
-                    .action(ArgAction::SetTrue),
-                Arg::new("repository-url")
-                    .short('r')
-                    .long("repository-url")
-                    .value_name("REPOSITORY_URL")
-                    .help("Repository URL of the project"),
-            ]),
+            Command::new("init")
+                .about("Setup a new Phylum project")
+                .args(&[
+                    Arg::new("project").value_name("PROJECT_NAME").help("Phylum project name"),
+                    Arg::new("group")
+                        .short('g')
+                        .long("group")
+                        .value_name("GROUP_NAME")
+                        .help("Group which will be the owner of the project"),
+                    Arg::new("depfile")
+                        .short('d')
+                        .long("dependency-file")
+                        .value_name("DEPENDENCY_FILE")
+                        .help("Project-relative dependency file path")
+                        .action(ArgAction::Append),
+                    Arg::new("type")
+                        .short('t')
+                        .long("type")
+                        .value_name("TYPE")
+                        .requires("depfile")
+                        .help("Dependency file type used for all lockfiles (default: auto)")
+                        .value_parser(PossibleValuesParser::new(parse::lockfile_types(true))),
+                    Arg::new("force")
+                        .short('f')
+                        .long("force")
+                        .help("Overwrite existing configurations without confirmation")
+                        .action(ArgAction::SetTrue),
+                    Arg::new("repository-url")
+                        .short('r')
+                        .long("repository-url")
+                        .value_name("REPOSITORY_URL")
+                        .help("Repository URL of the project"),
+                ])
+                .args(discovery_args()),
         )
         .subcommand(
             Command::new("status").about("Get Phylum project details").args(&[Arg::new("json")
@@ -543,7 +622,8 @@ pub fn add_subcommands(command: Command) -> Command {
         .subcommand(
             Command::new("find-dependency-files")
                 .about("Find all lockfile and manifest paths")
-                .hide(true),
+                .hide(true)
+                .args(discovery_args()),
         )
         .subcommand(
             Command::new("org")
@@ -802,8 +882,10 @@ pub fn add_subcommands(command: Command) -> Command {
     {
         app = app
             .subcommand(
-                Command::new("sandbox").hide(true).about("Run an application in a sandbox").args(
-                    &[
+                Command::new("sandbox")
+                    .hide(true)
+                    .about("Run an application in a sandbox")
+                    .args(&[
                         Arg::new("allow-read")
                             .help("Add filesystem read sandbox exception")
                             .long("allow-read")
@@ -833,10 +915,19 @@ pub fn add_subcommands(command: Command) -> Command {
                             .help("Add network access sandbox exception")
                             .long("allow-net")
                             .action(ArgAction::SetTrue),
+                        Arg::new("allow-domain")
+                            .help("Allow a domain through the sandbox's HTTP proxy")
+                            .long("allow-domain")
+                            .value_name("DOMAIN")
+                            .action(ArgAction::Append),
                         Arg::new("strict")
                             .help("Do not add any default sandbox exceptions")
                             .long("strict")
                             .action(ArgAction::SetTrue),
+                        Arg::new("learn")
+                            .help("Record denied accesses and print the required permissions")
+                            .long("learn")
+                            .action(ArgAction::SetTrue),
                         Arg::new("cmd")
                             .help("Command to be executed")
                             .value_name("CMD")
@@ -847,8 +938,54 @@ pub fn add_subcommands(command: Command) -> Command {
                             .trailing_var_arg(true)
                             .allow_hyphen_values(true)
                             .action(ArgAction::Append),
-                    ],
-                ),
+                    ])
+                    .args(sandbox_limit_args())
+                    .args_conflicts_with_subcommands(true)
+                    .subcommand_negates_reqs(true)
+                    .subcommand(
+                        Command::new("install")
+                            .about("Install the dependencies of a project in a sandbox")
+                            .args(&[
+                                Arg::new("type")
+                                    .short('t')
+                                    .long("type")
+                                    .value_name("TYPE")
+                                    .help("Dependency file type of the project (default: auto)")
+                                    .value_parser(PossibleValuesParser::new(
+                                        parse::lockfile_types(false),
+                                    )),
+                                Arg::new("allow-domain")
+                                    .long("allow-domain")
+                                    .value_name("DOMAIN")
+                                    .help("Allow network access to a domain besides the registry")
+                                    .action(ArgAction::Append),
+                                Arg::new("args")
+                                    .help("Additional arguments of the install command")
+                                    .value_name("ARG")
+                                    .trailing_var_arg(true)
+                                    .allow_hyphen_values(true)
+                                    .action(ArgAction::Append),
+                            ])
+                            .args(sandbox_limit_args()),
+                    ),
+            )
+            .subcommand(
+                Command::new("sandbox-limits")
+                    .args(sandbox_limit_args())
+                    .args(&[
+                        Arg::new("cmd")
+                            .help("Command to be executed")
+                            .value_name("CMD")
+                            .required(true),
+                        Arg::new("args")
+                            .help("Command arguments")
+                            .value_name("ARG")
+                            .trailing_var_arg(true)
+                            .allow_hyphen_values(true)
+                            .action(ArgAction::Append),
+                    ])
+                    .about("Run an application with resource limits")
+                    .hide(true),
             )
             .subcommand(
                 Command::new("parse-sandboxed")
@@ -870,16 +1007,88 @@ pub fn add_subcommands(command: Command) -> Command {
                             .long("generate-lockfile")
                             .help("Whether lockfile generation should be performed")
                             .action(ArgAction::SetTrue),
+                        Arg::new("out-of-tree")
+                            .long("out-of-tree")
+                            .help("Generate the lockfile in a scratch copy of the project")
+                            .action(ArgAction::SetTrue),
+                        Arg::new("scratch-dir")
+                            .long("scratch-dir")
+                            .value_name("DIR")
+                            .help("Directory for scratch copies shared with other generations"),
+                        Arg::new("offline")
+                            .long("offline")
+                            .help("Generate the lockfile without network access")
+                            .action(ArgAction::SetTrue),
+                        Arg::new("allow-domain")
+                            .long("allow-domain")
+                            .value_name("DOMAIN")
+                            .help("Domain allowed by the lockfile generation HTTP proxy")
+                            .action(ArgAction::Append),
+                        Arg::new("allow-read")
+                            .long("allow-read")
+                            .value_name("PATH")
+                            .help("Path readable during lockfile generation")
+                            .action(ArgAction::Append),
+                        Arg::new("allow-write")
+                            .long("allow-write")
+                            .value_name("PATH")
+                            .help("Path writable during lockfile generation")
+                            .action(ArgAction::Append),
+                        Arg::new("allow-run")
+                            .long("allow-run")
+                            .value_name("PATH")
+                            .help("Executable runnable during lockfile generation")
+                            .action(ArgAction::Append),
+                        Arg::new("allow-env")
+                            .long("allow-env")
+                            .value_name("ENV_VAR")
+                            .help("Environment variable accessible during lockfile generation")
+                            .action(ArgAction::Append),
                         Arg::new("skip-sandbox")
                             .long("skip-sandbox")
                             .help("Skip sandbox initialization")
                             .action(ArgAction::SetTrue),
                     ])
+                    .args(generation_limit_args())
                     .about("Run lockfile generation inside sandbox and write it to STDOUT")
                     .hide(true),
+            )
+            .subcommand(
+                Command::new("sandbox-profile")
+                    .about("Show the lockfile generation sandbox profile of a dependency file type")
+                    .arg(
+                        Arg::new("type")
+                            .value_name("TYPE")
+                            .required(true)
+                            .help("Dependency file type")
+                            .value_parser(PossibleValuesParser::new(parse::lockfile_types(false))),
+                    ),
             );
     }
 
+    #[cfg(target_os = "linux")]
+    {
+        app = app.subcommand(
+            Command::new("sandbox-trace")
+                .args(&[
+                    Arg::new("report")
+                        .long("report")
+                        .value_name("FILE")
+                        .required(true)
+                        .help("File the denied accesses are written to"),
+                    Arg::new("cmd").help("Command to be executed").value_name("CMD").required(true),
+                    Arg::new("args")
+                        .help("Command arguments")
+                        .value_name("ARG")
+                        .trailing_var_arg(true)
+                        .allow_hyphen_values(true)
+                        .action(ArgAction::Append),
+                ])
+                .about("Run an application, recording all denied sandbox accesses")
+                .hide(true),
+        );
+    }
+
     #[cfg(feature = "selfmanage")]
     {
         app = app
@@ -907,6 +1116,133 @@ pub fn add_subcommands(command: Command) -> Command {
     app
 }
 
+/// Arguments for parsing multiple dependency files concurrently.
+fn parallelism_args() -> [Arg; 2] {
+    [
+        Arg::new("jobs")
+            .long("jobs")
+            .value_name("N")
+            .help("Maximum number of dependency files parsed concurrently (default: CPU count)")
+            .value_parser(clap::value_parser!(NonZeroUsize)),
+        Arg::new("keep-going")
+            .long("keep-going")
+            .help("Continue parsing the remaining dependency files after a failure")
+            .action(ArgAction::SetTrue),
+    ]
+}
+
+/// Arguments for limiting lockfile generation processes.
+fn generation_limit_args() -> [Arg; 7] {
+    [
+        Arg::new("generation-timeout")
+            .long("generation-timeout")
+            .value_name("SECONDS")
+            .help("Maximum duration of each lockfile generation")
+            .value_parser(clap::value_parser!(u64)),
+        Arg::new("generation-cpu-limit")
+            .long("generation-cpu-limit")
+            .value_name("SECONDS")
+            .help("Maximum CPU time of each lockfile generation process (Unix only)")
+            .value_parser(clap::value_parser!(u64)),
+        Arg::new("generation-memory-limit")
+            .long("generation-memory-limit")
+            .value_name("MIB")
+            .help("Maximum memory of each lockfile generation process (Unix only)")
+            .value_parser(clap::value_parser!(u64)),
+        Arg::new("generation-file-size-limit")
+            .long("generation-file-size-limit")
+            .value_name("MIB")
+            .help("Maximum size of files written during lockfile generation (Unix only)")
+            .value_parser(clap::value_parser!(u64)),
+        Arg::new("generation-process-limit")
+            .long("generation-process-limit")
+            .value_name("COUNT")
+            .help("Maximum number of processes during lockfile generation (Unix only)")
+            .value_parser(clap::value_parser!(u64)),
+        Arg::new("generation-open-files-limit")
+            .long("generation-open-files-limit")
+            .value_name("COUNT")
+            .help("Maximum number of open files of each lockfile generation process (Unix only)")
+            .value_parser(clap::value_parser!(u64)),
+        Arg::new("generation-output-limit")
+            .long("generation-output-limit")
+            .value_name("MIB")
+            .help("Maximum output of each lockfile generation process")
+            .value_parser(clap::value_parser!(u64)),
+    ]
+}
+
+/// Arguments for limiting the resources of sandboxed processes.
+#[cfg(unix)]
+fn sandbox_limit_args() -> [Arg; 6] {
+    [
+        Arg::new("max-processes")
+            .long("max-processes")
+            .value_name("COUNT")
+            .help("Maximum number of processes of the current user")
+            .value_parser(clap::value_parser!(u64)),
+        Arg::new("max-memory")
+            .long("max-memory")
+            .value_name("MIB")
+            .help("Maximum memory of each process")
+            .value_parser(clap::value_parser!(u64)),
+        Arg::new("max-cpu-time")
+            .long("max-cpu-time")
+            .value_name("SECONDS")
+            .help("Maximum CPU time of each process")
+            .value_parser(clap::value_parser!(u64)),
+        Arg::new("max-open-files")
+            .long("max-open-files")
+            .value_name("COUNT")
+            .help("Maximum number of open files of each process")
+            .value_parser(clap::value_parser!(u64)),
+        Arg::new("max-file-size")
+            .long("max-file-size")
+            .value_name("MIB")
+            .help("Maximum size of written files")
+            .value_parser(clap::value_parser!(u64)),
+        Arg::new("max-output")
+            .long("max-output")
+            .value_name("MIB")
+            .help("Maximum combined size of STDOUT and STDERR")
+            .value_parser(clap::value_parser!(u64)),
+    ]
+}
+
+/// Arguments for configuring dependency file discovery.
+fn discovery_args() -> [Arg; 5] {
+    [
+        Arg::new("max-depth")
+            .long("max-depth")
+            .value_name("DEPTH")
+            .help(
+                "Maximum directory depth searched for dependency files, or `unlimited` (default: \
+                 5)",
+            )
+            .value_parser(clap::value_parser!(MaxDepth)),
+        Arg::new("include")
+            .long("include")
+            .value_name("GLOB")
+            .help("Only search for dependency files matching this glob")
+            .action(ArgAction::Append),
+        Arg::new("exclude")
+            .long("exclude")
+            .value_name("GLOB")
+            .help("Exclude paths matching this glob from the dependency file search")
+            .action(ArgAction::Append),
+        Arg::new("format")
+            .long("format")
+            .value_name("TYPE")
+            .help("Only search for dependency files of this type")
+            .value_parser(PossibleValuesParser::new(parse::lockfile_types(false)))
+            .action(ArgAction::Append),
+        Arg::new("follow-symlinks")
+            .long("follow-symlinks")
+            .help("Follow symbolic links when searching for dependency files")
+            .action(ArgAction::SetTrue),
+    ]
+}
+
 /// Check if a non-extension subcommand exists.
 pub fn is_builtin_subcommand(name: &str) -> bool {
     add_subcommands(Command::new("phylum"))
@@ -15,7 +15,7 @@ use phylum_cli::commands::sandbox;
 use phylum_cli::commands::uninstall;
 use phylum_cli::commands::{
     auth, exception, find_dependency_files, firewall, group, init, jobs, org, packages, parse,
-    project, status, CommandResult, ExitCode,
+    project, recover, status, CommandResult, ExitCode,
 };
 use phylum_cli::config::{self, Config};
 use phylum_cli::spinner::Spinner;
@@ -113,6 +113,12 @@ async fn handle_commands() -> CommandResult {
 
     let (subcommand, sub_matches) = matches.subcommand().unwrap();
 
+    // Journal files relocated during lockfile generation, to allow restoring them
+    // after a crash.
+    if let Err(err) = recover::init_journal() {
+        log::warn!("Lockfile generation journal unavailable: {err}");
+    }
+
     // Check for updates unless we're running without config or the `update`
     // subcommand.
     if cfg!(feature = "selfmanage") && config.path.is_some() && subcommand != "update" {
@@ -130,9 +136,12 @@ async fn handle_commands() -> CommandResult {
             auth::handle_auth(config, sub_matches, timeout).await
         },
         "version" => handle_version(&app_name, &ver),
-        "parse" => parse::handle_parse(sub_matches),
+        "parse" => parse::handle_parse(sub_matches, &config).await,
+        "recover" => recover::handle_recover(sub_matches),
         #[cfg(unix)]
         "parse-sandboxed" => parse::handle_parse_sandboxed(sub_matches),
+        #[cfg(unix)]
+        "sandbox-profile" => parse::handle_sandbox_profile(sub_matches, &config),
         "ping" => handle_ping(Spinner::wrap(api).await?).await,
         "project" => {
             project::handle_project(&Spinner::wrap(api).await?, app_helper, sub_matches, config)
@@ -158,13 +167,20 @@ async fn handle_commands() -> CommandResult {
         "update" => handle_update(sub_matches, config.ignore_certs()).await,
 
         #[cfg(feature = "extensions")]
-        "extension" => extensions::handle_extensions(Box::pin(api), sub_matches, app_helper).await,
+        "extension" => {
+            extensions::handle_extensions(Box::pin(api), sub_matches, app_helper, &config).await
+        },
+        #[cfg(unix)]
+        "sandbox" => sandbox::handle_sandbox(sub_matches, &config).await,
         #[cfg(unix)]
-        "sandbox" => sandbox::handle_sandbox(sub_matches).await,
-        "find-dependency-files" => find_dependency_files::handle_command(),
+        "sandbox-limits" => sandbox::handle_sandbox_limits(sub_matches),
+        #[cfg(target_os = "linux")]
+        "sandbox-trace" => sandbox::handle_sandbox_trace(sub_matches),
+        "find-dependency-files" => find_dependency_files::handle_command(sub_matches),
         #[cfg(feature = "extensions")]
         extension_subcmd => {
-            extensions::handle_run_extension(Box::pin(api), extension_subcmd, sub_matches).await
+            extensions::handle_run_extension(Box::pin(api), extension_subcmd, sub_matches, &config)
+                .await
         },
         #[cfg(not(feature = "extensions"))]
         _ => unreachable!(),
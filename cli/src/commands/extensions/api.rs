@@ -16,6 +16,7 @@ use std::str::FromStr;
 use anyhow::{anyhow, Error, Result};
 use deno_core::{op2, OpDecl, OpState};
 use deno_runtime::deno_permissions::PermissionsContainer;
+use phylum_lockfile::generator::limits::{Cancellation, Limits};
 use phylum_lockfile::ParsedLockfile;
 use phylum_project::ProjectConfig;
 use phylum_types::types::auth::{AccessToken, RefreshToken};
@@ -30,10 +31,10 @@ use crate::commands::extensions::state::ExtensionState;
 use crate::commands::parse;
 #[cfg(unix)]
 use crate::commands::ExitCode;
-use crate::config::Config;
+use crate::config::{self, Config};
 #[cfg(unix)]
 use crate::dirs;
-use crate::permissions::{self, Permission};
+use crate::permissions::{self, Permission, ResourceLimits};
 use crate::types::{
     AnalysisPackageDescriptor, ListUserGroupsResponse, Package, PackageSpecifier,
     PackageSubmitResponse, PolicyEvaluationResponse, PolicyEvaluationResponseRaw, ProjectListEntry,
@@ -99,8 +100,10 @@ struct ProcessException {
     run: Permission,
     #[serde(default)]
     env: Permission,
+    #[serde(default, deserialize_with = "permissions::deserialize_net_permission")]
+    net: Permission,
     #[serde(default)]
-    net: bool,
+    limits: ResourceLimits,
     #[serde(default)]
     strict: bool,
 }
@@ -113,8 +116,9 @@ impl From<ProcessException> for permissions::Permissions {
             write: process_exception.write,
             run: process_exception.run,
             env: process_exception.env,
-            net: Permission::Boolean(process_exception.net),
+            net: process_exception.net,
             unsandboxed_run: Permission::default(),
+            limits: process_exception.limits,
         }
     }
 }
@@ -182,7 +186,7 @@ async fn analyze(
     };
 
     let api_packages: Vec<_> = packages.into_iter().map(From::from).collect();
-    let job_id = api.submit_request(&api_packages, project, label, group).await?;
+    let job_id = api.submit_request(&api_packages, project, label, group, &[]).await?;
 
     Ok(job_id)
 }
@@ -438,18 +442,34 @@ async fn parse_depfile(
     let current_project = phylum_project::get_current_project();
     let project_root = current_project.as_ref().map(|p| p.root());
 
+    // Apply the lockfile generation sandbox profiles, including their domains.
+    let state = ExtensionState::from(op_state);
+    let profiles = config::sandbox_profiles(state.config(), current_project.as_ref())?;
+
     // Attempt to parse as requested dependency file type.
     let sandbox = sandbox_generation.unwrap_or(true);
-    let generate_lockfiles = generate_lockfiles.unwrap_or(true);
-    let parsed = parse::parse_depfile(
-        depfile,
-        project_root,
-        depfile_type.as_deref(),
+    let generation = parse::GenerationOptions {
+        enabled: generate_lockfiles.unwrap_or(true),
+        out_of_tree: sandbox,
+        scratch_dir: None,
+        offline: false,
+        allowed_domains: Vec::new(),
+        sandbox_profiles: profiles.into_values().collect(),
         sandbox,
-        generate_lockfiles,
-    )?;
+        limits: Limits::default(),
+        cancellation: Cancellation::default(),
+    };
+    let mut parsed =
+        parse::parse_depfile(depfile, project_root, depfile_type.as_deref(), &generation)?
+            .into_iter();
+
+    // Merge nested dependency files of binaries into their outermost file.
+    let mut depfile = parsed.next().ok_or_else(|| anyhow!("Missing dependency file"))?;
+    for mut nested in parsed {
+        depfile.packages.append(&mut nested.packages);
+    }
 
-    Ok(parsed)
+    Ok(depfile)
 }
 
 /// Run a command inside a sandbox.
@@ -521,24 +541,28 @@ fn add_permission_args<'a>(
     // Add filesystem exception arguments.
     let home_dir = dirs::home_dir()?;
     for path in permissions.read.sandbox_paths().iter() {
-        let path = dirs::expand_home_path(path, &home_dir);
         sandbox_args.push("--allow-read".into());
-        sandbox_args.push(path.to_string_lossy().into_owned().into());
+        sandbox_args.push(permissions::expand_home_entry(path, &home_dir).into());
     }
     for path in permissions.write.sandbox_paths().iter() {
-        let path = dirs::expand_home_path(path, &home_dir);
         sandbox_args.push("--allow-write".into());
-        sandbox_args.push(path.to_string_lossy().into_owned().into());
+        sandbox_args.push(permissions::expand_home_entry(path, &home_dir).into());
     }
     for path in permissions.run.sandbox_paths().iter() {
-        let path = dirs::expand_home_path(path, &home_dir);
         sandbox_args.push("--allow-run".into());
-        sandbox_args.push(path.to_string_lossy().into_owned().into());
+        sandbox_args.push(permissions::expand_home_entry(path, &home_dir).into());
     }
 
-    // Add network exception argument.
-    if let permissions::Permission::Boolean(true) = permissions.net {
-        sandbox_args.push("--allow-net".into());
+    // Add network exception arguments.
+    match &permissions.net {
+        Permission::Boolean(true) => sandbox_args.push("--allow-net".into()),
+        Permission::List(domains) => {
+            for domain in domains {
+                sandbox_args.push("--allow-domain".into());
+                sandbox_args.push(domain.into());
+            }
+        },
+        Permission::Boolean(false) => (),
     }
 
     // Add environment variable exception arguments.
@@ -554,6 +578,9 @@ fn add_permission_args<'a>(
         Permission::Boolean(false) => (),
     }
 
+    // Add resource limit arguments.
+    sandbox_args.extend(permissions.limits.sandbox_args().into_iter().map(Cow::Owned));
+
     Ok(())
 }
 
@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::cmp::Ordering;
 use std::fs::{self, DirBuilder};
 #[cfg(unix)]
 use std::os::unix::fs::DirBuilderExt;
@@ -13,12 +14,19 @@ use serde::{Deserialize, Serialize};
 use walkdir::WalkDir;
 
 use crate::api::PhylumApi;
+use crate::commands::extensions::lock::LOCK_NAME;
+use crate::commands::extensions::signature::SIGNATURE_NAME;
+use crate::commands::extensions::source;
 use crate::commands::CommandResult;
+use crate::config::Config;
 use crate::permissions::Permissions;
-use crate::{deno, dirs, fs_compare};
+use crate::{deno, dirs, print_user_warning};
 
 const MANIFEST_NAME: &str = "PhylumExt.toml";
 
+/// Version of the running CLI, checked against the extensions' minimum version.
+const CLI_VERSION: &str = env!("CARGO_PKG_VERSION");
+
 lazy_static! {
     static ref EXTENSION_NAME_RE: Regex = Regex::new(r#"^[a-z][a-z0-9-]+$"#).unwrap();
 }
@@ -26,6 +34,8 @@ lazy_static! {
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct ExtensionManifest {
     name: String,
+    version: Option<String>,
+    min_cli_version: Option<String>,
     description: Option<String>,
     entry_point: Option<String>,
     permissions: Option<Permissions>,
@@ -35,6 +45,8 @@ impl ExtensionManifest {
     pub fn new(name: impl Into<String>) -> Self {
         Self {
             name: name.into(),
+            version: Some("0.1.0".into()),
+            min_cli_version: Default::default(),
             description: Default::default(),
             entry_point: Default::default(),
             permissions: Default::default(),
@@ -62,6 +74,28 @@ impl Extension {
         self.manifest.description.as_deref()
     }
 
+    pub fn version(&self) -> Option<&str> {
+        self.manifest.version.as_deref()
+    }
+
+    /// Ensure the running CLI satisfies the extension's minimum CLI version.
+    pub fn check_cli_version(&self) -> Result<()> {
+        let min_cli_version = match self.manifest.min_cli_version.as_deref() {
+            Some(min_cli_version) => min_cli_version,
+            None => return Ok(()),
+        };
+
+        if compare_versions(CLI_VERSION, min_cli_version) == Some(Ordering::Less) {
+            return Err(anyhow!(
+                "{}: requires Phylum CLI version {min_cli_version} or newer, but the installed \
+                 version is {CLI_VERSION}",
+                self.name()
+            ));
+        }
+
+        Ok(())
+    }
+
     pub fn permissions(&self) -> Cow<'_, Permissions> {
         match self.manifest.permissions.as_ref() {
             Some(permissions) => Cow::Borrowed(permissions),
@@ -69,11 +103,31 @@ impl Extension {
         }
     }
 
+    /// Warn about wildcard domains, which only apply to sandboxed processes.
+    pub fn warn_wildcard_domains(&self) {
+        let permissions = self.permissions();
+        let domains = permissions.wildcard_domains();
+        if !domains.is_empty() {
+            print_user_warning!(
+                "{}: wildcard domains only apply to `Phylum.runSandboxed`, the extension itself \
+                 can't access {}",
+                self.name(),
+                domains.join(", ")
+            );
+        }
+    }
+
     /// Copy the extension to a new path.
+    ///
+    /// Module locks are skipped, since they are not covered by the signature.
     fn copy_to<P: AsRef<Path>>(&self, dest: P) -> Result<()> {
         for entry in WalkDir::new(&self.path) {
             let source_path = entry?.into_path();
-            let dest_path = dest.as_ref().join(source_path.strip_prefix(&self.path)?);
+            let relative_path = source_path.strip_prefix(&self.path)?;
+            if relative_path == Path::new(LOCK_NAME) {
+                continue;
+            }
+            let dest_path = dest.as_ref().join(relative_path);
 
             if source_path.is_dir() {
                 let mut builder = DirBuilder::new();
@@ -136,7 +190,7 @@ impl Extension {
     }
 
     /// Return true if this is an installed extension.
-    fn installed(&self) -> bool {
+    pub fn installed(&self) -> bool {
         let installed_path = extension_path(self.name())
             .ok()
             .and_then(|installed_path| installed_path.canonicalize().ok());
@@ -168,24 +222,34 @@ impl Extension {
     pub async fn run(
         self,
         api: BoxFuture<'static, Result<PhylumApi>>,
+        config: &Config,
         args: Vec<String>,
     ) -> CommandResult {
+        self.check_cli_version()?;
+
         // Disable logging for running extensions.
         log::set_max_level(LevelFilter::Off);
 
         // Execute Deno extension.
-        deno::run(api, self, args).await
+        deno::run(api, self, config.clone(), args).await
     }
 }
 
 impl PartialEq for Extension {
     fn eq(&self, other: &Self) -> bool {
         self.path == other.path
-            || (self.name() == other.name()
-                && fs_compare::dir_compare(&self.path, &other.path).unwrap_or(false))
+            || (self.name() == other.name() && same_files(&self.path, &other.path).unwrap_or(false))
     }
 }
 
+/// Compare the files of two extensions.
+///
+/// Module locks are ignored, since they are created during installation.
+fn same_files(a: &Path, b: &Path) -> Result<bool> {
+    let signature = |path: &Path| fs::read(path.join(SIGNATURE_NAME)).ok();
+    Ok(source::content_hash(a)? == source::content_hash(b)? && signature(a) == signature(b))
+}
+
 // Load the extension from the specified path.
 impl TryFrom<PathBuf> for Extension {
     type Error = anyhow::Error;
@@ -218,6 +282,17 @@ impl TryFrom<PathBuf> for Extension {
 
         validate_name(&manifest.name)?;
 
+        for (key, version) in
+            [("version", &manifest.version), ("min_cli_version", &manifest.min_cli_version)]
+        {
+            if let Some(version) = version.as_deref().filter(|v| parse_version(v).is_none()) {
+                return Err(anyhow!(
+                    "{}: invalid {key} {version:?}, must be a version like \"1.2.3\"",
+                    manifest.name
+                ));
+            }
+        }
+
         // TODO add further validation if necessary:
         // - Check that the entry point is a supported format (.wasm?)
         // - Check that the entry point is appropriately signed
@@ -238,6 +313,28 @@ pub fn validate_name(name: &str) -> Result<(), anyhow::Error> {
     }
 }
 
+/// Parse the numeric components of a version, ignoring pre-release and build
+/// metadata.
+fn parse_version(version: &str) -> Option<Vec<u64>> {
+    let release = version.split(['-', '+']).next()?;
+    release.split('.').map(|component| component.parse().ok()).collect()
+}
+
+/// Compare two versions.
+///
+/// Missing components are treated as zero, so `1.2` is equal to `1.2.0`.
+pub fn compare_versions(version: &str, other: &str) -> Option<Ordering> {
+    let version = parse_version(version)?;
+    let other = parse_version(other)?;
+
+    let len = version.len().max(other.len());
+    let component = |components: &[u64], i| components.get(i).copied().unwrap_or(0);
+    let ordering =
+        (0..len).map(|i| component(&version, i).cmp(&component(&other, i))).find(|o| o.is_ne());
+
+    Some(ordering.unwrap_or(Ordering::Equal))
+}
+
 // Construct and return the extension path: $XDG_DATA_HOME/phylum/extensions
 pub fn extensions_path() -> Result<PathBuf> {
     Ok(dirs::data_dir()?.join("phylum").join("extensions"))
@@ -250,3 +347,16 @@ fn extension_path(name: &str) -> Result<PathBuf> {
 fn extension_state_path(name: &str) -> Result<PathBuf> {
     Ok(dirs::state_dir()?.join("phylum/extensions").join(name))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_ordering() {
+        assert_eq!(compare_versions("1.2.0", "1.2"), Some(Ordering::Equal));
+        assert_eq!(compare_versions("1.10.0", "1.9.3"), Some(Ordering::Greater));
+        assert_eq!(compare_versions("7.5.0", "8.0.0-rc1"), Some(Ordering::Less));
+        assert_eq!(compare_versions("1.0.0", "latest"), None);
+    }
+}
@@ -0,0 +1,85 @@
+//! Record of installed extensions.
+//!
+//! The sources of all installed extensions are recorded in
+//! `$XDG_DATA_HOME/phylum/extensions.toml`, which allows reproducing the same
+//! installation on other systems.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::commands::extensions::source::FetchedExtension;
+use crate::dirs;
+
+/// Sources of all installed extensions.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct InstalledExtensions {
+    #[serde(default)]
+    pub extensions: BTreeMap<String, InstalledExtension>,
+}
+
+impl InstalledExtensions {
+    /// Load the installed extensions manifest.
+    pub fn load() -> Result<Self> {
+        let path = manifest_path()?;
+        match fs::read_to_string(&path) {
+            Ok(manifest) => toml::from_str(&manifest)
+                .with_context(|| format!("Invalid extension manifest {}", path.display())),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Write the installed extensions manifest.
+    pub fn save(&self) -> Result<()> {
+        let path = manifest_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Record the source of an installed extension.
+    pub fn insert(&mut self, fetched: &FetchedExtension, pinned: bool) {
+        let extension = InstalledExtension {
+            source: fetched.source.clone(),
+            reference: fetched.reference.clone(),
+            commit: fetched.commit.clone(),
+            sha256: fetched.sha256.clone(),
+            pinned,
+        };
+        self.extensions.insert(fetched.extension.name().into(), extension);
+    }
+}
+
+/// Source of an installed extension.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct InstalledExtension {
+    /// Directory, archive or git repository the extension was installed from.
+    pub source: String,
+    /// Requested git branch, tag or commit.
+    #[serde(rename = "ref", default, skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    /// Resolved git commit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    /// SHA-256 hash of the extension's files.
+    pub sha256: String,
+    /// Exclude the extension from updates.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub pinned: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+/// Path of the installed extensions manifest.
+pub fn manifest_path() -> Result<PathBuf> {
+    Ok(dirs::data_dir()?.join("phylum").join("extensions.toml"))
+}
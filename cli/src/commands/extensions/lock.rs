@@ -0,0 +1,200 @@
+//! Lock file and cache of remote extension modules.
+//!
+//! The SHA-256 hashes of all remote modules imported by an extension are
+//! recorded in its `PhylumExt.lock` file. Module contents are cached in
+//! `$XDG_DATA_HOME/phylum/modules`, allowing extensions to run offline.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::fs;
+use url::Url;
+
+use crate::app::USER_AGENT;
+use crate::dirs;
+
+/// File name of the extension's module lock.
+pub const LOCK_NAME: &str = "PhylumExt.lock";
+
+/// Hashes of an extension's remote modules.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct ModuleLock {
+    /// SHA-256 hashes of remote modules, indexed by URL.
+    #[serde(default)]
+    pub remote: BTreeMap<String, String>,
+}
+
+impl ModuleLock {
+    /// Load the module lock of the extension in `extension_path`.
+    pub fn load(extension_path: &Path) -> Result<Option<Self>> {
+        let path = extension_path.join(LOCK_NAME);
+        match std::fs::read_to_string(&path) {
+            Ok(lock) => toml::from_str(&lock)
+                .map(Some)
+                .with_context(|| format!("Invalid module lock {}", path.display())),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Write the module lock to the extension in `extension_path`.
+    pub fn save(&self, extension_path: &Path) -> Result<()> {
+        std::fs::write(extension_path.join(LOCK_NAME), toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Check if no remote modules are locked.
+    pub fn is_empty(&self) -> bool {
+        self.remote.is_empty()
+    }
+}
+
+/// Remote module loader verifying modules against a [`ModuleLock`].
+///
+/// Without an existing lock, all loaded modules are recorded in a new lock
+/// instead.
+pub struct RemoteModules {
+    lock: RefCell<ModuleLock>,
+    locked: bool,
+    cache_dir: PathBuf,
+}
+
+impl RemoteModules {
+    /// Create a loader for modules locked by `lock`.
+    pub fn new(lock: Option<ModuleLock>) -> Result<Self> {
+        Ok(Self::with_cache(lock, cache_dir()?))
+    }
+
+    fn with_cache(lock: Option<ModuleLock>, cache_dir: PathBuf) -> Self {
+        let locked = lock.is_some();
+        Self { lock: RefCell::new(lock.unwrap_or_default()), locked, cache_dir }
+    }
+
+    /// Get the module lock.
+    pub fn lock(&self) -> ModuleLock {
+        self.lock.borrow().clone()
+    }
+
+    /// Load a remote module.
+    pub async fn load(&self, url: &Url) -> Result<String> {
+        let code = if self.locked {
+            let hash = self.lock.borrow().remote.get(url.as_str()).cloned();
+            let hash = hash.ok_or_else(|| {
+                anyhow!(
+                    "Remote module {url} is not in the extension's {LOCK_NAME}, only statically \
+                     imported modules are locked"
+                )
+            })?;
+            self.load_locked(url, &hash).await?
+        } else {
+            let code = download(url).await?;
+            let hash = sha256(&code);
+            self.store(&hash, &code).await?;
+            self.lock.borrow_mut().remote.insert(url.to_string(), hash);
+            code
+        };
+
+        String::from_utf8(code).with_context(|| anyhow!("Remote module {url} is not UTF-8"))
+    }
+
+    /// Load a locked module from the cache, downloading it if necessary.
+    async fn load_locked(&self, url: &Url, hash: &str) -> Result<Vec<u8>> {
+        // Ignore corrupted cache entries, since they can be downloaded again.
+        if let Ok(code) = fs::read(self.cache_dir.join(hash)).await {
+            if sha256(&code) == hash {
+                return Ok(code);
+            }
+        }
+
+        let code = download(url).await?;
+        let actual = sha256(&code);
+        if actual != hash {
+            return Err(anyhow!(
+                "Remote module {url} does not match the extension's {LOCK_NAME}, expected {hash} \
+                 but found {actual}"
+            ));
+        }
+
+        self.store(hash, &code).await?;
+
+        Ok(code)
+    }
+
+    /// Add a module to the cache.
+    async fn store(&self, hash: &str, code: &[u8]) -> Result<()> {
+        fs::create_dir_all(&self.cache_dir).await?;
+
+        // Write to a temporary file first, to avoid partially written modules.
+        let tmp_path = self.cache_dir.join(format!("{hash}.tmp"));
+        fs::write(&tmp_path, code).await?;
+        fs::rename(tmp_path, self.cache_dir.join(hash)).await?;
+
+        Ok(())
+    }
+}
+
+/// Download a remote module.
+async fn download(url: &Url) -> Result<Vec<u8>> {
+    let client = reqwest::Client::builder().user_agent(USER_AGENT.as_str()).build()?;
+    let response = client.get(url.clone()).send().await?.error_for_status()?;
+    Ok(response.bytes().await?.to_vec())
+}
+
+/// Calculate the SHA-256 hash of a module.
+fn sha256(code: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(code))
+}
+
+/// Path of the remote module cache.
+fn cache_dir() -> Result<PathBuf> {
+    Ok(dirs::data_dir()?.join("phylum").join("modules"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "https://example.invalid/mod.ts";
+    const CODE: &str = "export const value = 3;\n";
+
+    #[tokio::test]
+    async fn locked_module_is_loaded_from_cache() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let hash = sha256(CODE.as_bytes());
+        std::fs::write(cache_dir.path().join(&hash), CODE).unwrap();
+
+        let mut lock = ModuleLock::default();
+        lock.remote.insert(URL.into(), hash);
+        let modules = RemoteModules::with_cache(Some(lock), cache_dir.path().into());
+
+        let code = modules.load(&Url::parse(URL).unwrap()).await.unwrap();
+        assert_eq!(code, CODE);
+    }
+
+    #[tokio::test]
+    async fn unlocked_module_is_rejected() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let modules =
+            RemoteModules::with_cache(Some(ModuleLock::default()), cache_dir.path().into());
+
+        let err = modules.load(&Url::parse(URL).unwrap()).await.unwrap_err();
+        assert!(err.to_string().contains("is not in the extension's PhylumExt.lock"));
+    }
+
+    #[test]
+    fn lock_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(ModuleLock::load(dir.path()).unwrap(), None);
+
+        let mut lock = ModuleLock::default();
+        lock.remote.insert(URL.into(), sha256(CODE.as_bytes()));
+        lock.save(dir.path()).unwrap();
+
+        assert_eq!(ModuleLock::load(dir.path()).unwrap(), Some(lock));
+    }
+}
@@ -3,7 +3,7 @@ use std::ffi::OsStr;
 use std::fmt::Display;
 use std::fs;
 use std::io::ErrorKind;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Context, Result};
 use clap::{arg, Arg, ArgAction, ArgMatches, Command, ValueHint};
@@ -15,13 +15,22 @@ use log::error;
 
 use crate::api::PhylumApi;
 use crate::commands::extensions::extension::{Extension, ExtensionManifest};
+use crate::commands::extensions::installed::InstalledExtensions;
+use crate::commands::extensions::lock::ModuleLock;
+use crate::commands::extensions::signature::TrustPolicy;
+use crate::commands::extensions::source::{ExtensionSource, FetchedExtension};
 use crate::commands::{CommandResult, ExitCode};
-use crate::permissions::Permission;
+use crate::config::Config;
+use crate::permissions::{Permission, Permissions, ResourceLimits};
 use crate::print::print_sc_help;
-use crate::{app, print_user_success, print_user_warning};
+use crate::{app, deno, print_user_failure, print_user_success, print_user_warning};
 
 pub mod api;
 pub mod extension;
+pub mod installed;
+pub mod lock;
+pub mod signature;
+pub mod source;
 pub mod state;
 
 const EXTENSION_SKELETON: &[u8] = b"\
@@ -45,14 +54,61 @@ pub fn command() -> Command {
                     .help("Automatically accept requested permissions")
                 )
                 .arg(arg!(--overwrite "Overwrite existing extension"))
-                .arg(arg!([PATH]).required(true).value_hint(ValueHint::DirPath)),
+                .arg(arg!(--pin "Pin the extension, excluding it from `phylum extension update`"))
+                .arg(
+                    Arg::new("ref")
+                        .long("ref")
+                        .value_name("REF")
+                        .help("Git branch, tag or commit to install"),
+                )
+                .arg(
+                    Arg::new("sha256")
+                        .long("sha256")
+                        .value_name("HASH")
+                        .help("Expected SHA-256 hash of the extension's files"),
+                )
+                .arg(
+                    arg!([PATH] "Extension directory, `.tar.gz`/`.zip` archive or git repository URL")
+                        .required(true)
+                        .value_hint(ValueHint::AnyPath),
+                ),
         )
         .subcommand(
             Command::new("uninstall").about("Uninstall extension").arg(arg!([NAME]).required(true)),
         )
+        .subcommand(
+            Command::new("update")
+                .about("Update extensions from the source they were installed from")
+                .arg(arg!(-y --yes "Automatically accept additional permissions"))
+                .arg(arg!(--pin "Pin the updated extensions, excluding them from future updates"))
+                .arg(arg!([NAME] "Extension to update (default: all unpinned extensions)")),
+        )
+        .subcommand(
+            Command::new("lock")
+                .about("Refresh the lock of an extension's remote modules")
+                .arg(arg!([NAME] "Extension name").required(true)),
+        )
         .subcommand(
             Command::new("new").about("Create a new extension").arg(arg!([PATH]).required(true)),
         )
+        .subcommand(
+            Command::new("sign")
+                .about("Sign an extension")
+                .arg(
+                    Arg::new("key")
+                        .short('k')
+                        .long("key")
+                        .value_name("KEY")
+                        .value_hint(ValueHint::FilePath)
+                        .help("PEM-encoded RSA private key")
+                        .required(true),
+                )
+                .arg(
+                    arg!([PATH] "Extension directory")
+                        .required(true)
+                        .value_hint(ValueHint::DirPath),
+                ),
+        )
         .subcommand(
             Command::new("run")
                 .about("Run an extension from a directory")
@@ -106,24 +162,48 @@ pub async fn handle_extensions(
     api: BoxFuture<'static, Result<PhylumApi>>,
     matches: &ArgMatches,
     app: &mut Command,
+    config: &Config,
 ) -> CommandResult {
     match matches.subcommand() {
         Some(("install", matches)) => {
             let yes = matches.get_flag("yes");
             handle_install_extension(
+                &TrustPolicy::from_config(config)?,
                 matches.get_one::<String>("PATH").unwrap(),
+                matches.get_one::<String>("ref").map(String::as_str),
+                matches.get_one::<String>("sha256").map(String::as_str),
                 yes || matches.get_flag("accept-permissions"),
                 yes || matches.get_flag("overwrite"),
+                matches.get_flag("pin"),
+            )
+            .await
+        },
+        Some(("update", matches)) => {
+            handle_update_extensions(
+                &TrustPolicy::from_config(config)?,
+                matches.get_one::<String>("NAME").map(String::as_str),
+                matches.get_flag("yes"),
+                matches.get_flag("pin"),
             )
             .await
         },
         Some(("uninstall", matches)) => {
             handle_uninstall_extension(matches.get_one::<String>("NAME").unwrap()).await
         },
-        Some(("run", matches)) => handle_run_extension_from_path(app, api, matches).await,
+        Some(("lock", matches)) => {
+            handle_lock_extension(matches.get_one::<String>("NAME").unwrap()).await
+        },
+        Some(("run", matches)) => handle_run_extension_from_path(app, api, matches, config).await,
         Some(("new", matches)) => {
             handle_create_extension(matches.get_one::<String>("PATH").unwrap()).await
         },
+        Some(("sign", matches)) => {
+            handle_sign_extension(
+                matches.get_one::<String>("PATH").unwrap(),
+                matches.get_one::<String>("key").unwrap(),
+            )
+            .await
+        },
         Some(("list", _)) | None => handle_list_extensions().await,
         _ => unreachable!(),
     }
@@ -136,12 +216,14 @@ pub async fn handle_run_extension(
     api: BoxFuture<'static, Result<PhylumApi>>,
     name: &str,
     args: &ArgMatches,
+    config: &Config,
 ) -> CommandResult {
     let options = args.get_many("OPTIONS").map(|options| options.cloned().collect());
 
     let extension = Extension::load(name)?;
+    TrustPolicy::from_config(config)?.verify(&extension)?;
 
-    extension.run(api, options.unwrap_or_default()).await
+    extension.run(api, config, options.unwrap_or_default()).await
 }
 
 /// Handle the `extension run <PATH>` command path.
@@ -151,6 +233,7 @@ pub async fn handle_run_extension_from_path(
     app: &mut Command,
     api: BoxFuture<'static, Result<PhylumApi>>,
     matches: &ArgMatches,
+    config: &Config,
 ) -> CommandResult {
     let path = matches.get_one::<String>("PATH").unwrap();
     let options = matches.get_many("OPTIONS").map(|options| options.cloned().collect());
@@ -163,29 +246,51 @@ pub async fn handle_run_extension_from_path(
     let extension_path =
         fs::canonicalize(path).with_context(|| anyhow!("Invalid extension path: {path:?}"))?;
     let extension = Extension::try_from(extension_path)?;
+    TrustPolicy::from_config(config)?.verify(&extension)?;
+    extension.warn_wildcard_domains();
 
     if !matches.get_flag("yes") && !extension.permissions().is_allow_none() {
         ask_permissions(&extension)?;
     }
 
-    extension.run(api, options.unwrap_or_default()).await
+    extension.run(api, config, options.unwrap_or_default()).await
 }
 
 /// Handle the `extension install` subcommand path.
 ///
-/// Install the extension from the specified path.
+/// Install the extension from the specified directory, archive or git
+/// repository.
 async fn handle_install_extension(
+    trust_policy: &TrustPolicy,
     path: &str,
+    reference: Option<&str>,
+    sha256: Option<&str>,
     accept_permissions: bool,
     overwrite: bool,
+    pin: bool,
 ) -> CommandResult {
     // NOTE: Extension installation without slashes is reserved for the marketplace.
     if !path.contains('/') && !path.contains('\\') {
         return Err(anyhow!("Ambiguous extension URI '{}', use './{0}' instead", path));
     }
 
-    let extension_path = PathBuf::from(path);
-    let extension = Extension::try_from(extension_path)?;
+    // Download the extension on a separate thread, since git is blocking.
+    let source = ExtensionSource::parse(path);
+    let reference = reference.map(String::from);
+    let fetched = tokio::task::spawn_blocking(move || source.fetch(reference.as_deref())).await??;
+
+    if let Some(sha256) = sha256 {
+        if !fetched.sha256.eq_ignore_ascii_case(sha256) {
+            return Err(anyhow!(
+                "Extension hash mismatch, expected {sha256} but found {}",
+                fetched.sha256
+            ));
+        }
+    }
+
+    let extension = &fetched.extension;
+    extension.check_cli_version()?;
+    trust_policy.verify(extension)?;
 
     // Ensure extension subcommand does not exist yet.
     let name = extension.name();
@@ -200,25 +305,168 @@ async fn handle_install_extension(
 
     if !overwrite {
         if let Ok(installed_extension) = Extension::load(name) {
-            if extension == installed_extension {
+            if *extension == installed_extension {
+                record_installation(&fetched, pin)?;
                 print_user_success!("Extension {name} already installed, nothing to do");
                 return Ok(ExitCode::Ok);
             }
-            ask_overwrite(&extension)?;
+            ask_overwrite(extension)?;
         }
     }
 
+    extension.warn_wildcard_domains();
     if !accept_permissions && !extension.permissions().is_allow_none() {
-        ask_permissions(&extension)?;
+        ask_permissions(extension)?;
     }
 
-    extension.install()?;
+    install_locked(extension).await?;
+    record_installation(&fetched, pin)?;
 
     print_user_success!("Extension {name} installed successfully");
 
     Ok(ExitCode::Ok)
 }
 
+/// Install an extension and lock its remote modules.
+///
+/// Locks shipped with the extension are ignored, since they are not covered by
+/// its signature.
+async fn install_locked(extension: &Extension) -> Result<()> {
+    // Fetch remote modules before installation, to avoid incomplete installs.
+    let lock = deno::lock_modules(extension).await?;
+
+    extension.install()?;
+
+    // Always save the lock, so installed extensions never record new modules.
+    lock.save(&Extension::load(extension.name())?.path())?;
+
+    Ok(())
+}
+
+/// Record the source of an installed extension.
+fn record_installation(fetched: &FetchedExtension, pinned: bool) -> Result<()> {
+    let mut installed = InstalledExtensions::load()?;
+    installed.insert(fetched, pinned);
+    installed.save()
+}
+
+/// Handle the `extension update` subcommand path.
+///
+/// Update extensions from their recorded source.
+async fn handle_update_extensions(
+    trust_policy: &TrustPolicy,
+    name: Option<&str>,
+    accept_permissions: bool,
+    pin: bool,
+) -> CommandResult {
+    let mut installed = InstalledExtensions::load()?;
+
+    let names: Vec<String> = match name {
+        Some(name) if installed.extensions.contains_key(name) => vec![name.into()],
+        Some(name) if Extension::load(name).is_ok() => {
+            return Err(anyhow!(
+                "No source recorded for extension {name:?}, reinstall it to enable updates"
+            ));
+        },
+        Some(name) => return Err(anyhow!("No extension with name {name:?} installed")),
+        None => installed.extensions.keys().cloned().collect(),
+    };
+    let update_all = name.is_none();
+
+    if names.is_empty() {
+        println!("No extensions to update.");
+        return Ok(ExitCode::Ok);
+    }
+
+    // Keep updating the remaining extensions when updating all of them fails.
+    let mut failed = false;
+    for name in names {
+        let result =
+            update_extension(trust_policy, &mut installed, &name, accept_permissions, pin).await;
+        match result {
+            Err(err) if update_all => {
+                print_user_failure!("Unable to update extension {name}: {err:#}");
+                failed = true;
+            },
+            result => result?,
+        }
+    }
+
+    if failed {
+        return Err(anyhow!("Extension update failed"));
+    }
+
+    Ok(ExitCode::Ok)
+}
+
+/// Update an extension from its recorded source.
+async fn update_extension(
+    trust_policy: &TrustPolicy,
+    installed: &mut InstalledExtensions,
+    name: &str,
+    accept_permissions: bool,
+    pin: bool,
+) -> Result<()> {
+    let record = installed.extensions[name].clone();
+    if record.pinned {
+        print_user_warning!("Skipping pinned extension {name}, reinstall it to unpin");
+        return Ok(());
+    }
+
+    let current = Extension::load(name)
+        .with_context(|| anyhow!("Recorded extension {name:?} is not installed"))?;
+
+    // Fetch the latest version from the recorded source.
+    let source = ExtensionSource::parse(&record.source);
+    let reference = record.reference.clone();
+    let fetched = tokio::task::spawn_blocking(move || source.fetch(reference.as_deref())).await??;
+    let extension = &fetched.extension;
+
+    if extension.name() != name {
+        return Err(anyhow!(
+            "Source of extension {name:?} now contains extension {:?}",
+            extension.name()
+        ));
+    }
+
+    if fetched.sha256 == record.sha256 {
+        if pin {
+            installed.insert(&fetched, true);
+            installed.save()?;
+        }
+        print_user_success!("Extension {name} is up to date");
+        return Ok(());
+    }
+
+    extension.check_cli_version()?;
+    trust_policy.verify(extension)?;
+
+    match (current.version(), extension.version()) {
+        (Some(current), Some(latest)) => {
+            println!("Updating extension {name} ({current} -> {latest})...")
+        },
+        _ => println!("Updating extension {name}..."),
+    }
+
+    // Require acceptance of additional permissions.
+    let current_permissions = current.permissions();
+    let permissions = extension.permissions();
+    if print_permission_changes(&current_permissions, &permissions)
+        && !accept_permissions
+        && permissions.subset_of(&current_permissions).is_err()
+    {
+        ask_permission_changes(extension)?;
+    }
+
+    install_locked(extension).await?;
+    installed.insert(&fetched, pin);
+    installed.save()?;
+
+    print_user_success!("Extension {name} updated successfully");
+
+    Ok(())
+}
+
 fn ask_overwrite(extension: &Extension) -> Result<()> {
     let prompt = Confirm::new()
         .with_prompt(format!(
@@ -298,6 +546,97 @@ fn ask_permissions(extension: &Extension) -> Result<()> {
     }
 }
 
+/// Print the permission changes of an extension update.
+///
+/// Returns `true` if any permissions changed.
+fn print_permission_changes(current: &Permissions, latest: &Permissions) -> bool {
+    fn permission_entries(permission: &Permission, resource: &str) -> Vec<String> {
+        match permission {
+            Permission::Boolean(true) => vec![format!("any {resource}")],
+            Permission::Boolean(false) => Vec::new(),
+            Permission::List(entries) => entries.iter().map(|entry| format!("'{entry}'")).collect(),
+        }
+    }
+
+    fn limit_entries(limits: &ResourceLimits) -> Vec<String> {
+        let limits = [
+            ("processes", limits.processes),
+            ("memory", limits.memory),
+            ("cpu_time", limits.cpu_time),
+            ("open_files", limits.open_files),
+            ("file_size", limits.file_size),
+            ("output", limits.output),
+        ];
+        limits
+            .into_iter()
+            .filter_map(|(name, limit)| Some(format!("{name} = {}", limit?)))
+            .collect()
+    }
+
+    let categories = [
+        ("Read", "path", &current.read, &latest.read),
+        ("Write", "path", &current.write, &latest.write),
+        ("Run", "command", &current.run, &latest.run),
+        ("Run", "unsandboxed command", &current.unsandboxed_run, &latest.unsandboxed_run),
+        ("Access", "domain", &current.net, &latest.net),
+        ("Set", "environment variable", &current.env, &latest.env),
+    ];
+    let mut changes: Vec<_> = categories
+        .into_iter()
+        .map(|(key, resource, current, latest)| {
+            let heading = format!("{} the following {resource}s:", style(key).blue().bold());
+            (heading, permission_entries(current, resource), permission_entries(latest, resource))
+        })
+        .collect();
+    changes.push((
+        format!("{} the following resources:", style("Limit").blue().bold()),
+        limit_entries(&current.limits),
+        limit_entries(&latest.limits),
+    ));
+
+    let mut changed = false;
+    for (heading, current, latest) in changes {
+        let added = latest.iter().filter(|entry| !current.contains(entry));
+        let removed = current.iter().filter(|entry| !latest.contains(entry));
+        let lines: Vec<_> = added
+            .map(|entry| style(format!("+ {entry}")).green())
+            .chain(removed.map(|entry| style(format!("- {entry}")).red()))
+            .collect();
+
+        if lines.is_empty() {
+            continue;
+        }
+
+        if !changed {
+            println!("The permissions of the extension have changed:");
+            changed = true;
+        }
+
+        println!("\n  {heading}");
+        for line in lines {
+            println!("    {line}");
+        }
+    }
+
+    changed
+}
+
+fn ask_permission_changes(extension: &Extension) -> Result<()> {
+    if !Term::stdout().is_term() {
+        return Err(anyhow!(
+            "Can't ask for permissions: not a terminal. Extension {:?} requires additional \
+             permissions, use the -y / --yes flag to accept them.",
+            extension.name()
+        ));
+    }
+
+    if !Confirm::new().with_prompt("\nDo you accept?").default(false).interact()? {
+        Err(anyhow!("permissions not granted, aborting"))
+    } else {
+        Ok(())
+    }
+}
+
 /// Handle the `extension uninstall` subcommand path.
 ///
 /// Uninstall the extension named as specified.
@@ -309,6 +648,47 @@ async fn handle_uninstall_extension(name: &str) -> CommandResult {
 
     extension.uninstall()?;
 
+    let mut installed = InstalledExtensions::load()?;
+    if installed.extensions.remove(name).is_some() {
+        installed.save()?;
+    }
+
+    Ok(ExitCode::Ok)
+}
+
+/// Handle the `extension lock` subcommand path.
+///
+/// Fetch all remote modules of an installed extension again, replacing its
+/// module lock.
+async fn handle_lock_extension(name: &str) -> CommandResult {
+    let extension = match Extension::load(name) {
+        Ok(extension) => extension,
+        Err(_) => return Err(anyhow!("No extension with name {name:?} installed")),
+    };
+
+    let current = ModuleLock::load(&extension.path())?.unwrap_or_default();
+    let lock = deno::lock_modules(&extension).await?;
+
+    let added = lock.remote.keys().filter(|url| !current.remote.contains_key(*url));
+    let removed = current.remote.keys().filter(|url| !lock.remote.contains_key(*url));
+    let changed = lock
+        .remote
+        .iter()
+        .filter(|(url, hash)| current.remote.get(*url).is_some_and(|locked| locked != *hash));
+    for url in added {
+        println!("  {}", style(format!("+ {url}")).green());
+    }
+    for url in removed {
+        println!("  {}", style(format!("- {url}")).red());
+    }
+    for (url, _) in changed {
+        println!("  {}", style(format!("~ {url}")).yellow());
+    }
+
+    lock.save(&extension.path())?;
+
+    print_user_success!("Locked {} remote modules of extension {name}", lock.remote.len());
+
     Ok(ExitCode::Ok)
 }
 
@@ -369,6 +749,22 @@ pub async fn handle_create_extension(path: &str) -> CommandResult {
     Ok(ExitCode::Ok)
 }
 
+/// Handle the `extension sign` subcommand path.
+///
+/// Sign the extension in the specified directory.
+async fn handle_sign_extension(path: &str, key: &str) -> CommandResult {
+    let extension_path =
+        fs::canonicalize(path).with_context(|| anyhow!("Invalid extension path: {path:?}"))?;
+    let extension = Extension::try_from(extension_path)?;
+
+    let hash = signature::sign(&extension.path(), Path::new(key))?;
+
+    print_user_success!("Extension {} signed successfully", extension.name());
+    println!("Signed content hash: {hash}");
+
+    Ok(ExitCode::Ok)
+}
+
 /// Handle the `extension` / `extension list` subcommand path.
 ///
 /// List installed extensions.
@@ -381,11 +777,16 @@ async fn handle_list_extensions() -> CommandResult {
     if extensions.is_empty() {
         println!("No extensions are currently installed.");
     } else {
-        let heading = style("Extension Name         Description").blue();
+        let heading = style("Extension Name         Version      Description").blue();
         println!("{heading}");
 
         for extension in extensions {
-            println!("{:20}   {}", extension.name(), extension.description().unwrap_or(""));
+            println!(
+                "{:20}   {:10}   {}",
+                extension.name(),
+                extension.version().unwrap_or(""),
+                extension.description().unwrap_or("")
+            );
         }
     }
 
@@ -0,0 +1,208 @@
+//! Extension signatures.
+//!
+//! Extensions are signed with a detached RSA signature of their content hash,
+//! which is stored in the `PhylumExt.sig` file of the extension.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use rsa::pkcs1::DecodeRsaPrivateKey;
+use rsa::pkcs1v15::{Signature, SigningKey, VerifyingKey};
+use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey};
+use rsa::sha2::Sha256;
+use rsa::signature::{SignatureEncoding, Signer, Verifier};
+use rsa::{RsaPrivateKey, RsaPublicKey};
+
+use crate::commands::extensions::extension::Extension;
+use crate::commands::extensions::source;
+use crate::config::Config;
+use crate::{dirs, print_user_warning};
+
+/// File name of the detached extension signature.
+pub const SIGNATURE_NAME: &str = "PhylumExt.sig";
+
+/// Sign the extension in `path` with a PEM-encoded RSA private key.
+///
+/// Returns the signed content hash.
+pub fn sign(path: &Path, private_key_path: &Path) -> Result<String> {
+    let pem = fs::read_to_string(private_key_path)
+        .with_context(|| anyhow!("Unable to read private key {}", private_key_path.display()))?;
+    let private_key = RsaPrivateKey::from_pkcs8_pem(&pem)
+        .or_else(|_| RsaPrivateKey::from_pkcs1_pem(&pem))
+        .map_err(|_| anyhow!("{}: invalid RSA private key", private_key_path.display()))?;
+    let signing_key = SigningKey::<Sha256>::new(private_key);
+
+    let hash = source::content_hash(path)?;
+    let signature = signing_key.sign(hash.as_bytes());
+    fs::write(path.join(SIGNATURE_NAME), format!("{}\n", STANDARD.encode(signature.to_bytes())))?;
+
+    Ok(hash)
+}
+
+/// Signature state of an extension.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SignatureStatus {
+    /// Extension has no signature.
+    Unsigned,
+    /// Signature does not match any trusted key.
+    Untrusted,
+    /// Signature matches the trusted key at the contained path.
+    Trusted(PathBuf),
+}
+
+/// Keys trusted to sign extensions.
+pub struct TrustPolicy {
+    keys: Vec<(PathBuf, VerifyingKey<Sha256>)>,
+    require_signatures: bool,
+}
+
+impl TrustPolicy {
+    /// Load the trusted keys of the user and the selected organization.
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let signatures = &config.extension_signatures;
+        let org = config.org().and_then(|org| signatures.organizations.get(org));
+
+        let mut require_signatures = signatures.require_signatures;
+        let mut key_paths: Vec<&String> = signatures.trusted_keys.iter().collect();
+        if let Some(org) = org {
+            require_signatures |= org.require_signatures;
+            key_paths.extend(&org.trusted_keys);
+        }
+
+        let home = dirs::home_dir()?;
+        let keys = key_paths
+            .into_iter()
+            .map(|path| {
+                let path = dirs::expand_home_path(path, &home);
+                let key = load_public_key(&path)?;
+                Ok((path, key))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self { keys, require_signatures })
+    }
+
+    /// Check the signature of an extension.
+    pub fn status(&self, extension_path: &Path) -> Result<SignatureStatus> {
+        let signature = match fs::read_to_string(extension_path.join(SIGNATURE_NAME)) {
+            Ok(signature) => signature,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(SignatureStatus::Unsigned),
+            Err(err) => return Err(err.into()),
+        };
+
+        // Treat malformed signatures like signatures of unknown keys.
+        let signature = STANDARD
+            .decode(signature.trim())
+            .ok()
+            .and_then(|signature| Signature::try_from(signature.as_slice()).ok());
+        let signature = match signature {
+            Some(signature) => signature,
+            None => return Ok(SignatureStatus::Untrusted),
+        };
+
+        let hash = source::content_hash(extension_path)?;
+        let signer =
+            self.keys.iter().find(|(_, key)| key.verify(hash.as_bytes(), &signature).is_ok());
+
+        match signer {
+            Some((path, _)) => Ok(SignatureStatus::Trusted(path.clone())),
+            None => Ok(SignatureStatus::Untrusted),
+        }
+    }
+
+    /// Ensure an extension satisfies the signature policy.
+    ///
+    /// Extensions without a trusted signature are rejected if signatures are
+    /// required, otherwise a warning is printed for untrusted signatures.
+    pub fn verify(&self, extension: &Extension) -> Result<()> {
+        // Skip verification when no signatures are configured.
+        if self.keys.is_empty() && !self.require_signatures {
+            return Ok(());
+        }
+
+        let name = extension.name();
+        match self.status(&extension.path())? {
+            SignatureStatus::Trusted(key) => {
+                log::info!("Extension {name} is signed by {}", key.display());
+                Ok(())
+            },
+            SignatureStatus::Unsigned if self.require_signatures => {
+                Err(anyhow!("Extension {name} is not signed, but signatures are required"))
+            },
+            SignatureStatus::Unsigned => Ok(()),
+            SignatureStatus::Untrusted if self.require_signatures => {
+                Err(anyhow!("Extension {name} is not signed by a trusted key"))
+            },
+            SignatureStatus::Untrusted => {
+                print_user_warning!("Extension {name} is not signed by a trusted key");
+                Ok(())
+            },
+        }
+    }
+}
+
+/// Load a PEM-encoded RSA public key.
+fn load_public_key(path: &Path) -> Result<VerifyingKey<Sha256>> {
+    let pem = fs::read_to_string(path)
+        .with_context(|| anyhow!("Unable to read trusted key {}", path.display()))?;
+    let public_key = RsaPublicKey::from_public_key_pem(&pem)
+        .map_err(|_| anyhow!("{}: invalid RSA public key", path.display()))?;
+    Ok(VerifyingKey::new(public_key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_path(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/signing").join(name)
+    }
+
+    fn policy(require_signatures: bool) -> TrustPolicy {
+        let path = key_path("public.pem");
+        let key = load_public_key(&path).unwrap();
+        TrustPolicy { keys: vec![(path, key)], require_signatures }
+    }
+
+    /// Create an extension directory.
+    fn extension_dir() -> tempfile::TempDir {
+        let tempdir = tempfile::tempdir().unwrap();
+        fs::write(tempdir.path().join("PhylumExt.toml"), "name = \"sample\"\n").unwrap();
+        fs::write(tempdir.path().join("main.ts"), "console.log('Hello, World!');\n").unwrap();
+        tempdir
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        let extension = extension_dir();
+        let policy = policy(true);
+
+        assert_eq!(policy.status(extension.path()).unwrap(), SignatureStatus::Unsigned);
+
+        // Signing must not change the content hash.
+        let hash = source::content_hash(extension.path()).unwrap();
+        assert_eq!(sign(extension.path(), &key_path("private.pem")).unwrap(), hash);
+        assert_eq!(source::content_hash(extension.path()).unwrap(), hash);
+
+        let status = policy.status(extension.path()).unwrap();
+        assert_eq!(status, SignatureStatus::Trusted(key_path("public.pem")));
+
+        // Modifications invalidate the signature.
+        fs::write(extension.path().join("main.ts"), "").unwrap();
+        assert_eq!(policy.status(extension.path()).unwrap(), SignatureStatus::Untrusted);
+    }
+
+    #[test]
+    fn untrusted_key() {
+        let extension = extension_dir();
+        let policy = policy(true);
+
+        sign(extension.path(), &key_path("untrusted.pem")).unwrap();
+
+        assert_eq!(policy.status(extension.path()).unwrap(), SignatureStatus::Untrusted);
+    }
+}
@@ -0,0 +1,442 @@
+//! Extension installation sources.
+
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use anyhow::{anyhow, Context, Result};
+use flate2::read::GzDecoder;
+use git2::build::CheckoutBuilder;
+use git2::Repository;
+use reqwest::blocking::Client;
+use sha2::{Digest, Sha256};
+use tar::Archive;
+use tempfile::TempDir;
+use url::Url;
+use walkdir::WalkDir;
+use zip::ZipArchive;
+
+use crate::app::USER_AGENT;
+use crate::commands::extensions::extension::Extension;
+use crate::commands::extensions::lock::LOCK_NAME;
+use crate::commands::extensions::signature::SIGNATURE_NAME;
+
+/// Maximum size of downloaded extension archives.
+const MAX_ARCHIVE_LEN: u64 = 64 * 1024 * 1024;
+
+/// Location an extension is installed from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExtensionSource {
+    /// Local extension directory.
+    Directory(PathBuf),
+    /// Local path or URL of an extension archive.
+    Archive(String, ArchiveFormat),
+    /// Git repository URL.
+    Git(String),
+}
+
+impl ExtensionSource {
+    /// Determine the type of an extension source.
+    ///
+    /// URLs are treated as git repositories, unless they point to an archive or
+    /// are explicitly prefixed with `git+`. Like git, scp-like URLs such as
+    /// `git@example.com:org/extension.git` are supported too.
+    pub fn parse(source: &str) -> Self {
+        if let Some(url) = source.strip_prefix("git+") {
+            return Self::Git(url.into());
+        }
+
+        if source.contains("://") {
+            let path = Url::parse(source).map(|url| url.path().to_owned());
+            match ArchiveFormat::from_name(path.as_deref().unwrap_or(source)) {
+                Some(format) => Self::Archive(source.into(), format),
+                None => Self::Git(source.into()),
+            }
+        } else if is_scp_url(source) {
+            Self::Git(source.into())
+        } else {
+            match ArchiveFormat::from_name(source) {
+                Some(format) => Self::Archive(source.into(), format),
+                None => Self::Directory(source.into()),
+            }
+        }
+    }
+
+    /// Retrieve the extension from its source.
+    ///
+    /// The `reference` selects the branch, tag or commit of git repositories.
+    pub fn fetch(&self, reference: Option<&str>) -> Result<FetchedExtension> {
+        if reference.is_some() && !matches!(self, Self::Git(_)) {
+            return Err(anyhow!("A git reference can only be used with git repositories"));
+        }
+
+        let (extension, source, commit, tempdir) = match self {
+            Self::Directory(path) => {
+                let extension = Extension::try_from(path.clone())?;
+                let source = extension.path().display().to_string();
+                (extension, source, None, None)
+            },
+            Self::Archive(location, format) => {
+                let tempdir = tempfile::tempdir()?;
+                let (bytes, source) = read_archive(location)?;
+                let root = format.extract(&bytes, tempdir.path())?;
+                let extension = Extension::try_from(root)?;
+                (extension, source, None, Some(tempdir))
+            },
+            Self::Git(url) => {
+                let tempdir = tempfile::tempdir()?;
+                let commit = clone_repository(url, reference, tempdir.path())?;
+                let extension = Extension::try_from(tempdir.path().to_path_buf())?;
+                (extension, url.clone(), Some(commit), Some(tempdir))
+            },
+        };
+
+        let sha256 = content_hash(&extension.path())?;
+
+        Ok(FetchedExtension {
+            extension,
+            source,
+            reference: reference.map(String::from),
+            commit,
+            sha256,
+            _tempdir: tempdir,
+        })
+    }
+}
+
+/// Check if a source is an scp-like git URL, like `git@example.com:org/repo`.
+///
+/// Like git, sources with a colon before their first slash are treated as
+/// scp-like URLs, unless the colon follows a Windows drive letter.
+fn is_scp_url(source: &str) -> bool {
+    match source.split_once(':') {
+        Some((host, _)) => host.len() > 1 && !host.contains(['/', '\\']),
+        None => false,
+    }
+}
+
+/// Extension retrieved from its source.
+pub struct FetchedExtension {
+    pub extension: Extension,
+    /// Location the extension can be installed from again.
+    pub source: String,
+    /// Requested git reference.
+    pub reference: Option<String>,
+    /// Resolved git commit.
+    pub commit: Option<String>,
+    /// SHA-256 hash of the extension's files.
+    pub sha256: String,
+    // Temporary download directory, removed once the extension is installed.
+    _tempdir: Option<TempDir>,
+}
+
+/// Supported extension archive formats.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    TarGz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Detect the archive format from its file name.
+    fn from_name(name: &str) -> Option<Self> {
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else {
+            None
+        }
+    }
+
+    /// Extract an archive, returning the extension's root directory.
+    fn extract(&self, bytes: &[u8], dest: &Path) -> Result<PathBuf> {
+        match self {
+            Self::TarGz => Archive::new(GzDecoder::new(bytes)).unpack(dest)?,
+            Self::Zip => ZipArchive::new(Cursor::new(bytes))?.extract(dest)?,
+        }
+
+        // Archives commonly wrap their content in a single top-level directory.
+        let entries = fs::read_dir(dest)?.collect::<Result<Vec<_>, _>>()?;
+        match entries.as_slice() {
+            [entry] if entry.file_type()?.is_dir() => Ok(entry.path()),
+            _ => Ok(dest.into()),
+        }
+    }
+}
+
+/// Read a local or remote archive.
+///
+/// Returns the archive's content and its canonical location.
+fn read_archive(location: &str) -> Result<(Vec<u8>, String)> {
+    let path = if location.contains("://") {
+        let url = Url::parse(location)?;
+        if url.scheme() != "file" {
+            return Ok((download(url.as_str())?, location.into()));
+        }
+        url.to_file_path().map_err(|_| anyhow!("Invalid archive path: {location:?}"))?
+    } else {
+        PathBuf::from(location)
+    };
+
+    let path =
+        path.canonicalize().with_context(|| anyhow!("Invalid archive path: {location:?}"))?;
+    let bytes = fs::read(&path)?;
+
+    Ok((bytes, path.display().to_string()))
+}
+
+/// Download a remote archive.
+fn download(url: &str) -> Result<Vec<u8>> {
+    let client = Client::builder().user_agent(USER_AGENT.as_str()).build()?;
+    let response = client.get(url).send()?.error_for_status()?;
+
+    // Limit the amount of data read, since the archive's size is untrusted.
+    let mut bytes = Vec::new();
+    response.take(MAX_ARCHIVE_LEN + 1).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 > MAX_ARCHIVE_LEN {
+        return Err(anyhow!(
+            "Extension archive exceeds the size limit of {} MiB",
+            MAX_ARCHIVE_LEN / 1024 / 1024
+        ));
+    }
+
+    Ok(bytes)
+}
+
+/// Clone a git repository, returning the checked out commit.
+///
+/// Repositories are cloned using the `git` CLI, so the user's git
+/// configuration and credentials apply. The repository's metadata is removed
+/// after checkout, so only the extension's files remain in `dest`.
+fn clone_repository(url: &str, reference: Option<&str>, dest: &Path) -> Result<String> {
+    let output = Command::new("git")
+        .args(["clone", "--quiet", "--"])
+        .arg(url)
+        .arg(dest)
+        .stdin(Stdio::null())
+        .output()
+        .context("Unable to run git, is it installed?")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("Unable to clone {url:?}: {}", stderr.trim()));
+    }
+
+    let repo = Repository::open(dest)?;
+
+    let commit = match reference {
+        Some(reference) => {
+            // Prefer remote branches, since no local branches exist for them.
+            let object = repo
+                .revparse_single(&format!("origin/{reference}"))
+                .or_else(|_| repo.revparse_single(reference))
+                .with_context(|| anyhow!("Unknown git reference {reference:?}"))?;
+            let commit = object.peel_to_commit()?;
+
+            repo.checkout_tree(commit.as_object(), Some(CheckoutBuilder::new().force()))?;
+            repo.set_head_detached(commit.id())?;
+
+            commit.id()
+        },
+        None => repo.head()?.peel_to_commit()?.id(),
+    };
+
+    drop(repo);
+    fs::remove_dir_all(dest.join(".git"))?;
+
+    Ok(commit.to_string())
+}
+
+/// Calculate the SHA-256 hash of an extension's files.
+///
+/// Files are hashed together with their path relative to the extension root,
+/// so the hash does not depend on where the extension is located. Symlinks are
+/// ignored, since they are not installed, and so are the extension's signature
+/// and module lock.
+pub fn content_hash(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+
+    for entry in WalkDir::new(path).sort_by_file_name() {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let relative_path = entry.path().strip_prefix(path)?;
+        if relative_path == Path::new(SIGNATURE_NAME) || relative_path == Path::new(LOCK_NAME) {
+            continue;
+        }
+
+        let components: Vec<_> = relative_path
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect();
+        let content = fs::read(entry.path())?;
+
+        hasher.update(components.join("/").as_bytes());
+        hasher.update([0]);
+        hasher.update((content.len() as u64).to_le_bytes());
+        hasher.update(&content);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use git2::{Repository, Signature};
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+
+    use super::*;
+
+    const MANIFEST: &str = "name = \"sample\"\n";
+    const ENTRY_POINT: &str = "console.log('Hello, World!');\n";
+
+    /// Create an extension directory.
+    fn extension_dir() -> TempDir {
+        let tempdir = tempfile::tempdir().unwrap();
+        fs::write(tempdir.path().join("PhylumExt.toml"), MANIFEST).unwrap();
+        fs::write(tempdir.path().join("main.ts"), ENTRY_POINT).unwrap();
+        tempdir
+    }
+
+    #[test]
+    fn parse_source() {
+        assert_eq!(
+            ExtensionSource::parse("./extension"),
+            ExtensionSource::Directory("./extension".into())
+        );
+        assert_eq!(
+            ExtensionSource::parse("./extension.tar.gz"),
+            ExtensionSource::Archive("./extension.tar.gz".into(), ArchiveFormat::TarGz)
+        );
+        assert_eq!(
+            ExtensionSource::parse("https://example.com/extension.zip?download=1"),
+            ExtensionSource::Archive(
+                "https://example.com/extension.zip?download=1".into(),
+                ArchiveFormat::Zip
+            )
+        );
+        assert_eq!(
+            ExtensionSource::parse("https://github.com/phylum-dev/extension"),
+            ExtensionSource::Git("https://github.com/phylum-dev/extension".into())
+        );
+        assert_eq!(
+            ExtensionSource::parse("git+file:///tmp/extension"),
+            ExtensionSource::Git("file:///tmp/extension".into())
+        );
+        assert_eq!(
+            ExtensionSource::parse("git@github.com:phylum-dev/extension.git"),
+            ExtensionSource::Git("git@github.com:phylum-dev/extension.git".into())
+        );
+        assert_eq!(
+            ExtensionSource::parse("C:\\extension"),
+            ExtensionSource::Directory("C:\\extension".into())
+        );
+        assert_eq!(
+            ExtensionSource::parse("./extension:v1"),
+            ExtensionSource::Directory("./extension:v1".into())
+        );
+    }
+
+    #[test]
+    fn content_hash_ignores_location() {
+        let first = extension_dir();
+        let second = extension_dir();
+
+        let hash = content_hash(first.path()).unwrap();
+        assert_eq!(hash, content_hash(second.path()).unwrap());
+
+        fs::write(second.path().join("main.ts"), "").unwrap();
+        assert_ne!(hash, content_hash(second.path()).unwrap());
+    }
+
+    #[test]
+    fn fetch_tar_archive() {
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        for (path, content) in
+            [("sample/PhylumExt.toml", MANIFEST), ("sample/main.ts", ENTRY_POINT)]
+        {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            builder.append_data(&mut header, path, content.as_bytes()).unwrap();
+        }
+        let archive = builder.into_inner().unwrap().finish().unwrap();
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let archive_path = tempdir.path().join("sample.tar.gz");
+        fs::write(&archive_path, archive).unwrap();
+
+        let source = ExtensionSource::parse(archive_path.to_str().unwrap());
+        let fetched = source.fetch(None).unwrap();
+
+        assert_eq!(fetched.extension.name(), "sample");
+        assert_eq!(fetched.source, archive_path.canonicalize().unwrap().display().to_string());
+        assert_eq!(fetched.sha256, content_hash(extension_dir().path()).unwrap());
+    }
+
+    #[test]
+    fn fetch_zip_archive() {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for (path, content) in [("PhylumExt.toml", MANIFEST), ("main.ts", ENTRY_POINT)] {
+            writer.start_file(path, SimpleFileOptions::default()).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        let archive = writer.finish().unwrap().into_inner();
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let archive_path = tempdir.path().join("sample.zip");
+        fs::write(&archive_path, archive).unwrap();
+
+        let source = ExtensionSource::parse(archive_path.to_str().unwrap());
+        let fetched = source.fetch(None).unwrap();
+
+        assert_eq!(fetched.extension.name(), "sample");
+        assert_eq!(fetched.sha256, content_hash(extension_dir().path()).unwrap());
+    }
+
+    #[test]
+    fn fetch_git_reference() {
+        let extension = extension_dir();
+        let repo = Repository::init(extension.path()).unwrap();
+        let signature = Signature::now("Phylum", "phylum@example.com").unwrap();
+
+        // Commit the initial version and tag it.
+        let commit = |message: &str| {
+            let mut index = repo.index().unwrap();
+            index.add_all(["*"], git2::IndexAddOption::DEFAULT, None).unwrap();
+            index.write().unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let parents: Vec<_> =
+                repo.head().ok().map(|head| head.peel_to_commit().unwrap()).into_iter().collect();
+            let parents: Vec<_> = parents.iter().collect();
+            repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &parents).unwrap()
+        };
+        let initial = commit("Initial commit");
+        repo.tag_lightweight("v1.0.0", &repo.find_object(initial, None).unwrap(), false).unwrap();
+
+        fs::write(extension.path().join("main.ts"), "").unwrap();
+        let latest = commit("Update entry point");
+
+        let url = extension.path().to_str().unwrap();
+        let source = ExtensionSource::Git(url.into());
+
+        let fetched = source.fetch(None).unwrap();
+        assert_eq!(fetched.commit, Some(latest.to_string()));
+        assert!(!fetched.extension.path().join(".git").exists());
+
+        let fetched = source.fetch(Some("v1.0.0")).unwrap();
+        assert_eq!(fetched.commit, Some(initial.to_string()));
+        assert_eq!(fetched.reference.as_deref(), Some("v1.0.0"));
+        assert_eq!(fetched.sha256, content_hash(extension_dir().path()).unwrap());
+
+        assert!(source.fetch(Some("missing")).is_err());
+    }
+}
@@ -10,6 +10,7 @@ use futures::future::BoxFuture;
 use tokio::sync::OnceCell;
 
 use crate::commands::extensions::{Extension, PhylumApi};
+use crate::config::Config;
 
 struct OnceFuture<T> {
     future: Cell<Option<BoxFuture<'static, T>>>,
@@ -55,6 +56,7 @@ impl<T: Unpin> OnceFuture<T> {
 pub struct ExtensionStateInner {
     api: OnceFuture<Result<PhylumApi>>,
     extension: Extension,
+    config: Config,
 }
 
 impl ExtensionStateInner {
@@ -70,6 +72,11 @@ impl ExtensionStateInner {
     pub fn extension(&self) -> &Extension {
         &self.extension
     }
+
+    /// Returns a reference to the CLI configuration.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
 }
 
 /// Extension state wrapper.
@@ -81,9 +88,13 @@ impl ExtensionStateInner {
 pub struct ExtensionState(Rc<ExtensionStateInner>);
 
 impl ExtensionState {
-    pub fn new(api: BoxFuture<'static, Result<PhylumApi>>, extension: Extension) -> Self {
+    pub fn new(
+        api: BoxFuture<'static, Result<PhylumApi>>,
+        extension: Extension,
+        config: Config,
+    ) -> Self {
         let api = OnceFuture::new(api);
-        Self(Rc::new(ExtensionStateInner { api, extension }))
+        Self(Rc::new(ExtensionStateInner { api, extension, config }))
     }
 }
 
@@ -1,10 +1,16 @@
 //! `phylum find-dependency-files` subcommand.
 
+use clap::ArgMatches;
+
 use crate::commands::{CommandResult, ExitCode};
+use crate::config;
 
 /// Handle `phylum find-dependency-files` subcommand.
-pub fn handle_command() -> CommandResult {
-    let depfiles = phylum_lockfile::DepFiles::find_at(".");
+pub fn handle_command(matches: &ArgMatches) -> CommandResult {
+    let project = phylum_project::get_current_project();
+    let options = config::discovery_options(matches, project.as_ref())?;
+
+    let depfiles = phylum_lockfile::DepFiles::find_with_options(".", &options)?;
     let json = serde_json::to_string(&depfiles)?;
     println!("{json}");
     Ok(ExitCode::Ok)
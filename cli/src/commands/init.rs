@@ -8,7 +8,7 @@ use clap::parser::ValuesRef;
 use clap::ArgMatches;
 use dialoguer::{Confirm, FuzzySelect, Input, MultiSelect};
 use git2::Repository;
-use phylum_lockfile::LockfileFormat;
+use phylum_lockfile::{DiscoveryOptions, LockfileFormat};
 use phylum_project::{DepfileConfig, ProjectConfig, PROJ_CONF_FILE};
 use reqwest::StatusCode;
 
@@ -64,7 +64,9 @@ pub async fn handle_init(api: &PhylumApi, matches: &ArgMatches, config: Config)
         prompt_project(&groups, cli_project, cli_group, cli_repository_url).await?;
 
     // Interactively prompt for missing dependency file information.
-    let depfiles = prompt_depfiles(cli_depfiles, cli_depfile_type)?;
+    let discovery = config::cli_discovery_config(matches);
+    let discovery_options = config::discovery_options(matches, None)?;
+    let depfiles = prompt_depfiles(cli_depfiles, cli_depfile_type, &discovery_options)?;
 
     // Attempt to create the project.
     let result = project::create_project(
@@ -88,8 +90,14 @@ pub async fn handle_init(api: &PhylumApi, matches: &ArgMatches, config: Config)
         project_config => project_config.context("Unable to create project")?,
     };
 
-    // Override project dependency file info.
-    project_config.set_depfiles(depfiles);
+    // Override project dependency file info, searching for dependency files
+    // during analysis if discovery settings were specified instead.
+    if depfiles.is_empty() && !discovery.is_empty() {
+        project_config.unset_depfiles();
+    } else {
+        project_config.set_depfiles(depfiles);
+    }
+    project_config.discovery = discovery;
 
     // Save project config.
     config::save_config(Path::new(PROJ_CONF_FILE), &project_config)
@@ -210,11 +218,12 @@ async fn prompt_repository_url() -> anyhow::Result<Option<String>> {
 fn prompt_depfiles(
     cli_depfiles: Option<ValuesRef<'_, String>>,
     cli_depfile_type: Option<&String>,
-) -> dialoguer::Result<Vec<DepfileConfig>> {
+    discovery_options: &DiscoveryOptions,
+) -> anyhow::Result<Vec<DepfileConfig>> {
     // Prompt for dependency files if they weren't specified.
     let depfiles = match cli_depfiles {
         Some(depfiles) => depfiles.cloned().collect(),
-        None => prompt_depfile_names()?,
+        None => prompt_depfile_names(discovery_options)?,
     };
 
     // Find dependency file type for each file.
@@ -229,9 +238,9 @@ fn prompt_depfiles(
 }
 
 /// Ask for the dependency file names.
-fn prompt_depfile_names() -> dialoguer::Result<Vec<String>> {
+fn prompt_depfile_names(discovery_options: &DiscoveryOptions) -> anyhow::Result<Vec<String>> {
     // Find all known dependency files below the current directory.
-    let mut depfiles = phylum_lockfile::find_depfiles_at(".")
+    let mut depfiles = phylum_lockfile::find_depfiles_with_options(".", discovery_options)?
         .iter()
         .flat_map(|(path, _)| Some(path.to_str()?.to_owned()))
         .collect::<Vec<_>>();
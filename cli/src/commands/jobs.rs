@@ -6,7 +6,6 @@ use std::str::FromStr;
 use anyhow::{anyhow, Context, Result};
 use console::style;
 use log::debug;
-use phylum_lockfile::ParseError;
 use phylum_project::DepfileConfig;
 use phylum_types::types::common::{JobId, ProjectId};
 use phylum_types::types::package::PackageDescriptor;
@@ -20,7 +19,7 @@ use crate::auth::jwt::RealmRole;
 use crate::commands::{parse, CommandResult, ExitCode};
 use crate::config::{self, Config};
 use crate::format::Format;
-use crate::types::AnalysisPackageDescriptor;
+use crate::types::{AnalysisPackageDescriptor, LockfileProvenance};
 #[cfg(feature = "vulnreach")]
 use crate::vulnreach;
 use crate::{print_user_failure, print_user_success, print_user_warning};
@@ -100,50 +99,38 @@ pub async fn handle_analyze(
     matches: &clap::ArgMatches,
     config: Config,
 ) -> CommandResult {
-    let sandbox_generation = !matches.get_flag("skip-sandbox");
-    let generate_lockfiles = !matches.get_flag("no-generation");
+    let mut options = parse::ParseOptions::from_matches(matches);
     let label = matches.get_one::<String>("label");
     let pretty_print = !matches.get_flag("json");
 
-    let jobs_project = JobsProject::new(api, matches, config).await?;
-
     // Get .phylum_project path.
     let current_project = phylum_project::get_current_project();
     let project_root = current_project.as_ref().map(|p| p.root());
 
-    let mut packages = Vec::new();
-    for depfile in jobs_project.depfiles {
-        let parse_result = parse::parse_depfile(
-            &depfile.path,
-            project_root,
-            Some(&depfile.depfile_type),
-            sandbox_generation,
-            generate_lockfiles,
-        );
-
-        // Map dedicated exit codes for failures due to disabled generation or
-        // unknown dependency file format.
-        let parsed_depfile = match parse_result {
-            Ok(parsed_depfile) => parsed_depfile,
-            Err(err @ ParseError::ManifestWithoutGeneration(_)) => {
-                print_user_failure!("Could not parse manifest: {}", err);
-                return Ok(ExitCode::ManifestWithoutGeneration);
-            },
-            Err(err @ ParseError::UnknownManifestFormat(_)) => {
-                print_user_failure!("Could not parse manifest: {}", err);
-                return Ok(ExitCode::UnknownManifestFormat);
-            },
-            Err(ParseError::Other(err)) => {
-                return Err(err).with_context(|| {
-                    format!(
-                        "Could not parse dependency file {:?} as {:?} type",
-                        depfile.path.display(),
-                        depfile.depfile_type
-                    )
-                });
-            },
+    let profiles = config::sandbox_profiles(&config, current_project.as_ref())?;
+    options.generation.sandbox_profiles = profiles.into_values().collect();
+
+    let jobs_project = JobsProject::new(api, matches, config).await?;
+
+    // Explain dependency file parsing without submitting anything.
+    if matches.get_flag("dry-run") {
+        let plans =
+            parse::explain_depfiles(&jobs_project.depfiles, project_root, &options.generation)?;
+        plans.write_stdout(pretty_print);
+        return Ok(ExitCode::Ok);
+    }
+
+    let parsed_depfiles =
+        match parse::parse_depfiles(jobs_project.depfiles, project_root, options).await? {
+            Ok(parsed_depfiles) => parsed_depfiles,
+            Err(exit_code) => return Ok(exit_code),
         };
 
+    let provenance: Vec<_> =
+        parsed_depfiles.iter().filter_map(LockfileProvenance::from_lockfile).collect();
+
+    let mut packages = Vec::new();
+    for parsed_depfile in parsed_depfiles {
         if pretty_print {
             print_user_success!(
                 "Successfully parsed dependency file {:?} as type {:?}",
@@ -157,6 +144,20 @@ pub async fn handle_analyze(
         packages.append(&mut analysis_packages);
     }
 
+    for parsed_depfile in parse::parse_images(matches)? {
+        if pretty_print {
+            print_user_success!(
+                "Successfully parsed image dependency file {:?} as type {:?}",
+                parsed_depfile.path,
+                parsed_depfile.format.name()
+            );
+        }
+
+        let mut analysis_packages =
+            AnalysisPackageDescriptor::descriptors_from_lockfile(parsed_depfile);
+        packages.append(&mut analysis_packages);
+    }
+
     let ignored_packages: Vec<PackageDescriptor> = match matches.get_one::<String>("base") {
         Some(base) => {
             let base_text = fs::read_to_string(base)?;
@@ -178,6 +179,7 @@ pub async fn handle_analyze(
             jobs_project.project_id,
             label.map(String::from),
             jobs_project.group,
+            &provenance,
         )
         .await?;
     debug!("Response => {job_id:?}");
@@ -13,6 +13,7 @@ pub mod org;
 pub mod packages;
 pub mod parse;
 pub mod project;
+pub mod recover;
 #[cfg(unix)]
 pub mod sandbox;
 pub mod status;
@@ -39,9 +40,16 @@ pub enum ExitCode {
     ManifestWithoutGeneration,
     UnknownManifestFormat,
     MissingOrg,
+    GenerationLimitExceeded,
     FailedPolicy,
     SandboxStart,
     SandboxStartCollision,
+    SandboxCpuTimeLimit,
+    SandboxMemoryLimit,
+    SandboxFileSizeLimit,
+    SandboxOutputLimit,
+    SandboxProcessLimit,
+    SandboxOpenFilesLimit,
     Custom(i32),
 }
 
@@ -69,9 +77,16 @@ impl From<&ExitCode> for i32 {
             ExitCode::ManifestWithoutGeneration => 20,
             ExitCode::UnknownManifestFormat => 21,
             ExitCode::MissingOrg => 22,
+            ExitCode::GenerationLimitExceeded => 23,
             ExitCode::FailedPolicy => 100,
             ExitCode::SandboxStart => 117,
             ExitCode::SandboxStartCollision => 118,
+            ExitCode::SandboxCpuTimeLimit => 119,
+            ExitCode::SandboxMemoryLimit => 120,
+            ExitCode::SandboxFileSizeLimit => 121,
+            ExitCode::SandboxOutputLimit => 122,
+            ExitCode::SandboxProcessLimit => 123,
+            ExitCode::SandboxOpenFilesLimit => 124,
             ExitCode::Custom(code) => *code,
         }
     }
@@ -1,12 +1,22 @@
 //! `phylum parse` command for lockfile parsing
 
 use std::borrow::Cow;
+#[cfg(unix)]
+use std::io::Read;
+use std::num::NonZeroUsize;
+#[cfg(unix)]
+use std::os::unix::process::CommandExt;
 use std::path::{Path, PathBuf};
 #[cfg(unix)]
-use std::process::Command as StdCommand;
+use std::process::{Child, Command as StdCommand, Output};
 use std::result::Result as StdResult;
 use std::str::FromStr;
-use std::{env, fs, io};
+#[cfg(unix)]
+use std::sync::Mutex;
+use std::time::Duration;
+#[cfg(unix)]
+use std::time::Instant;
+use std::{env, fs, io, thread};
 
 #[cfg(unix)]
 use anyhow::anyhow;
@@ -16,10 +26,23 @@ use birdcage::process::{Command, Stdio};
 #[cfg(unix)]
 use birdcage::{Birdcage, Exception, Sandbox};
 use clap::ArgMatches;
-use phylum_lockfile::{LockfileFormat, ParseError, ParsedLockfile};
-
-use crate::commands::{CommandResult, ExitCode};
-use crate::types::AnalysisPackageDescriptor;
+use futures::stream::{self, StreamExt};
+#[cfg(unix)]
+use phylum_lockfile::generator::journal;
+use phylum_lockfile::generator::limits::{Cancellation, Limits};
+use phylum_lockfile::generator::{Explanation, Options as GeneratorOptions};
+use phylum_lockfile::{Generation, LockfileFormat, ParseError, ParsedLockfile};
+use phylum_project::{DepfileConfig, SandboxProfile};
+use serde::Serialize;
+use tokio::task;
+
+use crate::commands::{recover, CommandResult, ExitCode};
+use crate::config::Config;
+use crate::format::Format;
+#[cfg(unix)]
+use crate::proxy::FilteringProxy;
+use crate::spinner::Spinner;
+use crate::types::{AnalysisPackageDescriptor, LockfileProvenance};
 use crate::{config, print_user_failure, print_user_warning};
 #[cfg(unix)]
 use crate::{dirs, permissions};
@@ -35,70 +58,112 @@ pub fn lockfile_types(add_auto: bool) -> Vec<&'static str> {
     lockfile_types
 }
 
-pub fn handle_parse(matches: &ArgMatches) -> CommandResult {
-    let sandbox_generation = !matches.get_flag("skip-sandbox");
-    let generate_lockfiles = !matches.get_flag("no-generation");
+pub async fn handle_parse(matches: &ArgMatches, config: &Config) -> CommandResult {
+    let mut options = ParseOptions::from_matches(matches);
 
     let project = phylum_project::get_current_project();
+    let profiles = config::sandbox_profiles(config, project.as_ref())?;
+    options.generation.sandbox_profiles = profiles.into_values().collect();
     let project_root = project.as_ref().map(|p| p.root());
     let depfiles = config::depfiles(matches, project.as_ref())?;
 
-    let mut pkgs = Vec::new();
-    for depfile in depfiles {
-        let parse_result = parse_depfile(
-            &depfile.path,
-            project_root,
-            Some(&depfile.depfile_type),
-            sandbox_generation,
-            generate_lockfiles,
-        );
+    if matches.get_flag("dry-run") {
+        let plans = explain_depfiles(&depfiles, project_root, &options.generation)?;
+        plans.write_stdout(false);
+        return Ok(ExitCode::Ok);
+    }
 
-        // Map dedicated exit codes for failures due to disabled generation or
-        // unknown dependency file format.
-        let parsed_lockfile = match parse_result {
-            Ok(parsed_lockfile) => parsed_lockfile,
-            Err(err @ ParseError::ManifestWithoutGeneration(_)) => {
-                print_user_failure!("Could not parse manifest: {}", err);
-                return Ok(ExitCode::ManifestWithoutGeneration);
-            },
-            Err(err @ ParseError::UnknownManifestFormat(_)) => {
-                print_user_failure!("Could not parse manifest: {}", err);
-                return Ok(ExitCode::UnknownManifestFormat);
-            },
-            Err(ParseError::Other(err)) => {
-                return Err(err).with_context(|| {
-                    format!(
-                        "Could not parse dependency file {:?} as {:?} type",
-                        depfile.path.display(),
-                        depfile.depfile_type
-                    )
-                });
-            },
-        };
+    let parsed_lockfiles = match parse_depfiles(depfiles, project_root, options).await? {
+        Ok(parsed_lockfiles) => parsed_lockfiles,
+        Err(exit_code) => return Ok(exit_code),
+    };
+
+    let provenance: Vec<_> =
+        parsed_lockfiles.iter().filter_map(LockfileProvenance::from_lockfile).collect();
 
+    let mut pkgs = Vec::new();
+    for parsed_lockfile in parsed_lockfiles {
         let mut analysis_packages =
             AnalysisPackageDescriptor::descriptors_from_lockfile(parsed_lockfile);
         pkgs.append(&mut analysis_packages);
     }
 
-    serde_json::to_writer_pretty(&mut io::stdout(), &pkgs)?;
+    for parsed_lockfile in parse_images(matches)? {
+        let mut analysis_packages =
+            AnalysisPackageDescriptor::descriptors_from_lockfile(parsed_lockfile);
+        pkgs.append(&mut analysis_packages);
+    }
+
+    if matches.get_flag("provenance") {
+        let output = ParseOutput { packages: pkgs, provenance };
+        serde_json::to_writer_pretty(&mut io::stdout(), &output)?;
+    } else {
+        serde_json::to_writer_pretty(&mut io::stdout(), &pkgs)?;
+    }
 
     Ok(ExitCode::Ok)
 }
 
+/// `phylum parse --provenance` output.
+#[derive(Serialize)]
+struct ParseOutput {
+    packages: Vec<AnalysisPackageDescriptor>,
+    provenance: Vec<LockfileProvenance>,
+}
+
 #[cfg(unix)]
 pub fn handle_parse_sandboxed(matches: &ArgMatches) -> CommandResult {
     let path = PathBuf::from(matches.get_raw("depfile").unwrap().next().unwrap());
     let display_path = matches.get_one::<String>("display-path").unwrap();
-    let generate_lockfile = matches.get_flag("generate-lockfile");
     let lockfile_type = matches.get_one::<String>("type");
     let skip_sandbox = matches.get_flag("skip-sandbox");
+    let generation = GenerationOptions {
+        enabled: matches.get_flag("generate-lockfile"),
+        sandbox: !skip_sandbox,
+        out_of_tree: matches.get_flag("out-of-tree"),
+        scratch_dir: matches
+            .get_raw("scratch-dir")
+            .and_then(|mut dir| dir.next())
+            .map(PathBuf::from),
+        offline: matches.get_flag("offline"),
+        allowed_domains: allowed_domains(matches),
+        sandbox_profiles: vec![cli_sandbox_profile(matches)],
+        limits: generation_limits(matches),
+        cancellation: Cancellation::default(),
+    };
 
     if skip_sandbox {
-        child_parse_depfile(&path, display_path, lockfile_type, generate_lockfile)
+        child_parse_depfile(&path, display_path, lockfile_type, &generation)
+    } else {
+        spawn_sandbox(&path, display_path, lockfile_type, &generation)
+    }
+}
+
+/// Handle the `phylum sandbox-profile` subcommand.
+#[cfg(unix)]
+pub fn handle_sandbox_profile(matches: &ArgMatches, config: &Config) -> CommandResult {
+    let depfile_type = matches.get_one::<String>("type").unwrap();
+    let format = LockfileFormat::from_str(depfile_type).unwrap();
+
+    let project = phylum_project::get_current_project();
+    let profiles = config::sandbox_profiles(config, project.as_ref())?;
+
+    // Combine all profiles applied to this dependency file type.
+    let mut profile = SandboxProfile::default();
+    let mut names = Vec::new();
+    for (name, matching) in profiles.iter().filter(|(_, p)| p.applies_to(Some(format.name()))) {
+        profile.merge(matching);
+        names.push(name.as_str());
+    }
+
+    if names.is_empty() {
+        println!("# No sandbox profiles apply to {format}");
     } else {
-        spawn_sandbox(&path, display_path, lockfile_type, generate_lockfile)
+        println!("# Sandbox profiles: {}", names.join(", "));
+        print!("{}", serde_yaml::to_string(&profile)?);
     }
+
+    Ok(ExitCode::Ok)
 }
 
 /// Reexecute `parse-sandboxed` inside the sandbox.
@@ -107,14 +172,22 @@ fn spawn_sandbox(
     path: &Path,
     display_path: &str,
     lockfile_type: Option<&String>,
-    generate_lockfile: bool,
+    generation: &GenerationOptions,
 ) -> CommandResult {
+    // Point proxy-aware clients at a filtering proxy when domains are listed.
+    let proxy = match generation.network() {
+        Network::Proxied(domains) => Some(FilteringProxy::start(domains)?),
+        Network::Unrestricted | Network::Offline => None,
+    };
+
     // Setup sandbox for lockfile generation.
-    let birdcage = depfile_parsing_sandbox(path)?;
+    let birdcage = depfile_parsing_sandbox(path, generation)?;
 
     // Reexecute command inside sandbox.
-    let command =
-        parse_sandboxed_command(path, display_path, lockfile_type, generate_lockfile, true)?;
+    let mut command = parse_sandboxed_command(path, display_path, lockfile_type, generation, true)?;
+    for (key, value) in proxy.iter().flat_map(FilteringProxy::environment) {
+        command.env(key, value);
+    }
     let mut child = birdcage.spawn(command)?;
 
     // Check for process failure.
@@ -132,16 +205,21 @@ fn child_parse_depfile(
     path: &PathBuf,
     display_path: &str,
     lockfile_type: Option<&String>,
-    generate_lockfile: bool,
+    generation: &GenerationOptions,
 ) -> CommandResult {
     let lockfile_type = lockfile_type.map(|t| LockfileFormat::from_str(t).unwrap());
 
-    let generation_path = generate_lockfile.then(|| path.clone());
+    // Restore relocated files when generation is interrupted.
+    if generation.enabled {
+        recover::restore_on_signal(generation.cancellation.clone())?;
+    }
+
+    let generation = generation.enabled.then(|| generation.for_manifest(path.clone()));
     let contents = fs::read_to_string(path)?;
 
     // Parse dependency file.
     let parse_result =
-        phylum_lockfile::parse_depfile(&contents, display_path, lockfile_type, generation_path);
+        phylum_lockfile::parse_depfile(&contents, display_path, lockfile_type, generation);
 
     // Map lockfile generation failure to specific exit code.
     let parsed = match parse_result {
@@ -150,6 +228,10 @@ fn child_parse_depfile(
             return Ok(ExitCode::ManifestWithoutGeneration)
         },
         Err(ParseError::UnknownManifestFormat(_)) => return Ok(ExitCode::UnknownManifestFormat),
+        Err(ParseError::GenerationLimitExceeded(err)) => {
+            print_user_failure!("{err:?}");
+            return Ok(ExitCode::GenerationLimitExceeded);
+        },
         Err(ParseError::Other(err)) => return Err(err),
     };
 
@@ -159,14 +241,352 @@ fn child_parse_depfile(
     Ok(ExitCode::Ok)
 }
 
+/// Lockfile generation settings.
+#[derive(Clone, Debug)]
+pub struct GenerationOptions {
+    /// Generate lockfiles for manifests.
+    pub enabled: bool,
+    /// Run lockfile generation inside a sandbox.
+    pub sandbox: bool,
+    /// Generate lockfiles in a scratch copy of the project.
+    pub out_of_tree: bool,
+    /// Directory for the scratch copies shared by all generations of a run.
+    pub scratch_dir: Option<PathBuf>,
+    /// Generate lockfiles from local package caches, without network access.
+    pub offline: bool,
+    /// Domains allowed by the filtering proxy of sandboxed lockfile generation.
+    ///
+    /// No proxy is used if this is empty.
+    pub allowed_domains: Vec<String>,
+    /// Additional exceptions of the lockfile generation sandbox.
+    pub sandbox_profiles: Vec<SandboxProfile>,
+    /// Time and resource limits of lockfile generation processes.
+    pub limits: Limits,
+    /// Cancellation of all lockfile generation processes using these settings.
+    pub cancellation: Cancellation,
+}
+
+impl GenerationOptions {
+    /// Get the sandbox profile for a dependency file type, combining all
+    /// matching profiles.
+    pub fn sandbox_profile(&self, format: Option<LockfileFormat>) -> SandboxProfile {
+        let format = format.map(|format| format.name());
+
+        let mut profile = SandboxProfile::default();
+        for matching in self.sandbox_profiles.iter().filter(|p| p.applies_to(format)) {
+            profile.merge(matching);
+        }
+
+        profile
+    }
+
+    /// Get the generation settings for a dependency file type.
+    ///
+    /// Only the sandbox profile matching the dependency file type is kept and
+    /// its domains are added to the accessible domains.
+    pub(crate) fn for_format(&self, format: Option<LockfileFormat>) -> Self {
+        let profile = self.sandbox_profile(format);
+
+        let mut generation = self.clone();
+        for domain in &profile.net {
+            if !generation.allowed_domains.contains(domain) {
+                generation.allowed_domains.push(domain.clone());
+            }
+        }
+        generation.sandbox_profiles = vec![profile];
+
+        generation
+    }
+
+    /// Get the settings of the lockfile generator's processes.
+    pub(crate) fn generator_options(&self) -> GeneratorOptions {
+        GeneratorOptions {
+            limits: self.limits,
+            offline: self.offline,
+            cancellation: self.cancellation.clone(),
+        }
+    }
+
+    /// Get the lockfile generation for a manifest.
+    fn for_manifest(&self, manifest_path: PathBuf) -> Generation {
+        Generation::new(manifest_path)
+            .out_of_tree(self.out_of_tree)
+            .scratch_dir(self.scratch_dir.clone())
+            .options(self.generator_options())
+    }
+
+    /// Get the network access of lockfile generation.
+    #[cfg(unix)]
+    pub(crate) fn network(&self) -> Network<'_> {
+        if self.offline {
+            Network::Offline
+        } else if self.allowed_domains.is_empty() {
+            Network::Unrestricted
+        } else {
+            Network::Proxied(&self.allowed_domains)
+        }
+    }
+
+    /// Get lockfile generation settings from the CLI arguments.
+    ///
+    /// Lockfiles are generated out of tree by default when the sandbox is
+    /// enabled, which is only supported on Unix platforms.
+    pub fn from_matches(matches: &ArgMatches) -> Self {
+        let sandbox = !matches.get_flag("skip-sandbox");
+        let out_of_tree = match matches.get_one::<String>("generate-in").map(String::as_str) {
+            Some("scratch") => true,
+            Some(_) => false,
+            None => cfg!(unix) && sandbox,
+        };
+
+        Self {
+            enabled: !matches.get_flag("no-generation"),
+            offline: matches.get_flag("offline"),
+            allowed_domains: allowed_domains(matches),
+            sandbox_profiles: Vec::new(),
+            limits: generation_limits(matches),
+            cancellation: Cancellation::default(),
+            sandbox,
+            out_of_tree,
+            scratch_dir: None,
+        }
+    }
+}
+
+/// Network access of lockfile generation.
+#[cfg(unix)]
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum Network<'a> {
+    /// Access to all domains.
+    Unrestricted,
+    /// Access to all domains, with a [`FilteringProxy`] for these domains.
+    ///
+    /// Only clients using the proxy environment variables are filtered.
+    Proxied(&'a [String]),
+    /// No network access.
+    Offline,
+}
+
+/// Get the domains accessible during lockfile generation from the CLI
+/// arguments.
+fn allowed_domains(matches: &ArgMatches) -> Vec<String> {
+    matches.get_many::<String>("allow-domain").unwrap_or_default().cloned().collect()
+}
+
+/// Get the sandbox exceptions of `parse-sandboxed` from the CLI arguments.
+#[cfg(unix)]
+fn cli_sandbox_profile(matches: &ArgMatches) -> SandboxProfile {
+    let strings = |id: &str| -> Vec<String> {
+        matches.get_many::<String>(id).unwrap_or_default().cloned().collect()
+    };
+
+    SandboxProfile {
+        read: strings("allow-read"),
+        write: strings("allow-write"),
+        run: strings("allow-run"),
+        env: strings("allow-env"),
+        ..SandboxProfile::default()
+    }
+}
+
+/// Get the lockfile generation limits from the CLI arguments.
+fn generation_limits(matches: &ArgMatches) -> Limits {
+    let seconds = |id| matches.get_one::<u64>(id).map(|secs| Duration::from_secs(*secs));
+    let mebibytes = |id| matches.get_one::<u64>(id).map(|mib| mib.saturating_mul(1024 * 1024));
+
+    Limits {
+        timeout: seconds("generation-timeout"),
+        cpu_time: seconds("generation-cpu-limit"),
+        memory: mebibytes("generation-memory-limit"),
+        file_size: mebibytes("generation-file-size-limit"),
+        processes: matches.get_one::<u64>("generation-process-limit").copied(),
+        open_files: matches.get_one::<u64>("generation-open-files-limit").copied(),
+        output_size: mebibytes("generation-output-limit"),
+    }
+}
+
+/// Options for parsing multiple dependency files.
+#[derive(Clone, Debug)]
+pub struct ParseOptions {
+    pub generation: GenerationOptions,
+    /// Maximum number of dependency files parsed concurrently.
+    pub jobs: NonZeroUsize,
+    /// Continue parsing the remaining dependency files after a failure.
+    pub keep_going: bool,
+}
+
+impl ParseOptions {
+    /// Get parsing options from the CLI arguments.
+    pub fn from_matches(matches: &ArgMatches) -> Self {
+        let jobs = matches.get_one::<NonZeroUsize>("jobs").copied();
+        let jobs =
+            jobs.or_else(|| thread::available_parallelism().ok()).unwrap_or(NonZeroUsize::MIN);
+
+        Self {
+            generation: GenerationOptions::from_matches(matches),
+            keep_going: matches.get_flag("keep-going"),
+            jobs,
+        }
+    }
+}
+
+/// Parse multiple dependency files concurrently.
+///
+/// The parsed dependency files are returned in the order of `depfiles`,
+/// independent of the order in which their parsing completed.
+///
+/// Failures are reported to the user and mapped to their dedicated exit code.
+/// Without [`ParseOptions::keep_going`], parsing is stopped at the first
+/// failure. Otherwise all dependency files are parsed and the exit code of the
+/// first failure is returned.
+pub async fn parse_depfiles(
+    depfiles: Vec<DepfileConfig>,
+    project_root: Option<&PathBuf>,
+    mut options: ParseOptions,
+) -> Result<StdResult<Vec<ParsedLockfile>, ExitCode>> {
+    // Failures only cancel the generations of this run.
+    options.generation.cancellation = Cancellation::default();
+
+    if options.generation.enabled {
+        recover::prepare_generation(options.generation.cancellation.clone())?;
+    }
+
+    // Copy every project only once for all out-of-tree generations.
+    let scratch_dir = if options.generation.enabled && options.generation.out_of_tree {
+        Some(tempfile::tempdir()?)
+    } else {
+        None
+    };
+    options.generation.scratch_dir = scratch_dir.as_ref().map(|dir| dir.path().into());
+
+    let total = depfiles.len();
+    let spinner = Spinner::new_with_message(format!("Parsing dependency files (0/{total})"));
+
+    // Spawn a blocking task for each dependency file, since lockfile generation
+    // waits for its subprocess to exit.
+    let mut parse_results = stream::iter(depfiles)
+        .map(|depfile| {
+            let project_root = project_root.cloned();
+            let generation = options.generation.clone();
+            async move {
+                // Skip dependency files which were not started before cancellation.
+                if generation.cancellation.is_cancelled() {
+                    return None;
+                }
+
+                let task = task::spawn_blocking(move || {
+                    let result = parse_depfile(
+                        &depfile.path,
+                        project_root.as_ref(),
+                        Some(&depfile.depfile_type),
+                        &generation,
+                    );
+                    (depfile, result)
+                });
+                Some(task.await.expect("dependency file parsing panicked"))
+            }
+        })
+        .buffered(options.jobs.get());
+
+    let mut results = Vec::with_capacity(total);
+    while let Some(parsed) = parse_results.next().await {
+        // Ignore the results of jobs stopped by the cancellation.
+        let (depfile, result) = match parsed {
+            Some(parsed) if !options.generation.cancellation.is_cancelled() => parsed,
+            _ => continue,
+        };
+
+        let message = format!(
+            "Parsing dependency files ({}/{total}): {}",
+            results.len() + 1,
+            depfile.path.display()
+        );
+        spinner.set_message(message).await;
+
+        let failed = result.is_err();
+        results.push((depfile, result));
+
+        // Stop all running jobs, but wait for them to exit before reporting the
+        // failure.
+        if failed && !options.keep_going {
+            options.generation.cancellation.cancel();
+        }
+    }
+
+    // Restore files relocated by killed lockfile generators.
+    if options.generation.cancellation.is_cancelled() && options.generation.enabled {
+        recover::restore_abandoned();
+    }
+
+    spinner.stop().await;
+
+    let mut parsed_lockfiles = Vec::new();
+    let mut exit_code = None;
+    for (depfile, result) in results {
+        let err = match result {
+            Ok(mut parsed) => {
+                parsed_lockfiles.append(&mut parsed);
+                continue;
+            },
+            Err(err) => err,
+        };
+
+        if !options.keep_going {
+            return depfile_failure(&depfile, err).map(Err);
+        }
+
+        // Report all failures before returning the first one.
+        let failure_code = depfile_failure(&depfile, err).unwrap_or_else(|err| {
+            print_user_failure!("{err:?}");
+            ExitCode::Generic
+        });
+        exit_code.get_or_insert(failure_code);
+    }
+
+    match exit_code {
+        Some(exit_code) => Ok(Err(exit_code)),
+        None => Ok(Ok(parsed_lockfiles)),
+    }
+}
+
+/// Report a dependency file parsing failure.
+///
+/// Failures due to disabled generation or unknown dependency file format are
+/// mapped to their dedicated exit codes.
+fn depfile_failure(depfile: &DepfileConfig, err: ParseError) -> CommandResult {
+    match err {
+        err @ ParseError::ManifestWithoutGeneration(_) => {
+            print_user_failure!("Could not parse manifest: {}", err);
+            Ok(ExitCode::ManifestWithoutGeneration)
+        },
+        err @ ParseError::UnknownManifestFormat(_) => {
+            print_user_failure!("Could not parse manifest: {}", err);
+            Ok(ExitCode::UnknownManifestFormat)
+        },
+        ParseError::GenerationLimitExceeded(err) => {
+            print_user_failure!("Could not generate lockfile for {:?}: {err:?}", depfile.path);
+            Ok(ExitCode::GenerationLimitExceeded)
+        },
+        ParseError::Other(err) => Err(err).with_context(|| {
+            format!(
+                "Could not parse dependency file {:?} as {:?} type",
+                depfile.path.display(),
+                depfile.depfile_type
+            )
+        }),
+    }
+}
+
 /// Parse a dependency file.
+///
+/// Binaries like Java archives can contain multiple nested dependency files,
+/// all other formats always return a single dependency file.
 pub fn parse_depfile(
     path: impl Into<PathBuf>,
     project_root: Option<&PathBuf>,
     depfile_type: Option<&str>,
-    sandbox_generation: bool,
-    generate_lockfiles: bool,
-) -> StdResult<ParsedLockfile, ParseError> {
+    generation: &GenerationOptions,
+) -> StdResult<Vec<ParsedLockfile>, ParseError> {
     // Try and determine dependency file format.
     let path = path.into();
     let (format, path) = match find_depfile_format(&path, depfile_type) {
@@ -177,14 +597,234 @@ pub fn parse_depfile(
 
     let display_path = strip_root_path(&path, project_root)?.display().to_string();
 
-    if cfg!(unix) && sandbox_generation && generate_lockfiles {
-        parse_depfile_sandboxed(path, format, display_path, generate_lockfiles)
+    // Installed environments are only read, so they never need a sandbox.
+    if path.is_dir() {
+        let depfile = phylum_lockfile::parse_installed(&path, display_path, format)?;
+        return Ok(vec![depfile]);
+    }
+
+    // Binaries are identified by their content and only read, like installed
+    // environments.
+    if format.is_none_or(|format| format.binary_parser().is_some()) {
+        let contents = fs::read(&path).map_err(anyhow::Error::from)?;
+        let is_binary = LockfileFormat::iter()
+            .filter_map(|format| format.binary_parser())
+            .any(|parser| parser.is_binary(&contents));
+
+        if format.is_some() || is_binary {
+            return phylum_lockfile::parse_binary(&contents, display_path, format);
+        }
+    }
+
+    let depfile = if cfg!(unix) && generation.sandbox && generation.enabled {
+        parse_depfile_sandboxed(path, format, display_path, generation)?
     } else {
         let contents = fs::read_to_string(&path).map_err(anyhow::Error::from)?;
-        let generation_path = generate_lockfiles.then(|| path.clone());
+        let generation = generation.enabled.then(|| generation.for_manifest(path.clone()));
 
-        phylum_lockfile::parse_depfile(&contents, display_path, format, generation_path)
+        phylum_lockfile::parse_depfile(&contents, display_path, format, generation)?
+    };
+
+    Ok(vec![depfile])
+}
+
+/// Planned handling of a dependency file, reported by `--dry-run`.
+#[derive(Serialize, Debug)]
+pub struct DepfilePlan {
+    /// Dependency file path.
+    pub path: PathBuf,
+    /// Detected dependency file format.
+    pub format: Option<LockfileFormat>,
+    #[serde(flatten)]
+    pub action: PlanAction,
+}
+
+/// Action which would be taken for a dependency file.
+#[derive(Serialize, Debug)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum PlanAction {
+    /// Read the packages of an installed environment.
+    ReadInstalled,
+    /// Read the dependencies embedded in a binary.
+    ParseBinary,
+    /// Parse an existing lockfile.
+    ParseLockfile { lockfile: PathBuf },
+    /// Try parsing the dependency file with all lockfile parsers.
+    DetectFormat,
+    /// Generate a lockfile for a manifest.
+    GenerateLockfile {
+        #[serde(flatten)]
+        explanation: Explanation,
+        /// Generate the lockfile in a scratch copy of the project root.
+        out_of_tree: bool,
+        /// Sandbox exceptions, unless the sandbox is disabled.
+        sandbox_exceptions: Option<Vec<String>>,
+    },
+    /// Manifest requires lockfile generation, but it was disabled.
+    GenerationDisabled,
+    /// Manifest format does not support lockfile generation.
+    GenerationUnsupported,
+}
+
+/// Explain how dependency files would be parsed, without parsing them.
+pub fn explain_depfiles(
+    depfiles: &[DepfileConfig],
+    project_root: Option<&PathBuf>,
+    generation: &GenerationOptions,
+) -> Result<Vec<DepfilePlan>> {
+    depfiles
+        .iter()
+        .map(|depfile| {
+            explain_depfile(&depfile.path, project_root, Some(&depfile.depfile_type), generation)
+                .with_context(|| format!("Could not explain dependency file {:?}", depfile.path))
+        })
+        .collect()
+}
+
+/// Explain how a dependency file would be parsed, without parsing it.
+///
+/// This mirrors the decisions of [`parse_depfile`], but never generates
+/// lockfiles.
+fn explain_depfile(
+    path: &Path,
+    project_root: Option<&PathBuf>,
+    depfile_type: Option<&str>,
+    generation: &GenerationOptions,
+) -> Result<DepfilePlan> {
+    let (format, lockfile) = match find_depfile_format(path, depfile_type) {
+        Some((format, lockfile)) => (Some(format), lockfile),
+        None => (None, Some(path.into())),
+    };
+
+    let plan_path = strip_root_path(path, project_root)?;
+    let plan = |action| DepfilePlan { path: plan_path, format, action };
+
+    if path.is_dir() {
+        return Ok(plan(PlanAction::ReadInstalled));
+    }
+
+    if format.is_none_or(|format| format.binary_parser().is_some()) {
+        let is_binary = format.is_some() || {
+            let contents = fs::read(path)?;
+            LockfileFormat::iter()
+                .filter_map(|format| format.binary_parser())
+                .any(|parser| parser.is_binary(&contents))
+        };
+
+        if is_binary {
+            return Ok(plan(PlanAction::ParseBinary));
+        }
+    }
+
+    let (format, lockfile) = match (format, lockfile) {
+        (Some(format), lockfile) => (format, lockfile),
+        (None, _) => return Ok(plan(PlanAction::DetectFormat)),
+    };
+
+    if let Some(lockfile) = lockfile {
+        return Ok(plan(PlanAction::ParseLockfile { lockfile }));
+    }
+
+    if !generation.enabled {
+        return Ok(plan(PlanAction::GenerationDisabled));
+    }
+
+    let generator = match format.parser().generator() {
+        Some(generator) => generator,
+        None => return Ok(plan(PlanAction::GenerationUnsupported)),
+    };
+
+    let explanation = generator.explain(path, &generation.generator_options())?;
+    let generation = generation.for_format(Some(format));
+    let sandbox_exceptions = generation_sandbox_exceptions(path, &generation)?;
+
+    Ok(plan(PlanAction::GenerateLockfile {
+        explanation,
+        out_of_tree: generation.out_of_tree,
+        sandbox_exceptions,
+    }))
+}
+
+/// Get all exceptions of the lockfile generation sandbox.
+///
+/// Returns `None` if lockfile generation is not sandboxed.
+#[cfg(unix)]
+fn generation_sandbox_exceptions(
+    manifest_path: &Path,
+    generation: &GenerationOptions,
+) -> Result<Option<Vec<String>>> {
+    if !generation.sandbox {
+        return Ok(None);
+    }
+
+    let manifest_path = manifest_path.canonicalize()?;
+    let mut exceptions: Vec<_> = permissions::default_exceptions()
+        .into_iter()
+        .chain(depfile_parsing_exceptions(&manifest_path, generation)?)
+        .filter_map(|exception| describe_exception(&exception))
+        .collect();
+
+    // The proxy only filters clients using it, while the sandbox still allows all
+    // network access.
+    if let Network::Proxied(domains) = generation.network() {
+        exceptions.push(format!("proxy: {}", domains.join(", ")));
+    }
+
+    Ok(Some(exceptions))
+}
+
+/// Get all exceptions of the lockfile generation sandbox.
+///
+/// Returns `None` if lockfile generation is not sandboxed.
+#[cfg(not(unix))]
+fn generation_sandbox_exceptions(
+    _manifest_path: &Path,
+    _generation: &GenerationOptions,
+) -> Result<Option<Vec<String>>> {
+    Ok(None)
+}
+
+/// Describe a sandbox exception.
+///
+/// Returns `None` for exceptions of missing paths, since these are ignored by
+/// the sandbox.
+#[cfg(unix)]
+fn describe_exception(exception: &Exception) -> Option<String> {
+    let (access, path) = match exception {
+        Exception::Read(path) => ("read", path),
+        Exception::WriteAndRead(path) => ("write", path),
+        Exception::ExecuteAndRead(path) => ("execute", path),
+        Exception::Environment(var) => return Some(format!("env: {var}")),
+        Exception::FullEnvironment => return Some("env: *".into()),
+        Exception::Networking => return Some("net".into()),
+    };
+
+    path.exists().then(|| format!("{access}: {}", path.display()))
+}
+
+/// Parse all dependency files in the container images passed with `--image`.
+///
+/// Dependency file paths are prefixed with the image and layer they were found
+/// in, using the format `<IMAGE>@<LAYER>:<PATH>`.
+pub fn parse_images(matches: &ArgMatches) -> Result<Vec<ParsedLockfile>> {
+    let images = match matches.try_get_many::<String>("image") {
+        Ok(Some(images)) => images,
+        _ => return Ok(Vec::new()),
+    };
+
+    let mut depfiles = Vec::new();
+    for image in images {
+        let image_depfiles = phylum_lockfile::scan_image(Path::new(image))
+            .with_context(|| format!("Could not scan container image {image:?}"))?;
+
+        for image_depfile in image_depfiles {
+            let mut depfile = image_depfile.depfile;
+            depfile.path = format!("{image}@{}:{}", image_depfile.layer, depfile.path);
+            depfiles.push(depfile);
+        }
     }
+
+    Ok(depfiles)
 }
 
 #[cfg(unix)]
@@ -192,22 +832,19 @@ fn parse_depfile_sandboxed(
     path: PathBuf,
     format: Option<LockfileFormat>,
     display_path: String,
-    generate_lockfiles: bool,
+    generation: &GenerationOptions,
 ) -> StdResult<ParsedLockfile, ParseError> {
     // Spawn separate process to allow sandboxing lockfile generation.
     let path = path.canonicalize().map_err(anyhow::Error::from)?;
     let lockfile_type = format.map(|format| format.to_string());
-    let mut command = parse_sandboxed_command(
-        &path,
-        &display_path,
-        lockfile_type.as_ref(),
-        generate_lockfiles,
-        false,
-    )?;
+    let generation = generation.for_format(format);
+    let mut command =
+        parse_sandboxed_command(&path, &display_path, lockfile_type.as_ref(), &generation, false)?;
     command.stderr(Stdio::inherit());
     #[allow(clippy::useless_conversion)]
     let mut std_command: StdCommand = command.into();
-    let output = std_command.output().map_err(anyhow::Error::from)?;
+    let output = sandboxed_output(&mut std_command, &generation.cancellation)
+        .map_err(anyhow::Error::from)?;
 
     if !output.status.success() {
         // Forward STDOUT to the user on failure.
@@ -219,6 +856,9 @@ fn parse_depfile_sandboxed(
             Err(ParseError::ManifestWithoutGeneration(display_path))
         } else if output.status.code() == Some(i32::from(&ExitCode::UnknownManifestFormat)) {
             Err(ParseError::UnknownManifestFormat(display_path))
+        } else if output.status.code() == Some(i32::from(&ExitCode::GenerationLimitExceeded)) {
+            let err = anyhow!("Lockfile generation exceeded its limits");
+            Err(ParseError::GenerationLimitExceeded(err))
         } else {
             Err(ParseError::Other(anyhow!("Dependency file parsing failed")))
         }
@@ -229,12 +869,103 @@ fn parse_depfile_sandboxed(
     }
 }
 
+/// Interval for checking if a sandboxed parsing process was cancelled.
+#[cfg(unix)]
+const CANCELLATION_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Time for a cancelled sandboxed parsing process to kill its lockfile
+/// generator, before it is killed itself.
+#[cfg(unix)]
+const CANCELLATION_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// Process groups of all running sandboxed parsing processes.
+#[cfg(unix)]
+static SANDBOXED_GROUPS: Mutex<Vec<libc::pid_t>> = Mutex::new(Vec::new());
+
+/// Send a signal to all running sandboxed parsing processes.
+///
+/// Since these processes run in their own process group, they do not receive
+/// signals sent to the terminal's foreground process group.
+#[cfg(unix)]
+pub fn signal_sandboxed(signal: libc::c_int) {
+    for group in SANDBOXED_GROUPS.lock().unwrap().iter() {
+        // SAFETY: `killpg` has no memory safety requirements.
+        unsafe { libc::killpg(*group, signal) };
+    }
+}
+
+/// Run a sandboxed parsing process, killing it once generation is cancelled.
+///
+/// STDOUT is captured, while STDERR is inherited from the current process.
+#[cfg(unix)]
+fn sandboxed_output(command: &mut StdCommand, cancellation: &Cancellation) -> io::Result<Output> {
+    // Use a separate process group, to also kill the lockfile generator.
+    command.process_group(0).stdout(std::process::Stdio::piped());
+    let mut child = command.spawn()?;
+    let group = child.id() as libc::pid_t;
+    SANDBOXED_GROUPS.lock().unwrap().push(group);
+    let result = wait_sandboxed(&mut child, cancellation);
+    SANDBOXED_GROUPS.lock().unwrap().retain(|running| *running != group);
+    result
+}
+
+/// Wait for a sandboxed parsing process to exit, killing it on cancellation.
+#[cfg(unix)]
+fn wait_sandboxed(child: &mut Child, cancellation: &Cancellation) -> io::Result<Output> {
+    // Read STDOUT in the background, so a full pipe never blocks the process.
+    let mut pipe = child.stdout.take().expect("piped STDOUT");
+    let stdout = thread::spawn(move || {
+        let mut stdout = Vec::new();
+        pipe.read_to_end(&mut stdout).map(|_| stdout)
+    });
+
+    loop {
+        if let Some(status) = child.try_wait()? {
+            let stdout = stdout.join().expect("STDOUT reader panicked")?;
+            return Ok(Output { status, stdout, stderr: Vec::new() });
+        }
+
+        if cancellation.is_cancelled() {
+            kill_sandboxed(child)?;
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "parsing was cancelled"));
+        }
+
+        thread::sleep(CANCELLATION_POLL_INTERVAL);
+    }
+}
+
+/// Kill a cancelled sandboxed parsing process.
+///
+/// The lockfile generator runs in its own process group, so the parsing
+/// process is terminated first, allowing it to kill the generator.
+#[cfg(unix)]
+fn kill_sandboxed(child: &mut Child) -> io::Result<()> {
+    let group = child.id() as libc::pid_t;
+
+    // SAFETY: `killpg` has no memory safety requirements.
+    unsafe { libc::killpg(group, libc::SIGTERM) };
+
+    let start = Instant::now();
+    while start.elapsed() < CANCELLATION_GRACE_PERIOD {
+        if child.try_wait()?.is_some() {
+            return Ok(());
+        }
+        thread::sleep(CANCELLATION_POLL_INTERVAL);
+    }
+
+    // SAFETY: `killpg` has no memory safety requirements.
+    unsafe { libc::killpg(group, libc::SIGKILL) };
+    child.wait()?;
+
+    Ok(())
+}
+
 #[cfg(not(unix))]
 fn parse_depfile_sandboxed(
     _path: PathBuf,
     _format: Option<LockfileFormat>,
     _display_path: String,
-    _generate_lockfiles: bool,
+    _generation: &GenerationOptions,
 ) -> StdResult<ParsedLockfile, ParseError> {
     unreachable!()
 }
@@ -370,7 +1101,7 @@ fn parse_sandboxed_command(
     path: &Path,
     display_path: &str,
     lockfile_type: Option<&String>,
-    generate_lockfile: bool,
+    generation: &GenerationOptions,
     skip_sandbox: bool,
 ) -> Result<Command> {
     let current_exe = env::current_exe()?;
@@ -385,10 +1116,57 @@ fn parse_sandboxed_command(
         command.args(["--type", &lockfile_type.to_string()]);
     }
 
-    if generate_lockfile {
+    if generation.enabled {
         command.arg("--generate-lockfile");
     }
 
+    if generation.out_of_tree {
+        command.arg("--out-of-tree");
+    }
+
+    if let Some(scratch_dir) = &generation.scratch_dir {
+        command.arg("--scratch-dir").arg(scratch_dir);
+    }
+
+    if generation.offline {
+        command.arg("--offline");
+    }
+
+    for domain in &generation.allowed_domains {
+        command.args(["--allow-domain", domain]);
+    }
+
+    let profile = generation.sandbox_profile(None);
+    let profile_args = [
+        ("--allow-read", &profile.read),
+        ("--allow-write", &profile.write),
+        ("--allow-run", &profile.run),
+        ("--allow-env", &profile.env),
+    ];
+    for (arg, values) in profile_args {
+        for value in values {
+            command.args([arg, value]);
+        }
+    }
+
+    let seconds = |duration: Option<Duration>| duration.map(|duration| duration.as_secs());
+    let mebibytes = |bytes: Option<u64>| bytes.map(|bytes| bytes / 1024 / 1024);
+    let limits = generation.limits;
+    let limit_args = [
+        ("--generation-timeout", seconds(limits.timeout)),
+        ("--generation-cpu-limit", seconds(limits.cpu_time)),
+        ("--generation-memory-limit", mebibytes(limits.memory)),
+        ("--generation-file-size-limit", mebibytes(limits.file_size)),
+        ("--generation-process-limit", limits.processes),
+        ("--generation-open-files-limit", limits.open_files),
+        ("--generation-output-limit", mebibytes(limits.output_size)),
+    ];
+    for (arg, limit) in limit_args {
+        if let Some(limit) = limit {
+            command.args([arg, &limit.to_string()]);
+        }
+    }
+
     if skip_sandbox {
         command.arg("--skip-sandbox");
     }
@@ -401,19 +1179,69 @@ fn parse_sandboxed_command(
 /// This sandbox will automatically add all exceptions necessary to generate
 /// lockfiles for any ecosystem.
 #[cfg(unix)]
-fn depfile_parsing_sandbox(canonical_manifest_path: &Path) -> Result<Birdcage> {
+fn depfile_parsing_sandbox(
+    canonical_manifest_path: &Path,
+    generation: &GenerationOptions,
+) -> Result<Birdcage> {
     let mut birdcage = permissions::default_sandbox()?;
 
-    // Allow all networking.
-    birdcage.add_exception(Exception::Networking)?;
+    // Create the journal directory, since exceptions for missing paths are ignored.
+    if let Some(journal_dir) = journal::journal_dir() {
+        fs::create_dir_all(journal_dir)?;
+    }
+
+    for exception in depfile_parsing_exceptions(canonical_manifest_path, generation)? {
+        permissions::add_exception(&mut birdcage, exception)?;
+    }
+
+    Ok(birdcage)
+}
+
+/// Sandbox exceptions required for dependency file parsing.
+///
+/// For out-of-tree generation, the project directory is only readable.
+///
+/// For offline generation, networking is denied and the package caches of all
+/// ecosystems are accessible instead.
+#[cfg(unix)]
+pub(crate) fn depfile_parsing_exceptions(
+    canonical_manifest_path: &Path,
+    generation: &GenerationOptions,
+) -> Result<Vec<Exception>> {
+    let mut exceptions = Vec::new();
+
+    // Allow networking, which is filtered by the proxy for restricted domains.
+    match generation.network() {
+        Network::Unrestricted => exceptions.push(Exception::Networking),
+        Network::Proxied(_) => {
+            exceptions.push(Exception::Networking);
+            exceptions.extend(permissions::proxy_exceptions());
+        },
+        Network::Offline => (),
+    }
 
     // Allow reexecuting phylum.
     let current_exe = env::current_exe()?;
-    permissions::add_exception(&mut birdcage, Exception::ExecuteAndRead(current_exe))?;
+    exceptions.push(Exception::ExecuteAndRead(current_exe));
+
+    // Allow journaling files relocated during lockfile generation.
+    if let Some(journal_dir) = journal::journal_dir() {
+        exceptions.push(Exception::WriteAndRead(journal_dir.into()));
+    }
 
     // Add exception for the manifest's parent directory.
     let project_path = canonical_manifest_path.parent().expect("Invalid manifest path");
-    permissions::add_exception(&mut birdcage, Exception::WriteAndRead(project_path.into()))?;
+    let project_exception = if generation.out_of_tree {
+        Exception::Read(project_path.into())
+    } else {
+        Exception::WriteAndRead(project_path.into())
+    };
+    exceptions.push(project_exception);
+
+    // Allow creating the scratch copies shared with other generations.
+    if let Some(scratch_dir) = generation.scratch_dir.as_ref().filter(|_| generation.out_of_tree) {
+        exceptions.push(Exception::WriteAndRead(scratch_dir.clone()));
+    }
 
     // Add exception for all the executables required for generation.
     let ecosystem_bins = [
@@ -422,91 +1250,148 @@ fn depfile_parsing_sandbox(canonical_manifest_path: &Path) -> Result<Birdcage> {
     ];
     for bin in ecosystem_bins {
         let absolute_path = permissions::resolve_bin_path(bin);
-        permissions::add_exception(&mut birdcage, Exception::ExecuteAndRead(absolute_path))?;
+        exceptions.push(Exception::ExecuteAndRead(absolute_path));
     }
 
     // Allow any executable in common binary directories.
     //
     // Reading binaries shouldn't be an attack vector, but significantly simplifies
     // complex ecosystems (like Python's symlinks).
-    permissions::add_exception(&mut birdcage, Exception::ExecuteAndRead("/usr/bin".into()))?;
-    permissions::add_exception(&mut birdcage, Exception::ExecuteAndRead("/bin".into()))?;
+    exceptions.push(Exception::ExecuteAndRead("/usr/bin".into()));
+    exceptions.push(Exception::ExecuteAndRead("/bin".into()));
 
     // Add paths required by specific ecosystems.
     let home = dirs::home_dir()?;
     // Cargo.
-    permissions::add_exception(&mut birdcage, Exception::ExecuteAndRead(home.join(".rustup")))?;
-    permissions::add_exception(&mut birdcage, Exception::ExecuteAndRead(home.join(".cargo")))?;
-    permissions::add_exception(&mut birdcage, Exception::Read("/etc/passwd".into()))?;
+    exceptions.push(Exception::ExecuteAndRead(home.join(".rustup")));
+    exceptions.push(Exception::ExecuteAndRead(home.join(".cargo")));
+    exceptions.push(Exception::Read("/etc/passwd".into()));
     // Bundle.
-    permissions::add_exception(&mut birdcage, Exception::Read("/dev/urandom".into()))?;
+    exceptions.push(Exception::Read("/dev/urandom".into()));
     // Maven.
-    permissions::add_exception(&mut birdcage, Exception::WriteAndRead(home.join(".m2")))?;
-    permissions::add_exception(&mut birdcage, Exception::WriteAndRead("/var/folders".into()))?;
-    permissions::add_exception(&mut birdcage, Exception::ExecuteAndRead("/opt/maven".into()))?;
-    permissions::add_exception(
-        &mut birdcage,
-        Exception::ExecuteAndRead("/usr/local/Cellar/maven".into()),
-    )?;
-    permissions::add_exception(&mut birdcage, Exception::Read("/usr/local/Cellar/openjdk".into()))?;
-    permissions::add_exception(
-        &mut birdcage,
-        Exception::ExecuteAndRead("/usr/local/opt/openjdk".into()),
-    )?;
-    permissions::add_exception(
-        &mut birdcage,
-        Exception::ExecuteAndRead("/opt/homebrew/Cellar/maven".into()),
-    )?;
-    permissions::add_exception(
-        &mut birdcage,
-        Exception::ExecuteAndRead("/usr/share/maven".into()),
-    )?;
-    permissions::add_exception(
-        &mut birdcage,
-        Exception::Read("/opt/homebrew/Cellar/openjdk".into()),
-    )?;
-    permissions::add_exception(
-        &mut birdcage,
-        Exception::ExecuteAndRead("/opt/homebrew/opt/openjdk".into()),
-    )?;
-    permissions::add_exception(
-        &mut birdcage,
-        Exception::ExecuteAndRead("/etc/alternatives".into()),
-    )?;
-    permissions::add_exception(
-        &mut birdcage,
-        Exception::ExecuteAndRead("/Library/Java/JavaVirtualMachines".into()),
-    )?;
-    permissions::add_exception(
-        &mut birdcage,
-        Exception::ExecuteAndRead(home.join("Library/Java/JavaVirtualMachines")),
-    )?;
-    permissions::add_exception(&mut birdcage, Exception::ExecuteAndRead("/etc/maven".into()))?;
+    exceptions.push(Exception::WriteAndRead(home.join(".m2")));
+    exceptions.push(Exception::WriteAndRead("/var/folders".into()));
+    exceptions.push(Exception::ExecuteAndRead("/opt/maven".into()));
+    exceptions.push(Exception::ExecuteAndRead("/usr/local/Cellar/maven".into()));
+    exceptions.push(Exception::Read("/usr/local/Cellar/openjdk".into()));
+    exceptions.push(Exception::ExecuteAndRead("/usr/local/opt/openjdk".into()));
+    exceptions.push(Exception::ExecuteAndRead("/opt/homebrew/Cellar/maven".into()));
+    exceptions.push(Exception::ExecuteAndRead("/usr/share/maven".into()));
+    exceptions.push(Exception::Read("/opt/homebrew/Cellar/openjdk".into()));
+    exceptions.push(Exception::ExecuteAndRead("/opt/homebrew/opt/openjdk".into()));
+    exceptions.push(Exception::ExecuteAndRead("/etc/alternatives".into()));
+    exceptions.push(Exception::ExecuteAndRead("/Library/Java/JavaVirtualMachines".into()));
+    exceptions.push(Exception::ExecuteAndRead(home.join("Library/Java/JavaVirtualMachines")));
+    exceptions.push(Exception::ExecuteAndRead("/etc/maven".into()));
     for jdk_path in jdk_paths()? {
-        permissions::add_exception(&mut birdcage, Exception::Read(jdk_path))?;
+        exceptions.push(Exception::Read(jdk_path));
     }
     // Gradle.
-    permissions::add_exception(&mut birdcage, Exception::WriteAndRead(home.join(".gradle")))?;
-    permissions::add_exception(&mut birdcage, Exception::Read("/opt/gradle".into()))?;
-    permissions::add_exception(&mut birdcage, Exception::ExecuteAndRead("/usr/share/java".into()))?;
-    permissions::add_exception(&mut birdcage, Exception::Read("/usr/local/Cellar/gradle".into()))?;
-    permissions::add_exception(
-        &mut birdcage,
-        Exception::Read("/opt/homebrew/Cellar/gradle".into()),
-    )?;
+    exceptions.push(Exception::WriteAndRead(home.join(".gradle")));
+    exceptions.push(Exception::Read("/opt/gradle".into()));
+    exceptions.push(Exception::ExecuteAndRead("/usr/share/java".into()));
+    exceptions.push(Exception::Read("/usr/local/Cellar/gradle".into()));
+    exceptions.push(Exception::Read("/opt/homebrew/Cellar/gradle".into()));
     // Pnpm.
-    permissions::add_exception(&mut birdcage, Exception::WriteAndRead("/tmp".into()))?;
+    exceptions.push(Exception::WriteAndRead("/tmp".into()));
     // Yarn.
-    permissions::add_exception(&mut birdcage, Exception::Read(home.join("./yarn")))?;
+    exceptions.push(Exception::Read(home.join("./yarn")));
     // Python.
-    permissions::add_exception(
-        &mut birdcage,
-        Exception::ExecuteAndRead("/usr/share/pyenv".into()),
-    )?;
-    permissions::add_exception(&mut birdcage, Exception::ExecuteAndRead(home.join(".pyenv")))?;
-    permissions::add_exception(&mut birdcage, Exception::Read(home.join(".local/lib")))?;
+    exceptions.push(Exception::ExecuteAndRead("/usr/share/pyenv".into()));
+    exceptions.push(Exception::ExecuteAndRead(home.join(".pyenv")));
+    exceptions.push(Exception::Read(home.join(".local/lib")));
 
-    Ok(birdcage)
+    if generation.offline {
+        exceptions.extend(package_cache_exceptions(&home));
+    }
+
+    // Add exceptions of the user's sandbox profiles.
+    //
+    // Only profiles for all dependency file types are applied here, the others
+    // must be selected with `GenerationOptions::for_format`.
+    let profile = generation.sandbox_profile(None);
+    exceptions.extend(profile.read.iter().map(|path| Exception::Read(path.into())));
+    exceptions.extend(profile.write.iter().map(|path| Exception::WriteAndRead(path.into())));
+    for bin in &profile.run {
+        exceptions.push(Exception::ExecuteAndRead(permissions::resolve_bin_path(bin)));
+    }
+    for var in &profile.env {
+        if var == "*" {
+            exceptions.push(Exception::FullEnvironment);
+        } else {
+            exceptions.push(Exception::Environment(var.clone()));
+        }
+    }
+
+    Ok(exceptions)
+}
+
+/// Sandbox exceptions for the package caches used by offline generation.
+///
+/// Caches stay writable, since most tools update their cache index or write
+/// logs even when all packages are cached already.
+#[cfg(unix)]
+pub(crate) fn package_cache_exceptions(home: &Path) -> Vec<Exception> {
+    let mut exceptions = Vec::new();
+
+    // Allow tools to locate their caches.
+    let cache_vars = [
+        "HOME",
+        "XDG_CACHE_HOME",
+        "XDG_DATA_HOME",
+        "npm_config_cache",
+        "GOPATH",
+        "GOMODCACHE",
+        "PIP_CACHE_DIR",
+        "PIP_FIND_LINKS",
+        "NUGET_PACKAGES",
+        "GEM_HOME",
+        "GEM_PATH",
+    ];
+    for var in cache_vars {
+        exceptions.push(Exception::Environment(var.into()));
+    }
+
+    // Caches with custom locations.
+    for var in ["GOMODCACHE", "NUGET_PACKAGES", "PIP_CACHE_DIR", "npm_config_cache"] {
+        if let Some(path) = env::var_os(var) {
+            exceptions.push(Exception::WriteAndRead(path.into()));
+        }
+    }
+    if let Some(gopath) = env::var_os("GOPATH") {
+        exceptions.push(Exception::WriteAndRead(PathBuf::from(gopath).join("pkg/mod")));
+    }
+    if let Ok(find_links) = env::var("PIP_FIND_LINKS") {
+        let paths = find_links.split_whitespace().map(PathBuf::from);
+        exceptions.extend(paths.filter(|path| path.is_absolute()).map(Exception::Read));
+    }
+
+    // Npm.
+    exceptions.push(Exception::WriteAndRead(home.join(".npm")));
+    // Pnpm.
+    exceptions.push(Exception::WriteAndRead(home.join(".local/share/pnpm")));
+    exceptions.push(Exception::WriteAndRead(home.join(".cache/pnpm")));
+    exceptions.push(Exception::WriteAndRead(home.join("Library/pnpm")));
+    // Yarn.
+    exceptions.push(Exception::WriteAndRead(home.join(".yarn/berry")));
+    // Python.
+    exceptions.push(Exception::WriteAndRead(home.join(".cache/pip")));
+    exceptions.push(Exception::WriteAndRead(home.join(".cache/pipenv")));
+    exceptions.push(Exception::WriteAndRead(home.join(".cache/pypoetry")));
+    exceptions.push(Exception::WriteAndRead(home.join("Library/Caches/pip")));
+    exceptions.push(Exception::WriteAndRead(home.join("Library/Caches/pipenv")));
+    exceptions.push(Exception::WriteAndRead(home.join("Library/Caches/pypoetry")));
+    // Go.
+    exceptions.push(Exception::WriteAndRead(home.join("go/pkg/mod")));
+    // Bundler.
+    exceptions.push(Exception::Read(home.join(".gem")));
+    exceptions.push(Exception::Read(home.join(".local/share/gem")));
+    exceptions.push(Exception::WriteAndRead(home.join(".bundle")));
+    // .NET.
+    exceptions.push(Exception::WriteAndRead(home.join(".nuget/packages")));
+    exceptions.push(Exception::WriteAndRead(home.join(".local/share/NuGet")));
+
+    exceptions
 }
 
 /// Get all JDK paths in `/etc`.
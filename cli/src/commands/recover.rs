@@ -0,0 +1,96 @@
+//! `phylum recover` command for restoring files after interrupted lockfile
+//! generation.
+
+#[cfg(unix)]
+use std::{process, thread};
+
+use anyhow::Result;
+use clap::ArgMatches;
+use phylum_lockfile::generator::journal;
+use phylum_lockfile::generator::limits::Cancellation;
+#[cfg(unix)]
+use signal_hook::consts::{SIGINT, SIGTERM};
+#[cfg(unix)]
+use signal_hook::iterator::Signals;
+
+#[cfg(unix)]
+use crate::commands::parse;
+use crate::commands::{CommandResult, ExitCode};
+use crate::{dirs, print_user_success, print_user_warning};
+
+/// Handle the `phylum recover` subcommand.
+pub fn handle_recover(matches: &ArgMatches) -> CommandResult {
+    let restored =
+        if matches.get_flag("force") { journal::recover_all()? } else { journal::recover()? };
+
+    if restored.is_empty() {
+        print_user_success!("No files left behind by lockfile generation");
+    }
+
+    for relocation in restored {
+        print_user_success!("Restored {:?}", relocation.original_path);
+    }
+
+    Ok(ExitCode::Ok)
+}
+
+/// Enable journaling of files relocated during lockfile generation.
+///
+/// Journal entries are stored in the user's state directory.
+pub fn init_journal() -> Result<()> {
+    journal::set_journal_dir(dirs::state_dir()?.join("phylum/generation_journal"));
+    Ok(())
+}
+
+/// Prepare the current process for lockfile generation.
+///
+/// This restores all files left behind by previously interrupted lockfile
+/// generation and ensures files are restored when the process is terminated.
+pub fn prepare_generation(cancellation: Cancellation) -> Result<()> {
+    restore_abandoned();
+    restore_on_signal(cancellation)
+}
+
+/// Restore all files left behind by lockfile generation processes which are no
+/// longer running.
+pub fn restore_abandoned() {
+    match journal::recover() {
+        Ok(restored) => {
+            for relocation in restored {
+                print_user_warning!(
+                    "Restored {:?} after interrupted lockfile generation",
+                    relocation.original_path
+                );
+            }
+        },
+        Err(err) => print_user_warning!("Could not restore interrupted lockfile generation: {err}"),
+    }
+}
+
+/// Restore relocated files before exiting on SIGINT or SIGTERM.
+///
+/// Lockfile generators run in their own process group, so they are killed
+/// through their `cancellation` first.
+#[cfg(unix)]
+pub fn restore_on_signal(cancellation: Cancellation) -> Result<()> {
+    let mut signals = Signals::new([SIGINT, SIGTERM])?;
+
+    thread::spawn(move || {
+        if let Some(signal) = signals.forever().next() {
+            cancellation.cancel();
+
+            // We're exiting either way, so there's nothing to do on failure.
+            let _ = journal::restore_process();
+            parse::signal_sandboxed(signal);
+            process::exit(128 + signal);
+        }
+    });
+
+    Ok(())
+}
+
+/// Restore relocated files before exiting on SIGINT or SIGTERM.
+#[cfg(not(unix))]
+pub fn restore_on_signal(_cancellation: Cancellation) -> Result<()> {
+    Ok(())
+}
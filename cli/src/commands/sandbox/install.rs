@@ -0,0 +1,438 @@
+//! Sandboxed package installation.
+
+use std::collections::BTreeSet;
+use std::ffi::OsString;
+use std::fmt::{self, Display, Formatter};
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use std::{env, fs};
+
+use anyhow::{anyhow, Result};
+use birdcage::process::Command;
+use birdcage::{Exception, Sandbox};
+use clap::ArgMatches;
+use phylum_lockfile::generator::limits::{Cancellation, Limits};
+use phylum_lockfile::{DepFiles, DiscoveryOptions, LockfileFormat};
+
+use super::{command_exit_code, limits_command, sandbox_limits};
+use crate::commands::parse::{self, GenerationOptions, Network};
+use crate::commands::{CommandResult, ExitCode};
+use crate::config::{self, Config};
+use crate::proxy::FilteringProxy;
+use crate::{dirs, permissions, print_user_failure};
+
+/// Entry point for the `sandbox install` subcommand.
+pub fn handle_install(matches: &ArgMatches, config: &Config) -> CommandResult {
+    let project_dir = env::current_dir()?;
+    let format = matches.get_one::<String>("type").map(|t| t.parse::<LockfileFormat>().unwrap());
+    let (installer, depfiles) = detect_installer(&project_dir, format)?;
+
+    // Filter proxy-aware clients to the registries of the package manager.
+    let mut allowed_domains: Vec<String> =
+        installer.registries().iter().map(|domain| domain.to_string()).collect();
+    for domain in matches.get_many::<String>("allow-domain").unwrap_or_default() {
+        if !allowed_domains.contains(domain) {
+            allowed_domains.push(domain.clone());
+        }
+    }
+
+    // Apply the lockfile generation sandbox profiles of the dependency file type.
+    let project = phylum_project::get_current_project();
+    let profiles = config::sandbox_profiles(config, project.as_ref())?;
+    let generation = GenerationOptions {
+        enabled: true,
+        sandbox: true,
+        out_of_tree: false,
+        scratch_dir: None,
+        offline: false,
+        allowed_domains,
+        sandbox_profiles: profiles.into_values().collect(),
+        limits: Limits::default(),
+        cancellation: Cancellation::default(),
+    };
+    let generation = generation.for_format(Some(depfiles[0].1));
+
+    let proxy = match generation.network() {
+        Network::Proxied(domains) => Some(FilteringProxy::start(domains)?),
+        Network::Unrestricted | Network::Offline => None,
+    };
+
+    // Setup sandbox permitting writes to the project and package caches.
+    let home = dirs::home_dir()?;
+    let manifest_path = depfiles[0].0.canonicalize()?;
+    let installer_exceptions = installer.exceptions(&home);
+
+    // Create missing package manager directories, since exceptions for missing
+    // paths are ignored.
+    for exception in &installer_exceptions {
+        if let Exception::WriteAndRead(path) = exception {
+            if path.starts_with(&home) {
+                let _ = fs::create_dir_all(path);
+            }
+        }
+    }
+
+    let mut sandbox = permissions::default_sandbox()?;
+    let exceptions = parse::depfile_parsing_exceptions(&manifest_path, &generation)?
+        .into_iter()
+        .chain(parse::package_cache_exceptions(&home))
+        .chain(installer_exceptions);
+    for exception in exceptions {
+        permissions::add_exception(&mut sandbox, exception)?;
+    }
+
+    // Build install command, passing through additional arguments.
+    let paths: Vec<_> = depfiles.iter().map(|(path, _)| path.as_path()).collect();
+    let mut argv = installer.command(&project_dir, &paths);
+    argv.extend(matches.get_many::<String>("args").unwrap_or_default().map(OsString::from));
+
+    let limits = sandbox_limits(matches);
+    let limited = !limits.is_unlimited();
+    if limited {
+        argv = limits_command(&mut sandbox, &limits, argv)?;
+    }
+
+    log::info!("Installing {installer} dependencies inside sandbox");
+
+    let mut command = Command::new(&argv[0]);
+    command.args(&argv[1..]);
+    for (key, value) in proxy.iter().flat_map(FilteringProxy::environment) {
+        command.env(key, value);
+    }
+    let mut child = sandbox.spawn(command)?;
+
+    let status = match child.wait() {
+        Ok(status) => status,
+        Err(err) => {
+            eprintln!("Process {:?} failed to start: {err}", installer.program());
+            return Ok(ExitCode::SandboxStart);
+        },
+    };
+
+    install_result(installer, status, limited)
+}
+
+/// Report the result of a sandboxed installation.
+fn install_result(installer: Installer, status: ExitStatus, limited: bool) -> CommandResult {
+    if status.success() {
+        return Ok(ExitCode::Ok);
+    }
+
+    print_user_failure!("Sandboxed {installer} install failed");
+    print_user_failure!(
+        "This could mean one of your packages attempted to access a restricted resource"
+    );
+
+    match (status.code(), status.signal()) {
+        (Some(code), _) if limited => Ok(ExitCode::Custom(code)),
+        (Some(code), _) => Ok(command_exit_code(code)),
+        (None, Some(signal)) => Err(anyhow!("Sandbox process failed with signal {signal}")),
+        (None, None) => unreachable!("Sandbox process terminated without exit code or signal"),
+    }
+}
+
+/// Find the package manager and dependency files of a project.
+///
+/// Lockfiles take precedence over manifests, since they determine the package
+/// manager in ecosystems sharing the same manifest.
+fn detect_installer(
+    project_dir: &Path,
+    format: Option<LockfileFormat>,
+) -> Result<(Installer, Vec<(PathBuf, LockfileFormat)>)> {
+    let options = DiscoveryOptions {
+        max_depth: Some(1),
+        formats: format.into_iter().collect(),
+        ..DiscoveryOptions::default()
+    };
+    let depfiles = DepFiles::find_with_options(project_dir, &options)?;
+
+    let lockfiles: Vec<_> = depfiles
+        .lockfiles
+        .into_iter()
+        .filter_map(|(path, format)| Some((Installer::from_lockfile(format)?, path, format)))
+        .collect();
+    let candidates = if lockfiles.is_empty() {
+        depfiles
+            .manifests
+            .into_iter()
+            .filter_map(|(path, format)| Some((Installer::from_manifest(format)?, path, format)))
+            .collect()
+    } else {
+        lockfiles
+    };
+
+    let installers: BTreeSet<_> = candidates.iter().map(|(installer, ..)| *installer).collect();
+    let mut installers = installers.into_iter();
+    let installer = match (installers.next(), installers.next()) {
+        (Some(installer), None) => installer,
+        (Some(_), Some(_)) => {
+            let names: BTreeSet<_> =
+                candidates.iter().map(|(_, _, format)| format.name()).collect();
+            let names = names.into_iter().collect::<Vec<_>>().join(", ");
+            return Err(anyhow!(
+                "Found dependency files of multiple types ({names}), select one with `--type`"
+            ));
+        },
+        (None, _) => {
+            return Err(anyhow!(
+                "No supported dependency files found in {}",
+                project_dir.display()
+            ));
+        },
+    };
+
+    let mut depfiles: Vec<_> = candidates
+        .into_iter()
+        .filter(|(candidate, ..)| *candidate == installer)
+        .map(|(_, path, format)| (path, format))
+        .collect();
+    depfiles.sort();
+
+    Ok((installer, depfiles))
+}
+
+/// Package manager installing the dependencies of a project.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Installer {
+    Npm,
+    Yarn,
+    Pnpm,
+    Pip,
+    Pipenv,
+    Poetry,
+    Bundler,
+    Maven,
+    Go,
+    Cargo,
+    Dotnet,
+}
+
+impl Installer {
+    /// Get the package manager for a lockfile type.
+    fn from_lockfile(format: LockfileFormat) -> Option<Self> {
+        match format {
+            LockfileFormat::Npm => Some(Self::Npm),
+            LockfileFormat::Yarn => Some(Self::Yarn),
+            LockfileFormat::Pnpm => Some(Self::Pnpm),
+            LockfileFormat::Pip => Some(Self::Pip),
+            LockfileFormat::Pipenv => Some(Self::Pipenv),
+            LockfileFormat::Poetry => Some(Self::Poetry),
+            LockfileFormat::Gem => Some(Self::Bundler),
+            LockfileFormat::Maven => Some(Self::Maven),
+            LockfileFormat::GoMod | LockfileFormat::Go => Some(Self::Go),
+            LockfileFormat::Cargo => Some(Self::Cargo),
+            LockfileFormat::Msbuild | LockfileFormat::NugetLock => Some(Self::Dotnet),
+            _ => None,
+        }
+    }
+
+    /// Get the package manager for a manifest type.
+    ///
+    /// Without a lockfile, `package.json` is always installed with npm.
+    fn from_manifest(format: LockfileFormat) -> Option<Self> {
+        match format {
+            LockfileFormat::Yarn | LockfileFormat::Pnpm => Some(Self::Npm),
+            format => Self::from_lockfile(format),
+        }
+    }
+
+    /// Executable of the package manager.
+    fn program(&self) -> &'static str {
+        match self {
+            Self::Npm => "npm",
+            Self::Yarn => "yarn",
+            Self::Pnpm => "pnpm",
+            Self::Pip => "python3",
+            Self::Pipenv => "pipenv",
+            Self::Poetry => "poetry",
+            Self::Bundler => "bundle",
+            Self::Maven => "mvn",
+            Self::Go => "go",
+            Self::Cargo => "cargo",
+            Self::Dotnet => "dotnet",
+        }
+    }
+
+    /// Get the command installing the dependencies.
+    fn command(&self, project_dir: &Path, depfiles: &[&Path]) -> Vec<OsString> {
+        let args: &[&str] = match self {
+            Self::Npm | Self::Yarn | Self::Pnpm | Self::Pipenv | Self::Poetry | Self::Bundler => {
+                &["install"]
+            },
+            Self::Pip => &["-m", "pip", "install"],
+            Self::Maven => &["dependency:resolve"],
+            Self::Go => &["mod", "download"],
+            Self::Cargo => &["fetch"],
+            Self::Dotnet => &["restore"],
+        };
+
+        let mut command: Vec<OsString> =
+            [self.program()].iter().chain(args).map(|arg| arg.into()).collect();
+
+        // Pip requires explicit requirements files.
+        if *self == Self::Pip {
+            let requirements = depfiles.iter().copied().filter(|path| {
+                path.extension().is_some_and(|extension| extension == "txt" || extension == "in")
+            });
+            let mut has_requirements = false;
+            for path in requirements {
+                let path = path.strip_prefix(project_dir).unwrap_or(path);
+                command.extend(["-r".into(), path.into()]);
+                has_requirements = true;
+            }
+
+            // Install the project itself for `setup.py` and `pyproject.toml`.
+            if !has_requirements {
+                command.push(".".into());
+            }
+        }
+
+        command
+    }
+
+    /// Domains of the default package registries.
+    fn registries(&self) -> &'static [&'static str] {
+        match self {
+            Self::Npm | Self::Pnpm => &["registry.npmjs.org"],
+            Self::Yarn => &["registry.yarnpkg.com", "registry.npmjs.org", "repo.yarnpkg.com"],
+            Self::Pip | Self::Pipenv | Self::Poetry => &["pypi.org", "files.pythonhosted.org"],
+            Self::Bundler => &["rubygems.org", "index.rubygems.org"],
+            Self::Maven => &["repo.maven.apache.org", "repo1.maven.org"],
+            Self::Go => &["proxy.golang.org", "sum.golang.org"],
+            Self::Cargo => &["index.crates.io", "static.crates.io", "crates.io"],
+            Self::Dotnet => &["api.nuget.org"],
+        }
+    }
+
+    /// Additional sandbox exceptions for installing packages.
+    ///
+    /// Package caches which are also used for lockfile generation are always
+    /// writable.
+    fn exceptions(&self, home: &Path) -> Vec<Exception> {
+        let mut exceptions = Vec::new();
+
+        match self {
+            Self::Yarn => {
+                exceptions.push(Exception::WriteAndRead(home.join(".cache/yarn")));
+                exceptions.push(Exception::WriteAndRead(home.join("Library/Caches/Yarn")));
+            },
+            // Packages are installed into the active virtual environment.
+            Self::Pip => {
+                exceptions.push(Exception::Environment("VIRTUAL_ENV".into()));
+                if let Some(venv) = env::var_os("VIRTUAL_ENV") {
+                    exceptions.push(Exception::WriteAndRead(venv.into()));
+                }
+            },
+            Self::Pipenv => {
+                exceptions.push(Exception::Environment("WORKON_HOME".into()));
+                let workon_home = env::var_os("WORKON_HOME").map(PathBuf::from);
+                let workon_home =
+                    workon_home.unwrap_or_else(|| home.join(".local/share/virtualenvs"));
+                exceptions.push(Exception::WriteAndRead(workon_home));
+            },
+            Self::Bundler => {
+                if let Some(gem_home) = env::var_os("GEM_HOME") {
+                    exceptions.push(Exception::WriteAndRead(gem_home.into()));
+                }
+                exceptions.push(Exception::WriteAndRead(home.join(".gem")));
+                exceptions.push(Exception::WriteAndRead(home.join(".local/share/gem")));
+            },
+            Self::Cargo => {
+                exceptions.push(Exception::Environment("CARGO_HOME".into()));
+                let cargo_home = env::var_os("CARGO_HOME").map(PathBuf::from);
+                let cargo_home = cargo_home.unwrap_or_else(|| home.join(".cargo"));
+                exceptions.push(Exception::WriteAndRead(cargo_home.join("registry")));
+                exceptions.push(Exception::WriteAndRead(cargo_home.join("git")));
+            },
+            Self::Npm | Self::Pnpm | Self::Poetry | Self::Maven | Self::Go | Self::Dotnet => (),
+        }
+
+        exceptions
+    }
+}
+
+impl Display for Installer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Npm => write!(f, "npm"),
+            Self::Yarn => write!(f, "yarn"),
+            Self::Pnpm => write!(f, "pnpm"),
+            Self::Pip => write!(f, "pip"),
+            Self::Pipenv => write!(f, "pipenv"),
+            Self::Poetry => write!(f, "poetry"),
+            Self::Bundler => write!(f, "bundler"),
+            Self::Maven => write!(f, "maven"),
+            Self::Go => write!(f, "go"),
+            Self::Cargo => write!(f, "cargo"),
+            Self::Dotnet => write!(f, "dotnet"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Create a project with empty dependency files.
+    fn project(files: &[&str]) -> tempfile::TempDir {
+        let tempdir = tempfile::tempdir().unwrap();
+        for file in files {
+            fs::write(tempdir.path().join(file), "").unwrap();
+        }
+        tempdir
+    }
+
+    #[test]
+    fn detect_lockfile_installer() {
+        let project = project(&["package.json", "yarn.lock"]);
+
+        let (installer, depfiles) = detect_installer(project.path(), None).unwrap();
+
+        assert_eq!(installer, Installer::Yarn);
+        assert_eq!(depfiles, [(project.path().join("yarn.lock"), LockfileFormat::Yarn)]);
+    }
+
+    #[test]
+    fn detect_manifest_installer() {
+        let project = project(&["package.json"]);
+
+        let (installer, _) = detect_installer(project.path(), None).unwrap();
+
+        assert_eq!(installer, Installer::Npm);
+    }
+
+    #[test]
+    fn detect_multiple_installers() {
+        let project = project(&["package-lock.json", "Cargo.lock"]);
+
+        assert!(detect_installer(project.path(), None).is_err());
+
+        let format = Some(LockfileFormat::Cargo);
+        let (installer, _) = detect_installer(project.path(), format).unwrap();
+        assert_eq!(installer, Installer::Cargo);
+    }
+
+    #[test]
+    fn pip_requirements_command() {
+        let project_dir = Path::new("/project");
+        let depfiles =
+            [Path::new("/project/requirements.txt"), Path::new("/project/requirements-dev.txt")];
+
+        let command = Installer::Pip.command(project_dir, &depfiles);
+
+        assert_eq!(command, [
+            "python3",
+            "-m",
+            "pip",
+            "install",
+            "-r",
+            "requirements.txt",
+            "-r",
+            "requirements-dev.txt"
+        ]);
+
+        let command = Installer::Pip.command(project_dir, &[Path::new("/project/pyproject.toml")]);
+        assert_eq!(command, ["python3", "-m", "pip", "install", "."]);
+    }
+}
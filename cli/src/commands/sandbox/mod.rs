@@ -0,0 +1,528 @@
+//! Sandbox subcommand handling.
+
+#[cfg(target_os = "linux")]
+use std::collections::BTreeSet;
+use std::ffi::OsString;
+use std::os::unix::process::ExitStatusExt;
+#[cfg(target_os = "linux")]
+use std::path::{self, Path, PathBuf};
+use std::{env, iter, process};
+#[cfg(target_os = "linux")]
+use std::{fs, io};
+
+use anyhow::{anyhow, Result};
+use birdcage::process::Command;
+use birdcage::{Birdcage, Exception, Sandbox};
+use clap::ArgMatches;
+use phylum_lockfile::generator::limits::{self, Limited, Limits, Resource};
+#[cfg(target_os = "linux")]
+use serde::Serialize;
+#[cfg(target_os = "linux")]
+use tempfile::NamedTempFile;
+
+use crate::commands::{CommandResult, ExitCode};
+use crate::config::Config;
+#[cfg(target_os = "linux")]
+use crate::dirs;
+use crate::permissions::{self, ResourceLimits};
+#[cfg(target_os = "linux")]
+use crate::permissions::{Permission, Permissions};
+use crate::proxy::FilteringProxy;
+#[cfg(target_os = "linux")]
+use crate::tracer::{self, DeniedAccesses};
+
+mod install;
+
+/// Entry point for the `sandbox` subcommand.
+pub async fn handle_sandbox(matches: &ArgMatches, config: &Config) -> CommandResult {
+    if let Some(("install", matches)) = matches.subcommand() {
+        return install::handle_install(matches, config);
+    }
+
+    let learn = matches.get_flag("learn");
+    if learn && !cfg!(target_os = "linux") {
+        return Err(anyhow!("Sandbox learning mode is only supported on Linux"));
+    }
+
+    // Point proxy-aware clients at a filtering proxy when domains are listed.
+    //
+    // In learning mode, the proxy is also used to record all denied domains.
+    let domains: Vec<String> =
+        matches.get_many("allow-domain").unwrap_or_default().cloned().collect();
+    let proxy = if matches.get_flag("allow-net") || (domains.is_empty() && !learn) {
+        None
+    } else {
+        Some(FilteringProxy::start(&domains)?)
+    };
+
+    // Setup sandbox.
+    let mut sandbox = sandbox_config(matches, proxy.is_some())?;
+
+    // Start sandboxed subprocess.
+    let cmd = matches.get_one::<String>("cmd").unwrap();
+    let args = matches.get_many::<String>("args").unwrap_or_default();
+    let mut argv: Vec<OsString> = iter::once(cmd).chain(args).map(OsString::from).collect();
+
+    // Resource limits are applied by a wrapper process inside the sandbox, which
+    // reports exceeded limits through its exit code.
+    let limits = sandbox_limits(matches);
+    let limited = !limits.is_unlimited();
+    if limited {
+        argv = limits_command(&mut sandbox, &limits, argv)?;
+    }
+
+    // In learning mode, the command is run by the syscall tracer, which reports
+    // all denied accesses through a file outside of the sandbox.
+    #[cfg(target_os = "linux")]
+    let report = if learn { Some(NamedTempFile::new()?) } else { None };
+    #[cfg(target_os = "linux")]
+    if let Some(report) = &report {
+        argv = trace_command(&mut sandbox, report.path(), argv)?;
+    }
+
+    let mut command = Command::new(&argv[0]);
+    command.args(&argv[1..]);
+    for (key, value) in proxy.iter().flat_map(FilteringProxy::environment) {
+        command.env(key, value);
+    }
+    let mut child = sandbox.spawn(command)?;
+
+    // Wait for subprocess to complete.
+    let status = match child.wait() {
+        Ok(status) => status,
+        Err(err) => {
+            eprintln!("Process {cmd:?} failed to start: {err}");
+            return Ok(ExitCode::SandboxStart);
+        },
+    };
+
+    #[cfg(target_os = "linux")]
+    if let Some(report) = report {
+        print_learned_permissions(matches, report.path(), proxy.as_ref())?;
+    }
+
+    if let Some(code) = status.code() {
+        // The limits wrapper already remapped the command's exit code.
+        if limited {
+            return Ok(ExitCode::Custom(code));
+        }
+
+        Ok(command_exit_code(code))
+    } else if let Some(signal) = status.signal() {
+        Err(anyhow!("Sandbox process failed with signal {signal}"))
+    } else {
+        unreachable!("Sandbox process terminated without exit code or signal");
+    }
+}
+
+/// Entry point for the `sandbox-limits` subcommand.
+///
+/// This runs inside the sandbox, applying the resource limits to the command.
+pub fn handle_sandbox_limits(matches: &ArgMatches) -> CommandResult {
+    let cmd = matches.get_one::<String>("cmd").unwrap();
+    let args = matches.get_many::<String>("args").unwrap_or_default();
+    let limits = Limits::from(&sandbox_limits(matches));
+
+    let mut command = process::Command::new(cmd);
+    command.args(args);
+    let output = match limits::status(&mut command, &limits) {
+        Ok(Limited::Completed(output) | Limited::TimedOut(output)) => output,
+        Ok(Limited::OutputExceeded(_)) => {
+            eprintln!("Process {cmd:?} exceeded its {} limit", Resource::OutputSize);
+            return Ok(ExitCode::SandboxOutputLimit);
+        },
+        Err(err) => {
+            eprintln!("Process {cmd:?} failed to start: {err}");
+            return Ok(ExitCode::SandboxStart);
+        },
+    };
+
+    if let Some(resource) = limits::exceeded_resource(&output, &limits) {
+        eprintln!("Process {cmd:?} exceeded its {resource} limit");
+        return Ok(match resource {
+            Resource::CpuTime => ExitCode::SandboxCpuTimeLimit,
+            Resource::Memory => ExitCode::SandboxMemoryLimit,
+            Resource::FileSize => ExitCode::SandboxFileSizeLimit,
+            Resource::OutputSize => ExitCode::SandboxOutputLimit,
+            Resource::Processes => ExitCode::SandboxProcessLimit,
+            Resource::OpenFiles => ExitCode::SandboxOpenFilesLimit,
+        });
+    }
+
+    match (output.status.code(), output.status.signal()) {
+        (Some(code), _) => Ok(command_exit_code(code)),
+        (None, Some(signal)) => Err(anyhow!("Sandbox process failed with signal {signal}")),
+        (None, None) => unreachable!("Sandbox process terminated without exit code or signal"),
+    }
+}
+
+/// Get the resource limits from the CLI arguments.
+fn sandbox_limits(matches: &ArgMatches) -> ResourceLimits {
+    let limit = |id| matches.get_one::<u64>(id).copied();
+
+    ResourceLimits {
+        processes: limit("max-processes"),
+        memory: limit("max-memory"),
+        cpu_time: limit("max-cpu-time"),
+        open_files: limit("max-open-files"),
+        file_size: limit("max-file-size"),
+        output: limit("max-output"),
+    }
+}
+
+/// Get the exit code reported for a sandboxed command's exit code.
+///
+/// Exit codes matching our sandbox failure indicators are remapped, to ensure
+/// we can detect the failures reliably.
+fn command_exit_code(code: i32) -> ExitCode {
+    let reserved = [
+        ExitCode::SandboxStart,
+        ExitCode::SandboxCpuTimeLimit,
+        ExitCode::SandboxMemoryLimit,
+        ExitCode::SandboxFileSizeLimit,
+        ExitCode::SandboxOutputLimit,
+        ExitCode::SandboxProcessLimit,
+        ExitCode::SandboxOpenFilesLimit,
+    ];
+
+    if reserved.iter().any(|reserved| i32::from(reserved) == code) {
+        ExitCode::SandboxStartCollision
+    } else {
+        ExitCode::Custom(code)
+    }
+}
+
+/// Create the command running `argv` through the resource limits wrapper.
+fn limits_command(
+    birdcage: &mut Birdcage,
+    limits: &ResourceLimits,
+    argv: Vec<OsString>,
+) -> Result<Vec<OsString>> {
+    let current_exe = env::current_exe()?;
+    birdcage.add_exception(Exception::ExecuteAndRead(current_exe.clone()))?;
+
+    let mut command = vec![current_exe.into(), "--no-config".into(), "sandbox-limits".into()];
+    command.extend(limits.sandbox_args().into_iter().map(OsString::from));
+    command.push("--".into());
+    command.extend(argv);
+
+    Ok(command)
+}
+
+/// Create the sandbox configuration.
+///
+/// With `proxied_net`, networking is only permitted to reach the filtering
+/// proxy configured through the proxy environment variables.
+#[cfg(unix)]
+fn sandbox_config(matches: &ArgMatches, proxied_net: bool) -> Result<Birdcage> {
+    let mut birdcage =
+        if matches.get_flag("strict") { Birdcage::new() } else { permissions::default_sandbox()? };
+
+    // Apply filesystem exceptions.
+    let paths = |name: &str| -> Vec<String> {
+        matches.get_many(name).unwrap_or_default().cloned().collect()
+    };
+    let (read, write, run) = (paths("allow-read"), paths("allow-write"), paths("allow-run"));
+    for exception in permissions::filesystem_exceptions(&read, &write, &run) {
+        permissions::add_exception(&mut birdcage, exception)?;
+    }
+
+    // Apply network exceptions.
+    if matches.get_flag("allow-net") || proxied_net {
+        birdcage.add_exception(Exception::Networking)?;
+    }
+    if proxied_net {
+        for exception in permissions::proxy_exceptions() {
+            birdcage.add_exception(exception)?;
+        }
+    }
+
+    // Apply environment variable exceptions.
+    for var in matches.get_many::<String>("allow-env").unwrap_or_default() {
+        if var == "*" {
+            birdcage.add_exception(Exception::FullEnvironment)?;
+        } else {
+            birdcage.add_exception(Exception::Environment(var.to_owned()))?;
+        }
+    }
+
+    Ok(birdcage)
+}
+
+/// Entry point for the `sandbox-trace` subcommand.
+///
+/// This runs inside the sandbox, recording all denied accesses of the command
+/// to the report file.
+#[cfg(target_os = "linux")]
+pub fn handle_sandbox_trace(matches: &ArgMatches) -> CommandResult {
+    let report = matches.get_one::<String>("report").unwrap();
+    let cmd = matches.get_one::<String>("cmd").unwrap();
+    let args = matches.get_many::<String>("args").unwrap_or_default();
+
+    let mut command = process::Command::new(cmd);
+    command.args(args);
+    let (code, denied) = match tracer::trace(command) {
+        Ok((status, denied)) => match (status.code(), status.signal()) {
+            (Some(code), _) => (code, denied),
+            (None, Some(signal)) => (128 + signal, denied),
+            (None, None) => unreachable!("Traced process terminated without exit code or signal"),
+        },
+        // Record the command itself, if the sandbox prevents its execution.
+        Err(err)
+            if matches!(err.kind(), io::ErrorKind::NotFound | io::ErrorKind::PermissionDenied) =>
+        {
+            eprintln!("Process {cmd:?} failed to start: {err}");
+            let mut denied = DeniedAccesses::default();
+            denied.run.insert(cmd.into());
+            (127, denied)
+        },
+        Err(err) => return Err(err.into()),
+    };
+
+    fs::write(report, serde_json::to_vec(&denied)?)?;
+
+    Ok(ExitCode::Custom(code))
+}
+
+/// Create the command running `argv` through the syscall tracer.
+#[cfg(target_os = "linux")]
+fn trace_command(
+    birdcage: &mut Birdcage,
+    report: &Path,
+    argv: Vec<OsString>,
+) -> Result<Vec<OsString>> {
+    let current_exe = env::current_exe()?;
+    birdcage.add_exception(Exception::ExecuteAndRead(current_exe.clone()))?;
+    birdcage.add_exception(Exception::WriteAndRead(report.into()))?;
+
+    let mut command = vec![current_exe.into(), "--no-config".into(), "sandbox-trace".into()];
+    command.extend(["--report".into(), report.into(), "--".into()]);
+    command.extend(argv);
+
+    Ok(command)
+}
+
+/// Print the `PhylumExt.toml` permissions required by a traced command.
+#[cfg(target_os = "linux")]
+fn print_learned_permissions(
+    matches: &ArgMatches,
+    report: &Path,
+    proxy: Option<&FilteringProxy>,
+) -> Result<()> {
+    let report = fs::read(report)?;
+    if report.is_empty() {
+        return Err(anyhow!("Sandbox learning mode failed to record denied accesses"));
+    }
+    let mut denied: DeniedAccesses = serde_json::from_slice(&report)?;
+
+    // Proxy variables are provided by the sandbox.
+    for (var, _) in proxy.iter().flat_map(FilteringProxy::environment) {
+        denied.env.remove(var);
+    }
+
+    let denied_domains = proxy.map(FilteringProxy::denied_domains).unwrap_or_default();
+    let permissions = learned_permissions(matches, &denied, &denied_domains)?;
+
+    #[derive(Serialize)]
+    struct Manifest {
+        permissions: Permissions,
+    }
+    let manifest = toml::to_string(&Manifest { permissions })?;
+
+    eprintln!();
+    eprintln!("Sandbox permissions required by the command:");
+    eprintln!();
+    eprintln!("# Environment variables read without `getenv` (like by Go, Java or Python) must be");
+    eprintln!("# added manually.");
+    eprint!("{manifest}");
+
+    Ok(())
+}
+
+/// Get the minimal permissions covering all allowed and denied accesses.
+#[cfg(target_os = "linux")]
+fn learned_permissions(
+    matches: &ArgMatches,
+    denied: &DeniedAccesses,
+    denied_domains: &[String],
+) -> Result<Permissions> {
+    let home = dirs::home_dir()?;
+    let allowed = |id: &str| -> Vec<String> {
+        matches.get_many::<String>(id).unwrap_or_default().cloned().collect()
+    };
+    let absolute = |paths: &[String]| -> Vec<PathBuf> {
+        paths.iter().filter_map(|path| path::absolute(path).ok()).collect()
+    };
+
+    // Collect paths which are accessible by default.
+    let mut default_read = Vec::new();
+    let mut default_write = Vec::new();
+    let mut default_run = Vec::new();
+    let mut default_env = Vec::new();
+    if !matches.get_flag("strict") {
+        for exception in permissions::default_exceptions() {
+            match exception {
+                Exception::Read(path) => default_read.push(path),
+                Exception::WriteAndRead(path) => default_write.push(path),
+                Exception::ExecuteAndRead(path) => default_run.push(path),
+                Exception::Environment(var) => default_env.push(var),
+                _ => (),
+            }
+        }
+    }
+
+    let (allow_read, allow_write, allow_run) =
+        (allowed("allow-read"), allowed("allow-write"), allowed("allow-run"));
+    let run_paths: Vec<_> = allow_run.iter().map(permissions::resolve_bin_path).collect();
+
+    // Commands are resolved from `$PATH` outside of the sandbox.
+    let denied_run = denied.run.iter().map(permissions::resolve_bin_path).collect();
+    let covered: Vec<_> = [&default_run[..], &run_paths].concat();
+    let run = minimize_paths(host_paths(&denied_run, false), &covered);
+
+    let covered: Vec<_> = [&default_write[..], &absolute(&allow_write)].concat();
+    let write = minimize_paths(host_paths(&denied.write, true), &covered);
+
+    let covered: Vec<_> = [
+        &default_read[..],
+        &default_write,
+        &default_run,
+        &absolute(&allow_read),
+        &absolute(&allow_write),
+        &run_paths,
+        &run,
+        &write,
+    ]
+    .concat();
+    let read = minimize_paths(host_paths(&denied.read, false), &covered);
+
+    let permission = |allowed: Vec<String>, learned: Vec<PathBuf>| {
+        let learned = learned.iter().map(|path| display_path(path, &home));
+        let paths: Vec<_> = allowed.into_iter().chain(learned).collect();
+        if paths.is_empty() {
+            Permission::Boolean(false)
+        } else {
+            Permission::List(paths)
+        }
+    };
+
+    let allowed_env = allowed("allow-env");
+    let env = if allowed_env.iter().any(|var| var == "*") {
+        Permission::Boolean(true)
+    } else {
+        // Variables which are not set outside of the sandbox are ignored, since
+        // their lookups would fail regardless of the sandbox.
+        let learned = denied
+            .env
+            .iter()
+            .filter(|var| !allowed_env.contains(var) && !default_env.contains(var))
+            .filter(|var| env::var_os(var).is_some())
+            .cloned();
+        let vars: Vec<_> = allowed_env.iter().cloned().chain(learned).collect();
+        permission(vars, Vec::new())
+    };
+
+    let net = if matches.get_flag("allow-net") {
+        Permission::Boolean(true)
+    } else {
+        let mut domains = allowed("allow-domain");
+        for domain in denied_domains {
+            if !domains.contains(domain) {
+                domains.push(domain.clone());
+            }
+        }
+        permission(domains, Vec::new())
+    };
+
+    Ok(Permissions {
+        read: permission(allow_read, read),
+        write: permission(allow_write, write),
+        run: permission(allow_run, run),
+        env,
+        net,
+        unsandboxed_run: Permission::default(),
+        limits: sandbox_limits(matches),
+    })
+}
+
+/// Get the paths which must be accessible for denied accesses to succeed.
+///
+/// Paths which do not exist outside of the sandbox are ignored, since their
+/// accesses would fail regardless of the sandbox. For writes, the parent
+/// directory is used if a file doesn't exist yet.
+#[cfg(target_os = "linux")]
+fn host_paths(denied: &BTreeSet<PathBuf>, write: bool) -> Vec<PathBuf> {
+    denied
+        .iter()
+        // Process information is specific to the sandbox's PID namespace.
+        .filter(|path| !path.starts_with("/proc"))
+        .filter_map(|path| {
+            if path.exists() {
+                Some(path.clone())
+            } else if write {
+                path.parent().filter(|parent| parent.exists()).map(Path::to_path_buf)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Remove paths which are accessible through a covered or parent path.
+#[cfg(target_os = "linux")]
+fn minimize_paths(paths: Vec<PathBuf>, covered: &[PathBuf]) -> Vec<PathBuf> {
+    // Sorting ensures parent directories are processed before their children.
+    let paths: BTreeSet<_> = paths.into_iter().collect();
+
+    let mut minimized: Vec<PathBuf> = Vec::new();
+    for path in paths {
+        if !covered.iter().chain(&minimized).any(|parent| path.starts_with(parent)) {
+            minimized.push(path);
+        }
+    }
+
+    minimized
+}
+
+/// Format a path for `PhylumExt.toml`, using the `~/` home prefix.
+#[cfg(target_os = "linux")]
+fn display_path(path: &Path, home: &Path) -> String {
+    match path.strip_prefix(home) {
+        Ok(suffix) => Path::new("~").join(suffix).display().to_string(),
+        Err(_) => path.display().to_string(),
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minimize_covered_paths() {
+        let paths = vec![
+            PathBuf::from("/etc/hosts"),
+            PathBuf::from("/home/user/.npmrc"),
+            PathBuf::from("/usr/lib/node_modules/npm"),
+            PathBuf::from("/home/user/.npm/_cacache/index"),
+            PathBuf::from("/home/user/.npm"),
+        ];
+        let covered = [PathBuf::from("/usr/lib")];
+
+        let minimized = minimize_paths(paths, &covered);
+
+        assert_eq!(minimized, [
+            PathBuf::from("/etc/hosts"),
+            PathBuf::from("/home/user/.npm"),
+            PathBuf::from("/home/user/.npmrc"),
+        ]);
+    }
+
+    #[test]
+    fn display_home_paths() {
+        let home = Path::new("/home/user");
+
+        assert_eq!(display_path(Path::new("/home/user/.npm"), home), "~/.npm");
+        assert_eq!(display_path(Path::new("/home/username"), home), "/home/username");
+        assert_eq!(display_path(Path::new("/etc/hosts"), home), "/etc/hosts");
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::env::VarError;
 #[cfg(not(unix))]
 use std::fs::File;
@@ -7,11 +8,13 @@ use std::io::{self, Write};
 #[cfg(unix)]
 use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::{env, fs};
 
 use anyhow::{anyhow, Context, Result};
 use clap::ArgMatches;
-use phylum_project::{DepfileConfig, ProjectConfig};
+use phylum_lockfile::{DiscoveryOptions, LockfileFormat};
+use phylum_project::{DepfileConfig, DiscoveryConfig, MaxDepth, ProjectConfig, SandboxProfile};
 use phylum_types::types::auth::RefreshToken;
 use serde::{Deserialize, Deserializer, Serialize};
 
@@ -60,6 +63,41 @@ pub struct Config {
     org: Option<String>,
     #[serde(skip)]
     org_cli: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sandbox_profiles: BTreeMap<String, SandboxProfile>,
+    #[serde(default, skip_serializing_if = "ExtensionSignatures::is_empty")]
+    pub extension_signatures: ExtensionSignatures,
+}
+
+/// Extension signature verification settings.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ExtensionSignatures {
+    /// Refuse extensions without a signature of a trusted key.
+    #[serde(default)]
+    pub require_signatures: bool,
+    /// Paths of PEM-encoded RSA public keys trusted to sign extensions.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trusted_keys: Vec<String>,
+    /// Additional settings applied while an organization is selected.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub organizations: BTreeMap<String, OrgExtensionSignatures>,
+}
+
+impl ExtensionSignatures {
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Organization extension signature verification settings.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct OrgExtensionSignatures {
+    /// Refuse extensions without a signature of a trusted key.
+    #[serde(default)]
+    pub require_signatures: bool,
+    /// Paths of PEM-encoded RSA public keys trusted to sign extensions.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trusted_keys: Vec<String>,
 }
 
 impl Default for Config {
@@ -73,6 +111,8 @@ impl Default for Config {
             org_cli: Default::default(),
             path: Default::default(),
             org: Default::default(),
+            sandbox_profiles: Default::default(),
+            extension_signatures: Default::default(),
         }
     }
 }
@@ -250,12 +290,20 @@ pub fn depfiles(
                 .map(|depfile| DepfileConfig::new(depfile, depfile_type.clone()))
                 .collect())
         },
+        // Only scan images when they're the only explicitly specified source.
+        _ if matches.try_get_many::<String>("image").is_ok_and(|images| images.is_some()) => {
+            Ok(Vec::new())
+        },
         _ => {
-            // Try the project file first.
-            let project_depfiles = project.map(|project| project.depfiles());
-
-            // Fallback to walking the directory.
-            let depfiles = project_depfiles.unwrap_or_else(|| find_depfiles("."));
+            // Try the project file first, falling back to walking the project or current
+            // directory if it doesn't list any dependency files.
+            let depfiles = match project {
+                Some(project) if !project.discovers_depfiles() => project.depfiles(),
+                Some(project) => {
+                    find_depfiles(project.root(), &discovery_options(matches, Some(project))?)?
+                },
+                None => find_depfiles(".", &discovery_options(matches, None)?)?,
+            };
 
             // Ask for explicit dependency file if none were found.
             if depfiles.is_empty() {
@@ -268,11 +316,117 @@ pub fn depfiles(
 }
 
 /// Find dependency files at or below the specified directory.
-fn find_depfiles(directory: impl AsRef<Path>) -> Vec<DepfileConfig> {
-    phylum_lockfile::find_depfiles_at(directory)
+fn find_depfiles(
+    directory: impl AsRef<Path>,
+    options: &DiscoveryOptions,
+) -> Result<Vec<DepfileConfig>> {
+    Ok(phylum_lockfile::find_depfiles_with_options(directory, options)?
         .drain(..)
         .map(|(path, format)| DepfileConfig::new(path, format.to_string()))
-        .collect()
+        .collect())
+}
+
+/// Get dependency file discovery settings specified on the CLI.
+pub fn cli_discovery_config(matches: &ArgMatches) -> DiscoveryConfig {
+    let strings = |id: &str| -> Vec<String> {
+        match matches.try_get_many::<String>(id) {
+            Ok(Some(values)) => values.cloned().collect(),
+            _ => Vec::new(),
+        }
+    };
+
+    DiscoveryConfig {
+        max_depth: matches.try_get_one::<MaxDepth>("max-depth").ok().flatten().copied(),
+        include: strings("include"),
+        exclude: strings("exclude"),
+        formats: strings("format"),
+        follow_symlinks: matches
+            .try_get_one::<bool>("follow-symlinks")
+            .ok()
+            .flatten()
+            .copied()
+            .filter(|follow| *follow),
+    }
+}
+
+/// Get dependency file discovery settings from CLI, falling back to the
+/// current project when missing.
+///
+/// Include and exclude globs from both sources are combined.
+pub fn discovery_options(
+    matches: &ArgMatches,
+    project: Option<&ProjectConfig>,
+) -> Result<DiscoveryOptions> {
+    let cli = cli_discovery_config(matches);
+    let project = project.map(|project| project.discovery.clone()).unwrap_or_default();
+
+    let mut options = DiscoveryOptions::default();
+
+    if let Some(max_depth) = cli.max_depth.or(project.max_depth) {
+        options.max_depth = max_depth.limit();
+    }
+
+    options.include = project.include.into_iter().chain(cli.include).collect();
+    options.exclude = project.exclude.into_iter().chain(cli.exclude).collect();
+
+    let formats = if cli.formats.is_empty() { project.formats } else { cli.formats };
+    options.formats = formats
+        .iter()
+        .map(|format| {
+            LockfileFormat::from_str(format)
+                .with_context(|| format!("Invalid dependency file type {format:?}"))
+        })
+        .collect::<Result<_>>()?;
+
+    options.follow_symlinks =
+        cli.follow_symlinks.or(project.follow_symlinks).unwrap_or(options.follow_symlinks);
+
+    Ok(options)
+}
+
+/// Get the lockfile generation sandbox profiles of the user and the current
+/// project.
+///
+/// Project profiles replace user profiles with the same name. Paths starting
+/// with `~/` are resolved from the home directory, while relative paths of
+/// project profiles are resolved from the project root.
+pub fn sandbox_profiles(
+    config: &Config,
+    project: Option<&ProjectConfig>,
+) -> Result<BTreeMap<String, SandboxProfile>> {
+    let home = dirs::home_dir()?;
+
+    let user_profiles = config.sandbox_profiles.iter().map(|(name, profile)| (name, profile, None));
+    let project_profiles = project.into_iter().flat_map(|project| {
+        let root = Some(project.root().as_path());
+        project.sandbox_profiles.iter().map(move |(name, profile)| (name, profile, root))
+    });
+
+    let mut profiles = BTreeMap::new();
+    for (name, profile, root) in user_profiles.chain(project_profiles) {
+        let resolve = |path: &String| {
+            let path = dirs::expand_home_path(path, &home);
+            match root {
+                Some(root) if path.is_relative() => root.join(path).display().to_string(),
+                _ => path.display().to_string(),
+            }
+        };
+
+        let mut profile = profile.clone();
+        profile.read = profile.read.iter().map(resolve).collect();
+        profile.write = profile.write.iter().map(resolve).collect();
+
+        // Executables without any directory are resolved from `$PATH`.
+        let resolve_bin = |bin: &String| match Path::new(bin).components().count() {
+            0 | 1 => bin.clone(),
+            _ => resolve(bin),
+        };
+        profile.run = profile.run.iter().map(resolve_bin).collect();
+
+        profiles.insert(name.clone(), profile);
+    }
+
+    Ok(profiles)
 }
 
 pub fn get_home_settings_path() -> Result<PathBuf> {
@@ -334,6 +488,97 @@ mod tests {
         assert_eq!(config.auth_info.env_token, None);
     }
 
+    #[test]
+    fn discovery_options_merge_cli_and_project() {
+        let matches = crate::app::app().get_matches_from([
+            "phylum",
+            "parse",
+            "--max-depth",
+            "8",
+            "--exclude",
+            "vendor/",
+            "--format",
+            "npm",
+        ]);
+        let matches = matches.subcommand_matches("parse").unwrap();
+
+        let mut project = ProjectConfig::new(uuid::Uuid::nil(), "project".into(), None);
+        project.discovery = DiscoveryConfig {
+            max_depth: Some(MaxDepth::Limited(3)),
+            exclude: vec!["tests/fixtures".into()],
+            formats: vec!["cargo".into()],
+            follow_symlinks: Some(true),
+            ..DiscoveryConfig::default()
+        };
+
+        let options = discovery_options(matches, Some(&project)).unwrap();
+
+        assert_eq!(options.max_depth, Some(8));
+        assert_eq!(options.exclude, vec![String::from("tests/fixtures"), String::from("vendor/")]);
+        assert_eq!(options.formats, vec![LockfileFormat::Npm]);
+        assert!(options.follow_symlinks);
+
+        // Defaults are used without any configuration.
+        let options = discovery_options(matches, None).unwrap();
+        assert_eq!(options.exclude, vec![String::from("vendor/")]);
+        assert!(!options.follow_symlinks);
+    }
+
+    #[test]
+    fn discovery_options_unlimited_depth() {
+        let matches =
+            crate::app::app().get_matches_from(["phylum", "parse", "--max-depth", "unlimited"]);
+        let matches = matches.subcommand_matches("parse").unwrap();
+
+        let mut project = ProjectConfig::new(uuid::Uuid::nil(), "project".into(), None);
+        project.discovery.max_depth = Some(MaxDepth::Limited(3));
+
+        let options = discovery_options(matches, Some(&project)).unwrap();
+        assert_eq!(options.max_depth, None);
+
+        // Project settings can disable the limit too.
+        let matches = crate::app::app().get_matches_from(["phylum", "parse"]);
+        let matches = matches.subcommand_matches("parse").unwrap();
+        project.discovery.max_depth = Some(MaxDepth::Unlimited);
+
+        let options = discovery_options(matches, Some(&project)).unwrap();
+        assert_eq!(options.max_depth, None);
+    }
+
+    #[test]
+    fn empty_project_depfiles_are_not_discovered() {
+        let matches = crate::app::app().get_matches_from(["phylum", "parse"]);
+        let matches = matches.subcommand_matches("parse").unwrap();
+
+        // New projects have an explicitly empty list of dependency files.
+        let project = ProjectConfig::new(uuid::Uuid::nil(), "project".into(), None);
+        let err = depfiles(matches, Some(&project)).unwrap_err();
+        assert_eq!(err.to_string(), "Missing dependency file parameter");
+    }
+
+    #[test]
+    fn project_sandbox_profiles_replace_user_profiles() {
+        let profile = |run: &str| SandboxProfile {
+            formats: vec!["npm".into()],
+            run: vec![run.into()],
+            ..SandboxProfile::default()
+        };
+
+        let mut config = Config::default();
+        config.sandbox_profiles.insert("node".into(), profile("~/.asdf/shims/node"));
+        config.sandbox_profiles.insert("rust".into(), profile("~/.cargo/bin/cargo"));
+
+        let mut project = ProjectConfig::new(uuid::Uuid::nil(), "project".into(), None);
+        project.sandbox_profiles.insert("node".into(), profile("node"));
+
+        let profiles = sandbox_profiles(&config, Some(&project)).unwrap();
+
+        let home = dirs::home_dir().unwrap();
+        let cargo = home.join(".cargo/bin/cargo").display().to_string();
+        assert_eq!(profiles["node"].run, vec![String::from("node")]);
+        assert_eq!(profiles["rust"].run, vec![cargo]);
+    }
+
     #[test]
     fn prefer_env_token() {
         let auth = AuthInfo {
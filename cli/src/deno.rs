@@ -25,9 +25,11 @@ use tokio::fs;
 use url::Url;
 
 use crate::api::PhylumApi;
+use crate::commands::extensions::lock::{ModuleLock, RemoteModules};
 use crate::commands::extensions::state::ExtensionState;
 use crate::commands::extensions::{api, extension};
 use crate::commands::{CommandResult, ExitCode};
+use crate::config::Config;
 
 /// Internal extension module that creates global Phylum object.
 const EXTENSION_API: &[ExtensionFileSource] = &include_js_files!(
@@ -47,9 +49,10 @@ export const ApiVersion = Phylum.ApiVersion;
 pub async fn run(
     api: BoxFuture<'static, Result<PhylumApi>>,
     extension: extension::Extension,
+    config: Config,
     args: Vec<String>,
 ) -> CommandResult {
-    let state = ExtensionState::new(api, extension.clone());
+    let state = ExtensionState::new(api, extension.clone(), config);
     let phylum_api = Extension {
         name: "phylum-ext",
         middleware_fn: Some(Box::new(|op| match op.name {
@@ -68,7 +71,16 @@ pub async fn run(
     let bootstrap =
         BootstrapOptions { args, user_agent: "phylum-cli/extension".into(), ..Default::default() };
 
-    let module_loader = Rc::new(ExtensionsModuleLoader::new(extension.path()));
+    // Installed extensions without a lock, like extensions installed with older
+    // versions of the CLI, record their remote modules during their first run.
+    //
+    // The lock of other extensions is only written by `phylum extension lock`,
+    // so their modules are verified against an existing lock but never recorded.
+    let lock = ModuleLock::load(&extension.path())?;
+    let save_lock = lock.is_none() && extension.installed();
+    let remote_modules = Rc::new(RemoteModules::new(lock)?);
+    let module_loader =
+        Rc::new(ExtensionsModuleLoader::new(extension.path(), remote_modules.clone()));
 
     let origin_storage_dir = extension.state_path();
 
@@ -90,14 +102,44 @@ pub async fn run(
         MainWorker::bootstrap_from_options(main_module.clone(), permissions_container, options);
 
     // Execute extension code.
-    if let Err(error) = worker.execute_main_module(&main_module).await {
-        return print_js_error(error);
+    let result = match worker.execute_main_module(&main_module).await {
+        Ok(_) => worker.run_event_loop(false).await,
+        Err(error) => Err(error),
+    };
+
+    // Skip incomplete locks of failed runs, so they are recorded again next time.
+    if save_lock && result.is_ok() {
+        remote_modules.lock().save(&extension.path())?;
     }
-    if let Err(error) = worker.run_event_loop(false).await {
-        return print_js_error(error);
+
+    match result {
+        Ok(()) => Ok(ExitCode::Ok),
+        Err(error) => print_js_error(error),
     }
+}
+
+/// Fetch all remote modules statically imported by an extension, recording
+/// them in a new lock.
+///
+/// Dynamically imported modules are not recorded, since they are only loaded
+/// when the extension is executed.
+pub async fn lock_modules(extension: &extension::Extension) -> Result<ModuleLock> {
+    let main_module = deno_core::resolve_path(extension.entry_point(), &PathBuf::from("."))?;
+
+    let remote_modules = Rc::new(RemoteModules::new(None)?);
+    let module_loader =
+        Rc::new(ExtensionsModuleLoader::new(extension.path(), remote_modules.clone()));
+    let options = WorkerOptions { module_loader, ..Default::default() };
 
-    Ok(ExitCode::Ok)
+    // Modules are only loaded, so no permissions are necessary.
+    let worker_permissions = Permissions::from_options(&PermissionsOptions::default())?;
+    let permissions_container = PermissionsContainer::new(worker_permissions);
+
+    let mut worker =
+        MainWorker::bootstrap_from_options(main_module.clone(), permissions_container, options);
+    worker.preload_main_module(&main_module).await?;
+
+    Ok(remote_modules.lock())
 }
 
 /// Pretty-print an anyhow error as Deno JS error.
@@ -121,13 +163,15 @@ fn print_js_error(error: Error) -> CommandResult {
 /// See https://github.com/denoland/deno/blob/main/core/examples/ts_module_loader.rs.
 struct ExtensionsModuleLoader {
     extension_path: Rc<PathBuf>,
+    remote_modules: Rc<RemoteModules>,
     source_mapper: Rc<SourceMapper>,
 }
 
 impl ExtensionsModuleLoader {
-    fn new(extension_path: PathBuf) -> Self {
+    fn new(extension_path: PathBuf, remote_modules: Rc<RemoteModules>) -> Self {
         Self {
             extension_path: Rc::new(extension_path),
+            remote_modules,
             source_mapper: Rc::new(SourceMapper::new()),
         }
     }
@@ -151,11 +195,6 @@ impl ExtensionsModuleLoader {
 
         Ok(fs::read_to_string(path).await?)
     }
-
-    async fn load_from_remote(path: &Url) -> Result<String> {
-        let response = reqwest::get(path.clone()).await?;
-        Ok(response.text().await?)
-    }
 }
 
 impl ModuleLoader for ExtensionsModuleLoader {
@@ -181,6 +220,7 @@ impl ModuleLoader for ExtensionsModuleLoader {
     ) -> ModuleLoadResponse {
         let module_specifier = module_specifier.clone();
         let extension_path = self.extension_path.clone();
+        let remote_modules = self.remote_modules.clone();
         let source_mapper = self.source_mapper.clone();
 
         ModuleLoadResponse::Async(Box::pin(async move {
@@ -218,7 +258,7 @@ impl ModuleLoader for ExtensionsModuleLoader {
                     ExtensionsModuleLoader::load_from_filesystem(&extension_path, &module_specifier)
                         .await?
                 },
-                "https" => ExtensionsModuleLoader::load_from_remote(&module_specifier).await?,
+                "https" => remote_modules.load(&module_specifier).await?,
                 _ => return Err(anyhow!("Unsupported module specifier: {}", module_specifier)),
             };
 
@@ -16,6 +16,7 @@ use unicode_width::UnicodeWidthStr;
 use vulnreach_types::Vulnerability;
 
 use crate::commands::group::ListGroupsEntry;
+use crate::commands::parse::{DepfilePlan, PlanAction};
 use crate::commands::status::PhylumStatus;
 use crate::print::{self, table_format};
 use crate::types::{
@@ -161,6 +162,106 @@ impl Format for PolicyEvaluationResponseRaw {
     }
 }
 
+impl Format for Vec<DepfilePlan> {
+    fn pretty<W: Write>(&self, writer: &mut W) {
+        for (i, plan) in self.iter().enumerate() {
+            if i != 0 {
+                let _ = writeln!(writer);
+            }
+
+            let format = plan.format.map_or("auto", |format| format.name());
+            let _ = writeln!(writer, "{} ({format})", style(plan.path.display()).bold());
+
+            let (explanation, out_of_tree, sandbox_exceptions) = match &plan.action {
+                PlanAction::ReadInstalled => {
+                    let _ = writeln!(writer, "  Read installed environment");
+                    continue;
+                },
+                PlanAction::ParseBinary => {
+                    let _ = writeln!(writer, "  Read dependencies embedded in binary");
+                    continue;
+                },
+                PlanAction::ParseLockfile { lockfile } => {
+                    let _ = writeln!(writer, "  Parse lockfile {}", lockfile.display());
+                    continue;
+                },
+                PlanAction::DetectFormat => {
+                    let _ = writeln!(writer, "  Parse with all lockfile parsers");
+                    continue;
+                },
+                PlanAction::GenerationDisabled => {
+                    let _ = writeln!(writer, "  Lockfile generation is disabled");
+                    continue;
+                },
+                PlanAction::GenerationUnsupported => {
+                    let _ = writeln!(writer, "  Lockfile generation is not supported");
+                    continue;
+                },
+                PlanAction::GenerateLockfile { explanation, out_of_tree, sandbox_exceptions } => {
+                    (explanation, *out_of_tree, sandbox_exceptions)
+                },
+            };
+
+            let offline = if explanation.offline { " from local package caches" } else { "" };
+            let _ = writeln!(writer, "  Generate lockfile using {}{offline}", explanation.tool);
+
+            let command = explanation.command.join(" ");
+            let _ = writeln!(writer, "  {}: {command}", style("Command").blue());
+
+            let working_dir_label = style("Working Directory").blue();
+            if let (true, Some(project_root)) = (out_of_tree, &explanation.project_root) {
+                let working_dir = explanation.working_dir.strip_prefix(project_root);
+                let working_dir = working_dir.unwrap_or(&explanation.working_dir).display();
+                let project_root = project_root.display();
+                let _ = writeln!(
+                    writer,
+                    "  {working_dir_label}: {working_dir} in scratch copy of {project_root}"
+                );
+            } else if out_of_tree {
+                let working_dir = explanation.working_dir.display();
+                let _ = writeln!(
+                    writer,
+                    "  {working_dir_label}: {working_dir} in scratch copy of its project"
+                );
+            } else {
+                let working_dir = explanation.working_dir.display();
+                let _ = writeln!(writer, "  {working_dir_label}: {working_dir}");
+            }
+
+            let conflicting_label = if out_of_tree { "Excluded Files" } else { "Relocated Files" };
+            let conflicting_label = style(conflicting_label).blue();
+            match explanation.conflicting_files.as_deref() {
+                Some([]) => {
+                    let _ = writeln!(writer, "  {conflicting_label}: {}", style("none").italic());
+                },
+                Some(conflicting_files) => {
+                    let _ = writeln!(writer, "  {conflicting_label}:");
+                    for path in conflicting_files {
+                        let _ = writeln!(writer, "   - {}", path.display());
+                    }
+                },
+                None => {
+                    let _ =
+                        writeln!(writer, "  {conflicting_label}: {}", style("unknown").italic());
+                },
+            }
+
+            let sandbox_label = style("Sandbox Exceptions").blue();
+            match sandbox_exceptions {
+                Some(exceptions) => {
+                    let _ = writeln!(writer, "  {sandbox_label}:");
+                    for exception in exceptions {
+                        let _ = writeln!(writer, "   - {exception}");
+                    }
+                },
+                None => {
+                    let _ = writeln!(writer, "  {sandbox_label}: {}", style("disabled").yellow());
+                },
+            }
+        }
+    }
+}
+
 impl Format for Vec<ProjectListEntry> {
     fn pretty<W: Write>(&self, writer: &mut W) {
         // Maximum length of the project and group name column.
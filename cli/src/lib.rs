@@ -10,12 +10,15 @@ pub mod deno;
 pub mod dirs;
 pub mod filter;
 pub mod format;
-pub mod fs_compare;
 pub mod permissions;
 pub mod print;
+#[cfg(unix)]
+pub mod proxy;
 pub mod spinner;
 #[cfg(test)]
 mod test;
+#[cfg(target_os = "linux")]
+pub mod tracer;
 pub mod types;
 pub mod update;
 #[cfg(feature = "vulnreach")]
@@ -1,7 +1,8 @@
 #[cfg(unix)]
 use std::borrow::Cow;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use std::result::Result as StdResult;
+use std::time::Duration;
 use std::{env, fs};
 
 use anyhow::{anyhow, Result};
@@ -13,12 +14,26 @@ use birdcage::error::Result as SandboxResult;
 use birdcage::{Birdcage, Exception, Sandbox};
 #[cfg(feature = "extensions")]
 use deno_runtime::deno_permissions::PermissionsOptions;
+use phylum_lockfile::generator::limits::Limits;
 use serde::de::Error as _;
 use serde::{Deserialize, Deserializer, Serialize};
 
 use crate::dirs::{self, expand_home_path};
+#[cfg(unix)]
+use crate::proxy;
+
+/// Prefix of permission list entries denying access to a resource.
+const DENY_PREFIX: char = '!';
 
 /// Resource permissions.
+///
+/// Entries of a permission list starting with `!` deny access to the resource,
+/// taking precedence over all other entries of the list.
+///
+/// Filesystem entries may contain glob patterns, where `*` matches any
+/// sequence of characters and `?` any single character within a path
+/// component, while a `**` component matches any number of path components.
+/// Network entries starting with `*.` match all subdomains of a domain.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 #[serde(untagged)]
 pub enum Permission {
@@ -77,7 +92,66 @@ impl Permission {
         }
     }
 
+    /// Check that this permission does not exceed the `parent` permission.
+    ///
+    /// Returns the child's permission, restricted by all entries denied by the
+    /// parent.
     pub fn subset_of(&self, parent: &Permission) -> Result<Permission> {
+        self.subset_with(parent, |parent, child| {
+            Permission::check_paths_include_children(parent, child)?;
+            Ok(with_denied_entries(parent, child))
+        })
+    }
+
+    /// Check that this network permission does not exceed the `parent`
+    /// permission.
+    ///
+    /// Returns the child's permission, restricted by all domains denied by the
+    /// parent.
+    fn subset_of_domains(&self, parent: &Permission) -> Result<Permission> {
+        self.subset_with(parent, |parent, child| {
+            let (denied, allowed): (Vec<_>, Vec<_>) =
+                parent.iter().partition(|entry| entry.starts_with(DENY_PREFIX));
+
+            // Child domains which are only partially denied by the parent are
+            // accepted, since the parent's deny entries are inherited by the child.
+            let without_parent: Vec<_> = child
+                .iter()
+                .filter(|child| !child.starts_with(DENY_PREFIX))
+                .filter(|child| {
+                    !allowed.iter().any(|allowed| domain_entry_includes(allowed, child))
+                        || denied.iter().any(|denied| domain_entry_includes(&denied[1..], child))
+                })
+                .cloned()
+                .collect();
+
+            if without_parent.is_empty() {
+                Ok(with_denied_entries(parent, child))
+            } else {
+                Err(without_parent)
+            }
+        })
+    }
+
+    /// Check that this permission does not exceed the `parent` permission,
+    /// matching entries literally.
+    fn subset_of_names(&self, parent: &Permission) -> Result<Permission> {
+        self.subset_with(parent, |parent, child| {
+            let missing: Vec<_> =
+                child.iter().filter(|name| !parent.contains(name)).cloned().collect();
+
+            if missing.is_empty() {
+                Ok(child.to_vec())
+            } else {
+                Err(missing)
+            }
+        })
+    }
+
+    fn subset_with<F>(&self, parent: &Permission, check_lists: F) -> Result<Permission>
+    where
+        F: Fn(&[String], &[String]) -> StdResult<Vec<String>, Vec<String>>,
+    {
         match (parent, self) {
             // Child deny-all always succeeds, returning deny-all.
             (_, &Permission::Boolean(false)) => Ok(Permission::Boolean(false)),
@@ -94,14 +168,12 @@ impl Permission {
             // Parent set vs child set have to be validated.
             // This will error if child is not subset of parent, and return the child set otherwise.
             (Permission::List(parent), Permission::List(child)) => {
-                Permission::check_paths_include_children(parent, child)
-                    .map(|_| Permission::List(child.clone()))
-                    .map_err(|mismatches| {
-                        anyhow!(
-                            "The following paths are denied by the manifest: {}",
-                            mismatches.join(", ")
-                        )
-                    })
+                check_lists(parent, child).map(Permission::List).map_err(|mismatches| {
+                    anyhow!(
+                        "The following paths are denied by the manifest: {}",
+                        mismatches.join(", ")
+                    )
+                })
             },
         }
     }
@@ -110,18 +182,21 @@ impl Permission {
         parent: &[String],
         child: &[String],
     ) -> StdResult<(), Vec<String>> {
-        // Find all paths in `child` that don't have a prefix in `parent`.
+        let (parent_denied, parent_allowed) = split_entries(parent);
+
+        // Find all allowed paths in `child` that are not allowed by `parent`.
+        //
+        // Child paths which are only partially denied by the parent are accepted,
+        // since the parent's deny entries are inherited by the child.
         let without_parent: Vec<_> = child
             .iter()
-            .filter_map(|child| {
-                // Using Path::starts_with rather than String::starts_with in order to get
-                // the correct semantics.
-                if parent.iter().any(|p| Path::new(&child).starts_with(p)) {
-                    None
-                } else {
-                    Some(child.clone())
-                }
+            .filter(|child| !child.starts_with(DENY_PREFIX))
+            .filter(|child| {
+                let child = PathPattern::new(child);
+                !parent_allowed.iter().any(|allowed| allowed.includes(&child))
+                    || parent_denied.iter().any(|denied| denied.includes(&child))
             })
+            .cloned()
             .collect::<Vec<_>>();
 
         // The above list must be empty for all child paths to be a subset of the
@@ -134,6 +209,20 @@ impl Permission {
     }
 }
 
+/// Add the parent's deny entries to a child's permission list entries.
+///
+/// Children of allowed paths or subdomains of allowed wildcard domains might
+/// still be denied by the parent.
+fn with_denied_entries(parent: &[String], child: &[String]) -> Vec<String> {
+    let mut entries = child.to_vec();
+    for entry in parent.iter().filter(|entry| entry.starts_with(DENY_PREFIX)) {
+        if !entries.contains(entry) {
+            entries.push(entry.clone());
+        }
+    }
+    entries
+}
+
 /// Deserializer for automatically resolving `~/` path prefix.
 pub fn deserialize_permission_paths<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
 where
@@ -145,12 +234,20 @@ where
     // Resolve `~/` home prefix.
     let home = dirs::home_dir().map_err(D::Error::custom)?;
     for path in &mut paths {
-        *path = expand_home_path(path, &home).display().to_string();
+        *path = expand_home_entry(path, &home);
     }
 
     Ok(paths)
 }
 
+/// Resolve the `~/` home prefix of a permission list entry.
+pub fn expand_home_entry(entry: &str, home: &Path) -> String {
+    match entry.strip_prefix(DENY_PREFIX) {
+        Some(path) => format!("{DENY_PREFIX}{}", expand_home_path(path, home).display()),
+        None => expand_home_path(entry, home).display().to_string(),
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Default, Debug)]
 pub struct Permissions {
     #[serde(default)]
@@ -165,10 +262,12 @@ pub struct Permissions {
     pub unsandboxed_run: Permission,
     #[serde(default, deserialize_with = "deserialize_net_permission")]
     pub net: Permission,
+    #[serde(default, skip_serializing_if = "ResourceLimits::is_unlimited")]
+    pub limits: ResourceLimits,
 }
 
 /// Deserialize network permissions.
-fn deserialize_net_permission<'de, D>(deserializer: D) -> Result<Permission, D::Error>
+pub fn deserialize_net_permission<'de, D>(deserializer: D) -> Result<Permission, D::Error>
 where
     D: Deserializer<'de>,
 {
@@ -187,6 +286,16 @@ where
             );
             return Err(D::Error::custom(err));
         }
+
+        // Only allow wildcards for all subdomains of a domain.
+        let domain = url.strip_prefix(DENY_PREFIX).unwrap_or(url);
+        let domain = domain.strip_prefix("*.").unwrap_or(domain);
+        if domain.is_empty() || domain.contains(['*', '?']) {
+            let err = format!(
+                "Invalid net permission {url:?}, wildcards are only supported as `*.` prefix"
+            );
+            return Err(D::Error::custom(err));
+        }
     }
 
     Ok(permission)
@@ -202,25 +311,52 @@ impl Permissions {
             && self.unsandboxed_run.get().is_none()
     }
 
+    /// Domains allowed by the filtering proxy.
+    ///
+    /// Returns `None` if network access is either fully allowed or denied.
+    ///
+    /// The proxy only filters clients using the proxy environment variables,
+    /// the sandbox itself allows all network access.
+    pub fn allowed_domains(&self) -> Option<&[String]> {
+        match &self.net {
+            Permission::List(domains) if !domains.is_empty() => Some(domains),
+            _ => None,
+        }
+    }
+
+    /// Allowed wildcard domains.
+    ///
+    /// Since Deno does not support wildcard subdomains, these domains only
+    /// apply to sandboxed processes and not to the extension itself.
+    pub fn wildcard_domains(&self) -> Vec<&str> {
+        let domains = self.net.get().map(Vec::as_slice).unwrap_or_default();
+        domains.iter().filter(|domain| domain.starts_with("*.")).map(String::as_str).collect()
+    }
+
     /// Build a sandbox matching the requested permissions.
+    ///
+    /// If network access is restricted to [`Self::allowed_domains`], the
+    /// sandboxed process must be started with the environment of a
+    /// [`FilteringProxy`](crate::proxy::FilteringProxy).
     #[cfg(unix)]
     pub fn build_sandbox(&self) -> Result<Birdcage> {
         let mut birdcage = default_sandbox()?;
 
-        for path in self.read.sandbox_paths().iter().map(PathBuf::from) {
-            add_exception(&mut birdcage, Exception::Read(path))?;
-        }
-        for path in self.write.sandbox_paths().iter().map(PathBuf::from) {
-            add_exception(&mut birdcage, Exception::WriteAndRead(path))?;
-        }
-        for path in self.run.sandbox_paths().iter() {
-            let absolute_path = resolve_bin_path(path);
-            add_exception(&mut birdcage, Exception::ExecuteAndRead(absolute_path))?;
+        let read = self.read.sandbox_paths();
+        let write = self.write.sandbox_paths();
+        let run = self.run.sandbox_paths();
+        for exception in filesystem_exceptions(&read, &write, &run) {
+            add_exception(&mut birdcage, exception)?;
         }
 
         if self.net.get().is_some() {
             birdcage.add_exception(Exception::Networking)?;
         }
+        if self.allowed_domains().is_some() {
+            for exception in proxy_exceptions() {
+                birdcage.add_exception(exception)?;
+            }
+        }
 
         let env_exceptions = match &self.env {
             Permission::Boolean(true) => vec![Exception::FullEnvironment],
@@ -242,22 +378,111 @@ impl Permissions {
         Ok(Permissions {
             read: self.read.subset_of(&other.read).map_err(err_ctx("read"))?,
             write: self.write.subset_of(&other.write).map_err(err_ctx("write"))?,
-            env: self.env.subset_of(&other.env).map_err(err_ctx("env"))?,
+            env: self.env.subset_of_names(&other.env).map_err(err_ctx("env"))?,
             run: self.run.subset_of(&other.run).map_err(err_ctx("run"))?,
-            net: self.net.subset_of(&other.net).map_err(err_ctx("net"))?,
+            net: self.net.subset_of_domains(&other.net).map_err(err_ctx("net"))?,
             unsandboxed_run: Permission::default(),
+            limits: self.limits.subset_of(&other.limits)?,
         })
     }
 }
 
+/// Resource limits of sandboxed processes.
+///
+/// Unset limits are inherited from the parent sandbox.
+#[derive(Serialize, Deserialize, Copy, Clone, Default, Debug, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ResourceLimits {
+    /// Maximum number of processes of the current user.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub processes: Option<u64>,
+    /// Maximum virtual memory in MiB.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory: Option<u64>,
+    /// Maximum CPU time in seconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu_time: Option<u64>,
+    /// Maximum number of open files.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub open_files: Option<u64>,
+    /// Maximum size of written files in MiB.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_size: Option<u64>,
+    /// Maximum combined size of STDOUT and STDERR in MiB.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<u64>,
+}
+
+impl ResourceLimits {
+    /// Check if no limit is configured.
+    pub fn is_unlimited(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Restrict these limits to the limits of a parent sandbox.
+    ///
+    /// Returns an error if any limit exceeds the parent's limit.
+    pub fn subset_of(&self, parent: &ResourceLimits) -> Result<ResourceLimits> {
+        let limit = |name: &str, child: Option<u64>, parent: Option<u64>| match (child, parent) {
+            (Some(child), Some(parent)) if child > parent => {
+                Err(anyhow!("Invalid {name} limit: {child} exceeds the parent limit of {parent}"))
+            },
+            (child, parent) => Ok(child.or(parent)),
+        };
+
+        Ok(ResourceLimits {
+            processes: limit("processes", self.processes, parent.processes)?,
+            memory: limit("memory", self.memory, parent.memory)?,
+            cpu_time: limit("cpu_time", self.cpu_time, parent.cpu_time)?,
+            open_files: limit("open_files", self.open_files, parent.open_files)?,
+            file_size: limit("file_size", self.file_size, parent.file_size)?,
+            output: limit("output", self.output, parent.output)?,
+        })
+    }
+
+    /// Get the `phylum sandbox` arguments applying these limits.
+    pub fn sandbox_args(&self) -> Vec<String> {
+        let limits = [
+            ("--max-processes", self.processes),
+            ("--max-memory", self.memory),
+            ("--max-cpu-time", self.cpu_time),
+            ("--max-open-files", self.open_files),
+            ("--max-file-size", self.file_size),
+            ("--max-output", self.output),
+        ];
+
+        let mut args = Vec::new();
+        for (arg, limit) in limits {
+            if let Some(limit) = limit {
+                args.push(arg.into());
+                args.push(limit.to_string());
+            }
+        }
+        args
+    }
+}
+
+impl From<&ResourceLimits> for Limits {
+    fn from(limits: &ResourceLimits) -> Self {
+        let mebibytes = |mib: Option<u64>| mib.map(|mib| mib.saturating_mul(1024 * 1024));
+
+        Self {
+            timeout: None,
+            cpu_time: limits.cpu_time.map(Duration::from_secs),
+            memory: mebibytes(limits.memory),
+            file_size: mebibytes(limits.file_size),
+            processes: limits.processes,
+            open_files: limits.open_files,
+            output_size: mebibytes(limits.output),
+        }
+    }
+}
+
 #[cfg(feature = "extensions")]
 impl From<&Permissions> for PermissionsOptions {
     fn from(value: &Permissions) -> Self {
-        let allow_read =
-            value.read.get().map(|read| read.iter().map(PathBuf::from).collect::<Vec<_>>());
-
-        let allow_write =
-            value.write.get().map(|write| write.iter().map(PathBuf::from).collect::<Vec<_>>());
+        let (allow_read, deny_read) = deno_paths(&value.read);
+        let (allow_write, deny_write) = deno_paths(&value.write);
 
         let allow_run = value.unsandboxed_run.get().cloned();
         let allow_env = value.env.get().cloned();
@@ -268,7 +493,7 @@ impl From<&Permissions> for PermissionsOptions {
         allow_net.allow_resource("api.staging.phylum.io".into());
         allow_net.allow_resource("api.phylum.io".into());
 
-        let allow_net = allow_net.get().cloned();
+        let (allow_net, deny_net) = deno_domains(&allow_net);
 
         PermissionsOptions {
             allow_all: false,
@@ -280,11 +505,11 @@ impl From<&Permissions> for PermissionsOptions {
             allow_sys: None,
             allow_ffi: None,
             allow_hrtime: false,
-            deny_read: None,
-            deny_write: None,
+            deny_read,
+            deny_write,
             deny_run: None,
             deny_env: None,
-            deny_net: None,
+            deny_net,
             deny_sys: None,
             deny_ffi: None,
             deny_hrtime: false,
@@ -293,53 +518,462 @@ impl From<&Permissions> for PermissionsOptions {
     }
 }
 
+/// Convert filesystem permissions to Deno's allowed and denied paths.
+///
+/// Since Deno does not support glob patterns, they are expanded to all
+/// currently existing paths matching them.
+#[cfg(feature = "extensions")]
+fn deno_paths(permission: &Permission) -> (Option<Vec<PathBuf>>, Option<Vec<PathBuf>>) {
+    let entries = match permission.get() {
+        Some(entries) if entries.is_empty() => return (Some(Vec::new()), None),
+        Some(entries) => entries,
+        None => return (None, None),
+    };
+
+    let (denied, allowed) = split_entries(entries);
+    let allowed: Vec<_> = allowed.iter().flat_map(PathPattern::expand).collect();
+    let denied: Vec<_> = denied.iter().flat_map(PathPattern::expand).collect();
+
+    // Avoid turning globs without any matches into "allow all".
+    ((!allowed.is_empty()).then_some(allowed), (!denied.is_empty()).then_some(denied))
+}
+
+/// Convert network permissions to Deno's allowed and denied domains.
+///
+/// Since Deno does not support wildcard subdomains, only exact domains are
+/// allowed and all domains matching a denied wildcard are removed from them.
+#[cfg(feature = "extensions")]
+fn deno_domains(permission: &Permission) -> (Option<Vec<String>>, Option<Vec<String>>) {
+    let entries = match permission.get() {
+        Some(entries) if entries.is_empty() => return (Some(Vec::new()), None),
+        Some(entries) => entries,
+        None => return (None, None),
+    };
+
+    let (denied, allowed): (Vec<_>, Vec<_>) =
+        entries.iter().partition(|entry| entry.starts_with(DENY_PREFIX));
+    let denied: Vec<_> = denied.iter().map(|entry| &entry[1..]).collect();
+
+    let allowed: Vec<String> = allowed
+        .into_iter()
+        .filter(|domain| !domain.starts_with("*."))
+        .filter(|domain| !denied.iter().any(|denied| domain_entry_includes(denied, domain)))
+        .cloned()
+        .collect();
+    let denied: Vec<String> =
+        denied.into_iter().filter(|domain| !domain.starts_with("*.")).map(String::from).collect();
+
+    ((!allowed.is_empty()).then_some(allowed), (!denied.is_empty()).then_some(denied))
+}
+
+/// Sandbox exceptions for filesystem permission entries.
+///
+/// Glob patterns are expanded to all existing paths matching them. Since
+/// sandbox exceptions always include all children of a path, directories with
+/// denied children are replaced by all of their children which are not denied.
+///
+/// Denied read paths are also excluded from write and execute exceptions, since
+/// both imply read access.
+#[cfg(unix)]
+pub fn filesystem_exceptions(read: &[String], write: &[String], run: &[String]) -> Vec<Exception> {
+    // Commands are resolved from `$PATH` outside of the sandbox.
+    let run: Vec<_> = run
+        .iter()
+        .map(|entry| {
+            if entry.starts_with(DENY_PREFIX) {
+                entry.clone()
+            } else {
+                resolve_bin_path(entry).display().to_string()
+            }
+        })
+        .collect();
+
+    let (read_denied, read_allowed) = split_entries(read);
+    let (mut write_denied, write_allowed) = split_entries(write);
+    let (mut run_denied, run_allowed) = split_entries(&run);
+    write_denied.extend_from_slice(&read_denied);
+    run_denied.extend_from_slice(&read_denied);
+
+    let read = exception_paths(&read_allowed, &read_denied).into_iter().map(Exception::Read);
+    let write =
+        exception_paths(&write_allowed, &write_denied).into_iter().map(Exception::WriteAndRead);
+    let run = exception_paths(&run_allowed, &run_denied).into_iter().map(Exception::ExecuteAndRead);
+
+    read.chain(write).chain(run).collect()
+}
+
+/// Resolve allowed path patterns to exception paths excluding denied paths.
+#[cfg(unix)]
+fn exception_paths(allowed: &[PathPattern], denied: &[PathPattern]) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    for path in allowed.iter().flat_map(PathPattern::expand) {
+        add_allowed_path(path, denied, &mut paths);
+    }
+    paths
+}
+
+/// Add a path to the exception paths, excluding all its denied children.
+#[cfg(unix)]
+fn add_allowed_path(path: PathBuf, denied: &[PathPattern], paths: &mut Vec<PathBuf>) {
+    if denied.iter().any(|pattern| pattern.matches(&path)) {
+        return;
+    }
+
+    if !denied.iter().any(|pattern| pattern.matches_children(&path)) {
+        paths.push(path);
+        return;
+    }
+
+    match fs::symlink_metadata(&path) {
+        // Skip directory symlinks, since their children cannot be excluded without
+        // following them.
+        Ok(metadata) if metadata.is_symlink() && path.is_dir() => (),
+        Ok(metadata) if metadata.is_dir() => {
+            for entry in fs::read_dir(&path).into_iter().flatten().flatten() {
+                add_allowed_path(entry.path(), denied, paths);
+            }
+        },
+        _ => paths.push(path),
+    }
+}
+
+/// Split permission list entries into denied and allowed path patterns.
+fn split_entries(entries: &[String]) -> (Vec<PathPattern>, Vec<PathPattern>) {
+    let mut denied = Vec::new();
+    let mut allowed = Vec::new();
+    for entry in entries {
+        match entry.strip_prefix(DENY_PREFIX) {
+            Some(entry) => denied.push(PathPattern::new(entry)),
+            None => allowed.push(PathPattern::new(entry)),
+        }
+    }
+    (denied, allowed)
+}
+
+/// Glob pattern matching paths and all their children.
+#[derive(Clone, Debug)]
+struct PathPattern {
+    components: Vec<PatternComponent>,
+}
+
+impl PathPattern {
+    fn new(pattern: &str) -> Self {
+        let components = path_components(Path::new(pattern))
+            .into_iter()
+            .map(|component| {
+                if component == "**" {
+                    PatternComponent::Recursive
+                } else if component.contains(['*', '?']) {
+                    PatternComponent::Glob(component.chars().collect())
+                } else {
+                    PatternComponent::Literal(component)
+                }
+            })
+            .collect();
+        Self { components }
+    }
+
+    /// Check if the pattern matches a path or any of its parents.
+    #[cfg(unix)]
+    fn matches(&self, path: &Path) -> bool {
+        matches_prefix(&self.components, &path_components(path))
+    }
+
+    /// Check if the pattern could match any children of a path.
+    #[cfg(unix)]
+    fn matches_children(&self, path: &Path) -> bool {
+        matches_children(&self.components, &path_components(path))
+    }
+
+    /// Check if all paths matched by `other` are also matched by this pattern.
+    ///
+    /// This might return `false` for complex patterns which do match all
+    /// paths of `other`, but it never returns `true` when they do not.
+    fn includes(&self, other: &PathPattern) -> bool {
+        includes(&self.components, &other.components)
+    }
+
+    /// Find all paths matching the pattern.
+    ///
+    /// Globs are only expanded to existing paths, while the components
+    /// following them do not need to exist.
+    #[cfg(any(unix, feature = "extensions"))]
+    fn expand(&self) -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+        expand(&self.components, PathBuf::new(), &mut paths);
+        paths.sort_unstable();
+        paths.dedup();
+        paths
+    }
+}
+
+/// Single component of a [`PathPattern`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum PatternComponent {
+    /// Exact path component.
+    Literal(String),
+    /// Path component with `*` or `?` wildcards.
+    Glob(Vec<char>),
+    /// Any number of path components.
+    Recursive,
+}
+
+impl PatternComponent {
+    /// Check if this component matches a path component.
+    fn matches(&self, component: &str) -> bool {
+        match self {
+            Self::Literal(literal) => literal == component,
+            Self::Glob(glob) => glob_matches(glob, &component.chars().collect::<Vec<_>>()),
+            Self::Recursive => true,
+        }
+    }
+
+    /// Check if all path components matched by `other` are also matched by this
+    /// component.
+    ///
+    /// A recursive `other` component is treated as matching any single path
+    /// component.
+    fn includes(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Recursive, _) => true,
+            (Self::Literal(literal), Self::Literal(other)) => literal == other,
+            (Self::Literal(_), _) => false,
+            (Self::Glob(_), Self::Literal(literal)) => self.matches(literal),
+            (Self::Glob(glob), Self::Glob(other)) => glob_includes(glob, other),
+            (Self::Glob(glob), Self::Recursive) => glob_includes(glob, &['*']),
+        }
+    }
+}
+
+/// Split a path into its components.
+fn path_components(path: &Path) -> Vec<String> {
+    path.components()
+        .map(|component| match component {
+            Component::RootDir => "/".into(),
+            component => component.as_os_str().to_string_lossy().into_owned(),
+        })
+        .collect()
+}
+
+/// Check if a pattern matches a path or any of its parents.
+#[cfg(unix)]
+fn matches_prefix(pattern: &[PatternComponent], path: &[String]) -> bool {
+    match (pattern.split_first(), path.split_first()) {
+        (None, _) => true,
+        (Some((PatternComponent::Recursive, rest)), _) => {
+            matches_prefix(rest, path) || (!path.is_empty() && matches_prefix(pattern, &path[1..]))
+        },
+        (Some((component, rest)), Some((head, tail))) => {
+            component.matches(head) && matches_prefix(rest, tail)
+        },
+        (Some(_), None) => false,
+    }
+}
+
+/// Check if a pattern could match any children of a path.
+#[cfg(unix)]
+fn matches_children(pattern: &[PatternComponent], path: &[String]) -> bool {
+    match (pattern.split_first(), path.split_first()) {
+        (None, _) => false,
+        (Some((PatternComponent::Recursive, _)), _) => true,
+        (Some(_), None) => true,
+        (Some((component, rest)), Some((head, tail))) => {
+            component.matches(head) && matches_children(rest, tail)
+        },
+    }
+}
+
+/// Check if all paths matched by the `other` pattern are matched by `pattern`.
+fn includes(pattern: &[PatternComponent], other: &[PatternComponent]) -> bool {
+    use PatternComponent::Recursive;
+
+    match (pattern.split_first(), other.split_first()) {
+        (None, _) => true,
+        (Some((Recursive, rest)), _) => {
+            includes(rest, other) || (!other.is_empty() && includes(pattern, &other[1..]))
+        },
+        (Some(_), None) => false,
+        // A recursive component might match no components, or any number of
+        // components which all have to be matched by `component`.
+        (Some((component, rest)), Some((Recursive, tail))) => {
+            includes(pattern, tail) && component.includes(&Recursive) && includes(rest, other)
+        },
+        (Some((component, rest)), Some((head, tail))) => {
+            component.includes(head) && includes(rest, tail)
+        },
+    }
+}
+
+/// Check if a glob matches a path component.
+fn glob_matches(glob: &[char], text: &[char]) -> bool {
+    match glob.split_first() {
+        None => text.is_empty(),
+        Some(('*', rest)) => (0..=text.len()).any(|i| glob_matches(rest, &text[i..])),
+        Some(('?', rest)) => !text.is_empty() && glob_matches(rest, &text[1..]),
+        Some((c, rest)) => text.first() == Some(c) && glob_matches(rest, &text[1..]),
+    }
+}
+
+/// Check if all path components matched by the `other` glob are matched by
+/// `glob`.
+fn glob_includes(glob: &[char], other: &[char]) -> bool {
+    match (glob.split_first(), other.split_first()) {
+        (None, _) => other.is_empty(),
+        (Some(('*', rest)), _) => {
+            glob_includes(rest, other) || (!other.is_empty() && glob_includes(glob, &other[1..]))
+        },
+        (Some(_), None) => false,
+        (Some(('?', rest)), Some((c, tail))) => *c != '*' && glob_includes(rest, tail),
+        (Some((c, rest)), Some((other_c, tail))) => c == other_c && glob_includes(rest, tail),
+    }
+}
+
+/// Add all existing paths matching a pattern below `path`.
+#[cfg(any(unix, feature = "extensions"))]
+fn expand(pattern: &[PatternComponent], path: PathBuf, paths: &mut Vec<PathBuf>) {
+    let (component, rest) = match pattern.split_first() {
+        Some(split) => split,
+        None => {
+            paths.push(path);
+            return;
+        },
+    };
+
+    // Read directory entries, using the working directory for relative patterns.
+    let entries = || {
+        let dir = if path.as_os_str().is_empty() { Path::new(".") } else { path.as_path() };
+        fs::read_dir(dir).into_iter().flatten().flatten()
+    };
+
+    match component {
+        PatternComponent::Literal(literal) => expand(rest, path.join(literal), paths),
+        PatternComponent::Glob(_) => {
+            for entry in entries() {
+                if component.matches(&entry.file_name().to_string_lossy()) {
+                    expand(rest, entry.path(), paths);
+                }
+            }
+        },
+        PatternComponent::Recursive => {
+            // Symlinks are not followed, to avoid infinite recursion.
+            for entry in entries().filter(|entry| entry.file_type().is_ok_and(|ty| ty.is_dir())) {
+                expand(pattern, entry.path(), paths);
+            }
+            expand(rest, path, paths);
+        },
+    }
+}
+
+/// Check if all domains matched by the `other` network permission entry are
+/// also matched by `entry`.
+///
+/// Entries without a port match the domain on all ports.
+fn domain_entry_includes(entry: &str, other: &str) -> bool {
+    let (entry_host, entry_port) = split_port(entry);
+    let (other_host, other_port) = split_port(other);
+
+    // Wildcards are only included by wildcards of the same or a parent domain.
+    let host_included = match other_host.strip_prefix("*.") {
+        Some(other_domain) => entry_host.strip_prefix("*.").is_some_and(|domain| {
+            domain.eq_ignore_ascii_case(other_domain) || domain_matches(entry_host, other_domain)
+        }),
+        None => domain_matches(entry_host, other_host),
+    };
+
+    host_included && (entry_port.is_none() || entry_port == other_port)
+}
+
+/// Split the port from a network permission entry.
+fn split_port(entry: &str) -> (&str, Option<&str>) {
+    match entry.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') || host.ends_with(']') => (host, Some(port)),
+        _ => (entry, None),
+    }
+}
+
+/// Check if a domain pattern matches a host.
+///
+/// Patterns starting with `*.` match all subdomains of the remaining domain.
+pub fn domain_matches(pattern: &str, host: &str) -> bool {
+    let host = host.to_ascii_lowercase();
+    let pattern = pattern.to_ascii_lowercase();
+
+    match pattern.strip_prefix("*.") {
+        Some(domain) => host
+            .strip_suffix(domain)
+            .is_some_and(|subdomain| subdomain.len() > 1 && subdomain.ends_with('.')),
+        None => pattern == host,
+    }
+}
+
 /// Construct sandbox with a set of pre-defined acceptable exceptions.
 #[cfg(unix)]
 pub fn default_sandbox() -> SandboxResult<Birdcage> {
     let mut birdcage = Birdcage::new();
+    for exception in default_exceptions() {
+        add_exception(&mut birdcage, exception)?;
+    }
+    Ok(birdcage)
+}
+
+/// Pre-defined acceptable exceptions of the default sandbox.
+#[cfg(unix)]
+pub fn default_exceptions() -> Vec<Exception> {
+    let mut exceptions = Vec::new();
 
     // Permit read access to lib for dynamic linking.
-    add_exception(&mut birdcage, Exception::ExecuteAndRead("/usr/lib".into()))?;
-    add_exception(&mut birdcage, Exception::ExecuteAndRead("/usr/lib32".into()))?;
-    add_exception(&mut birdcage, Exception::ExecuteAndRead("/usr/libx32".into()))?;
-    add_exception(&mut birdcage, Exception::ExecuteAndRead("/usr/lib64".into()))?;
-    add_exception(&mut birdcage, Exception::ExecuteAndRead("/lib".into()))?;
-    add_exception(&mut birdcage, Exception::ExecuteAndRead("/lib32".into()))?;
-    add_exception(&mut birdcage, Exception::ExecuteAndRead("/libx32".into()))?;
-    add_exception(&mut birdcage, Exception::ExecuteAndRead("/lib64".into()))?;
-    add_exception(&mut birdcage, Exception::ExecuteAndRead("/opt/homebrew".into()))?;
-    add_exception(&mut birdcage, Exception::ExecuteAndRead("/usr/local".into()))?;
+    exceptions.push(Exception::ExecuteAndRead("/usr/lib".into()));
+    exceptions.push(Exception::ExecuteAndRead("/usr/lib32".into()));
+    exceptions.push(Exception::ExecuteAndRead("/usr/libx32".into()));
+    exceptions.push(Exception::ExecuteAndRead("/usr/lib64".into()));
+    exceptions.push(Exception::ExecuteAndRead("/lib".into()));
+    exceptions.push(Exception::ExecuteAndRead("/lib32".into()));
+    exceptions.push(Exception::ExecuteAndRead("/libx32".into()));
+    exceptions.push(Exception::ExecuteAndRead("/lib64".into()));
+    exceptions.push(Exception::ExecuteAndRead("/opt/homebrew".into()));
+    exceptions.push(Exception::ExecuteAndRead("/usr/local".into()));
 
     // NixOS stores all system files under /nix/store.
-    add_exception(&mut birdcage, Exception::ExecuteAndRead("/nix/store".into()))?;
+    exceptions.push(Exception::ExecuteAndRead("/nix/store".into()));
 
     // Allow access to DNS list.
     //
     // While this is required to send DNS requests for network queries, this does
     // not automatically allow any network access.
-    add_exception(&mut birdcage, Exception::Read("/etc/resolv.conf".into()))?;
+    exceptions.push(Exception::Read("/etc/resolv.conf".into()));
 
     // Allow reading SSL certificates.
-    add_exception(&mut birdcage, Exception::Read("/etc/ca-certificates".into()))?;
-    add_exception(&mut birdcage, Exception::Read("/etc/ssl".into()))?;
+    exceptions.push(Exception::Read("/etc/ca-certificates".into()));
+    exceptions.push(Exception::Read("/etc/ssl".into()));
 
     // Allow mime types
-    add_exception(&mut birdcage, Exception::Read("/etc/apache2/mime.types".into()))?;
+    exceptions.push(Exception::Read("/etc/apache2/mime.types".into()));
 
     // Allow release info
-    add_exception(&mut birdcage, Exception::Read("/etc/os-release".into()))?;
-    add_exception(&mut birdcage, Exception::Read("/etc/debian_version".into()))?;
+    exceptions.push(Exception::Read("/etc/os-release".into()));
+    exceptions.push(Exception::Read("/etc/debian_version".into()));
 
     // Allow `env` exec to resolve binary paths.
-    add_exception(&mut birdcage, Exception::ExecuteAndRead("/usr/bin/env".into()))?;
+    exceptions.push(Exception::ExecuteAndRead("/usr/bin/env".into()));
 
     // Allow write access to null-sink.
-    add_exception(&mut birdcage, Exception::WriteAndRead("/dev/null".into()))?;
+    exceptions.push(Exception::WriteAndRead("/dev/null".into()));
 
     // Allow applications to read from `$PATH`.
-    birdcage.add_exception(Exception::Environment("PATH".into()))?;
+    exceptions.push(Exception::Environment("PATH".into()));
 
-    Ok(birdcage)
+    exceptions
+}
+
+/// Sandbox exceptions for routing traffic through a
+/// [`FilteringProxy`](crate::proxy::FilteringProxy).
+#[cfg(unix)]
+pub fn proxy_exceptions() -> Vec<Exception> {
+    proxy::PROXY_ENV_VARS
+        .into_iter()
+        .chain(proxy::NO_PROXY_ENV_VARS)
+        .map(|var| Exception::Environment(var.into()))
+        .collect()
 }
 
 /// Add an exception to the sandbox, ignoring invalid path errors.
@@ -398,6 +1032,7 @@ mod tests {
             run: Permission::List(vec![]),
             net: Permission::List(vec![]),
             unsandboxed_run: Permission::List(vec![]),
+            limits: ResourceLimits::default(),
         };
 
         let permissions_options = PermissionsOptions::from(&permissions);
@@ -425,6 +1060,7 @@ mod tests {
             run: Permission::Boolean(true),
             net: Permission::Boolean(true),
             unsandboxed_run: Permission::Boolean(true),
+            limits: ResourceLimits::default(),
         };
 
         let permissions_options = PermissionsOptions::from(&permissions);
@@ -576,6 +1212,451 @@ mod tests {
         assert!(matches!(&child.subset_of(&parent), Ok(Permission::Boolean(false))));
     }
 
+    #[test]
+    fn deny_entries_restrict_subset() {
+        let parent = Permission::List(vec![
+            "/home/user/.config".into(),
+            "!/home/user/.config/*/credentials".into(),
+        ]);
+
+        // Partially denied paths inherit the parent's deny entries.
+        let child = Permission::List(vec!["/home/user/.config/gcloud".into()]);
+        assert_eq!(
+            child.subset_of(&parent).unwrap(),
+            Permission::List(vec![
+                "/home/user/.config/gcloud".into(),
+                "!/home/user/.config/*/credentials".into(),
+            ])
+        );
+
+        // Fully denied paths are rejected.
+        let child = Permission::List(vec!["/home/user/.config/gcloud/credentials/key".into()]);
+        assert!(child.subset_of(&parent).is_err());
+
+        // Globs must be included in the parent's allowed paths.
+        let child = Permission::List(vec!["/home/user/*".into()]);
+        assert!(child.subset_of(&parent).is_err());
+        let child = Permission::List(vec!["/home/user/.config/*".into()]);
+        assert!(child.subset_of(&parent).is_ok());
+
+        // Child deny entries are always accepted.
+        let child = Permission::List(vec!["!/etc".into()]);
+        assert!(child.subset_of(&parent).is_ok());
+    }
+
+    #[test]
+    fn wildcard_domains_subset() {
+        let domains = |domains: &[&str]| {
+            Permission::List(domains.iter().map(|domain| domain.to_string()).collect())
+        };
+        let parent = domains(&["*.internal.example.com", "!secret.internal.example.com"]);
+
+        assert!(domains(&["api.internal.example.com"]).subset_of_domains(&parent).is_ok());
+        assert!(domains(&["*.dev.internal.example.com"]).subset_of_domains(&parent).is_ok());
+        assert!(domains(&["*.internal.example.com"]).subset_of_domains(&parent).is_ok());
+        assert!(domains(&["internal.example.com"]).subset_of_domains(&parent).is_err());
+        assert!(domains(&["*.example.com"]).subset_of_domains(&parent).is_err());
+        assert!(domains(&["secret.internal.example.com"]).subset_of_domains(&parent).is_err());
+        assert!(domains(&["API.Internal.example.com"]).subset_of_domains(&parent).is_ok());
+
+        // Entries without a port include all ports.
+        assert!(domains(&["api.internal.example.com:443"]).subset_of_domains(&parent).is_ok());
+        let parent = domains(&["*.internal.example.com:443"]);
+        assert!(domains(&["api.internal.example.com:443"]).subset_of_domains(&parent).is_ok());
+        assert!(domains(&["api.internal.example.com"]).subset_of_domains(&parent).is_err());
+        assert!(domains(&["*.internal.example.com:80"]).subset_of_domains(&parent).is_err());
+    }
+
+    #[test]
+    fn env_subset_is_literal() {
+        let parent = Permissions {
+            env: Permission::List(vec!["AWS_*".into(), "HOME".into()]),
+            ..Permissions::default()
+        };
+
+        let child = Permissions {
+            env: Permission::List(vec!["AWS_SECRET_ACCESS_KEY".into()]),
+            ..Permissions::default()
+        };
+        assert!(child.subset_of(&parent).is_err());
+
+        let child =
+            Permissions { env: Permission::List(vec!["HOME".into()]), ..Permissions::default() };
+        assert!(child.subset_of(&parent).is_ok());
+    }
+
+    #[test]
+    fn limits_subset() {
+        let parent = ResourceLimits { memory: Some(512), output: Some(16), ..Default::default() };
+
+        let child = ResourceLimits { memory: Some(1024), ..Default::default() };
+        assert!(child.subset_of(&parent).is_err());
+
+        let child = ResourceLimits { memory: Some(256), cpu_time: Some(60), ..Default::default() };
+        assert_eq!(child.subset_of(&parent).unwrap(), ResourceLimits {
+            memory: Some(256),
+            cpu_time: Some(60),
+            output: Some(16),
+            ..Default::default()
+        });
+
+        let limits = ResourceLimits::default().subset_of(&parent).unwrap();
+        assert_eq!(limits.sandbox_args(), ["--max-memory", "512", "--max-output", "16"]);
+    }
+
+    #[test]
+    fn deserialize_limits() {
+        let toml = "[limits]\nprocesses = 64\nmemory = 2048\ncpu_time = 300";
+        let permissions = toml::from_str::<Permissions>(toml).unwrap();
+        assert_eq!(permissions.limits, ResourceLimits {
+            processes: Some(64),
+            memory: Some(2048),
+            cpu_time: Some(300),
+            ..Default::default()
+        });
+
+        let toml = "[limits]\nthreads = 64";
+        assert!(toml::from_str::<Permissions>(toml).is_err());
+    }
+
+    #[test]
+    fn domain_wildcard_matching() {
+        assert!(domain_matches("*.example.com", "api.example.com"));
+        assert!(domain_matches("*.example.com", "a.b.example.com"));
+        assert!(domain_matches("*.Example.com", "API.example.COM"));
+        assert!(domain_matches("example.com", "example.com"));
+        assert!(!domain_matches("*.example.com", "example.com"));
+        assert!(!domain_matches("*.example.com", ".example.com"));
+        assert!(!domain_matches("*.example.com", "evilexample.com"));
+        assert!(!domain_matches("example.com", "api.example.com"));
+    }
+
+    #[test]
+    fn deserialize_net_wildcards() {
+        let permissions = toml::from_str::<Permissions>(
+            r#"net = ["*.internal.example.com", "!secret.internal.example.com"]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            permissions.net,
+            Permission::List(vec![
+                "*.internal.example.com".into(),
+                "!secret.internal.example.com".into()
+            ])
+        );
+
+        for invalid in [r#"net = ["*"]"#, r#"net = ["api.*.com"]"#, r#"net = ["!*.a?.com"]"#] {
+            assert!(toml::from_str::<Permissions>(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn deserialize_deny_entries() {
+        let home = dirs::home_dir().unwrap();
+
+        let permissions =
+            toml::from_str::<Permissions>(r#"read = ["~/.config", "!~/.config/*/credentials"]"#)
+                .unwrap();
+
+        assert_eq!(
+            permissions.read,
+            Permission::List(vec![
+                home.join(".config").display().to_string(),
+                format!("!{}", home.join(".config/*/credentials").display()),
+            ])
+        );
+    }
+
+    #[cfg(feature = "extensions")]
+    #[test]
+    fn deny_entries_become_deno_deny_lists() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let config = tempdir.path().join(".config");
+        fs::create_dir_all(config.join("gcloud/credentials")).unwrap();
+        fs::create_dir_all(config.join("npm")).unwrap();
+
+        let permissions = Permissions {
+            read: Permission::List(vec![
+                config.display().to_string(),
+                format!("!{}/*/credentials", config.display()),
+            ]),
+            write: Permission::List(vec![format!("{}/n*", config.display())]),
+            net: Permission::List(vec![
+                "*.internal.example.com".into(),
+                "secret.internal.example.com".into(),
+                "registry.npmjs.org".into(),
+                "!*.internal.example.com".into(),
+                "!evil.example.com".into(),
+            ]),
+            ..Permissions::default()
+        };
+
+        let options = PermissionsOptions::from(&permissions);
+
+        assert_eq!(options.allow_read, Some(vec![config.clone()]));
+        assert_eq!(
+            options.deny_read,
+            Some(vec![config.join("gcloud/credentials"), config.join("npm/credentials")])
+        );
+        assert_eq!(options.allow_write, Some(vec![config.join("npm")]));
+        assert_eq!(options.deny_write, None);
+        assert_eq!(
+            options.allow_net,
+            Some(vec![
+                "registry.npmjs.org".into(),
+                "api.staging.phylum.io".into(),
+                "api.phylum.io".into()
+            ])
+        );
+        assert_eq!(options.deny_net, Some(vec!["evil.example.com".into()]));
+
+        // Globs without any matches must not allow everything.
+        let permissions = Permissions {
+            read: Permission::List(vec![format!("{}/missing/*", config.display())]),
+            ..Permissions::default()
+        };
+        assert_eq!(PermissionsOptions::from(&permissions).allow_read, None);
+    }
+
+    /// Property tests comparing pattern algorithms against brute-force
+    /// matching over a small universe of paths.
+    #[cfg(unix)]
+    mod properties {
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+
+        use super::*;
+
+        const ITERATIONS: usize = 2000;
+        const NAMES: [&str; 3] = ["a", "b", "ab"];
+        const PATTERN_COMPONENTS: [&str; 8] = ["a", "b", "ab", "*", "?", "a*", "*b", "**"];
+
+        /// All paths below `/` with up to four components from [`NAMES`].
+        fn universe() -> Vec<PathBuf> {
+            let mut paths = vec![PathBuf::from("/")];
+            let mut start = 0;
+            for _ in 0..4 {
+                let end = paths.len();
+                for i in start..end {
+                    for name in NAMES {
+                        let path = paths[i].join(name);
+                        paths.push(path);
+                    }
+                }
+                start = end;
+            }
+            paths
+        }
+
+        fn random_pattern(rng: &mut StdRng) -> String {
+            let mut pattern = String::new();
+            for _ in 0..rng.random_range(1..=3) {
+                pattern.push('/');
+                pattern.push_str(PATTERN_COMPONENTS[rng.random_range(0..PATTERN_COMPONENTS.len())]);
+            }
+            pattern
+        }
+
+        fn random_entries(rng: &mut StdRng, deny_probability: f64) -> Vec<String> {
+            (0..rng.random_range(0..=3))
+                .map(|_| {
+                    let pattern = random_pattern(rng);
+                    if rng.random_bool(deny_probability) {
+                        format!("!{pattern}")
+                    } else {
+                        pattern
+                    }
+                })
+                .collect()
+        }
+
+        /// Brute-force check if a list of entries grants access to a path.
+        fn grants(entries: &[String], path: &Path) -> bool {
+            let (denied, allowed) = split_entries(entries);
+            allowed.iter().any(|pattern| pattern.matches(path))
+                && !denied.iter().any(|pattern| pattern.matches(path))
+        }
+
+        #[test]
+        fn glob_includes_is_sound() {
+            let mut rng = StdRng::seed_from_u64(0);
+            let alphabet = ['a', 'b', '*', '?'];
+            let random_glob = |rng: &mut StdRng| -> Vec<char> {
+                (0..rng.random_range(0..=4)).map(|_| alphabet[rng.random_range(0..4)]).collect()
+            };
+
+            // All strings with up to five characters from `a` and `b`.
+            let mut texts = vec![Vec::new()];
+            for i in 0.. {
+                if texts[i].len() == 5 {
+                    break;
+                }
+                for c in ['a', 'b'] {
+                    let mut text = texts[i].clone();
+                    text.push(c);
+                    texts.push(text);
+                }
+            }
+
+            for _ in 0..ITERATIONS {
+                let glob = random_glob(&mut rng);
+                let other = random_glob(&mut rng);
+
+                assert!(glob_includes(&glob, &glob), "{glob:?} does not include itself");
+
+                if glob_includes(&glob, &other) {
+                    for text in texts.iter().filter(|text| glob_matches(&other, text)) {
+                        assert!(
+                            glob_matches(&glob, text),
+                            "{glob:?} includes {other:?}, but does not match {text:?}"
+                        );
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn pattern_includes_is_sound() {
+            let mut rng = StdRng::seed_from_u64(1);
+            let universe = universe();
+
+            for _ in 0..ITERATIONS {
+                let pattern = random_pattern(&mut rng);
+                let other = random_pattern(&mut rng);
+                let (pattern_path, other_path) =
+                    (PathPattern::new(&pattern), PathPattern::new(&other));
+
+                assert!(pattern_path.includes(&pattern_path), "{pattern} does not include itself");
+
+                if pattern_path.includes(&other_path) {
+                    for path in universe.iter().filter(|path| other_path.matches(path)) {
+                        assert!(
+                            pattern_path.matches(path),
+                            "{pattern} includes {other}, but does not match {path:?}"
+                        );
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn literal_pattern_includes_is_exact() {
+            let universe = universe();
+
+            // Without globs, inclusion is equivalent to `Path::starts_with`.
+            for path in &universe {
+                for parent in &universe {
+                    let pattern = PathPattern::new(&parent.display().to_string());
+                    let other = PathPattern::new(&path.display().to_string());
+                    assert_eq!(pattern.includes(&other), path.starts_with(parent));
+                    assert_eq!(pattern.matches(path), path.starts_with(parent));
+                }
+            }
+        }
+
+        #[test]
+        fn matches_children_is_sound() {
+            let mut rng = StdRng::seed_from_u64(2);
+            let universe = universe();
+
+            for _ in 0..ITERATIONS {
+                let pattern = random_pattern(&mut rng);
+                let path_pattern = PathPattern::new(&pattern);
+
+                for path in universe.iter().filter(|path| !path_pattern.matches(path)) {
+                    let child_matches = universe.iter().any(|child| {
+                        child != path && child.starts_with(path) && path_pattern.matches(child)
+                    });
+                    if child_matches {
+                        assert!(
+                            path_pattern.matches_children(path),
+                            "{pattern} matches children of {path:?}"
+                        );
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn subset_is_intersection() {
+            let mut rng = StdRng::seed_from_u64(3);
+            let universe = universe();
+
+            let mut accepted = 0;
+            for _ in 0..ITERATIONS {
+                let parent = random_entries(&mut rng, 0.3);
+                let child = random_entries(&mut rng, 0.2);
+
+                let result = match Permission::List(child.clone())
+                    .subset_of(&Permission::List(parent.clone()))
+                {
+                    Ok(Permission::List(result)) => result,
+                    Ok(result) => panic!("unexpected subset {result:?}"),
+                    Err(_) => continue,
+                };
+                accepted += 1;
+
+                for path in &universe {
+                    assert_eq!(
+                        grants(&result, path),
+                        grants(&child, path) && grants(&parent, path),
+                        "{child:?} subset of {parent:?} as {result:?} for {path:?}"
+                    );
+                }
+            }
+
+            // Ensure the generated permissions are not trivially rejected.
+            assert!(accepted > ITERATIONS / 10, "only {accepted} subsets accepted");
+        }
+
+        #[test]
+        fn filesystem_exceptions_match_permissions() {
+            let mut rng = StdRng::seed_from_u64(4);
+
+            // Create the universe's paths below a temporary directory, with files as
+            // leaves.
+            let tempdir = tempfile::tempdir().unwrap();
+            let root = tempdir.path().canonicalize().unwrap();
+            let mut files = Vec::new();
+            for path in universe().iter().filter(|path| path.components().count() == 4) {
+                let path = root.join(path.strip_prefix("/").unwrap());
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(&path, "").unwrap();
+                files.push(path);
+            }
+
+            let rooted = |entries: Vec<String>| -> Vec<String> {
+                entries
+                    .into_iter()
+                    .map(|entry| match entry.strip_prefix(DENY_PREFIX) {
+                        Some(path) => format!("!{}{path}", root.display()),
+                        None => format!("{}{entry}", root.display()),
+                    })
+                    .collect()
+            };
+
+            for _ in 0..ITERATIONS / 10 {
+                let read = rooted(random_entries(&mut rng, 0.4));
+
+                let paths: Vec<_> = filesystem_exceptions(&read, &[], &[])
+                    .into_iter()
+                    .map(|exception| match exception {
+                        Exception::Read(path) => path,
+                        exception => panic!("unexpected exception {exception:?}"),
+                    })
+                    .collect();
+
+                for file in &files {
+                    assert_eq!(
+                        paths.iter().any(|path| file.starts_with(path)),
+                        grants(&read, file),
+                        "{read:?} as {paths:?} for {file:?}"
+                    );
+                }
+            }
+        }
+    }
+
     #[test]
     fn resolve_bin_paths() {
         // Ensure none of these paths are accidentally resolved.
@@ -0,0 +1,416 @@
+//! Domain filtering proxy for sandboxed processes.
+//!
+//! Sandboxed processes which may only access specific domains are pointed at
+//! this proxy through the standard proxy environment variables. The proxy
+//! forwards HTTP requests and `CONNECT` tunnels to allowed domains and rejects
+//! and reports all other connections.
+//!
+//! Since the proxy is reached through the loopback interface, the sandbox
+//! itself must still permit networking. Processes which deliberately ignore
+//! the proxy environment variables are not restricted by the proxy.
+
+use std::collections::BTreeSet;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr, TcpListener as StdTcpListener};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::runtime::Builder as RuntimeBuilder;
+use tokio::sync::oneshot;
+
+use crate::{permissions, print_user_warning};
+
+/// Environment variables pointing clients at the proxy.
+pub const PROXY_ENV_VARS: [&str; 6] =
+    ["HTTP_PROXY", "HTTPS_PROXY", "ALL_PROXY", "http_proxy", "https_proxy", "all_proxy"];
+
+/// Environment variables listing hosts which bypass the proxy.
+pub const NO_PROXY_ENV_VARS: [&str; 2] = ["NO_PROXY", "no_proxy"];
+
+/// Maximum size of a request's header.
+const MAX_HEADER_SIZE: usize = 16 * 1024;
+
+/// Response for successfully established `CONNECT` tunnels.
+const CONNECTION_ESTABLISHED: &[u8] = b"HTTP/1.1 200 Connection Established\r\n\r\n";
+
+/// Response for malformed requests.
+const BAD_REQUEST: &[u8] = b"HTTP/1.1 400 Bad Request\r\nConnection: close\r\n\r\n";
+
+/// Response for requests to domains which are not allowed.
+const FORBIDDEN: &[u8] = b"HTTP/1.1 403 Forbidden\r\nConnection: close\r\n\r\n";
+
+/// Response for allowed domains which could not be reached.
+const BAD_GATEWAY: &[u8] = b"HTTP/1.1 502 Bad Gateway\r\nConnection: close\r\n\r\n";
+
+/// HTTP proxy only permitting access to a list of domains.
+///
+/// The proxy runs on a background thread until it is dropped.
+pub struct FilteringProxy {
+    denied: Arc<Mutex<BTreeSet<String>>>,
+    address: SocketAddr,
+    shutdown: Option<oneshot::Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl FilteringProxy {
+    /// Start a proxy on the loopback interface.
+    ///
+    /// Domains may contain a port, like `example.com:8080`, to only allow
+    /// access to that specific port. Domains starting with `*.` allow access to
+    /// all subdomains, while domains starting with `!` are denied.
+    pub fn start(allowed_domains: &[String]) -> io::Result<Self> {
+        let allowlist = Arc::new(Allowlist::new(allowed_domains));
+        let denied = Arc::new(Mutex::new(BTreeSet::new()));
+
+        let listener = StdTcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        listener.set_nonblocking(true)?;
+        let address = listener.local_addr()?;
+
+        let runtime = RuntimeBuilder::new_current_thread().enable_all().build()?;
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+        let thread = {
+            let denied = denied.clone();
+            thread::spawn(move || {
+                runtime.block_on(serve(listener, allowlist, denied, shutdown_rx));
+            })
+        };
+
+        Ok(Self { denied, address, shutdown: Some(shutdown_tx), thread: Some(thread) })
+    }
+
+    /// Address of the proxy.
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Environment variables routing all traffic through the proxy.
+    pub fn environment(&self) -> Vec<(&'static str, String)> {
+        let url = format!("http://{}", self.address);
+        let proxy_vars = PROXY_ENV_VARS.into_iter().map(|var| (var, url.clone()));
+        let no_proxy_vars = NO_PROXY_ENV_VARS.into_iter().map(|var| (var, String::new()));
+        proxy_vars.chain(no_proxy_vars).collect()
+    }
+
+    /// Domains the proxy denied access to.
+    pub fn denied_domains(&self) -> Vec<String> {
+        self.denied.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+impl Drop for FilteringProxy {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Accept proxy connections until shutdown.
+async fn serve(
+    listener: StdTcpListener,
+    allowlist: Arc<Allowlist>,
+    denied: Arc<Mutex<BTreeSet<String>>>,
+    mut shutdown: oneshot::Receiver<()>,
+) {
+    let listener = match TcpListener::from_std(listener) {
+        Ok(listener) => listener,
+        Err(err) => {
+            print_user_warning!("Network proxy failed to start: {err}");
+            return;
+        },
+    };
+
+    loop {
+        tokio::select! {
+            _ = &mut shutdown => break,
+            accepted = listener.accept() => {
+                let Ok((client, _)) = accepted else { continue };
+                let allowlist = allowlist.clone();
+                let denied = denied.clone();
+                tokio::spawn(async move {
+                    let _ = handle_connection(client, &allowlist, &denied).await;
+                });
+            },
+        }
+    }
+}
+
+/// Forward a single client connection.
+async fn handle_connection(
+    mut client: TcpStream,
+    allowlist: &Allowlist,
+    denied: &Mutex<BTreeSet<String>>,
+) -> io::Result<()> {
+    let (head, body) = match read_head(&mut client).await? {
+        Some(request) => request,
+        None => return client.write_all(BAD_REQUEST).await,
+    };
+
+    let request = match Request::parse(&head) {
+        Some(request) => request,
+        None => return client.write_all(BAD_REQUEST).await,
+    };
+
+    if !allowlist.allows(&request.host, request.port) {
+        print_user_warning!("Sandbox denied network access to {request}");
+        denied.lock().unwrap().insert(request.host.clone());
+        return client.write_all(FORBIDDEN).await;
+    }
+
+    let mut upstream = match TcpStream::connect((request.host.as_str(), request.port)).await {
+        Ok(upstream) => upstream,
+        Err(_) => return client.write_all(BAD_GATEWAY).await,
+    };
+
+    // Tunnels start after the handshake, while plain requests are forwarded as-is.
+    if request.tunnel {
+        client.write_all(CONNECTION_ESTABLISHED).await?;
+    } else {
+        upstream.write_all(&head).await?;
+    }
+    upstream.write_all(&body).await?;
+
+    tokio::io::copy_bidirectional(&mut client, &mut upstream).await?;
+
+    Ok(())
+}
+
+/// Read the header of an HTTP request.
+///
+/// Returns the header and all bytes received after it, or `None` if the header
+/// is incomplete or too large.
+async fn read_head(client: &mut TcpStream) -> io::Result<Option<(Vec<u8>, Vec<u8>)>> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0; 4096];
+
+    loop {
+        if let Some(end) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            let body = buffer.split_off(end + 4);
+            return Ok(Some((buffer, body)));
+        }
+
+        if buffer.len() > MAX_HEADER_SIZE {
+            return Ok(None);
+        }
+
+        let len = client.read(&mut chunk).await?;
+        if len == 0 {
+            return Ok(None);
+        }
+        buffer.extend_from_slice(&chunk[..len]);
+    }
+}
+
+/// Target of a proxy request.
+#[derive(Debug, PartialEq, Eq)]
+struct Request {
+    host: String,
+    port: u16,
+    /// Whether the client requested a `CONNECT` tunnel.
+    tunnel: bool,
+}
+
+impl Request {
+    /// Parse the request line of an HTTP request header.
+    fn parse(head: &[u8]) -> Option<Self> {
+        let head = std::str::from_utf8(head).ok()?;
+        let request_line = head.lines().next()?;
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next()?;
+        let target = parts.next()?;
+
+        if method.eq_ignore_ascii_case("CONNECT") {
+            let (host, port) = split_host_port(target)?;
+            return Some(Self { host, port: port?, tunnel: true });
+        }
+
+        // Plain requests must use an absolute URI. HTTPS is only supported
+        // through tunnels.
+        let target = target.strip_prefix("http://")?;
+        let authority = target.split(['/', '?', '#']).next()?;
+        let authority = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+        let (host, port) = split_host_port(authority)?;
+
+        Some(Self { host, port: port.unwrap_or(80), tunnel: false })
+    }
+}
+
+impl Display for Request {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.host.contains(':') {
+            write!(f, "[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{}:{}", self.host, self.port)
+        }
+    }
+}
+
+/// Domains accessible through the proxy.
+///
+/// Domains starting with `!` are denied, even if they are matched by an
+/// allowed wildcard domain like `*.example.com`.
+#[derive(Debug)]
+struct Allowlist {
+    allowed: Vec<(String, Option<u16>)>,
+    denied: Vec<(String, Option<u16>)>,
+}
+
+impl Allowlist {
+    fn new(domains: &[String]) -> Self {
+        let mut allowed = Vec::new();
+        let mut denied = Vec::new();
+        for domain in domains {
+            let (entries, domain) = match domain.strip_prefix('!') {
+                Some(domain) => (&mut denied, domain),
+                None => (&mut allowed, domain.as_str()),
+            };
+            entries.extend(split_host_port(domain));
+        }
+        Self { allowed, denied }
+    }
+
+    /// Check if a connection to a host is allowed.
+    fn allows(&self, host: &str, port: u16) -> bool {
+        let host = normalize_host(host);
+        let matches = |(pattern, entry_port): &(String, Option<u16>)| {
+            permissions::domain_matches(pattern, &host)
+                && entry_port.is_none_or(|entry_port| entry_port == port)
+        };
+        self.allowed.iter().any(matches) && !self.denied.iter().any(matches)
+    }
+}
+
+/// Split an authority into its normalized host and optional port.
+fn split_host_port(authority: &str) -> Option<(String, Option<u16>)> {
+    let (host, port) = match authority.strip_prefix('[') {
+        // Bracketed IPv6 address.
+        Some(authority) => {
+            let (host, rest) = authority.split_once(']')?;
+            let port = match rest.strip_prefix(':') {
+                Some(port) => Some(port.parse().ok()?),
+                None if rest.is_empty() => None,
+                None => return None,
+            };
+            (host, port)
+        },
+        None => match authority.split_once(':') {
+            Some((host, port)) => (host, Some(port.parse().ok()?)),
+            None => (authority, None),
+        },
+    };
+
+    let host = normalize_host(host);
+    (!host.is_empty()).then_some((host, port))
+}
+
+/// Normalize a host for comparison.
+fn normalize_host(host: &str) -> String {
+    host.trim_end_matches('.').to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpStream as StdTcpStream;
+
+    use super::*;
+
+    /// Send a raw request to the proxy and read the first response line.
+    fn proxy_request(proxy: &FilteringProxy, request: &str) -> (StdTcpStream, String) {
+        let mut stream = StdTcpStream::connect(proxy.address()).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+
+        let mut response = Vec::new();
+        let mut byte = [0];
+        while !response.ends_with(b"\r\n\r\n") {
+            stream.read_exact(&mut byte).unwrap();
+            response.push(byte[0]);
+        }
+
+        let status = String::from_utf8(response).unwrap().lines().next().unwrap().to_string();
+        (stream, status)
+    }
+
+    #[test]
+    fn allowlist_matching() {
+        let allowlist = Allowlist::new(&["Registry.npmjs.org".into(), "example.com:8080".into()]);
+
+        assert!(allowlist.allows("registry.npmjs.org", 443));
+        assert!(allowlist.allows("registry.npmjs.org.", 80));
+        assert!(allowlist.allows("example.com", 8080));
+        assert!(!allowlist.allows("example.com", 443));
+        assert!(!allowlist.allows("evil.npmjs.org", 443));
+        assert!(!allowlist.allows("npmjs.org", 443));
+    }
+
+    #[test]
+    fn allowlist_wildcards() {
+        let allowlist = Allowlist::new(&[
+            "*.internal.example.com".into(),
+            "!secret.internal.example.com".into(),
+            "!*.dev.internal.example.com:443".into(),
+        ]);
+
+        assert!(allowlist.allows("api.internal.example.com", 443));
+        assert!(allowlist.allows("A.B.Internal.Example.com", 80));
+        assert!(allowlist.allows("x.dev.internal.example.com", 8080));
+        assert!(!allowlist.allows("x.dev.internal.example.com", 443));
+        assert!(!allowlist.allows("secret.internal.example.com", 443));
+        assert!(!allowlist.allows("internal.example.com", 443));
+        assert!(!allowlist.allows("evilinternal.example.com", 443));
+    }
+
+    #[test]
+    fn parse_requests() {
+        let connect = Request::parse(b"CONNECT pypi.org:443 HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(connect, Request { host: "pypi.org".into(), port: 443, tunnel: true });
+
+        let plain = Request::parse(b"GET http://user@Example.com/path HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(plain, Request { host: "example.com".into(), port: 80, tunnel: false });
+
+        let ipv6 = Request::parse(b"CONNECT [::1]:8443 HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(ipv6, Request { host: "::1".into(), port: 8443, tunnel: true });
+
+        assert_eq!(Request::parse(b"CONNECT pypi.org HTTP/1.1\r\n\r\n"), None);
+        assert_eq!(Request::parse(b"GET /path HTTP/1.1\r\n\r\n"), None);
+    }
+
+    #[test]
+    fn deny_unlisted_domain() {
+        let proxy = FilteringProxy::start(&["registry.npmjs.org".into()]).unwrap();
+
+        let (_, status) = proxy_request(&proxy, "CONNECT evil.example.com:443 HTTP/1.1\r\n\r\n");
+
+        assert_eq!(status, "HTTP/1.1 403 Forbidden");
+        assert_eq!(proxy.denied_domains(), ["evil.example.com"]);
+    }
+
+    #[test]
+    fn tunnel_allowed_domain() {
+        // Start echo server as the tunnel's target.
+        let server = StdTcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = server.local_addr().unwrap().port();
+        thread::spawn(move || {
+            let (mut stream, _) = server.accept().unwrap();
+            let mut buffer = [0; 5];
+            stream.read_exact(&mut buffer).unwrap();
+            stream.write_all(&buffer).unwrap();
+        });
+
+        let proxy = FilteringProxy::start(&["127.0.0.1".into()]).unwrap();
+        let request = format!("CONNECT 127.0.0.1:{port} HTTP/1.1\r\n\r\n");
+        let (mut stream, status) = proxy_request(&proxy, &request);
+        assert_eq!(status, "HTTP/1.1 200 Connection Established");
+
+        stream.write_all(b"hello").unwrap();
+        let mut echo = [0; 5];
+        stream.read_exact(&mut echo).unwrap();
+        assert_eq!(&echo, b"hello");
+    }
+}
@@ -0,0 +1,812 @@
+//! Syscall tracer recording denied sandbox accesses.
+//!
+//! The tracer uses `ptrace` to observe the filesystem syscalls of a process
+//! and all its descendants. Since the sandbox hides inaccessible paths instead
+//! of rejecting access to them, all paths of syscalls which failed with either
+//! a permission error or a missing file are recorded. Filtering out paths
+//! which do not exist outside of the sandbox is left to the caller.
+//!
+//! Environment variables are read from memory without any syscall, so the
+//! tracer inserts a breakpoint into `getenv` whenever libc is mapped into a
+//! traced process, recording the name of every variable looked up through it.
+//! Programs which read their environment directly, like Go, Java or Python
+//! programs, are not covered.
+
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap};
+use std::ffi::{c_void, OsStr};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+use std::{fs, io, mem, ptr};
+
+use libc::pid_t;
+use serde::{Deserialize, Serialize};
+
+/// `ptrace` request for syscall details, available since Linux 5.3.
+const PTRACE_GET_SYSCALL_INFO: libc::c_uint = 0x420e;
+
+/// [`SyscallInfo::op`] at syscall entry.
+const SYSCALL_INFO_ENTRY: u8 = 1;
+
+/// [`SyscallInfo::op`] at syscall exit.
+const SYSCALL_INFO_EXIT: u8 = 2;
+
+/// `ptrace` register set of the general purpose registers.
+const NT_PRSTATUS: libc::c_int = 1;
+
+/// ELF section type of the dynamic symbol table.
+const SHT_DYNSYM: u64 = 11;
+
+/// ELF program header type of loaded segments.
+const PT_LOAD: u64 = 1;
+
+/// Maximum length of a path read from the traced process.
+const MAX_PATH_LEN: usize = 4096;
+
+/// Paths the traced processes failed to access.
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
+pub struct DeniedAccesses {
+    pub read: BTreeSet<PathBuf>,
+    pub write: BTreeSet<PathBuf>,
+    pub run: BTreeSet<PathBuf>,
+    /// Environment variables looked up through `getenv`.
+    ///
+    /// Since the sandbox hides variables instead of rejecting access to them,
+    /// this includes all lookups, regardless of whether they succeeded.
+    #[serde(default)]
+    pub env: BTreeSet<String>,
+}
+
+/// Run a command, recording all of its denied filesystem accesses.
+///
+/// Tracing ends once the command itself exits. All of its descendants which
+/// are still running are killed, since they would crash on the `getenv`
+/// breakpoints without a tracer.
+pub fn trace(mut command: Command) -> io::Result<(ExitStatus, DeniedAccesses)> {
+    // SAFETY: `ptrace` is async-signal-safe.
+    unsafe {
+        command.pre_exec(|| {
+            let null = ptr::null_mut::<c_void>();
+            if libc::ptrace(libc::PTRACE_TRACEME, 0, null, null) == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    let root = command.spawn()?.id() as pid_t;
+
+    // Wait for the child to stop after its `execve`.
+    let (_, status) = wait(root)?;
+    if !libc::WIFSTOPPED(status) {
+        return Ok((ExitStatus::from_raw(status), DeniedAccesses::default()));
+    }
+
+    let options = libc::PTRACE_O_TRACESYSGOOD
+        | libc::PTRACE_O_TRACEFORK
+        | libc::PTRACE_O_TRACEVFORK
+        | libc::PTRACE_O_TRACECLONE
+        | libc::PTRACE_O_TRACEEXEC
+        | libc::PTRACE_O_EXITKILL;
+    // SAFETY: The root process is a stopped tracee.
+    if unsafe { libc::ptrace(libc::PTRACE_SETOPTIONS, root, 0, options) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut tracer = Tracer::default();
+    tracer.syscalls.insert(root, None);
+    tracer.spaces.insert(root, root);
+    resume(root, 0)?;
+
+    let mut pending_status = None;
+    loop {
+        let (pid, status) = match pending_status.take() {
+            Some(pending_status) => pending_status,
+            None => wait(-1)?,
+        };
+
+        // Stop tracing once the root process is gone.
+        if libc::WIFEXITED(status) || libc::WIFSIGNALED(status) {
+            tracer.exit(pid);
+            if pid == root {
+                tracer.kill_remaining();
+                return Ok((ExitStatus::from_raw(status), tracer.denied));
+            }
+            continue;
+        }
+
+        if !libc::WIFSTOPPED(status) {
+            continue;
+        }
+
+        let signal = libc::WSTOPSIG(status);
+        let event = status >> 16;
+        let signal = if let Entry::Vacant(entry) = tracer.syscalls.entry(pid) {
+            // Suppress the initial stop of new processes and threads.
+            entry.insert(None);
+            tracer.spawn(pid);
+            0
+        } else if signal == libc::SIGTRAP | 0x80 {
+            tracer.syscall_stop(pid);
+            0
+        } else if signal == libc::SIGTRAP && event == libc::PTRACE_EVENT_EXEC {
+            tracer.exec(pid);
+            0
+        } else if signal == libc::SIGTRAP && event != 0 {
+            0
+        } else if signal == libc::SIGTRAP {
+            match tracer.breakpoint_stop(pid) {
+                Trap::Breakpoint => 0,
+                Trap::Signal => signal,
+                // Handle tracees which exited while stepping over the breakpoint.
+                Trap::Exited(status) => {
+                    pending_status = Some((pid, status));
+                    continue;
+                },
+            }
+        } else {
+            signal
+        };
+
+        // Tracees might be killed at any point, so failed resumes are ignored.
+        let _ = resume(pid, signal);
+    }
+}
+
+/// Tracer state.
+#[derive(Default)]
+struct Tracer {
+    /// Pending syscall of each tracee, recorded at syscall entry.
+    syscalls: HashMap<pid_t, Option<Syscall>>,
+    /// Address space of each tracee, identified by the process owning it.
+    ///
+    /// Threads share the address space of their process.
+    spaces: HashMap<pid_t, pid_t>,
+    /// Original instructions replaced by `getenv` breakpoints, by address space
+    /// and address.
+    breakpoints: HashMap<(pid_t, u64), u64>,
+    /// File offset of `getenv` in each libc.
+    getenv_offsets: HashMap<PathBuf, Option<u64>>,
+    denied: DeniedAccesses,
+}
+
+impl Tracer {
+    /// Register a new process or thread.
+    ///
+    /// New processes inherit a copy of their parent's memory, including its
+    /// breakpoints.
+    fn spawn(&mut self, pid: pid_t) {
+        let Some((tgid, ppid)) = process_ids(pid) else { return };
+        self.spaces.insert(pid, tgid);
+        if tgid != pid {
+            return;
+        }
+
+        let Some(&parent_space) = self.spaces.get(&ppid) else { return };
+        let inherited: Vec<_> = self
+            .breakpoints
+            .iter()
+            .filter(|((space, _), _)| *space == parent_space)
+            .map(|((_, address), original)| ((pid, *address), *original))
+            .collect();
+        self.breakpoints.extend(inherited);
+    }
+
+    /// Handle the `execve` of a tracee, which replaces its address space.
+    ///
+    /// All other threads of the process are gone after the `execve`, and the
+    /// tracee continues with the process ID.
+    fn exec(&mut self, pid: pid_t) {
+        self.spaces.retain(|tid, space| *space != pid || *tid == pid);
+        self.syscalls.retain(|tid, _| self.spaces.contains_key(tid));
+        self.spaces.insert(pid, pid);
+        self.breakpoints.retain(|(space, _), _| *space != pid);
+    }
+
+    /// Remove an exited tracee.
+    ///
+    /// Breakpoints are removed once no tracee uses their address space anymore.
+    fn exit(&mut self, pid: pid_t) {
+        self.syscalls.remove(&pid);
+        let Some(space) = self.spaces.remove(&pid) else { return };
+        if !self.spaces.values().any(|other| *other == space) {
+            self.breakpoints.retain(|(breakpoint_space, _), _| *breakpoint_space != space);
+        }
+    }
+
+    /// Kill all remaining tracees and wait for them to exit.
+    fn kill_remaining(&mut self) {
+        for pid in self.syscalls.keys() {
+            // SAFETY: `kill` has no memory safety requirements.
+            unsafe { libc::kill(*pid, libc::SIGKILL) };
+        }
+
+        while !self.syscalls.is_empty() {
+            let Ok((pid, status)) = wait(-1) else { break };
+
+            if libc::WIFEXITED(status) || libc::WIFSIGNALED(status) {
+                self.exit(pid);
+            } else if let Entry::Vacant(entry) = self.syscalls.entry(pid) {
+                // Kill processes which were spawned before the others were killed.
+                entry.insert(None);
+                // SAFETY: `kill` has no memory safety requirements.
+                unsafe { libc::kill(pid, libc::SIGKILL) };
+            }
+        }
+    }
+
+    /// Handle a syscall entry or exit stop.
+    fn syscall_stop(&mut self, pid: pid_t) {
+        let Some(info) = syscall_info(pid) else { return };
+
+        match info.op {
+            SYSCALL_INFO_ENTRY => {
+                let mut args = [0; 6];
+                args.copy_from_slice(&info.data[1..]);
+                let syscall = match file_mapping(info.data[0], args) {
+                    Some(mapping) => Syscall::Mapping(mapping),
+                    None => Syscall::Paths(
+                        path_accesses(info.data[0], args)
+                            .into_iter()
+                            .filter_map(|(kind, dirfd, address)| {
+                                let path = read_path(pid, dirfd as i32, address)?;
+                                Some(PathAccess { kind, path })
+                            })
+                            .collect(),
+                    ),
+                };
+                self.syscalls.insert(pid, Some(syscall));
+            },
+            SYSCALL_INFO_EXIT => match self.syscalls.get_mut(&pid).and_then(Option::take) {
+                Some(Syscall::Paths(accesses)) => self.record_denied(accesses, info.data[0]),
+                // The exit data holds the mapping's address and the error flag.
+                Some(Syscall::Mapping(mapping)) if info.data[1] as u8 == 0 => {
+                    self.map_file(pid, mapping, info.data[0])
+                },
+                _ => (),
+            },
+            _ => (),
+        }
+    }
+
+    /// Record the accesses of a failed syscall.
+    fn record_denied(&mut self, accesses: Vec<PathAccess>, result: u64) {
+        let errno = -(result as i64);
+        let denied = [libc::ENOENT, libc::EACCES, libc::EPERM, libc::EROFS];
+        if !denied.iter().any(|denied| i64::from(*denied) == errno) {
+            return;
+        }
+
+        for access in accesses {
+            let paths = match access.kind {
+                AccessKind::Read => &mut self.denied.read,
+                AccessKind::Write => &mut self.denied.write,
+                AccessKind::Run => &mut self.denied.run,
+            };
+            paths.insert(access.path);
+        }
+    }
+
+    /// Insert a `getenv` breakpoint into a new executable mapping of libc.
+    fn map_file(&mut self, pid: pid_t, mapping: FileMapping, address: u64) {
+        let Ok(path) = fs::read_link(format!("/proc/{pid}/fd/{}", mapping.fd)) else { return };
+        let file_name = path.file_name().and_then(OsStr::to_str).unwrap_or_default();
+        if !file_name.starts_with("libc.so") && !file_name.starts_with("libc-") {
+            return;
+        }
+
+        let offset = *self.getenv_offsets.entry(path).or_insert_with_key(|path| {
+            let libc = fs::read(path).ok()?;
+            getenv_offset(&libc)
+        });
+        let mapped = mapping.offset..mapping.offset.saturating_add(mapping.len);
+        if let Some(offset) = offset.filter(|offset| mapped.contains(offset)) {
+            self.insert_breakpoint(pid, address + (offset - mapping.offset));
+        }
+    }
+
+    /// Replace the instruction at `address` with a breakpoint.
+    fn insert_breakpoint(&mut self, pid: pid_t, address: u64) {
+        let Some(&space) = self.spaces.get(&pid) else { return };
+        let Some(word) = peek(pid, address) else { return };
+        let original = word & arch::TRAP_MASK;
+        if original == arch::TRAP {
+            return;
+        }
+
+        if poke(pid, address, word & !arch::TRAP_MASK | arch::TRAP) {
+            self.breakpoints.insert((space, address), original);
+        }
+    }
+
+    /// Handle a `SIGTRAP` stop, which might be caused by a `getenv` breakpoint.
+    ///
+    /// Breakpoints are stepped over by temporarily restoring the original
+    /// instruction.
+    fn breakpoint_stop(&mut self, pid: pid_t) -> Trap {
+        let Some(mut regs) = registers(pid) else { return Trap::Signal };
+        let address = arch::pc(&regs).wrapping_sub(arch::TRAP_PC_OFFSET);
+        let Some(&space) = self.spaces.get(&pid) else { return Trap::Signal };
+        let Some(&original) = self.breakpoints.get(&(space, address)) else {
+            return Trap::Signal;
+        };
+        let Some(word) = peek(pid, address) else { return Trap::Signal };
+        if word & arch::TRAP_MASK != arch::TRAP {
+            return Trap::Signal;
+        }
+
+        if let Some(name) = read_string(pid, arch::arg0(&regs)) {
+            self.denied.env.insert(String::from_utf8_lossy(&name).into_owned());
+        }
+
+        arch::set_pc(&mut regs, address);
+        if !poke(pid, address, word & !arch::TRAP_MASK | original) || !set_registers(pid, &regs) {
+            return Trap::Breakpoint;
+        }
+
+        if let Some(status) = step(pid) {
+            return Trap::Exited(status);
+        }
+
+        if let Some(word) = peek(pid, address) {
+            poke(pid, address, word & !arch::TRAP_MASK | arch::TRAP);
+        }
+
+        Trap::Breakpoint
+    }
+}
+
+/// Syscall details recorded at syscall entry.
+enum Syscall {
+    /// Paths accessed by the syscall.
+    Paths(Vec<PathAccess>),
+    /// Executable file mapping created by the syscall.
+    Mapping(FileMapping),
+}
+
+/// Executable mapping of a file.
+struct FileMapping {
+    fd: i32,
+    offset: u64,
+    len: u64,
+}
+
+/// Cause of a `SIGTRAP` stop.
+enum Trap {
+    /// The tracee stepped over a `getenv` breakpoint.
+    Breakpoint,
+    /// The tracee exited while stepping over a `getenv` breakpoint.
+    Exited(i32),
+    /// The stop was caused by a regular signal.
+    Signal,
+}
+
+/// Path accessed by a syscall.
+struct PathAccess {
+    kind: AccessKind,
+    path: PathBuf,
+}
+
+/// Type of filesystem access.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum AccessKind {
+    Read,
+    Write,
+    Run,
+}
+
+/// Get the paths accessed by a syscall.
+///
+/// Paths are returned as the access type, the directory file descriptor the
+/// path is relative to and the address of the path in the tracee's memory.
+fn path_accesses(syscall: u64, args: [u64; 6]) -> Vec<(AccessKind, u64, u64)> {
+    use AccessKind::*;
+
+    let cwd = libc::AT_FDCWD as u64;
+    let open_kind = |flags: u64| {
+        let write_flags = libc::O_WRONLY | libc::O_RDWR | libc::O_CREAT | libc::O_TRUNC;
+        if flags as i32 & write_flags == 0 {
+            Read
+        } else {
+            Write
+        }
+    };
+
+    match syscall as libc::c_long {
+        libc::SYS_openat => vec![(open_kind(args[2]), args[0], args[1])],
+        libc::SYS_newfstatat
+        | libc::SYS_statx
+        | libc::SYS_faccessat
+        | libc::SYS_faccessat2
+        | libc::SYS_readlinkat => vec![(Read, args[0], args[1])],
+        libc::SYS_chdir => vec![(Read, cwd, args[0])],
+        libc::SYS_execve => vec![(Run, cwd, args[0])],
+        libc::SYS_execveat => vec![(Run, args[0], args[1])],
+        libc::SYS_mkdirat | libc::SYS_unlinkat => vec![(Write, args[0], args[1])],
+        libc::SYS_renameat | libc::SYS_renameat2 | libc::SYS_linkat => {
+            vec![(Write, args[0], args[1]), (Write, args[2], args[3])]
+        },
+        libc::SYS_symlinkat => vec![(Write, args[1], args[2])],
+        libc::SYS_truncate => vec![(Write, cwd, args[0])],
+        #[cfg(target_arch = "x86_64")]
+        libc::SYS_open => vec![(open_kind(args[1]), cwd, args[0])],
+        #[cfg(target_arch = "x86_64")]
+        libc::SYS_stat | libc::SYS_lstat | libc::SYS_access | libc::SYS_readlink => {
+            vec![(Read, cwd, args[0])]
+        },
+        #[cfg(target_arch = "x86_64")]
+        libc::SYS_creat | libc::SYS_mkdir | libc::SYS_rmdir | libc::SYS_unlink => {
+            vec![(Write, cwd, args[0])]
+        },
+        #[cfg(target_arch = "x86_64")]
+        libc::SYS_rename | libc::SYS_link => vec![(Write, cwd, args[0]), (Write, cwd, args[1])],
+        #[cfg(target_arch = "x86_64")]
+        libc::SYS_symlink => vec![(Write, cwd, args[1])],
+        _ => Vec::new(),
+    }
+}
+
+/// Get the executable file mapping created by a syscall.
+fn file_mapping(syscall: u64, args: [u64; 6]) -> Option<FileMapping> {
+    let fd = args[4] as i32;
+    let executable = args[2] as i32 & libc::PROT_EXEC != 0;
+
+    (syscall as libc::c_long == libc::SYS_mmap && executable && fd >= 0).then_some(FileMapping {
+        fd,
+        offset: args[5],
+        len: args[1],
+    })
+}
+
+/// Get the file offset of `getenv` in a 64-bit little-endian ELF libc.
+fn getenv_offset(elf: &[u8]) -> Option<u64> {
+    let u16_at = |offset: u64| -> Option<u64> {
+        let bytes = elf.get(offset as usize..)?.get(..2)?;
+        Some(u16::from_le_bytes(bytes.try_into().ok()?).into())
+    };
+    let u32_at = |offset: u64| -> Option<u64> {
+        let bytes = elf.get(offset as usize..)?.get(..4)?;
+        Some(u32::from_le_bytes(bytes.try_into().ok()?).into())
+    };
+    let u64_at = |offset: u64| -> Option<u64> {
+        let bytes = elf.get(offset as usize..)?.get(..8)?;
+        Some(u64::from_le_bytes(bytes.try_into().ok()?))
+    };
+
+    if !elf.starts_with(b"\x7fELF\x02\x01") {
+        return None;
+    }
+
+    // Find the dynamic symbol table and its string table.
+    let (section_headers, section_size, sections) = (u64_at(0x28)?, u16_at(0x3a)?, u16_at(0x3c)?);
+    let section = |index: u64| section_headers + index * section_size;
+    let symbols =
+        (0..sections).map(section).find(|header| u32_at(header + 4) == Some(SHT_DYNSYM))?;
+    let strings = u64_at(section(u32_at(symbols + 0x28)?) + 0x18)?;
+
+    // Find the address of `getenv`.
+    let (symbols, symbols_len, symbol_size) =
+        (u64_at(symbols + 0x18)?, u64_at(symbols + 0x20)?, u64_at(symbols + 0x38)?);
+    let address = (symbols..symbols + symbols_len).step_by(symbol_size.max(1) as usize).find_map(
+        |symbol| {
+            let name = elf.get((strings + u32_at(symbol)?) as usize..)?;
+            let defined = u16_at(symbol + 6)? != 0;
+            (name.starts_with(b"getenv\0") && defined).then(|| u64_at(symbol + 8)).flatten()
+        },
+    )?;
+
+    // Translate the address to a file offset using the loaded segments.
+    let (program_headers, program_size, programs) = (u64_at(0x20)?, u16_at(0x36)?, u16_at(0x38)?);
+    (0..programs).find_map(|index| {
+        let header = program_headers + index * program_size;
+        let (offset, vaddr, len) =
+            (u64_at(header + 8)?, u64_at(header + 0x10)?, u64_at(header + 0x20)?);
+        let loaded = u32_at(header)? == PT_LOAD;
+        (loaded && (vaddr..vaddr + len).contains(&address)).then(|| address - vaddr + offset)
+    })
+}
+
+/// Syscall details of a stopped tracee.
+///
+/// This mirrors the kernel's `struct ptrace_syscall_info`, with the `entry`
+/// and `exit` union variants stored in `data`.
+#[repr(C)]
+#[derive(Default)]
+struct SyscallInfo {
+    op: u8,
+    _pad: [u8; 3],
+    _arch: u32,
+    _instruction_pointer: u64,
+    _stack_pointer: u64,
+    data: [u64; 7],
+}
+
+/// Get the syscall details of a tracee in a syscall stop.
+fn syscall_info(pid: pid_t) -> Option<SyscallInfo> {
+    let mut info = SyscallInfo::default();
+    let size = mem::size_of::<SyscallInfo>();
+    let info_ptr = &mut info as *mut SyscallInfo as *mut c_void;
+
+    // SAFETY: The kernel writes at most `size` bytes to `info`.
+    let result = unsafe { libc::ptrace(PTRACE_GET_SYSCALL_INFO, pid, size, info_ptr) };
+
+    (result > 0).then_some(info)
+}
+
+/// Get the thread group ID and parent process ID of a tracee.
+fn process_ids(pid: pid_t) -> Option<(pid_t, pid_t)> {
+    let status = fs::read_to_string(format!("/proc/{pid}/status")).ok()?;
+    let field = |name: &str| {
+        let line = status.lines().find_map(|line| line.strip_prefix(name))?;
+        line.trim().parse().ok()
+    };
+    Some((field("Tgid:")?, field("PPid:")?))
+}
+
+/// Read an absolute path from a tracee's memory.
+fn read_path(pid: pid_t, dirfd: i32, address: u64) -> Option<PathBuf> {
+    let path = read_string(pid, address)?;
+    let path = Path::new(OsStr::from_bytes(&path));
+
+    if path.as_os_str().is_empty() {
+        return None;
+    } else if path.is_absolute() {
+        return Some(path.into());
+    }
+
+    // Resolve relative paths using the tracee's working directory or the
+    // directory file descriptor.
+    let base = if dirfd == libc::AT_FDCWD {
+        fs::read_link(format!("/proc/{pid}/cwd")).ok()?
+    } else {
+        fs::read_link(format!("/proc/{pid}/fd/{dirfd}")).ok()?
+    };
+
+    Some(base.join(path))
+}
+
+/// Read a NUL-terminated string from a tracee's memory.
+fn read_string(pid: pid_t, mut address: u64) -> Option<Vec<u8>> {
+    // SAFETY: `sysconf` has no preconditions.
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) }.max(1) as u64;
+
+    let mut string = Vec::new();
+    while string.len() < MAX_PATH_LEN {
+        // Avoid reading across page boundaries, since the next page might be unmapped.
+        let len = (page_size - address % page_size) as usize;
+        let mut buffer = vec![0u8; len.min(MAX_PATH_LEN - string.len())];
+
+        let local =
+            libc::iovec { iov_base: buffer.as_mut_ptr() as *mut c_void, iov_len: buffer.len() };
+        let remote = libc::iovec { iov_base: address as *mut c_void, iov_len: buffer.len() };
+
+        // SAFETY: The local buffer is valid for `iov_len` bytes.
+        let read = unsafe { libc::process_vm_readv(pid, &local, 1, &remote, 1, 0) };
+        if read <= 0 {
+            return None;
+        }
+        buffer.truncate(read as usize);
+
+        match buffer.iter().position(|byte| *byte == 0) {
+            Some(end) => {
+                string.extend_from_slice(&buffer[..end]);
+                return Some(string);
+            },
+            None => string.extend_from_slice(&buffer),
+        }
+
+        address += read as u64;
+    }
+
+    None
+}
+
+/// Wait for a state change of a tracee.
+fn wait(pid: pid_t) -> io::Result<(pid_t, i32)> {
+    let mut status = 0;
+    loop {
+        // SAFETY: `status` is a valid pointer.
+        let pid = unsafe { libc::waitpid(pid, &mut status, libc::__WALL) };
+        if pid != -1 {
+            return Ok((pid, status));
+        }
+
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+}
+
+/// Execute the next instruction of a stopped tracee.
+///
+/// Returns the tracee's exit status if it exited instead.
+fn step(pid: pid_t) -> Option<i32> {
+    let mut signal = 0;
+    loop {
+        // SAFETY: Invalid tracees are reported through `errno`.
+        if unsafe { libc::ptrace(libc::PTRACE_SINGLESTEP, pid, 0, signal) } == -1 {
+            return None;
+        }
+
+        let (_, status) = wait(pid).ok()?;
+        if libc::WIFEXITED(status) || libc::WIFSIGNALED(status) {
+            return Some(status);
+        }
+
+        // Signals stop the tracee before the instruction is executed, so
+        // stepping is repeated while delivering the signal.
+        signal = libc::WSTOPSIG(status);
+        if signal == libc::SIGTRAP {
+            return None;
+        }
+    }
+}
+
+/// Read a word from a tracee's memory.
+fn peek(pid: pid_t, address: u64) -> Option<u64> {
+    // SAFETY: `errno` is thread-local and `PTRACE_PEEKDATA` has no other side
+    // effects.
+    let word = unsafe {
+        *libc::__errno_location() = 0;
+        libc::ptrace(libc::PTRACE_PEEKDATA, pid, address, 0)
+    };
+
+    // Words can be `-1`, so errors are only reported through `errno`.
+    let failed = word == -1 && io::Error::last_os_error().raw_os_error() != Some(0);
+    (!failed).then_some(word as u64)
+}
+
+/// Write a word to a tracee's memory, ignoring page protections.
+fn poke(pid: pid_t, address: u64, word: u64) -> bool {
+    // SAFETY: Invalid tracees and addresses are reported through `errno`.
+    unsafe { libc::ptrace(libc::PTRACE_POKEDATA, pid, address, word) != -1 }
+}
+
+/// Read the general purpose registers of a stopped tracee.
+fn registers(pid: pid_t) -> Option<libc::user_regs_struct> {
+    // SAFETY: All-zero registers are valid.
+    let mut regs: libc::user_regs_struct = unsafe { mem::zeroed() };
+    let mut iov = libc::iovec {
+        iov_base: &mut regs as *mut libc::user_regs_struct as *mut c_void,
+        iov_len: mem::size_of::<libc::user_regs_struct>(),
+    };
+
+    // SAFETY: The kernel writes at most `iov_len` bytes to `regs`.
+    let result = unsafe { libc::ptrace(libc::PTRACE_GETREGSET, pid, NT_PRSTATUS, &mut iov) };
+
+    (result != -1).then_some(regs)
+}
+
+/// Write the general purpose registers of a stopped tracee.
+fn set_registers(pid: pid_t, regs: &libc::user_regs_struct) -> bool {
+    let mut iov = libc::iovec {
+        iov_base: regs as *const libc::user_regs_struct as *mut c_void,
+        iov_len: mem::size_of::<libc::user_regs_struct>(),
+    };
+
+    // SAFETY: The kernel reads at most `iov_len` bytes from `regs`.
+    unsafe { libc::ptrace(libc::PTRACE_SETREGSET, pid, NT_PRSTATUS, &mut iov) != -1 }
+}
+
+/// Architecture-specific breakpoint handling.
+#[cfg(target_arch = "x86_64")]
+mod arch {
+    /// Breakpoint instruction (`int3`).
+    pub const TRAP: u64 = 0xcc;
+
+    /// Bits of a word replaced by [`TRAP`].
+    pub const TRAP_MASK: u64 = 0xff;
+
+    /// Offset of the instruction pointer from a breakpoint which was hit.
+    pub const TRAP_PC_OFFSET: u64 = 1;
+
+    pub fn pc(regs: &libc::user_regs_struct) -> u64 {
+        regs.rip
+    }
+
+    pub fn set_pc(regs: &mut libc::user_regs_struct, pc: u64) {
+        regs.rip = pc;
+    }
+
+    /// First argument of a function call.
+    pub fn arg0(regs: &libc::user_regs_struct) -> u64 {
+        regs.rdi
+    }
+}
+
+/// Architecture-specific breakpoint handling.
+#[cfg(target_arch = "aarch64")]
+mod arch {
+    /// Breakpoint instruction (`brk #0`).
+    pub const TRAP: u64 = 0xd420_0000;
+
+    /// Bits of a word replaced by [`TRAP`].
+    pub const TRAP_MASK: u64 = 0xffff_ffff;
+
+    /// Offset of the instruction pointer from a breakpoint which was hit.
+    pub const TRAP_PC_OFFSET: u64 = 0;
+
+    pub fn pc(regs: &libc::user_regs_struct) -> u64 {
+        regs.pc
+    }
+
+    pub fn set_pc(regs: &mut libc::user_regs_struct, pc: u64) {
+        regs.pc = pc;
+    }
+
+    /// First argument of a function call.
+    pub fn arg0(regs: &libc::user_regs_struct) -> u64 {
+        regs.regs[0]
+    }
+}
+
+/// Resume a stopped tracee until its next syscall stop.
+fn resume(pid: pid_t, signal: i32) -> io::Result<()> {
+    // SAFETY: Invalid tracees are reported through `errno`.
+    let result = unsafe { libc::ptrace(libc::PTRACE_SYSCALL, pid, 0, signal) };
+    if result == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trace_shell(script: &str) -> (ExitStatus, DeniedAccesses) {
+        let mut command = Command::new("sh");
+        command.args(["-c", script]);
+        trace(command).unwrap()
+    }
+
+    #[test]
+    fn records_denied_accesses() {
+        let (status, denied) = trace_shell(
+            "cat /nonexistent/read 2>/dev/null; touch /nonexistent/write 2>/dev/null; \
+             /nonexistent/run 2>/dev/null; exit 3",
+        );
+
+        assert_eq!(status.code(), Some(3));
+        assert!(denied.read.contains(Path::new("/nonexistent/read")));
+        assert!(denied.write.contains(Path::new("/nonexistent/write")));
+        assert!(denied.run.contains(Path::new("/nonexistent/run")));
+    }
+
+    #[test]
+    fn records_environment_lookups() {
+        let (status, denied) = trace_shell("date >/dev/null && exit 3");
+
+        assert_eq!(status.code(), Some(3));
+        assert!(denied.env.contains("TZ"));
+    }
+
+    #[test]
+    fn kills_remaining_descendants() {
+        let dir = tempfile::tempdir().unwrap();
+        let pid_file = dir.path().join("pid");
+
+        let script = format!("sleep 30 & echo $! > {}; exit 3", pid_file.display());
+        let (status, _) = trace_shell(&script);
+
+        assert_eq!(status.code(), Some(3));
+        let pid = fs::read_to_string(&pid_file).unwrap();
+        let stat = fs::read_to_string(format!("/proc/{}/stat", pid.trim()));
+        assert!(stat.map_or(true, |stat| stat.contains(") Z ")));
+    }
+
+    #[test]
+    fn ignores_successful_accesses() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("file");
+        fs::write(&file, "content").unwrap();
+
+        let script = format!("cat {} >/dev/null", file.display());
+        let (status, denied) = trace_shell(&script);
+
+        assert!(status.success());
+        assert!(!denied.read.contains(&file));
+    }
+}
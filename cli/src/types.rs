@@ -1,8 +1,11 @@
 use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{self, Display, Formatter};
+use std::path::Path;
 use std::str::FromStr;
 
 use chrono::{DateTime, Utc};
+use phylum_lockfile::generator::Provenance;
 use phylum_lockfile::ParsedLockfile;
 use phylum_types::types::common::{JobId, ProjectId};
 use phylum_types::types::package::{
@@ -357,19 +360,63 @@ pub enum AnalysisPackageDescriptor {
 }
 
 impl AnalysisPackageDescriptor {
+    /// Get all packages of a lockfile, attributed to their dependency file.
+    ///
+    /// Dependencies required by only a single workspace member are attributed
+    /// to the member's `package.json`. All other packages are attributed to the
+    /// lockfile itself, so every package is reported only once.
     pub fn descriptors_from_lockfile(
         parsed_lockfile: ParsedLockfile,
     ) -> Vec<AnalysisPackageDescriptor> {
-        parsed_lockfile
-            .packages
-            .iter()
-            .map(|package_descriptor| {
-                AnalysisPackageDescriptor::PackageDescriptor(PackageDescriptorAndLockfile {
+        let workspace_root = Path::new(&parsed_lockfile.path).parent().unwrap_or(Path::new(""));
+
+        // Find the dependency file of every package.
+        let mut member_manifests = HashMap::new();
+        let mut shared_packages = HashSet::new();
+        for member in &parsed_lockfile.workspace_members {
+            let manifest_path = match member.path.as_str() {
+                "." => None,
+                path => Some(workspace_root.join(path).join("package.json")),
+            };
+
+            for package in &member.packages {
+                let attributed =
+                    member_manifests.contains_key(package) || shared_packages.contains(package);
+                match &manifest_path {
+                    Some(manifest_path) if !attributed => {
+                        member_manifests.insert(package, manifest_path.clone());
+                    },
+                    // Packages of the root or multiple members belong to the lockfile.
+                    _ => {
+                        member_manifests.remove(package);
+                        shared_packages.insert(package);
+                    },
+                }
+            }
+        }
+
+        let mut descriptors = Vec::new();
+        let mut seen = HashSet::new();
+        for package_descriptor in &parsed_lockfile.packages {
+            let lockfile = match member_manifests.get(package_descriptor) {
+                Some(manifest_path) => manifest_path.to_string_lossy().into_owned(),
+                None => parsed_lockfile.path.clone(),
+            };
+
+            // Deduplicate packages installed in multiple locations.
+            if !seen.insert((package_descriptor, lockfile.clone())) {
+                continue;
+            }
+
+            descriptors.push(AnalysisPackageDescriptor::PackageDescriptor(
+                PackageDescriptorAndLockfile {
                     package_descriptor: package_descriptor.clone(),
-                    lockfile: Some(parsed_lockfile.path.clone()),
-                })
-            })
-            .collect()
+                    lockfile: Some(lockfile),
+                },
+            ));
+        }
+
+        descriptors
     }
 }
 
@@ -387,6 +434,26 @@ pub struct SubmitPackageRequest {
     /// The group that owns the project, if applicable
     #[serde(skip_serializing_if = "Option::is_none")]
     pub group_name: Option<String>,
+    /// Provenance of all generated lockfiles
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub provenance: Vec<LockfileProvenance>,
+}
+
+/// Generation provenance of a dependency file.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Debug, Serialize, Deserialize)]
+pub struct LockfileProvenance {
+    /// Path of the dependency file the lockfile was generated for.
+    pub lockfile: String,
+    #[serde(flatten)]
+    pub provenance: Provenance,
+}
+
+impl LockfileProvenance {
+    /// Get the provenance of a parsed lockfile, if it was generated.
+    pub fn from_lockfile(parsed_lockfile: &ParsedLockfile) -> Option<Self> {
+        let provenance = parsed_lockfile.provenance.clone()?;
+        Some(Self { lockfile: parsed_lockfile.path.clone(), provenance })
+    }
 }
 
 /// Package URL with attached dependency file origin.
@@ -4,8 +4,14 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use lazy_static::lazy_static;
 use phylum_cli::commands::extensions::extension::Extension;
+use phylum_cli::commands::extensions::installed::InstalledExtensions;
+use phylum_cli::commands::extensions::lock::ModuleLock;
+use phylum_cli::commands::extensions::source;
+use phylum_cli::config::Config;
 #[cfg(unix)]
 use phylum_cli::permissions::{Permission, Permissions};
 use predicates::prelude::*;
@@ -109,6 +115,48 @@ fn extension_is_uninstalled_correctly() {
     test_cli.run(["extension", "uninstall", "sample"]).success();
 
     assert!(!extension_path.exists());
+
+    let manifest_path = test_cli.data_home().join("phylum").join("extensions.toml");
+    let manifest = std::fs::read_to_string(manifest_path).unwrap();
+    let installed: InstalledExtensions = toml::from_str(&manifest).unwrap();
+    assert!(installed.extensions.is_empty());
+}
+
+// When a user installs an extension from an archive, its source and content
+// hash should be recorded.
+#[test]
+fn extension_is_installed_from_archive() {
+    let test_cli = TestCli::builder().build();
+    let archive = sample_archive();
+
+    test_cli.install_extension(archive.path()).success();
+    test_cli.run(["sample"]).success().stdout("Hello, World!\n");
+
+    let manifest_path = test_cli.data_home().join("phylum").join("extensions.toml");
+    let manifest = std::fs::read_to_string(manifest_path).unwrap();
+    let installed: InstalledExtensions = toml::from_str(&manifest).unwrap();
+
+    let sample = &installed.extensions["sample"];
+    let archive_path = archive.path().canonicalize().unwrap();
+    assert_eq!(sample.source, archive_path.display().to_string());
+    assert_eq!(sample.sha256, source::content_hash(&fixtures_path().join("sample")).unwrap());
+}
+
+// When the extension's hash doesn't match the expected hash, it should not be
+// installed.
+#[test]
+fn extension_hash_mismatch_is_rejected() {
+    let test_cli = TestCli::builder().build();
+    let archive = sample_archive();
+    let archive_path = archive.path().to_string_lossy();
+
+    test_cli
+        .run(["extension", "install", "-y", "--sha256", "0000", &archive_path])
+        .failure()
+        .stderr(predicate::str::contains("Extension hash mismatch"));
+
+    let extension_path = test_cli.data_home().join("phylum").join("extensions").join("sample");
+    assert!(!extension_path.exists());
 }
 
 #[test]
@@ -140,6 +188,149 @@ fn extension_list_should_emit_output() {
     );
 }
 
+// When an extension's source changes, `extension update` should install the new
+// version, asking for acceptance of additional permissions.
+#[test]
+fn extension_is_updated_from_source() {
+    let test_cli = TestCli::builder().build();
+    let extension = sample_copy();
+
+    test_cli.install_extension(extension.path()).success();
+
+    let manifest = "name = \"sample\"\nversion = \"1.1.0\"\n\n[permissions]\nenv = [\"HOME\"]\n";
+    std::fs::write(extension.path().join("PhylumExt.toml"), manifest).unwrap();
+
+    test_cli
+        .run(["extension", "update", "sample"])
+        .failure()
+        .stderr(predicate::str::contains("requires additional permissions"));
+
+    test_cli
+        .run(["extension", "update", "--yes", "sample"])
+        .success()
+        .stdout(predicate::str::contains("+ 'HOME'"))
+        .stdout(predicate::str::contains("Extension sample updated successfully"));
+
+    test_cli
+        .run(["extension", "update"])
+        .success()
+        .stdout(predicate::str::contains("Extension sample is up to date"));
+
+    test_cli.run(["extension", "list"]).success().stdout(predicate::str::contains("1.1.0"));
+}
+
+// Updating all extensions should continue after failed updates.
+#[test]
+fn extension_update_continues_after_failure() {
+    let test_cli = TestCli::builder().build();
+
+    test_cli.install_extension(&fixtures_path().join("ping")).success();
+    test_cli.install_extension(&fixtures_path().join("sample")).success();
+
+    let extensions_path = test_cli.data_home().join("phylum").join("extensions");
+    std::fs::remove_dir_all(extensions_path.join("ping")).unwrap();
+
+    test_cli
+        .run(["extension", "update"])
+        .failure()
+        .stderr(predicate::str::contains("Recorded extension \"ping\" is not installed"))
+        .stdout(predicate::str::contains("Extension sample is up to date"));
+}
+
+// Pinned extensions should be excluded from updates.
+#[test]
+fn pinned_extension_is_not_updated() {
+    let test_cli = TestCli::builder().build();
+    let extension = sample_copy();
+
+    let extension_path = extension.path().to_string_lossy();
+    test_cli.run(["extension", "install", "-y", "--pin", &extension_path]).success();
+
+    std::fs::write(extension.path().join("main.ts"), "console.log('Updated');").unwrap();
+
+    test_cli
+        .run(["extension", "update"])
+        .success()
+        .stderr(predicate::str::contains("Skipping pinned extension sample"));
+    test_cli.run(["sample"]).success().stdout("Hello, World!\n");
+}
+
+// Extensions requiring a newer CLI version cannot be installed.
+#[test]
+fn extension_min_cli_version_is_enforced() {
+    let test_cli = TestCli::builder().build();
+    let extension = sample_copy();
+
+    let manifest = "name = \"sample\"\nmin_cli_version = \"999.0.0\"\n";
+    std::fs::write(extension.path().join("PhylumExt.toml"), manifest).unwrap();
+
+    test_cli
+        .install_extension(extension.path())
+        .failure()
+        .stderr(predicate::str::contains("requires Phylum CLI version 999.0.0 or newer"));
+}
+
+// Signed extensions can be installed when signatures are required.
+#[test]
+fn signed_extension_is_installed() {
+    let mut config = Config::default();
+    config.extension_signatures.require_signatures = true;
+    config.extension_signatures.trusted_keys =
+        vec![signing_key("public.pem").to_string_lossy().into_owned()];
+    let test_cli = TestCli::builder().with_config(config).build();
+    let extension = sample_copy();
+
+    test_cli.install_extension(extension.path()).failure().stderr(predicate::str::contains(
+        "Extension sample is not signed, but signatures are required",
+    ));
+
+    let extension_path = extension.path().to_string_lossy();
+    let key_path = signing_key("untrusted.pem");
+    let key_path = key_path.to_string_lossy();
+    test_cli.run(["extension", "sign", "--key", &key_path, &extension_path]).success();
+    test_cli
+        .install_extension(extension.path())
+        .failure()
+        .stderr(predicate::str::contains("Extension sample is not signed by a trusted key"));
+
+    let key_path = signing_key("private.pem");
+    let key_path = key_path.to_string_lossy();
+    test_cli
+        .run(["extension", "sign", "--key", &key_path, &extension_path])
+        .success()
+        .stdout(predicate::str::contains("Extension sample signed successfully"));
+    test_cli.install_extension(extension.path()).success();
+    test_cli.run(["sample"]).success().stdout("Hello, World!\n");
+}
+
+// Module locks are not covered by signatures, so shipped locks are replaced
+// during installation.
+#[test]
+fn signed_extension_lock_is_replaced() {
+    let mut config = Config::default();
+    config.extension_signatures.require_signatures = true;
+    config.extension_signatures.trusted_keys =
+        vec![signing_key("public.pem").to_string_lossy().into_owned()];
+    let test_cli = TestCli::builder().with_config(config).build();
+    let extension = sample_copy();
+
+    let extension_path = extension.path().to_string_lossy();
+    let key_path = signing_key("private.pem");
+    let key_path = key_path.to_string_lossy();
+    test_cli.run(["extension", "sign", "--key", &key_path, &extension_path]).success();
+
+    // Tamper with the lock after signing.
+    let mut lock = ModuleLock::default();
+    lock.remote.insert("https://example.com/malicious.ts".into(), "0".repeat(64));
+    lock.save(extension.path()).unwrap();
+
+    test_cli.install_extension(extension.path()).success();
+
+    let installed_path = test_cli.data_home().join("phylum").join("extensions").join("sample");
+    assert_eq!(ModuleLock::load(&installed_path).unwrap(), Some(ModuleLock::default()));
+    test_cli.run(["sample"]).success().stdout("Hello, World!\n");
+}
+
 // Extensions relying on the injected Phylum API work.
 #[test]
 fn injected_api() {
@@ -298,6 +489,7 @@ fn net_sandboxing_success() {
             run: Permission::Boolean(true),
             net: Permission::Boolean(true),
             unsandboxed_run: Permission::Boolean(true),
+            ..Permissions::default()
         })
         .build()
         .run()
@@ -361,6 +553,7 @@ fn fs_sandboxing_success() {
             run: Permission::Boolean(true),
             net: Permission::Boolean(true),
             unsandboxed_run: Permission::Boolean(true),
+            ..Permissions::default()
         })
         .build()
         .run()
@@ -409,6 +602,32 @@ fn project_root() -> PathBuf {
     Path::new(&env!("CARGO_MANIFEST_DIR")).ancestors().nth(1).unwrap().to_path_buf()
 }
 
+/// Copy the sample extension to a temporary directory.
+fn sample_copy() -> tempfile::TempDir {
+    let tempdir = tempfile::tempdir().unwrap();
+    for file in ["PhylumExt.toml", "main.ts"] {
+        std::fs::copy(fixtures_path().join("sample").join(file), tempdir.path().join(file))
+            .unwrap();
+    }
+    tempdir
+}
+
+/// Create a `.tar.gz` archive of the sample extension.
+fn sample_archive() -> tempfile::NamedTempFile {
+    let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    builder.append_dir_all("sample", fixtures_path().join("sample")).unwrap();
+    let archive = builder.into_inner().unwrap().finish().unwrap();
+
+    let file = tempfile::Builder::new().suffix(".tar.gz").tempfile().unwrap();
+    std::fs::write(file.path(), archive).unwrap();
+    file
+}
+
+/// Path of a signing key fixture.
+fn signing_key(name: &str) -> PathBuf {
+    project_root().join("cli").join("tests").join("fixtures").join("signing").join(name)
+}
+
 fn fixtures_path() -> PathBuf {
     project_root().join("cli").join("tests").join("fixtures").join("extensions")
 }
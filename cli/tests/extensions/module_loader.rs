@@ -4,6 +4,8 @@
 // - Symlinks are not allowed.
 // - Remote URLs under https://deno.land are supported -- i.e., the Deno's
 //   standard library.
+// - Remote modules are locked during installation and must match the lock.
+// - Installed extensions without a lock record it on their first run.
 // - No other URLs are supported.
 //   - We explicitly test that a https:// url which is not under `deno.land` is
 //     rejected.
@@ -12,6 +14,7 @@
 // These tests are based on the fixtures under
 // `fixtures/module-import-extension`.
 
+use phylum_cli::commands::extensions::lock::ModuleLock;
 use predicates::prelude::*;
 
 use crate::common::*;
@@ -31,6 +34,95 @@ fn good_module_loads_successfully() {
         .stdout(predicate::str::contains("I should contain 12345"));
 }
 
+// Remote modules are locked during installation, after which the extension can
+// run without network access.
+#[test]
+fn remote_modules_are_locked() {
+    let test_cli = TestCli::builder().build();
+    let ext_path =
+        test_cli.data_home().join("phylum").join("extensions").join("module-import-success");
+
+    test_cli.install_extension(&fixtures_path().join("module-import").join("successful")).success();
+
+    let lock = ModuleLock::load(&ext_path).unwrap().unwrap();
+    assert!(lock.remote.contains_key("https://deno.land/std@0.143.0/crypto/mod.ts"));
+
+    // Use an unreachable proxy to prevent network access.
+    test_cli
+        .cmd()
+        .env("HTTPS_PROXY", "http://127.0.0.1:9")
+        .arg("module-import-success")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("I should contain 12345"));
+}
+
+// Remote modules which do not match the lock are rejected until the lock is
+// refreshed.
+#[test]
+fn modified_remote_modules_are_rejected() {
+    let test_cli = TestCli::builder().build();
+    let ext_path =
+        test_cli.data_home().join("phylum").join("extensions").join("module-import-success");
+
+    test_cli.install_extension(&fixtures_path().join("module-import").join("successful")).success();
+
+    let mut lock = ModuleLock::load(&ext_path).unwrap().unwrap();
+    for hash in lock.remote.values_mut() {
+        *hash = "0".repeat(64);
+    }
+    lock.save(&ext_path).unwrap();
+
+    test_cli
+        .run(["module-import-success"])
+        .failure()
+        .stderr(predicate::str::contains("does not match the extension's PhylumExt.lock"));
+
+    test_cli
+        .run(["extension", "lock", "module-import-success"])
+        .success()
+        .stdout(predicate::str::contains("~ https://deno.land/std@0.143.0/crypto/mod.ts"));
+
+    test_cli
+        .run(["module-import-success"])
+        .success()
+        .stdout(predicate::str::contains("I should contain 12345"));
+}
+
+// Installed extensions without a lock record their remote modules on their
+// first run.
+#[test]
+fn missing_lock_is_recorded() {
+    let test_cli = TestCli::builder().build();
+    let ext_path =
+        test_cli.data_home().join("phylum").join("extensions").join("module-import-success");
+
+    test_cli.install_extension(&fixtures_path().join("module-import").join("successful")).success();
+    std::fs::remove_file(ext_path.join("PhylumExt.lock")).unwrap();
+
+    test_cli
+        .run(["module-import-success"])
+        .success()
+        .stdout(predicate::str::contains("I should contain 12345"));
+
+    let lock = ModuleLock::load(&ext_path).unwrap().unwrap();
+    assert!(lock.remote.contains_key("https://deno.land/std@0.143.0/crypto/mod.ts"));
+}
+
+// Running extensions which are not installed never writes their lock.
+#[test]
+fn extension_run_does_not_write_lock() {
+    let test_cli = TestCli::builder().build();
+    let ext_path = fixtures_path().join("module-import").join("successful");
+
+    test_cli
+        .run(["extension", "run", "-y", &ext_path.to_string_lossy()])
+        .success()
+        .stdout(predicate::str::contains("I should contain 12345"));
+
+    assert!(!ext_path.join("PhylumExt.lock").exists());
+}
+
 // The fixture for this test attempts a directory traversal.
 #[test]
 fn module_with_traversal_fails_to_load() {
@@ -1,4 +1,4 @@
-use phylum_cli::permissions::{Permission, Permissions};
+use phylum_cli::permissions::{Permission, Permissions, ResourceLimits};
 use predicates::prelude::*;
 
 use crate::extensions::{fixtures_path, TestCli};
@@ -142,6 +142,56 @@ fn correct_run_permission() {
         .stdout(predicate::str::contains("hello"));
 }
 
+#[test]
+#[cfg(unix)]
+fn sandbox_limits_are_inherited() {
+    let test_cli = TestCli::builder().build();
+
+    #[rustfmt::skip]
+    test_cli
+        .extension("
+            const output = Phylum.runSandboxed({
+                cmd: 'sh',
+                args: ['-c', 'while :; do echo output; done'],
+                stdout: 'piped',
+                exceptions: { run: true },
+            });
+            Deno.exit(output.code);
+        ")
+        .with_permissions(Permissions {
+            run: Permission::Boolean(true),
+            limits: ResourceLimits { output: Some(1), ..ResourceLimits::default() },
+            ..Permissions::default()
+        })
+        .build()
+        .run()
+        .code(122);
+}
+
+#[test]
+#[cfg(unix)]
+fn sandbox_limits_exceeding_manifest() {
+    let test_cli = TestCli::builder().build();
+
+    #[rustfmt::skip]
+    test_cli
+        .extension("
+            Phylum.runSandboxed({
+                cmd: 'true',
+                exceptions: { run: true, limits: { memory: 1024 } },
+            });
+        ")
+        .with_permissions(Permissions {
+            run: Permission::Boolean(true),
+            limits: ResourceLimits { memory: Some(512), ..ResourceLimits::default() },
+            ..Permissions::default()
+        })
+        .build()
+        .run()
+        .failure()
+        .stderr(predicate::str::contains("Invalid memory limit"));
+}
+
 #[tokio::test]
 pub async fn disallow_permission_request() {
     let test_cli = TestCli::builder().with_config(None).build();
@@ -47,3 +47,129 @@ fn parse_with_project_lockfile_relative_paths() {
     let not_sensitive_dir = predicate::str::contains("sensitive_dir_name").not();
     test_cli.cmd().args(["parse"]).assert().success().stdout(not_sensitive_dir);
 }
+
+#[test]
+fn parse_multiple_in_order() {
+    let test_cli = TestCli::builder().cwd_temp().build();
+    let temp_path = test_cli.temp_path();
+
+    // Copy lockfiles to temp dir.
+    fs::copy("../tests/fixtures/Cargo_v3.lock", temp_path.join("Cargo.lock")).unwrap();
+    fs::copy("../tests/fixtures/package-lock.json", temp_path.join("package-lock.json")).unwrap();
+
+    let output = test_cli
+        .cmd()
+        .args(["parse", "--jobs", "2", "package-lock.json", "Cargo.lock"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    // Ensure packages are reported in the order of their dependency files.
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let npm_start = stdout.find("\"lockfile\": \"package-lock.json\"").unwrap();
+    let cargo_start = stdout.find("\"lockfile\": \"Cargo.lock\"").unwrap();
+    let npm_end = stdout.rfind("\"lockfile\": \"package-lock.json\"").unwrap();
+    assert!(npm_start < cargo_start);
+    assert!(npm_end < cargo_start);
+}
+
+#[test]
+fn parse_multiple_keep_going() {
+    let test_cli = TestCli::builder().cwd_temp().build();
+    let temp_path = test_cli.temp_path();
+
+    // Write two invalid lockfiles to temp dir.
+    fs::write(temp_path.join("Cargo.lock"), "invalid").unwrap();
+    fs::write(temp_path.join("package-lock.json"), "invalid").unwrap();
+
+    // Ensure only the first failure is reported by default.
+    test_cli
+        .cmd()
+        .args(["parse", "--jobs", "1", "Cargo.lock", "package-lock.json"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("\"Cargo.lock\""))
+        .stderr(predicate::str::contains("\"package-lock.json\"").not());
+
+    // Ensure all failures are reported with `--keep-going`.
+    test_cli
+        .cmd()
+        .args(["parse", "--keep-going", "Cargo.lock", "package-lock.json"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("\"Cargo.lock\""))
+        .stderr(predicate::str::contains("\"package-lock.json\""));
+}
+
+#[test]
+fn parse_with_provenance() {
+    let test_cli = TestCli::builder().cwd_temp().build();
+    let temp_path = test_cli.temp_path();
+
+    fs::copy("../tests/fixtures/package-lock.json", temp_path.join("package-lock.json")).unwrap();
+
+    let output =
+        test_cli.cmd().args(["parse", "--provenance", "package-lock.json"]).output().unwrap();
+    assert!(output.status.success());
+
+    // Ensure lockfiles without generation have no provenance.
+    let output: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(!output["packages"].as_array().unwrap().is_empty());
+    assert_eq!(output["provenance"], serde_json::json!([]));
+}
+
+#[test]
+fn parse_dry_run() {
+    let test_cli = TestCli::builder().cwd_temp().build();
+    let temp_path = test_cli.temp_path();
+
+    fs::copy("../tests/fixtures/package-lock.json", temp_path.join("package-lock.json")).unwrap();
+
+    let output = test_cli.cmd().args(["parse", "--dry-run", "package-lock.json"]).output().unwrap();
+    assert!(output.status.success());
+
+    // Ensure the lockfile is explained without being parsed.
+    let output: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(output[0]["format"], "npm");
+    assert_eq!(output[0]["action"], "parse_lockfile");
+    assert!(output[0].get("packages").is_none());
+}
+
+#[test]
+fn parse_workspace_members() {
+    // `is-number` is shared by two members in the pnpm workspace.
+    let workspaces =
+        [("package-lock.json", "packages/b/package.json"), ("pnpm-lock.yaml", "pnpm-lock.yaml")];
+
+    for (lockfile, shared_lockfile) in workspaces {
+        let test_cli = TestCli::builder().cwd_temp().build();
+        let temp_path = test_cli.temp_path();
+
+        let fixture = format!("../tests/fixtures/workspace/{lockfile}");
+        fs::copy(fixture, temp_path.join(lockfile)).unwrap();
+
+        let output = test_cli.cmd().args(["parse", lockfile]).output().unwrap();
+        assert!(output.status.success());
+
+        // Ensure every package is attributed to exactly one dependency file.
+        let output: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let mut packages: Vec<_> = output
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|package| {
+                let name = package["name"].as_str().unwrap();
+                let version = package["version"].as_str().unwrap();
+                (format!("{name}@{version}"), package["lockfile"].as_str().unwrap().to_owned())
+            })
+            .collect();
+        packages.sort();
+        assert_eq!(packages, vec![
+            ("is-number@6.0.0".into(), shared_lockfile.into()),
+            ("is-odd@3.0.1".into(), "packages/b/package.json".into()),
+            ("left-pad@1.1.3".into(), "packages/b/package.json".into()),
+            ("left-pad@1.3.0".into(), "packages/a/package.json".into()),
+            ("lodash@4.17.21".into(), lockfile.into()),
+        ]);
+    }
+}
@@ -116,3 +116,22 @@ fn allow_net() {
         ])
         .success();
 }
+
+#[test]
+fn deny_unlisted_domain() {
+    let test_cli = TestCli::builder().build();
+
+    test_cli
+        .run([
+            "sandbox",
+            "--allow-run",
+            "/",
+            "--allow-domain",
+            "example.com",
+            "--",
+            "curl",
+            "https://veracode.com",
+        ])
+        .failure()
+        .stderr(predicate::str::contains("Sandbox denied network access to veracode.com:443"));
+}
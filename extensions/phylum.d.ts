@@ -405,6 +405,7 @@ declare namespace Phylum {
    *     write: false,
    *     run: ["ls"],
    *     net: false,
+   *     limits: { memory: 512 },
    *     strict: false,
    *   }
    * }
@@ -416,7 +417,19 @@ declare namespace Phylum {
    * For `run` the executables will be resolved from `$PATH` when they are
    * neither absolute nor start with `~/`.
    *
-   * The `net` permission accepts only boolean values.
+   * The `net` permission accepts either an array of domains, or a boolean.
+   * With an array, the process is pointed at a filtering proxy through the
+   * `HTTP_PROXY` and `HTTPS_PROXY` environment variables, which denies all
+   * other domains. Network access itself is not restricted, so clients
+   * ignoring these variables can connect to any domain.
+   *
+   * The `limits` exception restricts the resources of the process, using
+   * the `processes`, `memory` (MiB), `cpu_time` (seconds), `open_files`,
+   * `file_size` (MiB) and `output` (MiB) keys. Limits of the manifest are
+   * inherited and can only be lowered. Exceeded CPU time, memory, file size,
+   * output, process and open file limits are reported with the exit codes
+   * 119, 120, 121, 122, 123 and 124 respectively. Memory, process and open
+   * file limits are only detected from the error messages of the process.
    *
    * Some exceptions are added by default, to simplify the extension creation
    * process. If you're looking for more granular control, you can set strict
@@ -0,0 +1,434 @@
+//! Dependency metadata embedded in compiled binaries.
+//!
+//! Go embeds the module build info in every binary built with module support,
+//! while Rust binaries built with `cargo auditable` contain a compressed
+//! dependency list.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::str;
+
+use anyhow::{anyhow, Context};
+use flate2::read::ZlibDecoder;
+use object::{BinaryFormat, Object, ObjectSection};
+use phylum_types::types::package::PackageType;
+use serde::Deserialize;
+
+use crate::{EmbeddedPackage, Package, PackageVersion, Parse, ParseBinary};
+
+/// ELF file magic bytes.
+pub(crate) const ELF_MAGIC: &[u8] = b"\x7fELF";
+
+/// Section containing the Go build info.
+const GO_BUILDINFO_SECTION: &str = ".go.buildinfo";
+
+/// Magic bytes at the start of the Go build info.
+const GO_BUILDINFO_MAGIC: &[u8] = b"\xff Go buildinf:";
+
+/// Size of the Go build info header.
+const GO_BUILDINFO_HEADER_LEN: usize = 32;
+
+/// Go build info flag for inline strings, used since Go 1.18.
+const GO_BUILDINFO_INLINE: u8 = 0x2;
+
+/// Length of the sentinels surrounding Go's module info.
+const GO_MODINFO_SENTINEL_LEN: usize = 16;
+
+/// Section containing the `cargo auditable` dependency list.
+const CARGO_AUDITABLE_SECTION: &str = ".dep-v0";
+
+/// Maximum size of the decompressed `cargo auditable` dependency list.
+const CARGO_AUDITABLE_MAX_LEN: u64 = 8 * 1024 * 1024;
+
+pub struct Binary;
+
+impl Parse for Binary {
+    /// Compiled binaries can only be parsed from their raw bytes.
+    fn parse(&self, _data: &str) -> anyhow::Result<Vec<Package>> {
+        Err(anyhow!("Compiled binaries must be parsed from their raw bytes"))
+    }
+
+    /// Binaries cannot be identified by their name.
+    fn is_path_lockfile(&self, _path: &Path) -> bool {
+        false
+    }
+
+    fn is_path_manifest(&self, _path: &Path) -> bool {
+        false
+    }
+}
+
+impl ParseBinary for Binary {
+    fn is_binary(&self, data: &[u8]) -> bool {
+        data.starts_with(ELF_MAGIC)
+    }
+
+    /// Binaries are identified by their magic bytes, since executables usually
+    /// have no file extension.
+    fn is_path_binary(&self, path: &Path) -> bool {
+        let mut magic = [0; ELF_MAGIC.len()];
+        let read = File::open(path).and_then(|mut file| file.read_exact(&mut magic));
+        read.is_ok() && self.is_binary(&magic)
+    }
+
+    fn parse_binary(&self, data: &[u8]) -> anyhow::Result<Vec<EmbeddedPackage>> {
+        let file = object::File::parse(data).context("Invalid binary")?;
+        if file.format() != BinaryFormat::Elf {
+            return Err(anyhow!("Unsupported binary format {:?}", file.format()));
+        }
+
+        let mut packages = Vec::new();
+
+        if let Some(section) = file.section_by_name(GO_BUILDINFO_SECTION) {
+            let buildinfo = section.data().context("Invalid Go build info section")?;
+            packages.append(&mut parse_go_buildinfo(buildinfo)?);
+        }
+
+        if let Some(section) = file.section_by_name(CARGO_AUDITABLE_SECTION) {
+            let compressed = section.data().context("Invalid cargo auditable section")?;
+            packages.append(&mut parse_cargo_auditable(compressed)?);
+        }
+
+        let packages =
+            packages.into_iter().map(|package| EmbeddedPackage { package, origin: String::new() });
+        Ok(packages.collect())
+    }
+}
+
+/// Parse the Go build info into its module dependencies.
+///
+/// Only the inline string format used since Go 1.18 is supported. Older
+/// versions store pointers to the strings instead.
+fn parse_go_buildinfo(buildinfo: &[u8]) -> anyhow::Result<Vec<Package>> {
+    let header = buildinfo
+        .get(..GO_BUILDINFO_HEADER_LEN)
+        .filter(|header| header.starts_with(GO_BUILDINFO_MAGIC))
+        .ok_or_else(|| anyhow!("Invalid Go build info header"))?;
+
+    let flags = header[GO_BUILDINFO_MAGIC.len() + 1];
+    if flags & GO_BUILDINFO_INLINE == 0 {
+        return Err(anyhow!("Go build info of binaries before Go 1.18 is not supported"));
+    }
+
+    let mut data = &buildinfo[GO_BUILDINFO_HEADER_LEN..];
+    let _go_version = read_go_string(&mut data)?;
+    let mut modinfo = read_go_string(&mut data)?;
+
+    // Strip the sentinels surrounding the module info.
+    let len = modinfo.len();
+    if len > 2 * GO_MODINFO_SENTINEL_LEN && modinfo[len - GO_MODINFO_SENTINEL_LEN - 1] == b'\n' {
+        modinfo = &modinfo[GO_MODINFO_SENTINEL_LEN..len - GO_MODINFO_SENTINEL_LEN];
+    }
+
+    let modinfo = str::from_utf8(modinfo).context("Invalid Go module info")?;
+    parse_go_modinfo(modinfo)
+}
+
+/// Read a varint length-prefixed string from the Go build info.
+fn read_go_string<'a>(data: &mut &'a [u8]) -> anyhow::Result<&'a [u8]> {
+    let mut len = 0u64;
+    let mut shift = 0;
+    loop {
+        let (byte, rest) = data.split_first().ok_or_else(|| anyhow!("Truncated Go build info"))?;
+        *data = rest;
+
+        if shift > 63 {
+            return Err(anyhow!("Invalid Go build info string length"));
+        }
+        len |= u64::from(byte & 0x7f) << shift;
+        shift += 7;
+
+        if byte & 0x80 == 0 {
+            break;
+        }
+    }
+
+    let len = usize::try_from(len)?;
+    if len > data.len() {
+        return Err(anyhow!("Truncated Go build info"));
+    }
+
+    let (string, rest) = data.split_at(len);
+    *data = rest;
+
+    Ok(string)
+}
+
+/// Parse Go's module info into its module dependencies.
+///
+/// Every line contains tab-separated fields, with module dependencies being
+/// listed as `dep <MODULE> <VERSION> <HASH>`. Replacements are listed as
+/// `=> <MODULE> [<VERSION> <HASH>]` directly after the dependency they replace.
+fn parse_go_modinfo(modinfo: &str) -> anyhow::Result<Vec<Package>> {
+    let mut packages = Vec::new();
+
+    for line in modinfo.lines() {
+        let mut fields = line.split('\t');
+        match (fields.next(), fields.next(), fields.next()) {
+            (Some("dep"), Some(name), Some(version)) => packages.push(Package {
+                name: name.into(),
+                version: PackageVersion::FirstParty(version.into()),
+                package_type: PackageType::Golang,
+            }),
+            (Some("=>"), Some(name), version) => {
+                let package = packages
+                    .last_mut()
+                    .ok_or_else(|| anyhow!("Go module replacement without module: {line:?}"))?;
+
+                *package = match version.filter(|version| !version.is_empty()) {
+                    Some(version) => Package {
+                        name: name.into(),
+                        version: PackageVersion::FirstParty(version.into()),
+                        package_type: PackageType::Golang,
+                    },
+                    // Modules replaced by a local directory.
+                    None => Package {
+                        name: name.into(),
+                        version: PackageVersion::Path(Some(name.into())),
+                        package_type: PackageType::Golang,
+                    },
+                };
+            },
+            _ => (),
+        }
+    }
+
+    Ok(packages)
+}
+
+/// Dependency list embedded by `cargo auditable`.
+#[derive(Deserialize)]
+struct AuditableInfo {
+    packages: Vec<AuditablePackage>,
+}
+
+/// Package in the `cargo auditable` dependency list.
+#[derive(Deserialize)]
+struct AuditablePackage {
+    name: String,
+    version: String,
+    source: String,
+    #[serde(default)]
+    root: bool,
+}
+
+/// Parse the zlib-compressed `cargo auditable` dependency list.
+fn parse_cargo_auditable(compressed: &[u8]) -> anyhow::Result<Vec<Package>> {
+    // Limit the decompressed size to protect against decompression bombs.
+    let mut json = Vec::new();
+    ZlibDecoder::new(compressed)
+        .take(CARGO_AUDITABLE_MAX_LEN)
+        .read_to_end(&mut json)
+        .context("Invalid cargo auditable section")?;
+
+    let info: AuditableInfo = serde_json::from_slice(&json)?;
+
+    let packages = info
+        .packages
+        .into_iter()
+        // The root package is the binary itself.
+        .filter(|package| !package.root)
+        .filter_map(|package| {
+            let version = match package.source.as_str() {
+                "crates.io" => PackageVersion::FirstParty(package.version),
+                "local" => PackageVersion::Path(None),
+                // Standard library crates from `-Z build-std`.
+                "builtin" => return None,
+                // Git and alternative registry URLs are not recorded.
+                _ => PackageVersion::Unknown,
+            };
+
+            Some(Package { name: package.name, version, package_type: PackageType::Cargo })
+        })
+        .collect();
+
+    Ok(packages)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+
+    use super::*;
+
+    /// Create a minimal little-endian ELF64 file with the specified sections.
+    fn elf(sections: &[(&str, &[u8])]) -> Vec<u8> {
+        const HEADER_LEN: usize = 64;
+        const SECTION_HEADER_LEN: usize = 64;
+
+        // Section names, starting with the null section's empty name.
+        let mut shstrtab = vec![0];
+        let mut name_offsets = Vec::new();
+        for name in sections.iter().map(|(name, _)| *name).chain([".shstrtab"]) {
+            name_offsets.push(shstrtab.len() as u32);
+            shstrtab.extend_from_slice(name.as_bytes());
+            shstrtab.push(0);
+        }
+
+        // Section content, followed by the section name table.
+        let mut content = Vec::new();
+        let mut headers = Vec::new();
+        let section_data = sections.iter().map(|(_, data)| (1u32, *data));
+        let section_data = section_data.chain([(3, shstrtab.as_slice())]);
+        for ((sh_type, data), name_offset) in section_data.zip(&name_offsets) {
+            headers.push((*name_offset, sh_type, (HEADER_LEN + content.len()) as u64, data.len()));
+            content.extend_from_slice(data);
+        }
+        content.resize(content.len().next_multiple_of(8), 0);
+
+        let section_count = headers.len() + 1;
+        let mut elf = Vec::new();
+        elf.extend_from_slice(b"\x7fELF\x02\x01\x01\0\0\0\0\0\0\0\0\0");
+        elf.extend_from_slice(&2u16.to_le_bytes()); // e_type: executable
+        elf.extend_from_slice(&62u16.to_le_bytes()); // e_machine: x86-64
+        elf.extend_from_slice(&1u32.to_le_bytes()); // e_version
+        elf.extend_from_slice(&0u64.to_le_bytes()); // e_entry
+        elf.extend_from_slice(&0u64.to_le_bytes()); // e_phoff
+        elf.extend_from_slice(&((HEADER_LEN + content.len()) as u64).to_le_bytes()); // e_shoff
+        elf.extend_from_slice(&0u32.to_le_bytes()); // e_flags
+        elf.extend_from_slice(&(HEADER_LEN as u16).to_le_bytes()); // e_ehsize
+        elf.extend_from_slice(&56u16.to_le_bytes()); // e_phentsize
+        elf.extend_from_slice(&0u16.to_le_bytes()); // e_phnum
+        elf.extend_from_slice(&(SECTION_HEADER_LEN as u16).to_le_bytes()); // e_shentsize
+        elf.extend_from_slice(&(section_count as u16).to_le_bytes()); // e_shnum
+        elf.extend_from_slice(&(section_count as u16 - 1).to_le_bytes()); // e_shstrndx
+        elf.extend_from_slice(&content);
+
+        // Null section header.
+        elf.extend_from_slice(&[0; SECTION_HEADER_LEN]);
+        for (name, sh_type, offset, size) in headers {
+            elf.extend_from_slice(&name.to_le_bytes());
+            elf.extend_from_slice(&sh_type.to_le_bytes());
+            elf.extend_from_slice(&0u64.to_le_bytes()); // sh_flags
+            elf.extend_from_slice(&0u64.to_le_bytes()); // sh_addr
+            elf.extend_from_slice(&offset.to_le_bytes());
+            elf.extend_from_slice(&(size as u64).to_le_bytes());
+            elf.extend_from_slice(&0u32.to_le_bytes()); // sh_link
+            elf.extend_from_slice(&0u32.to_le_bytes()); // sh_info
+            elf.extend_from_slice(&1u64.to_le_bytes()); // sh_addralign
+            elf.extend_from_slice(&0u64.to_le_bytes()); // sh_entsize
+        }
+
+        elf
+    }
+
+    /// Create Go 1.18+ build info for the specified module info.
+    fn go_buildinfo(modinfo: &str) -> Vec<u8> {
+        let mut buildinfo = GO_BUILDINFO_MAGIC.to_vec();
+        buildinfo.extend_from_slice(&[8, GO_BUILDINFO_INLINE]);
+        buildinfo.resize(GO_BUILDINFO_HEADER_LEN, 0);
+
+        let modinfo = format!("{}{modinfo}\n{}", "s".repeat(16), "e".repeat(16));
+        for string in ["go1.22.1", &modinfo] {
+            let mut len = string.len();
+            while len >= 0x80 {
+                buildinfo.push(len as u8 | 0x80);
+                len >>= 7;
+            }
+            buildinfo.push(len as u8);
+            buildinfo.extend_from_slice(string.as_bytes());
+        }
+
+        buildinfo
+    }
+
+    /// Get all packages embedded in a binary.
+    fn embedded_packages(binary: &[u8]) -> Vec<Package> {
+        let embedded = Binary.parse_binary(binary).unwrap();
+        embedded.into_iter().map(|embedded| embedded.package).collect()
+    }
+
+    fn package(name: &str, version: &str, package_type: PackageType) -> Package {
+        Package {
+            name: name.into(),
+            version: PackageVersion::FirstParty(version.into()),
+            package_type,
+        }
+    }
+
+    #[test]
+    fn parse_go_binary() {
+        let modinfo = [
+            "path\texample.com/app",
+            "mod\texample.com/app\t(devel)\t",
+            "dep\tgithub.com/google/uuid\tv1.6.0\th1:NIvaJDMOsjHA8n1jAhLSgzrAzy1Hgr+hNrb57e+94F0=",
+            "dep\tgithub.com/pkg/errors\tv0.9.1\th1:FEBLx1zS214owpjy7qsBeixbURkuhQAwrK5UwLGTwt4=",
+            "=>\tgithub.com/pkg/errors\tv0.8.1\th1:iURUrRGxPUNPdy5/HRSm+Yj6okJ6UtLINN0Q9M4+h3I=",
+            "dep\texample.com/local\tv0.0.0-00010101000000-000000000000\t",
+            "=>\t../local\t\t",
+            "build\t-compiler=gc",
+            "build\tGOOS=linux",
+        ]
+        .join("\n");
+        let binary =
+            elf(&[(".text", b"\x90\x90"), (GO_BUILDINFO_SECTION, &go_buildinfo(&modinfo))]);
+
+        assert!(Binary.is_binary(&binary));
+        let pkgs = embedded_packages(&binary);
+
+        assert_eq!(pkgs, vec![
+            package("github.com/google/uuid", "v1.6.0", PackageType::Golang),
+            package("github.com/pkg/errors", "v0.8.1", PackageType::Golang),
+            Package {
+                name: "../local".into(),
+                version: PackageVersion::Path(Some("../local".into())),
+                package_type: PackageType::Golang,
+            },
+        ]);
+    }
+
+    #[test]
+    fn legacy_go_buildinfo_unsupported() {
+        let mut buildinfo = go_buildinfo("");
+        buildinfo[GO_BUILDINFO_MAGIC.len() + 1] = 0;
+        let binary = elf(&[(GO_BUILDINFO_SECTION, &buildinfo)]);
+
+        assert!(Binary.parse_binary(&binary).is_err());
+    }
+
+    #[test]
+    fn parse_cargo_auditable_binary() {
+        let json = r#"{"packages":[
+            {"name":"app","version":"0.1.0","source":"local","root":true,"dependencies":[1,2,3]},
+            {"name":"serde","version":"1.0.197","source":"crates.io"},
+            {"name":"cc","version":"1.0.90","source":"crates.io","kind":"build"},
+            {"name":"forked","version":"0.2.0","source":"git"},
+            {"name":"helper","version":"0.1.0","source":"local"},
+            {"name":"core","version":"0.0.0","source":"builtin"}
+        ]}"#;
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(json.as_bytes()).unwrap();
+        let binary = elf(&[(CARGO_AUDITABLE_SECTION, &encoder.finish().unwrap())]);
+
+        let pkgs = embedded_packages(&binary);
+
+        assert_eq!(pkgs, vec![
+            package("serde", "1.0.197", PackageType::Cargo),
+            package("cc", "1.0.90", PackageType::Cargo),
+            Package {
+                name: "forked".into(),
+                version: PackageVersion::Unknown,
+                package_type: PackageType::Cargo,
+            },
+            Package {
+                name: "helper".into(),
+                version: PackageVersion::Path(None),
+                package_type: PackageType::Cargo,
+            },
+        ]);
+    }
+
+    #[test]
+    fn binary_without_metadata() {
+        let binary = elf(&[(".text", b"\x90")]);
+        assert_eq!(embedded_packages(&binary), Vec::new());
+
+        assert!(!Binary.is_binary(b"{}"));
+        assert!(Binary.parse_binary(b"\x7fELF").is_err());
+        assert!(Binary.parse("\x7fELF").is_err());
+    }
+}
@@ -273,7 +273,8 @@ mod tests {
     #[test]
     fn lock_generate_csproj() {
         let path = Path::new("../tests/fixtures/lock_generate_csproj/lock_generate_csproj.csproj");
-        let lockfile = DotnetGenerator.generate_lockfile(path).unwrap();
+        let options = lockfile_generator::Options::default();
+        let lockfile = DotnetGenerator.generate_lockfile(path, &options).unwrap().contents;
 
         let pkgs = PackagesLock.parse(&lockfile).unwrap();
         assert_eq!(pkgs.len(), 15);
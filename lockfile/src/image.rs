@@ -0,0 +1,682 @@
+//! Container image scanning.
+//!
+//! Images are read from OCI image layouts, either as directory or tar archive,
+//! and from archives created by `docker save`. No container runtime is
+//! required.
+
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsStr;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{anyhow, Context};
+use flate2::read::GzDecoder;
+use log::{debug, warn};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use tar::Archive;
+use tempfile::TempDir;
+
+use crate::binary::ELF_MAGIC;
+use crate::parse_depfile::filter_packages;
+use crate::{
+    parse_binary, parse_depfile, Binary, DepFiles, DiscoveryOptions, LockfileFormat, ParseBinary,
+    ParsedLockfile,
+};
+
+/// Prefix of files marking the deletion of a lower layer's file.
+const WHITEOUT_PREFIX: &str = ".wh.";
+
+/// File marking a directory's lower layer content as deleted.
+const OPAQUE_WHITEOUT: &str = ".wh..wh..opq";
+
+/// Dependency file found in a container image.
+#[derive(Debug)]
+pub struct ImageDepfile {
+    /// Digest of the layer which added the dependency file.
+    pub layer: String,
+    /// Parsed dependency file, with its absolute path inside the image.
+    pub depfile: ParsedLockfile,
+}
+
+/// Find all dependency files and installed packages in a container image.
+///
+/// The `path` must point to an OCI image layout directory, or a tar archive
+/// containing either an OCI image layout or the output of `docker save`.
+///
+/// Installed environments are reported with one dependency file per package
+/// metadata file, so every package can be attributed to the layer which
+/// installed it.
+pub fn scan_image(path: &Path) -> anyhow::Result<Vec<ImageDepfile>> {
+    let source = ImageSource::open(path)?;
+
+    let mut rootfs = RootFs::new()?;
+    for layer in source.layers()? {
+        debug!("Extracting image layer {}", layer.digest);
+
+        let reader = decompress(source.read(&layer.path)?)?;
+        rootfs
+            .apply_layer(&layer.digest, reader)
+            .with_context(|| format!("Failed to extract image layer {}", layer.digest))?;
+    }
+
+    rootfs.depfiles()
+}
+
+/// Location of an image's files.
+enum ImageSource {
+    /// OCI image layout directory.
+    Directory(PathBuf),
+    /// Tar archive, with the offset and size of every file.
+    Archive { path: PathBuf, entries: HashMap<String, (u64, u64)> },
+}
+
+impl ImageSource {
+    fn open(path: &Path) -> anyhow::Result<Self> {
+        if path.is_dir() {
+            return Ok(Self::Directory(path.into()));
+        }
+
+        // Index archive entries, to allow reading blobs without extracting them.
+        let file = File::open(path).with_context(|| format!("Failed to open {path:?}"))?;
+        let mut archive = Archive::new(file);
+        let mut entries = HashMap::new();
+        for entry in archive.entries_with_seek().context("Invalid image archive")? {
+            let entry = entry?;
+            if let Some(name) = normalize_path(&entry.path()?) {
+                let name = name.to_string_lossy().into_owned();
+                entries.insert(name, (entry.raw_file_position(), entry.size()));
+            }
+        }
+
+        Ok(Self::Archive { path: path.into(), entries })
+    }
+
+    /// Check if a file exists in the image.
+    fn exists(&self, path: &str) -> bool {
+        match self {
+            Self::Directory(root) => root.join(path).is_file(),
+            Self::Archive { entries, .. } => entries.contains_key(path),
+        }
+    }
+
+    /// Read a file from the image.
+    fn read(&self, path: &str) -> anyhow::Result<Box<dyn Read>> {
+        let relative = normalize_path(Path::new(path))
+            .ok_or_else(|| anyhow!("Invalid image path {path:?}"))?;
+
+        match self {
+            Self::Directory(root) => {
+                let path = root.join(relative);
+                let file = File::open(&path).with_context(|| format!("Failed to open {path:?}"))?;
+                Ok(Box::new(file))
+            },
+            Self::Archive { path: archive, entries } => {
+                let (offset, size) = entries
+                    .get(&*relative.to_string_lossy())
+                    .ok_or_else(|| anyhow!("Image archive is missing {path:?}"))?;
+
+                let mut file = File::open(archive)?;
+                file.seek(SeekFrom::Start(*offset))?;
+
+                Ok(Box::new(file.take(*size)))
+            },
+        }
+    }
+
+    /// Read a JSON file from the image.
+    fn read_json<T: DeserializeOwned>(&self, path: &str) -> anyhow::Result<T> {
+        let reader = self.read(path)?;
+        serde_json::from_reader(reader).with_context(|| format!("Invalid image file {path:?}"))
+    }
+
+    /// Get all image layers, starting with the lowest.
+    fn layers(&self) -> anyhow::Result<Vec<Layer>> {
+        // Prefer the Docker manifest, since `docker save` may include both formats.
+        if self.exists("manifest.json") {
+            let manifests: Vec<DockerManifest> = self.read_json("manifest.json")?;
+            let manifest = manifests
+                .into_iter()
+                .next()
+                .ok_or_else(|| anyhow!("Image archive does not contain any images"))?;
+
+            let layers = manifest
+                .layers
+                .into_iter()
+                .map(|path| Layer { digest: docker_layer_digest(&path), path });
+            return Ok(layers.collect());
+        }
+
+        if !self.exists("index.json") {
+            return Err(anyhow!("Not an OCI image layout or `docker save` archive"));
+        }
+
+        // Follow the image index until we reach the first image manifest.
+        let mut document: OciDocument = self.read_json("index.json")?;
+        loop {
+            match document {
+                OciDocument::Index { manifests } => {
+                    let descriptor = manifests
+                        .into_iter()
+                        .next()
+                        .ok_or_else(|| anyhow!("Image index does not contain any images"))?;
+                    document = self.read_json(&blob_path(&descriptor.digest)?)?;
+                },
+                OciDocument::Manifest { layers } => {
+                    return layers
+                        .into_iter()
+                        .map(|layer| {
+                            Ok(Layer { path: blob_path(&layer.digest)?, digest: layer.digest })
+                        })
+                        .collect();
+                },
+            }
+        }
+    }
+}
+
+/// Image layer.
+struct Layer {
+    /// Layer tarball path inside the image.
+    path: String,
+    digest: String,
+}
+
+/// Image manifest created by `docker save`.
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DockerManifest {
+    layers: Vec<String>,
+}
+
+/// OCI image index or image manifest.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OciDocument {
+    Index { manifests: Vec<OciDescriptor> },
+    Manifest { layers: Vec<OciDescriptor> },
+}
+
+/// OCI content descriptor.
+#[derive(Deserialize)]
+struct OciDescriptor {
+    digest: String,
+}
+
+/// Get the blob path for an OCI digest.
+fn blob_path(digest: &str) -> anyhow::Result<String> {
+    // Restrict digests to the OCI grammar, which also prevents path traversal.
+    let valid_algorithm = |algorithm: &str| {
+        algorithm.starts_with(|c: char| c.is_ascii_alphanumeric())
+            && algorithm.chars().all(|c| c.is_ascii_alphanumeric() || "+._-".contains(c))
+    };
+    let valid_hash = |hash: &str| {
+        !hash.is_empty() && hash.chars().all(|c| c.is_ascii_alphanumeric() || "=_-".contains(c))
+    };
+
+    match digest.split_once(':') {
+        Some((algorithm, hash)) if valid_algorithm(algorithm) && valid_hash(hash) => {
+            Ok(format!("blobs/{algorithm}/{hash}"))
+        },
+        _ => Err(anyhow!("Invalid image digest {digest:?}")),
+    }
+}
+
+/// Get the digest of a layer from its path in a `docker save` archive.
+///
+/// Depending on the Docker version, layers are stored either as
+/// `blobs/<ALGORITHM>/<HASH>` or as `<ID>/layer.tar`.
+fn docker_layer_digest(path: &str) -> String {
+    if let Some((algorithm, hash)) = path.strip_prefix("blobs/").and_then(|b| b.split_once('/')) {
+        return format!("{algorithm}:{hash}");
+    }
+
+    match path.strip_suffix("/layer.tar") {
+        Some(id) => id.into(),
+        None => path.into(),
+    }
+}
+
+/// Transparently decompress a layer.
+fn decompress(reader: Box<dyn Read>) -> anyhow::Result<Box<dyn Read>> {
+    let mut reader = BufReader::new(reader);
+    let magic = reader.fill_buf()?;
+
+    if magic.starts_with(&[0x1f, 0x8b]) {
+        Ok(Box::new(GzDecoder::new(reader)))
+    } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        Err(anyhow!("Zstandard compressed image layers are not supported"))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+/// Flattened image filesystem.
+///
+/// Only files relevant for dependency discovery are extracted.
+struct RootFs {
+    dir: TempDir,
+    /// Digest of the layer which added each file.
+    origins: HashMap<PathBuf, String>,
+}
+
+impl RootFs {
+    fn new() -> anyhow::Result<Self> {
+        Ok(Self { dir: tempfile::tempdir()?, origins: HashMap::new() })
+    }
+
+    /// Apply a layer on top of the current filesystem.
+    fn apply_layer(&mut self, digest: &str, reader: impl Read) -> anyhow::Result<()> {
+        // Whiteouts only affect lower layers, but can appear anywhere within the
+        // layer, so all files are staged before they are merged.
+        let staging = tempfile::tempdir()?;
+        let mut staged = Vec::new();
+        let mut opaque_dirs = Vec::new();
+        let mut whiteouts = Vec::new();
+
+        let mut archive = Archive::new(reader);
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let path = match normalize_path(&entry.path()?) {
+                Some(path) => path,
+                None => continue,
+            };
+
+            let parent = path.parent().unwrap_or(Path::new("")).to_path_buf();
+            let file_name = path.file_name().and_then(OsStr::to_str).unwrap_or_default();
+            if file_name == OPAQUE_WHITEOUT {
+                opaque_dirs.push(parent);
+                continue;
+            } else if let Some(deleted) = file_name.strip_prefix(WHITEOUT_PREFIX) {
+                // Ignore whiteouts which do not refer to a sibling, like `.wh..`.
+                let mut components = Path::new(deleted).components();
+                if let (Some(Component::Normal(deleted)), None) =
+                    (components.next(), components.next())
+                {
+                    whiteouts.push(parent.join(deleted));
+                }
+                continue;
+            }
+
+            // Links are skipped, since they could point outside of the image.
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+
+            // Executables usually have no extension, so they're identified by content.
+            let mut magic = Vec::new();
+            if !is_relevant(&path) {
+                (&mut entry).take(ELF_MAGIC.len() as u64).read_to_end(&mut magic)?;
+                if !Binary.is_binary(&magic) {
+                    continue;
+                }
+            }
+
+            let target = staging.path().join(&path);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let mut file = File::create(&target)?;
+            file.write_all(&magic)?;
+            io::copy(&mut entry, &mut file)?;
+            staged.push(path);
+        }
+
+        for dir in opaque_dirs {
+            self.remove_children(&dir)?;
+        }
+        for path in whiteouts {
+            self.remove(&path)?;
+        }
+
+        for path in staged {
+            // Replace files or directories from lower layers.
+            for ancestor in path.ancestors().skip(1) {
+                if self.dir.path().join(ancestor).is_file() {
+                    self.remove(ancestor)?;
+                }
+            }
+            self.remove(&path)?;
+
+            let target = self.dir.path().join(&path);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::rename(staging.path().join(&path), &target)?;
+
+            self.origins.insert(path, digest.into());
+        }
+
+        Ok(())
+    }
+
+    /// Remove a file or directory.
+    fn remove(&mut self, path: &Path) -> io::Result<()> {
+        // Never remove anything outside of the filesystem or its root itself.
+        let target = self.dir.path().join(path);
+        let is_normal =
+            path.components().all(|component| matches!(component, Component::Normal(_)));
+        if !is_normal || target == self.dir.path() || !target.starts_with(self.dir.path()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid image path {path:?}"),
+            ));
+        }
+
+        match fs::symlink_metadata(&target) {
+            Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(&target)?,
+            Ok(_) => fs::remove_file(&target)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+            Err(err) => return Err(err),
+        }
+
+        self.origins.retain(|origin, _| !origin.starts_with(path));
+
+        Ok(())
+    }
+
+    /// Remove all files inside a directory.
+    fn remove_children(&mut self, dir: &Path) -> io::Result<()> {
+        let entries = match fs::read_dir(self.dir.path().join(dir)) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err),
+        };
+
+        for entry in entries {
+            self.remove(&dir.join(entry?.file_name()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Parse all dependency files in the filesystem.
+    fn depfiles(&self) -> anyhow::Result<Vec<ImageDepfile>> {
+        let root = self.dir.path();
+
+        // Images do not follow the ignore rules of a source repository.
+        let options = DiscoveryOptions {
+            max_depth: None,
+            formats: LockfileFormat::iter().collect(),
+            standard_filters: false,
+            ..DiscoveryOptions::default()
+        };
+        let found = DepFiles::find_with_options(root, &options)?;
+
+        for (path, _) in &found.manifests {
+            debug!("Ignoring image manifest without lockfile: {path:?}");
+        }
+
+        let mut depfiles = Vec::new();
+        for (path, format) in found.lockfiles {
+            let relative = path.strip_prefix(root)?;
+
+            // Binaries like Java archives may contain multiple dependency files.
+            if format.binary_parser().is_some() {
+                let contents = fs::read(&path)?;
+                match parse_binary(&contents, image_path(relative), Some(format)) {
+                    // Skip executables without dependency metadata, like most system binaries.
+                    Ok(binary_depfiles)
+                        if format == LockfileFormat::Binary
+                            && binary_depfiles
+                                .iter()
+                                .all(|depfile| depfile.packages.is_empty()) =>
+                    {
+                        debug!("Ignoring image binary without dependencies: {relative:?}");
+                    },
+                    Ok(binary_depfiles) => depfiles.extend(
+                        binary_depfiles
+                            .into_iter()
+                            .map(|depfile| ImageDepfile { layer: self.origin(relative), depfile }),
+                    ),
+                    Err(err) => warn!("Skipping image binary {relative:?}: {err}"),
+                }
+                continue;
+            }
+
+            let installed_parser = format.installed_parser().filter(|_| path.is_dir());
+            let installed = match installed_parser {
+                Some(parser) => parser.parse_installed(&path),
+                None => {
+                    let contents = fs::read_to_string(&path)?;
+                    match parse_depfile(&contents, image_path(relative), Some(format), None) {
+                        Ok(depfile) => {
+                            depfiles.push(ImageDepfile { layer: self.origin(relative), depfile });
+                        },
+                        Err(err) => warn!("Skipping image dependency file {relative:?}: {err}"),
+                    }
+                    continue;
+                },
+            };
+
+            let installed = match installed {
+                Ok(installed) => installed,
+                Err(err) => {
+                    warn!("Skipping installed packages in {relative:?}: {err:?}");
+                    continue;
+                },
+            };
+
+            // Attribute packages to the metadata file which declared them.
+            let mut metadata_files = BTreeMap::<_, Vec<_>>::new();
+            for installed in installed {
+                let metadata_path = installed.path.strip_prefix(root)?.to_path_buf();
+                metadata_files.entry(metadata_path).or_default().push(installed.package);
+            }
+
+            for (metadata_path, packages) in metadata_files {
+                let packages = filter_packages(packages);
+                let depfile = ParsedLockfile::new(image_path(&metadata_path), format, packages);
+                depfiles.push(ImageDepfile { layer: self.origin(&metadata_path), depfile });
+            }
+        }
+
+        Ok(depfiles)
+    }
+
+    /// Get the digest of the layer which added a file.
+    fn origin(&self, path: &Path) -> String {
+        self.origins.get(path).cloned().unwrap_or_default()
+    }
+}
+
+/// Check if a file is relevant for dependency discovery.
+fn is_relevant(path: &Path) -> bool {
+    // Lockfiles and manifests, which includes installed node modules.
+    let is_depfile = LockfileFormat::iter().any(|format| {
+        let parser = format.parser();
+        parser.is_path_lockfile(path) || parser.is_path_manifest(path)
+    });
+
+    // Python distribution metadata.
+    let is_python_metadata = path.extension() == Some(OsStr::new("egg-info"))
+        || path.parent().and_then(Path::extension).is_some_and(|extension| {
+            extension == OsStr::new("dist-info") || extension == OsStr::new("egg-info")
+        });
+
+    is_depfile || is_python_metadata
+}
+
+/// Convert an archive path to a relative path.
+///
+/// Returns `None` for empty paths and paths escaping the root directory.
+fn normalize_path(path: &Path) -> Option<PathBuf> {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(component) => normalized.push(component),
+            Component::RootDir | Component::CurDir => (),
+            Component::ParentDir | Component::Prefix(_) => return None,
+        }
+    }
+
+    (!normalized.as_os_str().is_empty()).then_some(normalized)
+}
+
+/// Get the absolute path of a file inside the image.
+fn image_path(relative: &Path) -> String {
+    format!("/{}", relative.display())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use phylum_types::types::package::PackageDescriptor;
+
+    use super::*;
+
+    const PACKAGE_LOCK: &str = r#"{
+        "lockfileVersion": 3,
+        "packages": {
+            "": { "name": "app" },
+            "node_modules/left-pad": {
+                "version": "1.3.0",
+                "resolved": "https://registry.npmjs.org/left-pad/-/left-pad-1.3.0.tgz"
+            }
+        }
+    }"#;
+
+    /// Create a tar archive from a list of files.
+    fn tarball(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            builder.append_data(&mut header, path, *content).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn metadata(name: &str, version: &str) -> Vec<u8> {
+        format!("Metadata-Version: 2.1\nName: {name}\nVersion: {version}\n").into_bytes()
+    }
+
+    /// Get all depfile paths, layers and packages sorted by path.
+    fn summarize(depfiles: Vec<ImageDepfile>) -> Vec<(String, String, Vec<PackageDescriptor>)> {
+        let mut summary: Vec<_> = depfiles
+            .into_iter()
+            .map(|depfile| (depfile.depfile.path, depfile.layer, depfile.depfile.packages))
+            .collect();
+        summary.sort_by(|a, b| a.0.cmp(&b.0));
+        summary
+    }
+
+    #[test]
+    fn docker_archive_with_whiteouts() {
+        let site_packages = "usr/lib/python3/site-packages";
+        let requests = metadata("requests", "2.31.0");
+        let six = metadata("six", "1.16.0");
+
+        let lower = tarball(&[
+            ("app/package-lock.json", PACKAGE_LOCK.as_bytes()),
+            ("app/old/package-lock.json", PACKAGE_LOCK.as_bytes()),
+            (&format!("{site_packages}/requests-2.31.0.dist-info/METADATA"), &requests),
+            ("usr/bin/python3", b"\x7fELF"),
+        ]);
+        let upper = gzip(&tarball(&[
+            ("app/.wh.old", b""),
+            (&format!("{site_packages}/.wh..wh..opq"), b""),
+            (&format!("{site_packages}/six-1.16.0.dist-info/METADATA"), &six),
+        ]));
+        let manifest = br#"[{
+            "Config": "config.json",
+            "RepoTags": ["app:latest"],
+            "Layers": ["lower/layer.tar", "blobs/sha256/upper"]
+        }]"#;
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let archive_path = tempdir.path().join("image.tar");
+        let archive = tarball(&[
+            ("manifest.json", manifest),
+            ("lower/layer.tar", &lower),
+            ("blobs/sha256/upper", &upper),
+        ]);
+        fs::write(&archive_path, archive).unwrap();
+
+        let depfiles = scan_image(&archive_path).unwrap();
+
+        let left_pad = PackageDescriptor {
+            name: "left-pad".into(),
+            version: "1.3.0".into(),
+            package_type: phylum_types::types::package::PackageType::Npm,
+        };
+        let six = PackageDescriptor {
+            name: "six".into(),
+            version: "1.16.0".into(),
+            package_type: phylum_types::types::package::PackageType::PyPi,
+        };
+        assert_eq!(summarize(depfiles), vec![
+            ("/app/package-lock.json".into(), "lower".into(), vec![left_pad]),
+            (
+                format!("/{site_packages}/six-1.16.0.dist-info/METADATA"),
+                "sha256:upper".into(),
+                vec![six]
+            ),
+        ]);
+    }
+
+    #[test]
+    fn reject_escaping_whiteouts() {
+        let lower = tarball(&[("app/package-lock.json", PACKAGE_LOCK.as_bytes())]);
+        let upper = tarball(&[("app/.wh..", b""), ("app/.wh...", b""), ("app/.wh.", b"")]);
+
+        let mut rootfs = RootFs::new().unwrap();
+        rootfs.apply_layer("sha256:lower", lower.as_slice()).unwrap();
+        rootfs.apply_layer("sha256:upper", upper.as_slice()).unwrap();
+
+        // Ensure the whiteouts did not delete the filesystem's root.
+        assert!(rootfs.dir.path().join("app/package-lock.json").is_file());
+        assert_eq!(rootfs.depfiles().unwrap().len(), 1);
+
+        assert!(rootfs.remove(Path::new("..")).is_err());
+        assert!(rootfs.remove(Path::new("app/../..")).is_err());
+        assert!(rootfs.remove(Path::new("")).is_err());
+        assert!(rootfs.dir.path().join("app/package-lock.json").is_file());
+    }
+
+    #[test]
+    fn oci_layout_directory() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let blobs = tempdir.path().join("blobs/sha256");
+        fs::create_dir_all(&blobs).unwrap();
+
+        let layer = tarball(&[("srv/package-lock.json", PACKAGE_LOCK.as_bytes())]);
+        fs::write(blobs.join("layer"), gzip(&layer)).unwrap();
+        fs::write(blobs.join("manifest"), r#"{ "layers": [{ "digest": "sha256:layer" }] }"#)
+            .unwrap();
+        fs::write(
+            tempdir.path().join("index.json"),
+            r#"{ "manifests": [{ "digest": "sha256:manifest" }] }"#,
+        )
+        .unwrap();
+        fs::write(tempdir.path().join("oci-layout"), r#"{ "imageLayoutVersion": "1.0.0" }"#)
+            .unwrap();
+
+        let depfiles = scan_image(tempdir.path()).unwrap();
+
+        assert_eq!(depfiles.len(), 1);
+        assert_eq!(depfiles[0].layer, "sha256:layer");
+        assert_eq!(depfiles[0].depfile.path, "/srv/package-lock.json");
+        assert_eq!(depfiles[0].depfile.format, LockfileFormat::Npm);
+    }
+
+    #[test]
+    fn reject_escaping_paths() {
+        assert_eq!(normalize_path(Path::new("/app/./a")), Some(PathBuf::from("app/a")));
+        assert_eq!(normalize_path(Path::new("../etc/passwd")), None);
+        assert_eq!(normalize_path(Path::new("./")), None);
+        assert!(blob_path("sha256:../../etc").is_err());
+        assert!(blob_path("..:..").is_err());
+    }
+}
@@ -0,0 +1,346 @@
+//! Inventory of installed environments.
+//!
+//! Contrary to lockfiles, these formats describe packages which are actually
+//! present on the filesystem, like a `node_modules` directory or Python's
+//! `site-packages`.
+
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context};
+use log::debug;
+use phylum_types::types::package::PackageType;
+use serde::Deserialize;
+#[cfg(not(target_arch = "wasm32"))]
+use walkdir::WalkDir;
+
+use crate::{InstalledPackage, Package, PackageVersion, Parse, ParseInstalled};
+
+pub struct NodeModules;
+
+impl Parse for NodeModules {
+    /// Installed node modules can only be parsed from a directory.
+    fn parse(&self, _data: &str) -> anyhow::Result<Vec<Package>> {
+        Err(anyhow!("Installed node modules must be parsed from a `node_modules` directory"))
+    }
+
+    fn is_path_lockfile(&self, path: &Path) -> bool {
+        path.file_name() == Some(OsStr::new("node_modules"))
+    }
+
+    fn is_path_manifest(&self, _path: &Path) -> bool {
+        false
+    }
+}
+
+impl ParseInstalled for NodeModules {
+    fn parse_installed(&self, root: &Path) -> anyhow::Result<Vec<InstalledPackage>> {
+        let mut packages = Vec::new();
+
+        // Symlinks are not followed, to avoid traversing into local packages and
+        // duplicate entries for pnpm's virtual store.
+        for path in walk_files(root)? {
+            let path = path.as_path();
+            if !is_node_module_manifest(path) {
+                continue;
+            }
+
+            let content = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let manifest: PackageJson = match serde_json::from_str(&content) {
+                Ok(manifest) => manifest,
+                Err(err) => {
+                    debug!("ignoring invalid package manifest {path:?}: {err}");
+                    continue;
+                },
+            };
+
+            match (manifest.name, manifest.version) {
+                (Some(name), Some(version)) => {
+                    let package = Package {
+                        name,
+                        version: PackageVersion::FirstParty(version),
+                        package_type: PackageType::Npm,
+                    };
+                    packages.push(InstalledPackage { package, path: path.into() });
+                },
+                _ => debug!("ignoring package manifest without name or version: {path:?}"),
+            }
+        }
+
+        Ok(packages)
+    }
+}
+
+/// Recursively list all entries below `root`, without following symlinks.
+#[cfg(not(target_arch = "wasm32"))]
+fn walk_files(root: &Path) -> anyhow::Result<impl Iterator<Item = PathBuf>> {
+    Ok(WalkDir::new(root).into_iter().flatten().map(|entry| entry.into_path()))
+}
+
+/// Directory walking is unavailable without a filesystem.
+#[cfg(target_arch = "wasm32")]
+fn walk_files(root: &Path) -> anyhow::Result<impl Iterator<Item = PathBuf>> {
+    Err::<std::iter::Empty<_>, _>(anyhow!(
+        "Cannot read {} without filesystem access",
+        root.display()
+    ))
+}
+
+/// Check if a path is the `package.json` of an installed module.
+///
+/// This accepts `node_modules/<NAME>/package.json` and
+/// `node_modules/@<SCOPE>/<NAME>/package.json`, ignoring manifests nested
+/// deeper inside a module's sources.
+fn is_node_module_manifest(path: &Path) -> bool {
+    if path.file_name() != Some(OsStr::new("package.json")) {
+        return false;
+    }
+
+    let mut ancestors = path.ancestors().skip(2);
+    let parent = ancestors.next();
+    let parent_name = parent.and_then(Path::file_name).and_then(OsStr::to_str);
+    match parent_name {
+        Some("node_modules") => true,
+        Some(scope) if scope.starts_with('@') => {
+            ancestors.next().and_then(Path::file_name) == Some(OsStr::new("node_modules"))
+        },
+        _ => false,
+    }
+}
+
+/// Relevant fields of an installed module's `package.json`.
+#[derive(Deserialize)]
+struct PackageJson {
+    name: Option<String>,
+    version: Option<String>,
+}
+
+pub struct SitePackages;
+
+impl Parse for SitePackages {
+    /// Installed Python packages can only be parsed from a directory.
+    fn parse(&self, _data: &str) -> anyhow::Result<Vec<Package>> {
+        Err(anyhow!("Installed Python packages must be parsed from a `site-packages` directory"))
+    }
+
+    fn is_path_lockfile(&self, path: &Path) -> bool {
+        let file_name = path.file_name();
+        file_name == Some(OsStr::new("site-packages"))
+            || file_name == Some(OsStr::new("dist-packages"))
+    }
+
+    fn is_path_manifest(&self, _path: &Path) -> bool {
+        false
+    }
+}
+
+impl ParseInstalled for SitePackages {
+    fn parse_installed(&self, root: &Path) -> anyhow::Result<Vec<InstalledPackage>> {
+        let mut packages = Vec::new();
+
+        let entries =
+            fs::read_dir(root).with_context(|| format!("Failed to read {}", root.display()))?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+
+            // Find the core metadata file of each distribution.
+            let metadata_path = match path.extension().and_then(OsStr::to_str) {
+                Some("dist-info") => path.join("METADATA"),
+                Some("egg-info") if path.is_dir() => path.join("PKG-INFO"),
+                // Legacy eggs may store their metadata in a single file.
+                Some("egg-info") => path.clone(),
+                _ => continue,
+            };
+
+            let metadata = match fs::read_to_string(&metadata_path) {
+                Ok(metadata) => metadata,
+                Err(err) => {
+                    debug!("ignoring distribution without metadata {path:?}: {err}");
+                    continue;
+                },
+            };
+
+            match parse_core_metadata(&metadata) {
+                Some((name, version)) => {
+                    let package = Package {
+                        name: name.into(),
+                        version: PackageVersion::FirstParty(version.into()),
+                        package_type: PackageType::PyPi,
+                    };
+                    packages.push(InstalledPackage { package, path: metadata_path });
+                },
+                None => debug!("ignoring distribution without name or version: {path:?}"),
+            }
+        }
+
+        Ok(packages)
+    }
+}
+
+/// Get name and version from Python core metadata.
+///
+/// Core metadata uses email header syntax, with the headers being terminated
+/// by the first empty line.
+fn parse_core_metadata(metadata: &str) -> Option<(&str, &str)> {
+    let mut name = None;
+    let mut version = None;
+
+    for line in metadata.lines().take_while(|line| !line.is_empty()) {
+        match line.split_once(':') {
+            Some(("Name", value)) => name = Some(value.trim()),
+            Some(("Version", value)) => version = Some(value.trim()),
+            _ => (),
+        }
+    }
+
+    name.zip(version)
+}
+
+pub struct GoVendor;
+
+impl Parse for GoVendor {
+    /// Parses `vendor/modules.txt` files into a vec of packages.
+    fn parse(&self, data: &str) -> anyhow::Result<Vec<Package>> {
+        let mut packages = Vec::new();
+
+        // Modules are listed as `# <MODULE> <VERSION> [=> <REPLACEMENT>]`, followed by
+        // annotations (`## explicit`) and the vendored packages of the module.
+        for line in data.lines() {
+            let module = match line.strip_prefix("# ") {
+                Some(module) => module,
+                None => continue,
+            };
+
+            let (module, replacement) = match module.split_once("=>") {
+                Some((module, replacement)) => (module, Some(replacement)),
+                None => (module, None),
+            };
+            let module: Vec<_> = module.split_whitespace().collect();
+            let replacement: Option<Vec<_>> = replacement.map(|r| r.split_whitespace().collect());
+
+            let package = match (module.as_slice(), replacement.as_deref()) {
+                // Modules replaced by a local directory.
+                (_, Some([path])) => Package {
+                    name: (*path).into(),
+                    version: PackageVersion::Path(Some(path.into())),
+                    package_type: PackageType::Golang,
+                },
+                // Modules replaced by a different module.
+                (_, Some([name, version])) => Package {
+                    name: (*name).into(),
+                    version: PackageVersion::FirstParty((*version).into()),
+                    package_type: PackageType::Golang,
+                },
+                ([name, version], None) => Package {
+                    name: (*name).into(),
+                    version: PackageVersion::FirstParty((*version).into()),
+                    package_type: PackageType::Golang,
+                },
+                _ => return Err(anyhow!("Invalid vendored module: {line:?}")),
+            };
+
+            // Protect against parsing foreign files with `#` comments.
+            if let PackageVersion::FirstParty(version) = &package.version {
+                if !version.starts_with('v') {
+                    return Err(anyhow!("Invalid vendored module version: {line:?}"));
+                }
+            }
+
+            packages.push(package);
+        }
+
+        Ok(packages)
+    }
+
+    fn is_path_lockfile(&self, path: &Path) -> bool {
+        let parent = path.parent().and_then(Path::file_name);
+        path.file_name() == Some(OsStr::new("modules.txt")) && parent == Some(OsStr::new("vendor"))
+    }
+
+    fn is_path_manifest(&self, _path: &Path) -> bool {
+        false
+    }
+}
+
+impl ParseInstalled for GoVendor {
+    fn parse_installed(&self, root: &Path) -> anyhow::Result<Vec<InstalledPackage>> {
+        let path = root.join("modules.txt");
+        let modules = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+
+        let packages = self.parse(&modules)?;
+        Ok(packages
+            .into_iter()
+            .map(|package| InstalledPackage { package, path: path.clone() })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+
+    fn package(name: &str, version: &str, package_type: PackageType) -> Package {
+        Package {
+            name: name.into(),
+            version: PackageVersion::FirstParty(version.into()),
+            package_type,
+        }
+    }
+
+    /// Get all unique packages of an installed environment.
+    fn installed_packages(parser: &dyn ParseInstalled, root: &str) -> Vec<Package> {
+        let installed = parser.parse_installed(Path::new(root)).unwrap();
+        let packages: BTreeSet<_> =
+            installed.into_iter().map(|installed| installed.package).collect();
+        packages.into_iter().collect()
+    }
+
+    #[test]
+    fn parse_node_modules() {
+        let pkgs = installed_packages(&NodeModules, "../tests/fixtures/installed/node_modules");
+
+        assert_eq!(pkgs, vec![
+            package("@scope/scoped", "2.0.0", PackageType::Npm),
+            package("is-number", "6.0.0", PackageType::Npm),
+            package("is-number", "7.0.0", PackageType::Npm),
+            package("is-odd", "3.0.1", PackageType::Npm),
+        ]);
+    }
+
+    #[test]
+    fn parse_site_packages() {
+        let pkgs = installed_packages(&SitePackages, "../tests/fixtures/installed/site-packages");
+
+        assert_eq!(pkgs, vec![
+            package("legacy-egg", "0.1.0", PackageType::PyPi),
+            package("requests", "2.31.0", PackageType::PyPi),
+            package("typing_extensions", "4.9.0", PackageType::PyPi),
+        ]);
+    }
+
+    #[test]
+    fn parse_go_vendor() {
+        let pkgs = installed_packages(&GoVendor, "../tests/fixtures/installed/vendor");
+
+        assert_eq!(pkgs, vec![
+            Package {
+                name: "../local".into(),
+                version: PackageVersion::Path(Some("../local".into())),
+                package_type: PackageType::Golang,
+            },
+            package("github.com/pkg/errors", "v0.9.1", PackageType::Golang),
+            package("golang.org/x/text", "v0.14.0", PackageType::Golang),
+        ]);
+    }
+
+    #[test]
+    fn installed_formats_require_directory() {
+        assert!(NodeModules.parse("{}").is_err());
+        assert!(SitePackages.parse("Name: requests").is_err());
+    }
+}
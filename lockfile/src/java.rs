@@ -1,4 +1,5 @@
 use std::ffi::OsStr;
+use std::io::{Cursor, Read, Seek};
 use std::path::Path;
 
 use anyhow::{anyhow, Context};
@@ -8,17 +9,35 @@ use lockfile_generator::gradle::Gradle as GradleGenerator;
 use lockfile_generator::maven::Maven as MavenGenerator;
 #[cfg(feature = "generator")]
 use lockfile_generator::Generator;
+use log::debug;
 use nom::Finish;
 use nom_language::error::convert_error;
 use phylum_types::ecosystems::maven::{Dependency, Plugin, Project};
 use phylum_types::types::package::PackageType;
 use serde::Deserialize;
+use zip::ZipArchive;
 
 use super::parsers::gradle_dep;
-use crate::{Package, PackageVersion, Parse};
+use crate::{EmbeddedPackage, Package, PackageVersion, Parse, ParseBinary};
+
+/// Zip local file header magic bytes.
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+/// Maximum nesting depth of Java archives.
+const MAX_ARCHIVE_DEPTH: usize = 4;
+
+/// Maximum size of nested archives, which are loaded into memory.
+const MAX_NESTED_ARCHIVE_LEN: u64 = 256 * 1024 * 1024;
+
+/// Maximum combined size of all nested archives within an archive.
+const MAX_NESTED_ARCHIVES_LEN: u64 = MAX_ARCHIVE_DEPTH as u64 * MAX_NESTED_ARCHIVE_LEN;
+
+/// Maximum size of metadata files inside an archive.
+const MAX_METADATA_LEN: u64 = 1024 * 1024;
 
 pub struct Pom;
 pub struct GradleLock;
+pub struct JavaArchive;
 
 impl Parse for GradleLock {
     /// Parses `gradle.lockfile` files into a vec of packages
@@ -183,6 +202,203 @@ impl Pom {
     }
 }
 
+impl Parse for JavaArchive {
+    /// Java archives can only be parsed from their raw bytes.
+    fn parse(&self, _data: &str) -> anyhow::Result<Vec<Package>> {
+        Err(anyhow!("Java archives must be parsed from their raw bytes"))
+    }
+
+    fn is_path_lockfile(&self, path: &Path) -> bool {
+        path.extension().and_then(OsStr::to_str).is_some_and(is_java_archive_extension)
+    }
+
+    fn is_path_manifest(&self, _path: &Path) -> bool {
+        false
+    }
+}
+
+impl ParseBinary for JavaArchive {
+    fn is_binary(&self, data: &[u8]) -> bool {
+        data.starts_with(ZIP_MAGIC)
+    }
+
+    /// Parses JAR, WAR and EAR files, including all nested archives like the
+    /// dependencies in `BOOT-INF/lib` or `WEB-INF/lib`.
+    fn parse_binary(&self, data: &[u8]) -> anyhow::Result<Vec<EmbeddedPackage>> {
+        let mut packages = Vec::new();
+        let mut budget = MAX_NESTED_ARCHIVES_LEN;
+        parse_java_archive(Cursor::new(data), "", 0, &mut budget, &mut packages)?;
+        Ok(packages)
+    }
+}
+
+/// Collect the packages of a Java archive and its nested archives.
+///
+/// Packages are identified through their Maven `pom.properties`, falling back
+/// to the `MANIFEST.MF` implementation attributes for archives without Maven
+/// metadata. Shaded archives contain one `pom.properties` per bundled package.
+///
+/// The `budget` limits the combined size of all nested archives which are
+/// loaded into memory.
+fn parse_java_archive(
+    reader: impl Read + Seek,
+    origin: &str,
+    depth: usize,
+    budget: &mut u64,
+    packages: &mut Vec<EmbeddedPackage>,
+) -> anyhow::Result<()> {
+    let mut archive = ZipArchive::new(reader).context("Invalid Java archive")?;
+
+    let mut has_pom_properties = false;
+    let mut manifest = None;
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        if !file.is_file() {
+            continue;
+        }
+        let name = file.name().to_owned();
+
+        if is_pom_properties(&name) {
+            let properties = read_metadata(&mut file)?;
+            match parse_pom_properties(&properties) {
+                Some(package) => {
+                    packages.push(EmbeddedPackage { package, origin: origin.into() });
+                    has_pom_properties = true;
+                },
+                None => debug!("ignoring incomplete Maven metadata {name:?} in {origin:?}"),
+            }
+        } else if name == "META-INF/MANIFEST.MF" {
+            manifest = Some(read_metadata(&mut file)?);
+        } else if is_nested_archive(&name) {
+            let nested_origin = match origin.is_empty() {
+                true => name,
+                false => format!("{origin}!/{name}"),
+            };
+
+            if depth >= MAX_ARCHIVE_DEPTH {
+                debug!("ignoring nested archive {nested_origin:?} exceeding depth limit");
+                continue;
+            }
+
+            // Limit the amount of data read, since the archive's size is untrusted.
+            let limit = MAX_NESTED_ARCHIVE_LEN.min(*budget);
+            let mut nested = Vec::new();
+            (&mut file).take(limit + 1).read_to_end(&mut nested)?;
+            *budget = budget.saturating_sub(nested.len() as u64);
+            if nested.len() as u64 > limit {
+                debug!("ignoring nested archive {nested_origin:?} exceeding size limit");
+                continue;
+            }
+
+            // Skip nested archives which cannot be read, like other file formats
+            // using the same extension.
+            let nested = Cursor::new(nested);
+            if let Err(err) =
+                parse_java_archive(nested, &nested_origin, depth + 1, budget, packages)
+            {
+                debug!("ignoring invalid nested archive {nested_origin:?}: {err:?}");
+            }
+        }
+    }
+
+    if !has_pom_properties {
+        match manifest.as_deref().and_then(parse_manifest) {
+            Some(package) => packages.push(EmbeddedPackage { package, origin: origin.into() }),
+            None => debug!("ignoring Java archive without package metadata: {origin:?}"),
+        }
+    }
+
+    Ok(())
+}
+
+/// Check if an extension belongs to a Java archive.
+fn is_java_archive_extension(extension: &str) -> bool {
+    ["jar", "war", "ear"].iter().any(|archive| extension.eq_ignore_ascii_case(archive))
+}
+
+/// Check if an archive entry is a nested Java archive.
+fn is_nested_archive(name: &str) -> bool {
+    Path::new(name).extension().and_then(OsStr::to_str).is_some_and(is_java_archive_extension)
+}
+
+/// Check if an archive entry is Maven's `pom.properties`.
+///
+/// Maven places these at `META-INF/maven/<GROUP>/<ARTIFACT>/pom.properties`,
+/// which Spring Boot moves below `BOOT-INF/classes`.
+fn is_pom_properties(name: &str) -> bool {
+    name.ends_with("/pom.properties")
+        && (name.starts_with("META-INF/maven/") || name.contains("/META-INF/maven/"))
+}
+
+/// Read a metadata file from a Java archive.
+fn read_metadata(file: impl Read) -> anyhow::Result<String> {
+    let mut metadata = Vec::new();
+    file.take(MAX_METADATA_LEN).read_to_end(&mut metadata)?;
+    Ok(String::from_utf8_lossy(&metadata).into_owned())
+}
+
+/// Get the package described by Maven's `pom.properties`.
+fn parse_pom_properties(properties: &str) -> Option<Package> {
+    let mut group_id = None;
+    let mut artifact_id = None;
+    let mut version = None;
+
+    for line in properties.lines().map(str::trim) {
+        if line.starts_with('#') || line.starts_with('!') {
+            continue;
+        }
+
+        let (key, value) = match line.split_once(['=', ':']) {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => continue,
+        };
+
+        match key {
+            "groupId" => group_id = Some(value),
+            "artifactId" => artifact_id = Some(value),
+            "version" => version = Some(value),
+            _ => (),
+        }
+    }
+
+    Some(Package {
+        name: format!("{}:{}", group_id?, artifact_id?),
+        version: PackageVersion::FirstParty(version?.into()),
+        package_type: PackageType::Maven,
+    })
+}
+
+/// Get the package described by a `MANIFEST.MF`'s implementation attributes.
+fn parse_manifest(manifest: &str) -> Option<Package> {
+    let mut vendor_id = None;
+    let mut title = None;
+    let mut version = None;
+
+    // Long attributes are wrapped, with continuation lines starting with a space.
+    let mut attributes: Vec<String> = Vec::new();
+    for line in manifest.lines().take_while(|line| !line.is_empty()) {
+        match (line.strip_prefix(' '), attributes.last_mut()) {
+            (Some(continuation), Some(attribute)) => attribute.push_str(continuation),
+            _ => attributes.push(line.into()),
+        }
+    }
+
+    for attribute in &attributes {
+        match attribute.split_once(':') {
+            Some(("Implementation-Vendor-Id", value)) => vendor_id = Some(value.trim()),
+            Some(("Implementation-Title", value)) => title = Some(value.trim()),
+            Some(("Implementation-Version", value)) => version = Some(value.trim()),
+            _ => (),
+        }
+    }
+
+    Some(Package {
+        name: format!("{}:{}", vendor_id?, title?),
+        version: PackageVersion::FirstParty(version?.into()),
+        package_type: PackageType::Maven,
+    })
+}
+
 #[derive(Deserialize)]
 enum EffectivePom {
     #[serde(rename = "project")]
@@ -199,8 +415,40 @@ struct Workspace {
 
 #[cfg(test)]
 mod tests {
+    use std::io::Write;
+
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+
     use super::*;
 
+    /// Create a zip archive from a list of files.
+    fn zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in files {
+            writer.start_file(*name, SimpleFileOptions::default()).unwrap();
+            writer.write_all(content).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn pom_properties(group_id: &str, artifact_id: &str, version: &str) -> Vec<u8> {
+        format!(
+            "#Created by Apache Maven \
+             3.9.6\nartifactId={artifact_id}\ngroupId={group_id}\nversion={version}\n"
+        )
+        .into_bytes()
+    }
+
+    fn embedded(name: &str, version: &str, origin: &str) -> EmbeddedPackage {
+        let package = Package {
+            name: name.into(),
+            version: PackageVersion::FirstParty(version.into()),
+            package_type: PackageType::Maven,
+        };
+        EmbeddedPackage { package, origin: origin.into() }
+    }
+
     #[test]
     fn lock_parse_gradle() {
         let pkgs = GradleLock.parse(include_str!("../../tests/fixtures/gradle.lockfile")).unwrap();
@@ -267,4 +515,97 @@ mod tests {
 
         assert!(pkgs.contains(&additional_dependency));
     }
+
+    #[test]
+    fn parse_spring_boot_jar() {
+        let guava = zip(&[(
+            "META-INF/maven/com.google.guava/guava/pom.properties",
+            &pom_properties("com.google.guava", "guava", "33.0.0-jre"),
+        )]);
+        let shaded = zip(&[
+            (
+                "META-INF/maven/org.example/shaded/pom.properties",
+                &pom_properties("org.example", "shaded", "1.0.0"),
+            ),
+            (
+                "META-INF/maven/org.ow2.asm/asm/pom.properties",
+                &pom_properties("org.ow2.asm", "asm", "9.6"),
+            ),
+        ]);
+        let legacy = zip(&[(
+            "META-INF/MANIFEST.MF",
+            b"Manifest-Version: 1.0\r\nImplementation-Title: legacy-util\r\n\
+              Implementation-Version: 2.1\r\nImplementation-Vendor-Id: org.exampl\r\n e.legacy\r\n\
+              \r\nName: org/example/\r\nImplementation-Version: 9.9\r\n",
+        )]);
+        let unknown = zip(&[("META-INF/MANIFEST.MF", b"Manifest-Version: 1.0\r\n")]);
+
+        let app = zip(&[
+            ("META-INF/MANIFEST.MF", b"Manifest-Version: 1.0\r\nMain-Class: Launcher\r\n"),
+            (
+                "BOOT-INF/classes/META-INF/maven/com.example/app/pom.properties",
+                &pom_properties("com.example", "app", "0.1.0"),
+            ),
+            ("BOOT-INF/lib/guava-33.0.0-jre.jar", &guava),
+            ("BOOT-INF/lib/shaded-1.0.0.jar", &shaded),
+            ("BOOT-INF/lib/legacy-util-2.1.jar", &legacy),
+            ("BOOT-INF/lib/unknown.jar", &unknown),
+            ("BOOT-INF/lib/corrupt.jar", b"not a zip"),
+        ]);
+
+        assert!(JavaArchive.is_binary(&app));
+        let mut pkgs = JavaArchive.parse_binary(&app).unwrap();
+        pkgs.sort();
+
+        assert_eq!(pkgs, vec![
+            embedded("com.example:app", "0.1.0", ""),
+            embedded("com.google.guava:guava", "33.0.0-jre", "BOOT-INF/lib/guava-33.0.0-jre.jar"),
+            embedded("org.example.legacy:legacy-util", "2.1", "BOOT-INF/lib/legacy-util-2.1.jar"),
+            embedded("org.example:shaded", "1.0.0", "BOOT-INF/lib/shaded-1.0.0.jar"),
+            embedded("org.ow2.asm:asm", "9.6", "BOOT-INF/lib/shaded-1.0.0.jar"),
+        ]);
+    }
+
+    #[test]
+    fn parse_ear_with_war() {
+        let library = zip(&[(
+            "META-INF/maven/org.slf4j/slf4j-api/pom.properties",
+            &pom_properties("org.slf4j", "slf4j-api", "2.0.12"),
+        )]);
+        let war = zip(&[("WEB-INF/lib/slf4j-api-2.0.12.jar", &library)]);
+        let ear = zip(&[("web.war", &war)]);
+
+        let pkgs = JavaArchive.parse_binary(&ear).unwrap();
+
+        assert_eq!(pkgs, vec![embedded(
+            "org.slf4j:slf4j-api",
+            "2.0.12",
+            "web.war!/WEB-INF/lib/slf4j-api-2.0.12.jar"
+        )]);
+    }
+
+    #[test]
+    fn nested_archive_size_limit() {
+        let library = zip(&[(
+            "META-INF/maven/org.slf4j/slf4j-api/pom.properties",
+            &pom_properties("org.slf4j", "slf4j-api", "2.0.12"),
+        )]);
+        let app = zip(&[("lib/a.jar", &library), ("lib/b.jar", &library)]);
+
+        // Ensure nested archives exceeding the remaining budget are skipped.
+        let mut budget = library.len() as u64;
+        let mut pkgs = Vec::new();
+        parse_java_archive(Cursor::new(app), "", 0, &mut budget, &mut pkgs).unwrap();
+
+        assert_eq!(pkgs, vec![embedded("org.slf4j:slf4j-api", "2.0.12", "lib/a.jar")]);
+        assert_eq!(budget, 0);
+    }
+
+    #[test]
+    fn java_archive_paths() {
+        assert!(JavaArchive.is_path_lockfile(Path::new("target/app.jar")));
+        assert!(JavaArchive.is_path_lockfile(Path::new("app.WAR")));
+        assert!(!JavaArchive.is_path_lockfile(Path::new("app.zip")));
+        assert!(JavaArchive.parse("PK").is_err());
+    }
 }
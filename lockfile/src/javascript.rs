@@ -1,9 +1,11 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::ffi::OsStr;
+use std::iter;
 use std::path::Path;
 use std::str::FromStr;
 
 use anyhow::{anyhow, Context};
+use glob::{MatchOptions, Pattern};
 #[cfg(feature = "generator")]
 use lockfile_generator::npm::Npm as NpmGenerator;
 #[cfg(feature = "generator")]
@@ -17,11 +19,13 @@ use nom::Finish;
 use nom_language::error::convert_error;
 use phylum_types::types::package::PackageType;
 use serde::Deserialize;
-use serde_json::Value as JsonValue;
+use serde_json::{Map as JsonMap, Value as JsonValue};
 use serde_yaml::Value as YamlValue;
 
 use super::parsers::yarn;
-use crate::{Package, PackageVersion, Parse, ThirdPartyVersion};
+use crate::{
+    Package, PackageVersion, Parse, ParseWorkspace, ThirdPartyVersion, Workspace, WorkspaceMember,
+};
 
 pub struct PackageLock;
 pub struct YarnLock;
@@ -29,119 +33,270 @@ pub struct YarnLock;
 impl Parse for PackageLock {
     /// Parses `package-lock.json` files into a vec of packages
     fn parse(&self, data: &str) -> anyhow::Result<Vec<Package>> {
-        let parsed: JsonValue = serde_json::from_str(data)?;
+        Ok(self.parse_workspace(data)?.packages)
+    }
 
-        // Get a field as string from a JSON object.
-        fn get_field(value: &JsonValue, key: &str) -> Option<String> {
-            value.get(key).and_then(|value| value.as_str()).map(|value| value.to_string())
-        }
+    fn is_path_lockfile(&self, path: &Path) -> bool {
+        let file_name = path.file_name();
+        file_name == Some(OsStr::new("package-lock.json"))
+            || file_name == Some(OsStr::new("npm-shrinkwrap.json"))
+    }
 
-        // Get version field from JSON object.
-        fn get_version(value: &JsonValue, name: &str) -> anyhow::Result<String> {
-            get_field(value, "version")
-                .ok_or_else(|| anyhow!("Failed to parse version for '{name}' dependency"))
-        }
+    fn is_path_manifest(&self, path: &Path) -> bool {
+        path.file_name() == Some(OsStr::new("package.json"))
+    }
 
-        if let Some(deps) = parsed.get("packages").and_then(|v| v.as_object()) {
+    #[cfg(feature = "generator")]
+    fn generator(&self) -> Option<&'static dyn Generator> {
+        Some(&NpmGenerator)
+    }
+}
+
+impl ParseWorkspace for PackageLock {
+    fn parse_workspace(&self, data: &str) -> anyhow::Result<Workspace> {
+        let parsed: JsonValue = serde_json::from_str(data)?;
+
+        let deps = match parsed.get("packages").and_then(|v| v.as_object()) {
             // Parser for package-lock.json >= v7.
+            Some(deps) => deps,
+            // Parser for package-lock.json <= v6, which has no workspace support.
+            None => {
+                let deps = parsed
+                    .get("dependencies")
+                    .and_then(|v| v.as_object())
+                    .ok_or_else(|| anyhow!("Failed to find dependencies"))?;
+
+                let packages = deps
+                    .into_iter()
+                    .map(|(name, keys)| {
+                        Ok(Package {
+                            version: PackageVersion::FirstParty(npm_version(keys, name)?),
+                            name: name.into(),
+                            package_type: PackageType::Npm,
+                        })
+                    })
+                    .collect::<anyhow::Result<_>>()?;
 
-            let mut packages = Vec::new();
-            for (name, keys) in deps {
-                // Discard version information of local packages.
-                //
-                // In NPM, versions for filesystem dependencies are in the object with the
-                // `name` corresponding to the path of the module, without any mention of the
-                // module's name itself.
-                //
-                // The module's name then shows up as a separate package with the `name` as
-                // `node_modules/<NAME>`, the path as `resolved`, `"link": true` and no version.
-                //
-                // Since we care more about the name of a local dependency than its package, we
-                // discard the version here and include the package later when it's mentioned by
-                // name.
-                if !name.starts_with("node_modules/") {
-                    continue;
-                }
+                return Ok(Workspace { packages, members: Vec::new() });
+            },
+        };
 
-                let name = match name.rsplit_once("node_modules/") {
-                    Some((_, name)) => name,
-                    None => continue,
-                };
+        let workspace_members = npm_workspace_members(deps);
 
-                // Get dependency type.
-                let resolved = match get_field(keys, "resolved") {
-                    Some(resolved) => resolved,
-                    // Ignore packages without clear resolution details.
-                    None => {
-                        debug!("ignoring package without `resolved` field: {name}");
-                        continue;
-                    },
-                };
+        let mut packages = Vec::new();
+        for (name, keys) in deps {
+            // Skip links to workspace members.
+            if npm_link_target(keys).is_some_and(|target| workspace_members.contains(target)) {
+                debug!("ignoring workspace member: {name}");
+                continue;
+            }
+
+            if let Some(package) = npm_package(name, keys)? {
+                packages.push(package);
+            }
+        }
 
-                // Handle aliased dependencies.
-                let name = get_field(keys, "name").unwrap_or_else(|| name.into());
-
-                // Get dependency version.
-                let version = if resolved.starts_with("https://registry.npmjs.org/") {
-                    PackageVersion::FirstParty(get_version(keys, &name)?)
-                } else if resolved.starts_with("git+") {
-                    PackageVersion::Git(resolved)
-                } else if resolved.starts_with("http") {
-                    // Split off `http(s)://`.
-                    let mut split = resolved.split('/');
-                    let _ = split.next();
-                    let _ = split.next();
-
-                    // Find registry's domain name.
-                    match split.next() {
-                        Some(registry) => PackageVersion::ThirdParty(ThirdPartyVersion {
-                            version: get_version(keys, &name)?,
-                            registry: registry.into(),
-                        }),
+        // Root package is always the first workspace member.
+        let member_paths = iter::once(String::new()).chain(workspace_members.iter().cloned());
+
+        let mut members = Vec::new();
+        for member_path in member_paths {
+            let keys = match deps.get(&member_path) {
+                Some(keys) => keys,
+                None => continue,
+            };
+
+            // Walk all transitive dependencies of the member.
+            let mut dependencies = BTreeSet::new();
+            let mut visited = HashSet::new();
+            let mut queue = vec![(member_path.clone(), npm_dependency_names(keys, true))];
+            while let Some((location, names)) = queue.pop() {
+                for dependency in names {
+                    let key = match npm_resolve(deps, &location, &dependency) {
+                        Some(key) => key,
                         None => {
-                            return Err(anyhow!("Invalid third party registry: {:?}", resolved));
+                            debug!("unresolved dependency {dependency:?} in {location:?}");
+                            continue;
                         },
+                    };
+
+                    // Skip other workspace members.
+                    if workspace_members.contains(&key) || !visited.insert(key.clone()) {
+                        continue;
                     }
-                } else {
-                    PackageVersion::Path(Some(resolved.into()))
-                };
 
-                packages.push(Package { version, name, package_type: PackageType::Npm });
+                    let keys = &deps[&key];
+                    if let Some(package) = npm_package(&key, keys)? {
+                        dependencies.insert(package);
+                    }
+                    queue.push((key, npm_dependency_names(keys, false)));
+                }
             }
-            Ok(packages)
-        } else if let Some(deps) = parsed.get("dependencies").and_then(|v| v.as_object()) {
-            // Parser for package-lock.json <= v6.
-
-            deps.into_iter()
-                .map(|(name, keys)| {
-                    Ok(Package {
-                        version: PackageVersion::FirstParty(get_version(keys, name)?),
-                        name: name.into(),
-                        package_type: PackageType::Npm,
-                    })
-                })
-                .collect()
-        } else {
-            Err(anyhow!("Failed to find dependencies"))
+
+            members.push(WorkspaceMember {
+                name: npm_field(keys, "name"),
+                path: if member_path.is_empty() { ".".into() } else { member_path },
+                dependencies: dependencies.into_iter().collect(),
+            });
         }
+
+        Ok(Workspace { packages, members })
     }
+}
 
-    fn is_path_lockfile(&self, path: &Path) -> bool {
-        let file_name = path.file_name();
-        file_name == Some(OsStr::new("package-lock.json"))
-            || file_name == Some(OsStr::new("npm-shrinkwrap.json"))
+/// Get a field as string from a JSON object.
+fn npm_field(value: &JsonValue, key: &str) -> Option<String> {
+    value.get(key).and_then(|value| value.as_str()).map(|value| value.to_string())
+}
+
+/// Get version field from JSON object.
+fn npm_version(value: &JsonValue, name: &str) -> anyhow::Result<String> {
+    npm_field(value, "version")
+        .ok_or_else(|| anyhow!("Failed to parse version for '{name}' dependency"))
+}
+
+/// Get the target of an npm link entry.
+fn npm_link_target(value: &JsonValue) -> Option<&str> {
+    if value.get("link").and_then(JsonValue::as_bool) != Some(true) {
+        return None;
     }
+    value.get("resolved").and_then(JsonValue::as_str)
+}
 
-    fn is_path_manifest(&self, path: &Path) -> bool {
-        path.file_name() == Some(OsStr::new("package.json"))
+/// Get the package directories of all workspace members, excluding the root.
+fn npm_workspace_members(deps: &JsonMap<String, JsonValue>) -> BTreeSet<String> {
+    // Get workspace globs from the root package.
+    let workspaces = deps.get("").and_then(|root| root.get("workspaces"));
+    let workspaces = match workspaces {
+        // Support both `["a/*"]` and `{ "packages": ["a/*"] }` syntax.
+        Some(JsonValue::Object(workspaces)) => workspaces.get("packages"),
+        workspaces => workspaces,
+    };
+    let patterns: Vec<_> = workspaces
+        .and_then(JsonValue::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(JsonValue::as_str)
+        .filter_map(|glob| Pattern::new(glob.strip_prefix("./").unwrap_or(glob)).ok())
+        .collect();
+
+    let options = MatchOptions { require_literal_separator: true, ..MatchOptions::new() };
+    deps.keys()
+        .filter(|path| !path.is_empty() && !path.contains("node_modules/"))
+        .filter(|path| patterns.iter().any(|pattern| pattern.matches_with(path, options)))
+        .cloned()
+        .collect()
+}
+
+/// Get the names of all dependencies required by an npm package entry.
+fn npm_dependency_names(value: &JsonValue, include_dev: bool) -> Vec<String> {
+    let mut fields = vec!["dependencies", "optionalDependencies", "peerDependencies"];
+    if include_dev {
+        fields.push("devDependencies");
     }
 
-    #[cfg(feature = "generator")]
-    fn generator(&self) -> Option<&'static dyn Generator> {
-        Some(&NpmGenerator)
+    fields
+        .into_iter()
+        .filter_map(|field| value.get(field).and_then(JsonValue::as_object))
+        .flat_map(|dependencies| dependencies.keys().cloned())
+        .collect()
+}
+
+/// Resolve a dependency using node's module resolution algorithm.
+///
+/// Returns the key of the package entry, following links to their target.
+fn npm_resolve(deps: &JsonMap<String, JsonValue>, location: &str, name: &str) -> Option<String> {
+    let mut base = location;
+    loop {
+        let key = if base.is_empty() {
+            format!("node_modules/{name}")
+        } else {
+            format!("{base}/node_modules/{name}")
+        };
+
+        if let Some(keys) = deps.get(&key) {
+            return match npm_link_target(keys) {
+                Some(target) => deps.contains_key(target).then(|| target.to_owned()),
+                None => Some(key),
+            };
+        }
+
+        if base.is_empty() {
+            return None;
+        }
+        base = base.rsplit_once('/').map_or("", |(parent, _)| parent);
     }
 }
 
+/// Parse a package entry from a package-lock.json >= v7.
+///
+/// Returns `None` for entries which do not describe an installed package.
+fn npm_package(name: &str, keys: &JsonValue) -> anyhow::Result<Option<Package>> {
+    // Discard version information of local packages.
+    //
+    // In NPM, versions for filesystem dependencies are in the object with the
+    // `name` corresponding to the path of the module, without any mention of the
+    // module's name itself.
+    //
+    // The module's name then shows up as a separate package with the `name` as
+    // `node_modules/<NAME>`, the path as `resolved`, `"link": true` and no version.
+    //
+    // Since we care more about the name of a local dependency than its package, we
+    // discard the version here and include the package later when it's mentioned by
+    // name.
+    //
+    // Packages installed below workspace members (i.e.
+    // `packages/a/node_modules/<NAME>`) are still included.
+    if !name.starts_with("node_modules/") && !name.contains("/node_modules/") {
+        return Ok(None);
+    }
+
+    let name = match name.rsplit_once("node_modules/") {
+        Some((_, name)) => name,
+        None => return Ok(None),
+    };
+
+    // Get dependency type.
+    let resolved = match npm_field(keys, "resolved") {
+        Some(resolved) => resolved,
+        // Ignore packages without clear resolution details.
+        None => {
+            debug!("ignoring package without `resolved` field: {name}");
+            return Ok(None);
+        },
+    };
+
+    // Handle aliased dependencies.
+    let name = npm_field(keys, "name").unwrap_or_else(|| name.into());
+
+    // Get dependency version.
+    let version = if resolved.starts_with("https://registry.npmjs.org/") {
+        PackageVersion::FirstParty(npm_version(keys, &name)?)
+    } else if resolved.starts_with("git+") {
+        PackageVersion::Git(resolved)
+    } else if resolved.starts_with("http") {
+        // Split off `http(s)://`.
+        let mut split = resolved.split('/');
+        let _ = split.next();
+        let _ = split.next();
+
+        // Find registry's domain name.
+        match split.next() {
+            Some(registry) => PackageVersion::ThirdParty(ThirdPartyVersion {
+                version: npm_version(keys, &name)?,
+                registry: registry.into(),
+            }),
+            None => {
+                return Err(anyhow!("Invalid third party registry: {:?}", resolved));
+            },
+        }
+    } else {
+        PackageVersion::Path(Some(resolved.into()))
+    };
+
+    Ok(Some(Package { version, name, package_type: PackageType::Npm }))
+}
+
 /// Check if a YAML file is a valid v2 yarn lockfile.
 ///
 /// Since some v1 yarn lockfiles can be parsed as valid yaml, this ensures that
@@ -150,122 +305,200 @@ fn is_yarn_v2(yaml: &&serde_yaml::Mapping) -> bool {
     yaml.iter().any(|(k, _v)| k.as_str().unwrap_or_default() == "__metadata")
 }
 
+/// Get all package entries of a v2 yarn lockfile.
+fn yarn_berry_entries(
+    yaml: &serde_yaml::Mapping,
+) -> impl Iterator<Item = (&str, &serde_yaml::Mapping)> {
+    yaml.iter()
+        // Filter lockfile data fields like "__metadata".
+        .filter_map(|(k, v)| Some((k.as_str()?, v.as_mapping()?)))
+        .filter(|(k, _v)| !k.starts_with('_'))
+}
+
+/// Parse a package entry from a v2 yarn lockfile.
+///
+/// Returns `None` for workspace members.
+fn yarn_berry_package(package: &serde_yaml::Mapping) -> anyhow::Result<Option<Package>> {
+    let resolution = package
+        .get("resolution".to_string())
+        .and_then(YamlValue::as_str)
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| anyhow!("Failed to parse yarn resolution field"))?;
+
+    let (name, mut resolver) = match resolution[1..].split_once('@') {
+        Some((name, resolver)) => (&resolution[..name.len() + 1], resolver.to_owned()),
+        None => return Err(anyhow!("Failed to parse yarn resolution field for '{}'", resolution)),
+    };
+
+    // Extract original resolver from patch.
+    if let Some((_, patch)) = resolver.split_once("patch:") {
+        // Extract resolver from `@scope/package@RESOLVER#patch`.
+        let patch = patch[1..].split_once('@');
+        let subresolver = patch.and_then(|(_, resolver)| resolver.split_once('#'));
+        resolver = match subresolver {
+            Some((resolver, _)) => resolver.to_owned(),
+            None => {
+                return Err(anyhow!("Failed to parse yarn patch dependency for '{}'", resolution))
+            },
+        };
+
+        // Revert character replacements.
+        resolver = resolver.replace("%3A", ":");
+        resolver = resolver.replace("%23", "#");
+        resolver = resolver.replace("%25", "%");
+    }
+
+    let version = if resolver.starts_with("workspace:") {
+        // Ignore workspace members, including the project itself.
+        return Ok(None);
+    } else if resolver.starts_with("file:") || resolver.starts_with("link:") {
+        PackageVersion::Path(None)
+    } else if resolver.starts_with("npm:") {
+        let version = package
+            .get("version".to_string())
+            .and_then(YamlValue::as_str)
+            .ok_or_else(|| anyhow!("Failed to parse yarn version for '{}'", resolution))?;
+
+        PackageVersion::FirstParty(version.into())
+    } else if resolver.starts_with("http:")
+        || resolver.starts_with("https:")
+        || resolver.starts_with("ssh:")
+    {
+        if resolver.contains("#commit=") {
+            PackageVersion::Git(resolver)
+        } else {
+            PackageVersion::DownloadUrl(resolver)
+        }
+    } else {
+        return Err(anyhow!("Failed to parse yarn dependency resolver for '{}'", resolution));
+    };
+
+    Ok(Some(Package { name: name.to_owned(), version, package_type: PackageType::Npm }))
+}
+
+/// Get the resolution field of a v2 yarn lockfile entry.
+fn yarn_berry_resolution(package: &serde_yaml::Mapping) -> Option<&str> {
+    package.get("resolution").and_then(YamlValue::as_str)
+}
+
+/// Get the names and descriptor ranges of a v2 yarn lockfile entry's
+/// dependencies.
+fn yarn_berry_dependencies(package: &serde_yaml::Mapping) -> Vec<(&str, &str)> {
+    ["dependencies", "peerDependencies"]
+        .into_iter()
+        .filter_map(|field| package.get(field).and_then(YamlValue::as_mapping))
+        .flatten()
+        .filter_map(|(name, range)| Some((name.as_str()?, range.as_str()?)))
+        .collect()
+}
+
 impl Parse for YarnLock {
     /// Parses `yarn.lock` files into a vec of packages
     fn parse(&self, data: &str) -> anyhow::Result<Vec<Package>> {
+        Ok(self.parse_workspace(data)?.packages)
+    }
+
+    fn is_path_lockfile(&self, path: &Path) -> bool {
+        path.file_name() == Some(OsStr::new("yarn.lock"))
+    }
+
+    fn is_path_manifest(&self, path: &Path) -> bool {
+        path.file_name() == Some(OsStr::new("package.json"))
+    }
+
+    #[cfg(feature = "generator")]
+    fn generator(&self) -> Option<&'static dyn Generator> {
+        Some(&YarnGenerator)
+    }
+}
+
+impl ParseWorkspace for YarnLock {
+    fn parse_workspace(&self, data: &str) -> anyhow::Result<Workspace> {
         let yaml = serde_yaml::from_str::<YamlValue>(data).ok();
         let yaml_mapping = yaml.as_ref().and_then(|yaml| yaml.as_mapping());
 
         // Check if we should use v1 or v2 yarn parser.
+        //
+        // Yarn v1 lockfiles have no workspace support.
         let yaml_v2 = match yaml_mapping.filter(is_yarn_v2) {
             Some(yaml_v2) => yaml_v2,
             _ => {
-                let (_, entries) = yarn::parse(data)
+                let (_, packages) = yarn::parse(data)
                     .finish()
                     .map_err(|e| anyhow!(convert_error(data, e)))
                     .context("Failed to parse yarn lockfile")?;
-                return Ok(entries);
+                return Ok(Workspace { packages, members: Vec::new() });
             },
         };
 
         let mut packages = Vec::new();
-        for package in yaml_v2
-            .iter()
-            // Filter lockfile data fields like "__metadata".
-            .filter(|(k, _v)| k.as_str().is_some_and(  |k| !k.starts_with('_')))
-            .flat_map(|(_k, v)| v.as_mapping())
-        {
-            let resolution = package
-                .get("resolution".to_string())
-                .and_then(YamlValue::as_str)
-                .filter(|s| !s.is_empty())
-                .ok_or_else(|| anyhow!("Failed to parse yarn resolution field"))?;
-
-            let (name, mut resolver) = match resolution[1..].split_once('@') {
-                Some((name, resolver)) => (&resolution[..name.len() + 1], resolver.to_owned()),
-                None => {
-                    return Err(anyhow!(
-                        "Failed to parse yarn resolution field for '{}'",
-                        resolution
-                    ))
-                },
+        for (_, package) in yarn_berry_entries(yaml_v2) {
+            if let Some(package) = yarn_berry_package(package)? {
+                packages.push(package);
+            }
+        }
+
+        // Map every descriptor to its package entry.
+        //
+        // Keys can contain multiple comma-separated descriptors resolving to the same
+        // package (i.e. `a@npm:^1.0.0, a@npm:^1.1.0`).
+        let mut descriptors = HashMap::new();
+        for (key, package) in yarn_berry_entries(yaml_v2) {
+            for descriptor in key.split(", ") {
+                descriptors.insert(descriptor, package);
+            }
+        }
+
+        // Find an entry by dependency name and range.
+        let resolve = |name: &str, range: &str| {
+            descriptors
+                .get(format!("{name}@{range}").as_str())
+                .or_else(|| descriptors.get(format!("{name}@npm:{range}").as_str()))
+                .copied()
+        };
+
+        let mut members = Vec::new();
+        for (_, member) in yarn_berry_entries(yaml_v2) {
+            let resolution = yarn_berry_resolution(member).unwrap_or_default();
+            let (name, path) = match resolution.get(1..).and_then(|r| r.split_once("@workspace:")) {
+                Some((name, path)) => (&resolution[..name.len() + 1], path),
+                None => continue,
             };
 
-            // Extract original resolver from patch.
-            if let Some((_, patch)) = resolver.split_once("patch:") {
-                // Extract resolver from `@scope/package@RESOLVER#patch`.
-                let patch = patch[1..].split_once('@');
-                let subresolver = patch.and_then(|(_, resolver)| resolver.split_once('#'));
-                resolver = match subresolver {
-                    Some((resolver, _)) => resolver.to_owned(),
+            // Walk all transitive dependencies of the member.
+            let mut dependencies = BTreeSet::new();
+            let mut visited = HashSet::new();
+            let mut queue = yarn_berry_dependencies(member);
+            while let Some((dependency, range)) = queue.pop() {
+                let package = match resolve(dependency, range) {
+                    Some(package) => package,
                     None => {
-                        return Err(anyhow!(
-                            "Failed to parse yarn patch dependency for '{}'",
-                            resolution
-                        ))
+                        debug!("unresolved dependency {dependency:?} with range {range:?}");
+                        continue;
                     },
                 };
 
-                // Revert character replacements.
-                resolver = resolver.replace("%3A", ":");
-                resolver = resolver.replace("%23", "#");
-                resolver = resolver.replace("%25", "%");
-            }
-
-            let version = if resolver.starts_with("workspace:")
-                || resolver.starts_with("file:")
-                || resolver.starts_with("link:")
-            {
-                // Ignore project itself.
-                if resolver == "workspace:." {
+                let resolution = yarn_berry_resolution(package);
+                if !visited.insert(resolution) {
                     continue;
                 }
 
-                PackageVersion::Path(None)
-            } else if resolver.starts_with("npm:") {
-                let version = package
-                    .get("version".to_string())
-                    .and_then(YamlValue::as_str)
-                    .ok_or_else(|| anyhow!("Failed to parse yarn version for '{}'", resolution))?;
-
-                PackageVersion::FirstParty(version.into())
-            } else if resolver.starts_with("http:")
-                || resolver.starts_with("https:")
-                || resolver.starts_with("ssh:")
-            {
-                if resolver.contains("#commit=") {
-                    PackageVersion::Git(resolver)
-                } else {
-                    PackageVersion::DownloadUrl(resolver)
+                // Skip other workspace members.
+                let package_resolution = yarn_berry_package(package)?;
+                if let Some(package_resolution) = package_resolution {
+                    dependencies.insert(package_resolution);
+                    queue.extend(yarn_berry_dependencies(package));
                 }
-            } else {
-                return Err(anyhow!(
-                    "Failed to parse yarn dependency resolver for '{}'",
-                    resolution
-                ));
-            };
+            }
 
-            packages.push(Package {
-                name: name.to_owned(),
-                version,
-                package_type: PackageType::Npm,
+            members.push(WorkspaceMember {
+                name: Some(name.into()),
+                path: path.into(),
+                dependencies: dependencies.into_iter().collect(),
             });
         }
 
-        Ok(packages)
-    }
-
-    fn is_path_lockfile(&self, path: &Path) -> bool {
-        path.file_name() == Some(OsStr::new("yarn.lock"))
-    }
-
-    fn is_path_manifest(&self, path: &Path) -> bool {
-        path.file_name() == Some(OsStr::new("package.json"))
-    }
-
-    #[cfg(feature = "generator")]
-    fn generator(&self) -> Option<&'static dyn Generator> {
-        Some(&YarnGenerator)
+        Ok(Workspace { packages, members })
     }
 }
 
@@ -274,8 +507,7 @@ pub struct Pnpm;
 impl Parse for Pnpm {
     /// Parses `pnpm-lock.yaml` files into a vec of packages.
     fn parse(&self, data: &str) -> anyhow::Result<Vec<Package>> {
-        let lockfile: PnpmLock = serde_yaml::from_str(data)?;
-        lockfile.packages()
+        Ok(self.parse_workspace(data)?.packages)
     }
 
     fn is_path_lockfile(&self, path: &Path) -> bool {
@@ -292,6 +524,15 @@ impl Parse for Pnpm {
     }
 }
 
+impl ParseWorkspace for Pnpm {
+    fn parse_workspace(&self, data: &str) -> anyhow::Result<Workspace> {
+        let lockfile: PnpmLock = serde_yaml::from_str(data)?;
+        let packages = lockfile.packages()?;
+        let members = lockfile.workspace_members()?;
+        Ok(Workspace { packages, members })
+    }
+}
+
 /// `pnpm-lock.yaml` structure.
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -300,44 +541,161 @@ struct PnpmLock {
     lockfile_version: String,
     #[serde(default)]
     packages: HashMap<String, PnpmPackage>,
+    #[serde(default)]
+    snapshots: HashMap<String, PnpmDependencies>,
+    #[serde(default)]
+    importers: HashMap<String, PnpmDependencies>,
+    /// Root project dependencies of lockfiles without workspaces.
+    #[serde(flatten)]
+    root: PnpmDependencies,
 }
 
 impl PnpmLock {
     /// Get all packages in the lockfile.
-    fn packages(self) -> anyhow::Result<Vec<Package>> {
+    fn packages(&self) -> anyhow::Result<Vec<Package>> {
+        let version = self.version()?;
+
         let mut packages = Vec::new();
+        for (key, package) in &self.packages {
+            // Ignore workspace members.
+            if self.is_member(package) {
+                debug!("ignoring workspace member: {key}");
+                continue;
+            }
 
-        // Try and parse manifest version.
-        let major = self.lockfile_version.split('.').next().and_then(|v| u8::from_str(v).ok());
-        let version = match major {
-            Some(9..) => PnpmVersion::V9,
-            Some(6..) => PnpmVersion::V6,
-            Some(5..) => PnpmVersion::V5,
-            _ => return Err(anyhow!("Invalid pnpm lockfile version: '{}'", self.lockfile_version)),
-        };
+            packages.push(Self::package(key, package, version)?);
+        }
 
-        for (key, package) in self.packages.into_iter() {
-            // Parse package based on available fields.
-            let directory = package.resolution.directory;
-            let tarball = package.resolution.tarball;
-            let git = package.resolution.repo.zip(package.resolution.commit);
+        Ok(packages)
+    }
 
-            let (name, version) = match package.name {
-                Some(name) => (name, None),
-                None => Self::parse_key(&key, version).map(|(n, v)| (n, Some(v)))?,
-            };
+    /// Get all workspace members and their dependencies.
+    fn workspace_members(mut self) -> anyhow::Result<Vec<WorkspaceMember>> {
+        let version = self.version()?;
 
-            let package = match (tarball, git, directory) {
-                (Some(tarball), ..) => Self::tarball_package(tarball, name),
-                (_, Some((repo, commit)), _) => Self::git_package(repo, commit, name),
-                (_, _, Some(directory)) => Self::path_package(directory, name),
-                _ => Self::firstparty_package(name, version)?,
-            };
+        // Lockfiles without workspaces only contain the root project.
+        if self.importers.is_empty() {
+            self.importers.insert(".".into(), std::mem::take(&mut self.root));
+        }
+
+        let mut members = Vec::new();
+        for (path, importer) in &self.importers {
+            // Walk all transitive dependencies of the member.
+            let mut dependencies = BTreeSet::new();
+            let mut visited = HashSet::new();
+            let mut queue = importer.dependencies(true);
+            while let Some((name, dependency)) = queue.pop() {
+                // Skip links to other workspace members and local directories.
+                if dependency.starts_with("link:") {
+                    continue;
+                }
+
+                let key = match self.resolve(name, dependency, version) {
+                    Some(key) => key,
+                    None => {
+                        debug!("unresolved dependency {name:?} with version {dependency:?}");
+                        continue;
+                    },
+                };
+
+                if !visited.insert(key) {
+                    continue;
+                }
+
+                // Strip peer dependency annotations for the package lookup.
+                let package_key = key.split_once('(').map_or(key, |(key, _)| key);
+                let package = self.packages.get(key).or_else(|| self.packages.get(package_key));
+
+                // Skip injected workspace members.
+                if package.is_some_and(|package| self.is_member(package)) {
+                    continue;
+                }
+
+                if let Some(package) = package {
+                    dependencies.insert(Self::package(package_key, package, version)?);
+                }
+
+                // V9 stores dependency information separately from package metadata.
+                let transitive = match version {
+                    PnpmVersion::V9 => self.snapshots.get(key),
+                    _ => package.map(|package| &package.dependencies),
+                };
+                queue.extend(transitive.into_iter().flat_map(|deps| deps.dependencies(false)));
+            }
+
+            members.push(WorkspaceMember {
+                name: None,
+                path: path.clone(),
+                dependencies: dependencies.into_iter().collect(),
+            });
+        }
+
+        Ok(members)
+    }
 
-            packages.push(package);
+    /// Check if a package is a workspace member.
+    fn is_member(&self, package: &PnpmPackage) -> bool {
+        let directory = package.resolution.directory.as_ref();
+        directory.is_some_and(|directory| self.importers.contains_key(directory))
+    }
+
+    /// Get the lockfile format version.
+    fn version(&self) -> anyhow::Result<PnpmVersion> {
+        // Try and parse manifest version.
+        let major = self.lockfile_version.split('.').next().and_then(|v| u8::from_str(v).ok());
+        match major {
+            Some(9..) => Ok(PnpmVersion::V9),
+            Some(6..) => Ok(PnpmVersion::V6),
+            Some(5..) => Ok(PnpmVersion::V5),
+            _ => Err(anyhow!("Invalid pnpm lockfile version: '{}'", self.lockfile_version)),
         }
+    }
 
-        Ok(packages)
+    /// Find the key of a dependency's package entry.
+    fn resolve<'a>(
+        &'a self,
+        name: &str,
+        dependency: &'a str,
+        pnpm_version: PnpmVersion,
+    ) -> Option<&'a str> {
+        // Dependencies with a key as version (i.e. aliases or tarballs).
+        let mut candidates = vec![dependency.to_owned()];
+
+        candidates.push(match pnpm_version {
+            PnpmVersion::V9 => format!("{name}@{dependency}"),
+            PnpmVersion::V6 => format!("/{name}@{dependency}"),
+            PnpmVersion::V5 => format!("/{name}/{dependency}"),
+        });
+
+        candidates.iter().find_map(|candidate| match pnpm_version {
+            PnpmVersion::V9 => self.snapshots.get_key_value(candidate).map(|(key, _)| key.as_str()),
+            _ => self.packages.get_key_value(candidate).map(|(key, _)| key.as_str()),
+        })
+    }
+
+    /// Parse a single package entry.
+    fn package(
+        key: &str,
+        package: &PnpmPackage,
+        pnpm_version: PnpmVersion,
+    ) -> anyhow::Result<Package> {
+        // Parse package based on available fields.
+        let resolution = &package.resolution;
+        let directory = resolution.directory.clone();
+        let tarball = resolution.tarball.clone();
+        let git = resolution.repo.clone().zip(resolution.commit.clone());
+
+        let (name, version) = match &package.name {
+            Some(name) => (name.clone(), None),
+            None => Self::parse_key(key, pnpm_version).map(|(n, v)| (n, Some(v)))?,
+        };
+
+        match (tarball, git, directory) {
+            (Some(tarball), ..) => Ok(Self::tarball_package(tarball, name)),
+            (_, Some((repo, commit)), _) => Ok(Self::git_package(repo, commit, name)),
+            (_, _, Some(directory)) => Ok(Self::path_package(directory, name)),
+            _ => Self::firstparty_package(name, version),
+        }
     }
 
     /// Parse package key.
@@ -416,6 +774,54 @@ impl PnpmLock {
 struct PnpmPackage {
     resolution: PnpmResolution,
     name: Option<String>,
+    #[serde(flatten)]
+    dependencies: PnpmDependencies,
+}
+
+/// `pnpm-lock.yaml` dependency lists of importers, packages, and snapshots.
+#[derive(Deserialize, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+struct PnpmDependencies {
+    #[serde(default)]
+    dependencies: HashMap<String, PnpmDependency>,
+    #[serde(default)]
+    dev_dependencies: HashMap<String, PnpmDependency>,
+    #[serde(default)]
+    optional_dependencies: HashMap<String, PnpmDependency>,
+}
+
+impl PnpmDependencies {
+    /// Get the name and resolved version of all dependencies.
+    fn dependencies(&self, include_dev: bool) -> Vec<(&str, &str)> {
+        let mut dependencies = vec![&self.dependencies, &self.optional_dependencies];
+        if include_dev {
+            dependencies.push(&self.dev_dependencies);
+        }
+
+        dependencies
+            .into_iter()
+            .flatten()
+            .map(|(name, dependency)| (name.as_str(), dependency.version()))
+            .collect()
+    }
+}
+
+/// `pnpm-lock.yaml` dependency version.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum PnpmDependency {
+    /// Version with specifier, used by lockfiles >= v6.
+    Specified { version: String },
+    /// Plain version, used by v5 lockfiles.
+    Version(String),
+}
+
+impl PnpmDependency {
+    fn version(&self) -> &str {
+        match self {
+            Self::Specified { version } | Self::Version(version) => version,
+        }
+    }
 }
 
 /// `pnpm-lock.yaml` resolution structure.
@@ -810,4 +1216,115 @@ mod tests {
             assert!(pkgs.contains(&expected_pkg), "missing package {expected_pkg:?}");
         }
     }
+
+    fn npm_package(name: &str, version: &str) -> Package {
+        Package {
+            name: name.into(),
+            version: PackageVersion::FirstParty(version.into()),
+            package_type: PackageType::Npm,
+        }
+    }
+
+    /// Get members sorted by path, with their dependencies.
+    fn member_dependencies(mut members: Vec<WorkspaceMember>) -> Vec<(String, Vec<Package>)> {
+        members.sort_by(|a, b| a.path.cmp(&b.path));
+        members.into_iter().map(|member| (member.path, member.dependencies)).collect()
+    }
+
+    /// Get the expected dependencies for the shared workspace fixtures.
+    fn expected_workspace() -> Vec<(String, Vec<Package>)> {
+        vec![
+            (".".into(), vec![npm_package("lodash", "4.17.21")]),
+            ("packages/a".into(), vec![npm_package("left-pad", "1.3.0")]),
+            ("packages/b".into(), vec![
+                npm_package("is-number", "6.0.0"),
+                npm_package("is-odd", "3.0.1"),
+                npm_package("left-pad", "1.1.3"),
+            ]),
+        ]
+    }
+
+    #[test]
+    fn package_lock_workspace() {
+        let data = include_str!("../../tests/fixtures/workspace/package-lock.json");
+
+        let mut pkgs = PackageLock.parse(data).unwrap();
+        pkgs.sort();
+        assert_eq!(pkgs, vec![
+            npm_package("is-number", "6.0.0"),
+            npm_package("is-odd", "3.0.1"),
+            npm_package("left-pad", "1.1.3"),
+            npm_package("left-pad", "1.3.0"),
+            npm_package("lodash", "4.17.21"),
+        ]);
+
+        let members = PackageLock.parse_workspace(data).unwrap().members;
+        let names: Vec<_> = members.iter().map(|member| member.name.as_deref()).collect();
+        assert_eq!(names, vec![Some("ws-root"), Some("member-a"), Some("member-b")]);
+        assert_eq!(member_dependencies(members), expected_workspace());
+    }
+
+    #[test]
+    fn package_lock_v6_without_workspace() {
+        let data = include_str!("../../tests/fixtures/package-lock-v6.json");
+        let workspace = PackageLock.parse_workspace(data).unwrap();
+        assert!(!workspace.packages.is_empty());
+        assert!(workspace.members.is_empty());
+    }
+
+    #[test]
+    fn yarn_workspace() {
+        let data = include_str!("../../tests/fixtures/workspace/yarn.lock");
+
+        let mut pkgs = YarnLock.parse(data).unwrap();
+        pkgs.sort();
+        assert_eq!(pkgs, vec![
+            npm_package("is-number", "6.0.0"),
+            npm_package("is-odd", "3.0.1"),
+            npm_package("left-pad", "1.1.3"),
+            npm_package("left-pad", "1.3.0"),
+            npm_package("lodash", "4.17.21"),
+        ]);
+
+        let members = YarnLock.parse_workspace(data).unwrap().members;
+        assert_eq!(member_dependencies(members), expected_workspace());
+    }
+
+    #[test]
+    fn yarn_v1_without_workspace() {
+        let data = include_str!("../../tests/fixtures/yarn-v1.lock");
+        let workspace = YarnLock.parse_workspace(data).unwrap();
+        assert!(!workspace.packages.is_empty());
+        assert!(workspace.members.is_empty());
+    }
+
+    #[test]
+    fn pnpm_workspace() {
+        let data = include_str!("../../tests/fixtures/workspace/pnpm-lock.yaml");
+
+        let mut pkgs = Pnpm.parse(data).unwrap();
+        pkgs.sort();
+        assert_eq!(pkgs, vec![
+            npm_package("is-number", "6.0.0"),
+            npm_package("is-odd", "3.0.1"),
+            npm_package("left-pad", "1.1.3"),
+            npm_package("left-pad", "1.3.0"),
+            npm_package("lodash", "4.17.21"),
+        ]);
+
+        let members = Pnpm.parse_workspace(data).unwrap().members;
+        let mut expected = expected_workspace();
+        expected.push(("packages/c".into(), vec![npm_package("is-number", "6.0.0")]));
+        assert_eq!(member_dependencies(members), expected);
+    }
+
+    #[test]
+    fn pnpm_without_workspace() {
+        let data = include_str!("../../tests/fixtures/pnpm-lock.yaml");
+
+        let members = Pnpm.parse_workspace(data).unwrap().members;
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0].path, ".");
+        assert!(!members[0].dependencies.is_empty());
+    }
 }
@@ -1,8 +1,14 @@
 use std::fmt::Display;
+#[cfg(not(target_arch = "wasm32"))]
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
+#[cfg(not(target_arch = "wasm32"))]
+use anyhow::Context;
+#[cfg(not(target_arch = "wasm32"))]
+use ignore::overrides::OverrideBuilder;
+#[cfg(not(target_arch = "wasm32"))]
 use ignore::WalkBuilder;
 #[cfg(feature = "generator")]
 pub use lockfile_generator as generator;
@@ -10,37 +16,54 @@ pub use lockfile_generator as generator;
 use lockfile_generator::Generator;
 pub use phylum_types;
 use phylum_types::types::package::PackageType;
+pub use purl;
 use purl::GenericPurl;
 use serde::de::IntoDeserializer;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+#[cfg(not(target_arch = "wasm32"))]
 use walkdir::WalkDir;
 
+pub use crate::binary::Binary;
 pub use crate::cargo::Cargo;
 pub use crate::csharp::{CSProj, PackagesConfig, PackagesLock};
 pub use crate::cyclonedx::CycloneDX;
 pub use crate::golang::{GoMod, GoSum};
-pub use crate::java::{GradleLock, Pom};
+#[cfg(not(target_arch = "wasm32"))]
+pub use crate::image::{scan_image, ImageDepfile};
+pub use crate::installed::{GoVendor, NodeModules, SitePackages};
+pub use crate::java::{GradleLock, JavaArchive, Pom};
 pub use crate::javascript::{PackageLock, Pnpm, YarnLock};
-pub use crate::parse_depfile::{parse_depfile, ParseError, ParsedLockfile};
+pub use crate::package_url::PurlError;
+pub use crate::parse_depfile::{
+    parse_binary, parse_depfile, parse_installed, Generation, ParseError, ParsedLockfile,
+    ParsedWorkspaceMember,
+};
 pub use crate::python::{PipFile, Poetry, PyRequirements};
 pub use crate::ruby::GemLock;
 pub use crate::spdx::Spdx;
 
+mod binary;
 mod cargo;
 mod csharp;
 mod cyclonedx;
 mod golang;
+#[cfg(not(target_arch = "wasm32"))]
+mod image;
+mod installed;
 mod java;
 mod javascript;
+mod package_url;
 mod parse_depfile;
 mod parsers;
 mod python;
 mod ruby;
 mod spdx;
+#[cfg(target_arch = "wasm32")]
+pub mod wasm;
 
-/// Maximum directory depth to recurse for finding lockfiles.
-const MAX_LOCKFILE_DEPTH: usize = 5;
+/// Default maximum directory depth to recurse for finding lockfiles.
+pub const MAX_LOCKFILE_DEPTH: usize = 5;
 
 /// A file format that can be parsed.
 #[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
@@ -67,6 +90,11 @@ pub enum LockfileFormat {
     Cargo,
     Spdx,
     CycloneDX,
+    NodeModules,
+    SitePackages,
+    GoVendor,
+    Binary,
+    Jar,
 }
 
 impl FromStr for LockfileFormat {
@@ -110,6 +138,11 @@ impl LockfileFormat {
             LockfileFormat::Cargo => "cargo",
             LockfileFormat::Spdx => "spdx",
             LockfileFormat::CycloneDX => "cyclonedx",
+            LockfileFormat::NodeModules => "nodemodules",
+            LockfileFormat::SitePackages => "sitepackages",
+            LockfileFormat::GoVendor => "govendor",
+            LockfileFormat::Binary => "binary",
+            LockfileFormat::Jar => "jar",
         }
     }
 
@@ -133,6 +166,46 @@ impl LockfileFormat {
             LockfileFormat::Cargo => &Cargo,
             LockfileFormat::Spdx => &Spdx,
             LockfileFormat::CycloneDX => &CycloneDX,
+            LockfileFormat::NodeModules => &NodeModules,
+            LockfileFormat::SitePackages => &SitePackages,
+            LockfileFormat::GoVendor => &GoVendor,
+            LockfileFormat::Binary => &Binary,
+            LockfileFormat::Jar => &JavaArchive,
+        }
+    }
+
+    /// Get the installed environment parser for the specified format.
+    ///
+    /// Returns `None` for lockfile and manifest formats.
+    pub fn installed_parser(&self) -> Option<&'static dyn ParseInstalled> {
+        match self {
+            LockfileFormat::NodeModules => Some(&NodeModules),
+            LockfileFormat::SitePackages => Some(&SitePackages),
+            LockfileFormat::GoVendor => Some(&GoVendor),
+            _ => None,
+        }
+    }
+
+    /// Get the compiled binary parser for the specified format.
+    ///
+    /// Returns `None` for all text-based formats.
+    pub fn binary_parser(&self) -> Option<&'static dyn ParseBinary> {
+        match self {
+            LockfileFormat::Binary => Some(&Binary),
+            LockfileFormat::Jar => Some(&JavaArchive),
+            _ => None,
+        }
+    }
+
+    /// Get the workspace-aware parser for the specified format.
+    ///
+    /// Returns `None` for formats without workspace support.
+    pub fn workspace_parser(&self) -> Option<&'static dyn ParseWorkspace> {
+        match self {
+            LockfileFormat::Yarn => Some(&YarnLock),
+            LockfileFormat::Npm => Some(&PackageLock),
+            LockfileFormat::Pnpm => Some(&Pnpm),
+            _ => None,
         }
     }
 
@@ -160,6 +233,11 @@ impl LockfileFormat {
             LockfileFormat::Cargo,
             LockfileFormat::Spdx,
             LockfileFormat::CycloneDX,
+            LockfileFormat::NodeModules,
+            LockfileFormat::SitePackages,
+            LockfileFormat::GoVendor,
+            LockfileFormat::Binary,
+            LockfileFormat::Jar,
         ];
 
         FORMATS.iter().copied()
@@ -187,6 +265,78 @@ pub trait Parse {
     }
 }
 
+/// Lockfile parser with support for workspaces.
+pub trait ParseWorkspace: Parse {
+    /// Parse all packages and workspace members from a string.
+    ///
+    /// Each member lists every package it depends on, directly or
+    /// transitively. Other workspace members are never included as
+    /// dependencies.
+    ///
+    /// Lockfile versions without workspace support have no members.
+    fn parse_workspace(&self, data: &str) -> anyhow::Result<Workspace>;
+}
+
+/// Parser for installed environments.
+pub trait ParseInstalled: Parse {
+    /// Collect all packages installed below a directory.
+    fn parse_installed(&self, root: &Path) -> anyhow::Result<Vec<InstalledPackage>>;
+}
+
+/// Parser for dependency metadata embedded in compiled binaries.
+pub trait ParseBinary: Parse {
+    /// Test if the data could be a binary supported by this parser.
+    fn is_binary(&self, data: &[u8]) -> bool;
+
+    /// Test if a file could be a binary supported by this parser.
+    ///
+    /// By default, binaries are identified by their name like lockfiles.
+    fn is_path_binary(&self, path: &Path) -> bool {
+        self.is_path_lockfile(path)
+    }
+
+    /// Parse from the raw bytes of a binary.
+    fn parse_binary(&self, data: &[u8]) -> anyhow::Result<Vec<EmbeddedPackage>>;
+}
+
+/// Package embedded in a binary.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct EmbeddedPackage {
+    pub package: Package,
+    /// Path of the nested archive containing the package.
+    ///
+    /// This is empty for packages embedded directly in the parsed binary.
+    pub origin: String,
+}
+
+/// Package found in an installed environment.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct InstalledPackage {
+    pub package: Package,
+    /// Metadata file describing the package.
+    pub path: PathBuf,
+}
+
+/// Packages of a lockfile, grouped by workspace member.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Workspace {
+    /// All packages in the lockfile, excluding workspace members.
+    pub packages: Vec<Package>,
+    /// Workspace members, including the workspace root.
+    pub members: Vec<WorkspaceMember>,
+}
+
+/// Member package of a workspace.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct WorkspaceMember {
+    /// Package name, if available.
+    pub name: Option<String>,
+    /// Member directory, relative to the workspace root.
+    pub path: String,
+    /// Packages required by this member.
+    pub dependencies: Vec<Package>,
+}
+
 /// Single package parsed from a lockfile.
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub struct Package {
@@ -250,6 +400,7 @@ pub fn get_depfile_path_format<P: AsRef<Path>>(path: P) -> Option<LockfileFormat
 ///
 /// Contrary to [`get_path_format`], the `path` argument must point to an
 /// existing manifest file within the project to find its lockfile.
+#[cfg(not(target_arch = "wasm32"))]
 pub fn find_manifest_lockfile<P: AsRef<Path>>(path: P) -> Option<(PathBuf, LockfileFormat)> {
     // Canonicalize the path, so calling `parent` always works.
     let path = path.as_ref();
@@ -275,6 +426,7 @@ pub fn find_manifest_lockfile<P: AsRef<Path>>(path: P) -> Option<(PathBuf, Lockf
 /// Walks the directory tree and returns all paths recognized as lockfiles.
 ///
 /// Paths excluded by gitignore are automatically ignored.
+#[cfg(not(target_arch = "wasm32"))]
 pub fn find_lockfiles() -> Vec<(PathBuf, LockfileFormat)> {
     find_lockfiles_at(".")
 }
@@ -284,16 +436,125 @@ pub fn find_lockfiles() -> Vec<(PathBuf, LockfileFormat)> {
 /// Walks the directory tree and returns all paths recognized as lockfiles.
 ///
 /// Paths excluded by gitignore are automatically ignored.
+#[cfg(not(target_arch = "wasm32"))]
 pub fn find_lockfiles_at(root: impl AsRef<Path>) -> Vec<(PathBuf, LockfileFormat)> {
-    let walker = WalkBuilder::new(root).max_depth(Some(MAX_LOCKFILE_DEPTH)).build();
-    walker
+    find_lockfiles_with_options(root, &DiscoveryOptions::default())
+        .expect("default discovery options are valid")
+}
+
+/// Find lockfiles at or below the specified root directory using custom
+/// discovery settings.
+///
+/// Walks the directory tree and returns all paths recognized as lockfiles.
+///
+/// Paths excluded by gitignore are automatically ignored.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn find_lockfiles_with_options(
+    root: impl AsRef<Path>,
+    options: &DiscoveryOptions,
+) -> anyhow::Result<Vec<(PathBuf, LockfileFormat)>> {
+    let walker = options.walk_builder(root)?.build();
+    let lockfiles = walker
         .into_iter()
         .flatten()
         .filter_map(|entry| {
             let path = entry.path().to_path_buf();
-            get_path_format(&path).map(|format| (path, format))
+            let format = options.formats().find(|format| match format.binary_parser() {
+                Some(parser) => parser.is_path_binary(&path),
+                None => format.parser().is_path_lockfile(&path),
+            })?;
+            Some((path, format))
+        })
+        .collect();
+    Ok(lockfiles)
+}
+
+/// Dependency file discovery settings.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct DiscoveryOptions {
+    /// Maximum directory depth to recurse into.
+    ///
+    /// Use `None` to remove the depth limit entirely.
+    pub max_depth: Option<usize>,
+    /// Globs selecting the paths which should be considered.
+    ///
+    /// If this is empty, all paths are considered.
+    pub include: Vec<String>,
+    /// Globs for paths which should never be considered.
+    pub exclude: Vec<String>,
+    /// Dependency file formats which should be detected.
+    ///
+    /// If this is empty, all lockfile and manifest formats are detected.
+    /// Installed environments and binaries are only detected when explicitly
+    /// listed.
+    pub formats: Vec<LockfileFormat>,
+    /// Whether symbolic links should be followed.
+    pub follow_symlinks: bool,
+    /// Whether hidden files and ignore files like `.gitignore` are respected.
+    pub standard_filters: bool,
+}
+
+impl Default for DiscoveryOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: Some(MAX_LOCKFILE_DEPTH),
+            follow_symlinks: false,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            formats: Vec::new(),
+            standard_filters: true,
+        }
+    }
+}
+
+impl DiscoveryOptions {
+    /// Iterate over all enabled formats, in detection priority order.
+    pub fn formats(&self) -> impl Iterator<Item = LockfileFormat> + '_ {
+        LockfileFormat::iter().filter(|format| match self.formats.is_empty() {
+            true => format.installed_parser().is_none() && format.binary_parser().is_none(),
+            false => self.formats.contains(format),
         })
-        .collect()
+    }
+
+    /// Create a directory walker for these settings.
+    ///
+    /// Globs are matched relative to `root` and follow gitignore syntax.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn walk_builder(&self, root: impl AsRef<Path>) -> anyhow::Result<WalkBuilder> {
+        let root = root.as_ref();
+
+        let mut overrides = OverrideBuilder::new(root);
+        for glob in &self.include {
+            overrides.add(glob).with_context(|| format!("invalid include glob {glob:?}"))?;
+        }
+        for glob in &self.exclude {
+            overrides
+                .add(&format!("!{glob}"))
+                .with_context(|| format!("invalid exclude glob {glob:?}"))?;
+        }
+
+        let mut builder = WalkBuilder::new(root);
+        builder
+            .max_depth(self.max_depth)
+            .follow_links(self.follow_symlinks)
+            .standard_filters(self.standard_filters)
+            .overrides(overrides.build()?);
+
+        // Never descend into detected installed environments.
+        let installed: Vec<_> =
+            self.formats().filter(|format| format.installed_parser().is_some()).collect();
+        if !installed.is_empty() {
+            builder.filter_entry(move |entry| {
+                let parent = entry.path().parent();
+                !parent.is_some_and(|parent| {
+                    installed.iter().any(|format| format.parser().is_path_lockfile(parent))
+                })
+            });
+        }
+
+        Ok(builder)
+    }
 }
 
 /// Collection of lockfiles and manifests.
@@ -303,6 +564,7 @@ pub struct DepFiles {
     pub manifests: Vec<(PathBuf, LockfileFormat)>,
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl DepFiles {
     /// Find dependency files at or below the specified root directory.
     ///
@@ -310,22 +572,40 @@ impl DepFiles {
     ///
     /// Paths excluded by gitignore are automatically ignored.
     pub fn find_at(root: impl AsRef<Path>) -> Self {
+        Self::find_with_options(root, &DiscoveryOptions::default())
+            .expect("default discovery options are valid")
+    }
+
+    /// Find dependency files at or below the specified root directory using
+    /// custom discovery settings.
+    ///
+    /// Walks the directory tree and returns all recognized files.
+    ///
+    /// Paths excluded by gitignore are automatically ignored.
+    pub fn find_with_options(
+        root: impl AsRef<Path>,
+        options: &DiscoveryOptions,
+    ) -> anyhow::Result<Self> {
         let mut depfiles = Self { lockfiles: Vec::new(), manifests: Vec::new() };
 
-        let walker = WalkBuilder::new(root).max_depth(Some(MAX_LOCKFILE_DEPTH)).build();
+        let walker = options.walk_builder(root)?.build();
 
         // Find all lockfiles and manifests in the specified directory.
         for entry in walker.into_iter().flatten() {
             let path = entry.path();
 
-            for format in LockfileFormat::iter() {
+            for format in options.formats() {
                 let parser = format.parser();
 
                 let mut format_found = false;
                 // GoMod can represent a manifest and lockfile which causes duplicate
                 // lockfiles being submitted when a go.sum is present. This removes
                 // go.mod files from being automatically recognized as a lockfile.
-                if format != LockfileFormat::GoMod && parser.is_path_lockfile(path) {
+                let is_lockfile = match format.binary_parser() {
+                    Some(binary_parser) => binary_parser.is_path_binary(path),
+                    None => parser.is_path_lockfile(path),
+                };
+                if format != LockfileFormat::GoMod && is_lockfile {
                     depfiles.lockfiles.push((path.to_path_buf(), format));
                     format_found = true;
                 }
@@ -343,7 +623,7 @@ impl DepFiles {
             }
         }
 
-        depfiles
+        Ok(depfiles)
     }
 }
 
@@ -356,8 +636,22 @@ impl DepFiles {
 /// [`DepFiles::find_at`].
 ///
 /// Paths excluded by gitignore are automatically ignored.
+#[cfg(not(target_arch = "wasm32"))]
 pub fn find_depfiles_at(root: impl AsRef<Path>) -> Vec<(PathBuf, LockfileFormat)> {
-    let mut depfiles = DepFiles::find_at(root);
+    find_depfiles_with_options(root, &DiscoveryOptions::default())
+        .expect("default discovery options are valid")
+}
+
+/// Find dependency files at or below the specified root directory using custom
+/// discovery settings.
+///
+/// See [`find_depfiles_at`] for details on how manifests are filtered.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn find_depfiles_with_options(
+    root: impl AsRef<Path>,
+    options: &DiscoveryOptions,
+) -> anyhow::Result<Vec<(PathBuf, LockfileFormat)>> {
+    let mut depfiles = DepFiles::find_with_options(root, options)?;
 
     for i in (0..depfiles.manifests.len()).rev() {
         let mut remove = false;
@@ -411,7 +705,7 @@ pub fn find_depfiles_at(root: impl AsRef<Path>) -> Vec<(PathBuf, LockfileFormat)
 
     // Return all manifests and lockfiles.
     depfiles.lockfiles.append(&mut depfiles.manifests);
-    depfiles.lockfiles
+    Ok(depfiles.lockfiles)
 }
 
 /// Define a custom error for unknown ecosystems.
@@ -628,6 +922,11 @@ mod tests {
             (LockfileFormat::Cargo, 3),
             (LockfileFormat::Spdx, 6),
             (LockfileFormat::CycloneDX, 7),
+            (LockfileFormat::NodeModules, 0),
+            (LockfileFormat::SitePackages, 0),
+            (LockfileFormat::GoVendor, 0),
+            (LockfileFormat::Binary, 0),
+            (LockfileFormat::Jar, 0),
         ] {
             let mut parsed_lockfiles = Vec::new();
             for lockfile in fs::read_dir("../tests/fixtures").unwrap().flatten() {
@@ -797,4 +1096,141 @@ mod tests {
         ];
         assert_eq!(lockfiles, expected);
     }
+
+    #[test]
+    fn discovery_max_depth() {
+        // Create desired directory structure.
+        let tempdir = tempfile::tempdir().unwrap();
+        let files =
+            [tempdir.path().join("Cargo.lock"), tempdir.path().join("a/b/c/d/e/f/g/Cargo.lock")];
+        for file in &files {
+            let dir = file.parent().unwrap();
+            fs::create_dir_all(dir).unwrap();
+            File::create(file).unwrap();
+        }
+
+        // Deeply nested lockfiles are ignored by default.
+        let lockfiles = find_depfiles_at(tempdir.path());
+        let expected = vec![(files[0].clone(), LockfileFormat::Cargo)];
+        assert_eq!(lockfiles, expected);
+
+        // Increasing the depth finds all lockfiles.
+        let options = DiscoveryOptions { max_depth: Some(8), ..DiscoveryOptions::default() };
+        let mut lockfiles = find_depfiles_with_options(tempdir.path(), &options).unwrap();
+        lockfiles.sort_unstable();
+        let expected = vec![
+            (files[0].clone(), LockfileFormat::Cargo),
+            (files[1].clone(), LockfileFormat::Cargo),
+        ];
+        assert_eq!(lockfiles, expected);
+    }
+
+    #[test]
+    fn discovery_include_exclude_globs() {
+        // Create desired directory structure.
+        let tempdir = tempfile::tempdir().unwrap();
+        let files = [
+            tempdir.path().join("app/package-lock.json"),
+            tempdir.path().join("app/Cargo.lock"),
+            tempdir.path().join("tests/fixtures/package-lock.json"),
+            tempdir.path().join("vendor/lib/package-lock.json"),
+        ];
+        for file in &files {
+            let dir = file.parent().unwrap();
+            fs::create_dir_all(dir).unwrap();
+            File::create(file).unwrap();
+        }
+
+        // Exclude globs skip entire directories.
+        let options = DiscoveryOptions {
+            exclude: vec!["tests/fixtures".into(), "vendor/".into()],
+            ..DiscoveryOptions::default()
+        };
+        let mut lockfiles = find_depfiles_with_options(tempdir.path(), &options).unwrap();
+        lockfiles.sort_unstable();
+        let expected = vec![
+            (files[1].clone(), LockfileFormat::Cargo),
+            (files[0].clone(), LockfileFormat::Npm),
+        ];
+        assert_eq!(lockfiles, expected);
+
+        // Include globs restrict results to matching files.
+        let options = DiscoveryOptions {
+            include: vec!["**/package-lock.json".into()],
+            exclude: vec!["vendor/".into()],
+            ..DiscoveryOptions::default()
+        };
+        let mut lockfiles = find_depfiles_with_options(tempdir.path(), &options).unwrap();
+        lockfiles.sort_unstable();
+        let expected =
+            vec![(files[0].clone(), LockfileFormat::Npm), (files[2].clone(), LockfileFormat::Npm)];
+        assert_eq!(lockfiles, expected);
+
+        // Invalid globs are reported.
+        let options =
+            DiscoveryOptions { include: vec!["a/{b".into()], ..DiscoveryOptions::default() };
+        assert!(find_depfiles_with_options(tempdir.path(), &options).is_err());
+    }
+
+    #[test]
+    fn discovery_format_filter() {
+        // Create desired directory structure.
+        let tempdir = tempfile::tempdir().unwrap();
+        let files = [tempdir.path().join("Cargo.lock"), tempdir.path().join("yarn.lock")];
+        for file in &files {
+            File::create(file).unwrap();
+        }
+
+        let options =
+            DiscoveryOptions { formats: vec![LockfileFormat::Yarn], ..DiscoveryOptions::default() };
+        let lockfiles = find_depfiles_with_options(tempdir.path(), &options).unwrap();
+        let expected = vec![(files[1].clone(), LockfileFormat::Yarn)];
+        assert_eq!(lockfiles, expected);
+    }
+
+    #[test]
+    fn discovery_installed_opt_in() {
+        // Create desired directory structure.
+        let tempdir = tempfile::tempdir().unwrap();
+        let node_modules = tempdir.path().join("node_modules");
+        fs::create_dir_all(node_modules.join("a/node_modules/b")).unwrap();
+        File::create(tempdir.path().join("package-lock.json")).unwrap();
+
+        // Installed environments are ignored by default.
+        let depfiles = find_depfiles_with_options(tempdir.path(), &DiscoveryOptions::default());
+        let expected = vec![(tempdir.path().join("package-lock.json"), LockfileFormat::Npm)];
+        assert_eq!(depfiles.unwrap(), expected);
+
+        // Nested environments are not reported separately.
+        let options = DiscoveryOptions {
+            formats: vec![LockfileFormat::NodeModules],
+            ..DiscoveryOptions::default()
+        };
+        let depfiles = find_depfiles_with_options(tempdir.path(), &options).unwrap();
+        assert_eq!(depfiles, vec![(node_modules, LockfileFormat::NodeModules)]);
+    }
+
+    #[test]
+    fn discovery_binary_opt_in() {
+        // Create desired directory structure.
+        let tempdir = tempfile::tempdir().unwrap();
+        let binary = tempdir.path().join("bin/server");
+        fs::create_dir_all(binary.parent().unwrap()).unwrap();
+        fs::write(&binary, b"\x7fELF\x02\x01\x01").unwrap();
+        fs::write(tempdir.path().join("bin/script"), "#!/bin/sh\n").unwrap();
+        File::create(tempdir.path().join("Cargo.lock")).unwrap();
+
+        // Binaries are ignored by default.
+        let depfiles = find_depfiles_with_options(tempdir.path(), &DiscoveryOptions::default());
+        let expected = vec![(tempdir.path().join("Cargo.lock"), LockfileFormat::Cargo)];
+        assert_eq!(depfiles.unwrap(), expected);
+
+        // Binaries are identified by their content.
+        let options = DiscoveryOptions {
+            formats: vec![LockfileFormat::Binary],
+            ..DiscoveryOptions::default()
+        };
+        let depfiles = find_depfiles_with_options(tempdir.path(), &options).unwrap();
+        assert_eq!(depfiles, vec![(binary, LockfileFormat::Binary)]);
+    }
 }
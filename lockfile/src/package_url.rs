@@ -0,0 +1,290 @@
+//! Conversion between packages and Package URLs (PURLs).
+//!
+//! Non-registry versions are encoded through the PURL qualifiers:
+//!
+//! - [`PackageVersion::ThirdParty`] uses `repository_url` with the version
+//! - [`PackageVersion::Git`] uses `vcs_url`
+//! - [`PackageVersion::Path`] uses `download_url` with the `file:` scheme
+//! - [`PackageVersion::DownloadUrl`] uses `download_url`
+//! - [`PackageVersion::Unknown`] has neither version nor qualifiers
+
+use std::path::PathBuf;
+
+use phylum_types::types::package::PackageType;
+use purl::GenericPurl;
+use thiserror::Error;
+
+use crate::{formatted_package_name, Package, PackageVersion, ThirdPartyVersion};
+
+/// Qualifier for packages from a foreign registry.
+const REPOSITORY_URL: &str = "repository_url";
+
+/// Qualifier for packages from a version control system.
+const VCS_URL: &str = "vcs_url";
+
+/// Qualifier for packages from a direct download or the filesystem.
+const DOWNLOAD_URL: &str = "download_url";
+
+/// URL scheme of filesystem packages.
+const FILE_SCHEME: &str = "file:";
+
+/// Errors during conversion between packages and PURLs.
+#[derive(Error, Debug)]
+pub enum PurlError {
+    #[error("Unsupported package URL type {0:?}")]
+    UnsupportedType(String),
+    #[error("Package URL with {REPOSITORY_URL:?} qualifier is missing its version")]
+    MissingVersion,
+    #[error(transparent)]
+    Purl(#[from] purl::ParseError),
+}
+
+impl Package {
+    /// Convert this package to a Package URL.
+    ///
+    /// The conversion is lossless, [`Package::from_purl`] will return the
+    /// original package. The only exceptions are empty filesystem paths, which
+    /// are indistinguishable from unknown paths, and download URLs using the
+    /// `file:` scheme, which are treated as filesystem paths.
+    pub fn to_purl(&self) -> Result<GenericPurl<String>, PurlError> {
+        let (namespace, name) = split_package_name(self.package_type, &self.name);
+
+        let (version, qualifier) = match &self.version {
+            PackageVersion::FirstParty(version) => (Some(version.as_str()), None),
+            PackageVersion::ThirdParty(ThirdPartyVersion { registry, version }) => {
+                (Some(version.as_str()), Some((REPOSITORY_URL, registry.clone())))
+            },
+            PackageVersion::Git(url) => (None, Some((VCS_URL, url.clone()))),
+            PackageVersion::Path(path) => {
+                let path = path.as_ref().map(|path| path.to_string_lossy()).unwrap_or_default();
+                (None, Some((DOWNLOAD_URL, format!("{FILE_SCHEME}{path}"))))
+            },
+            PackageVersion::DownloadUrl(url) => (None, Some((DOWNLOAD_URL, url.clone()))),
+            PackageVersion::Unknown => (None, None),
+        };
+
+        let mut builder = GenericPurl::builder(purl_type(self.package_type).to_owned(), name)
+            .with_namespace(namespace)
+            .with_version(version);
+        if let Some((key, value)) = qualifier {
+            builder = builder.with_qualifier(key, Some(value))?;
+        }
+
+        Ok(builder.build()?)
+    }
+
+    /// Create a package from a Package URL.
+    ///
+    /// See [`Package::to_purl`] for the encoding of non-registry versions.
+    pub fn from_purl(purl: &GenericPurl<String>) -> Result<Self, PurlError> {
+        let package_type = match purl.package_type().to_ascii_lowercase().as_str() {
+            "npm" => PackageType::Npm,
+            "pypi" => PackageType::PyPi,
+            "maven" => PackageType::Maven,
+            "gem" => PackageType::RubyGems,
+            "nuget" => PackageType::Nuget,
+            "golang" => PackageType::Golang,
+            "cargo" => PackageType::Cargo,
+            _ => return Err(PurlError::UnsupportedType(purl.package_type().clone())),
+        };
+
+        let name = formatted_package_name(&package_type, purl);
+
+        let qualifiers = purl.qualifiers();
+        let version = match (
+            purl.version(),
+            qualifiers.get(REPOSITORY_URL),
+            qualifiers.get(VCS_URL),
+            qualifiers.get(DOWNLOAD_URL),
+        ) {
+            (Some(version), Some(registry), ..) => PackageVersion::ThirdParty(ThirdPartyVersion {
+                registry: registry.into(),
+                version: version.into(),
+            }),
+            (None, Some(_), ..) => return Err(PurlError::MissingVersion),
+            (_, None, Some(url), _) => PackageVersion::Git(url.into()),
+            (_, None, None, Some(url)) => match url.strip_prefix(FILE_SCHEME) {
+                Some("") => PackageVersion::Path(None),
+                Some(path) => PackageVersion::Path(Some(PathBuf::from(path))),
+                None => PackageVersion::DownloadUrl(url.into()),
+            },
+            (Some(version), None, None, None) => PackageVersion::FirstParty(version.into()),
+            (None, None, None, None) => PackageVersion::Unknown,
+        };
+
+        Ok(Self { name, version, package_type })
+    }
+}
+
+/// Get the PURL type of a package ecosystem.
+fn purl_type(package_type: PackageType) -> &'static str {
+    match package_type {
+        PackageType::Npm => "npm",
+        PackageType::PyPi => "pypi",
+        PackageType::Maven => "maven",
+        PackageType::RubyGems => "gem",
+        PackageType::Nuget => "nuget",
+        PackageType::Golang => "golang",
+        PackageType::Cargo => "cargo",
+    }
+}
+
+/// Split a package name into PURL namespace and name.
+///
+/// This is the inverse of [`formatted_package_name`].
+fn split_package_name(package_type: PackageType, name: &str) -> (Option<&str>, &str) {
+    let split = match package_type {
+        PackageType::Maven => name.split_once(':'),
+        PackageType::Npm if name.starts_with('@') => name.split_once('/'),
+        PackageType::Golang => name.rsplit_once('/'),
+        _ => None,
+    };
+
+    match split {
+        Some((namespace, name)) => (Some(namespace), name),
+        None => (None, name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn package(name: &str, version: PackageVersion, package_type: PackageType) -> Package {
+        Package { name: name.into(), version, package_type }
+    }
+
+    #[test]
+    fn purl_encoding() {
+        let pkg =
+            package("@scope/name", PackageVersion::FirstParty("1.0.0".into()), PackageType::Npm);
+        let purl = pkg.to_purl().unwrap();
+        assert_eq!(purl.package_type(), "npm");
+        assert_eq!(purl.namespace(), Some("@scope"));
+        assert_eq!(purl.name(), "name");
+        assert_eq!(purl.version(), Some("1.0.0"));
+        assert!(purl.qualifiers().is_empty());
+
+        let registry = "https://repo.example.org";
+        let version = ThirdPartyVersion { registry: registry.into(), version: "2.0".into() };
+        let pkg = package(
+            "org.example:artifact",
+            PackageVersion::ThirdParty(version),
+            PackageType::Maven,
+        );
+        let purl = pkg.to_purl().unwrap();
+        assert_eq!(purl.namespace(), Some("org.example"));
+        assert_eq!(purl.name(), "artifact");
+        assert_eq!(purl.version(), Some("2.0"));
+        assert_eq!(purl.qualifiers().get(REPOSITORY_URL), Some(registry));
+
+        let pkg = package("github.com/pkg/errors", PackageVersion::Unknown, PackageType::Golang);
+        let purl = pkg.to_purl().unwrap();
+        assert_eq!(purl.namespace(), Some("github.com/pkg"));
+        assert_eq!(purl.name(), "errors");
+        assert_eq!(purl.version(), None);
+
+        let pkg = package("local", PackageVersion::Path(None), PackageType::Cargo);
+        let purl = pkg.to_purl().unwrap();
+        assert_eq!(purl.qualifiers().get(DOWNLOAD_URL), Some("file:"));
+    }
+
+    #[test]
+    fn purl_decoding() {
+        let purl = GenericPurl::<String>::from_str(
+            "pkg:pypi/requests?vcs_url=git%2Bhttps://github.com/psf/requests%4001ab",
+        )
+        .unwrap();
+        let expected = package(
+            "requests",
+            PackageVersion::Git("git+https://github.com/psf/requests@01ab".into()),
+            PackageType::PyPi,
+        );
+        assert_eq!(Package::from_purl(&purl).unwrap(), expected);
+
+        let purl =
+            GenericPurl::<String>::from_str("pkg:gem/rake?download_url=file:vendor/rake").unwrap();
+        let expected = package(
+            "rake",
+            PackageVersion::Path(Some("vendor/rake".into())),
+            PackageType::RubyGems,
+        );
+        assert_eq!(Package::from_purl(&purl).unwrap(), expected);
+    }
+
+    #[test]
+    fn unsupported_purl() {
+        let purl = GenericPurl::<String>::from_str("pkg:deb/debian/curl@7.50.3").unwrap();
+        assert!(matches!(Package::from_purl(&purl), Err(PurlError::UnsupportedType(_))));
+
+        let purl = GenericPurl::<String>::from_str("pkg:npm/a?repository_url=https://r").unwrap();
+        assert!(matches!(Package::from_purl(&purl), Err(PurlError::MissingVersion)));
+    }
+
+    fn package_type() -> impl Strategy<Value = PackageType> {
+        prop_oneof![
+            Just(PackageType::Npm),
+            Just(PackageType::PyPi),
+            Just(PackageType::Maven),
+            Just(PackageType::RubyGems),
+            Just(PackageType::Nuget),
+            Just(PackageType::Golang),
+            Just(PackageType::Cargo),
+        ]
+    }
+
+    /// Generate a package name valid for its ecosystem.
+    fn package_name(package_type: PackageType) -> BoxedStrategy<String> {
+        match package_type {
+            PackageType::Npm => "(@[a-z][a-z0-9-]{0,8}/)?[a-z][a-z0-9._-]{0,12}".boxed(),
+            PackageType::Maven => "[a-z][a-z0-9.]{0,12}:[a-z][a-zA-Z0-9._-]{0,12}".boxed(),
+            PackageType::Golang => {
+                "[a-z][a-z0-9-]{0,8}(\\.[a-z]{2,3})?(/[a-z][a-z0-9._-]{0,8}){0,3}".boxed()
+            },
+            _ => "[a-zA-Z][a-zA-Z0-9._-]{0,16}".boxed(),
+        }
+    }
+
+    fn package_version() -> impl Strategy<Value = PackageVersion> {
+        let version = "[0-9]{1,3}(\\.[0-9]{1,3}){0,2}(-[a-z0-9.+]{1,8})?";
+        let url = "https://[a-z]{1,10}\\.[a-z]{2,3}(/[a-zA-Z0-9._~-]{1,8}){0,3}";
+        prop_oneof![
+            version.prop_map(PackageVersion::FirstParty),
+            (url, version).prop_map(|(registry, version)| {
+                PackageVersion::ThirdParty(ThirdPartyVersion { registry, version })
+            }),
+            (url, "[0-9a-f]{0,40}")
+                .prop_map(|(url, rev)| PackageVersion::Git(format!("git+{url}#{rev}"))),
+            proptest::option::of("\\.{0,2}(/?[a-zA-Z0-9 ._-]{1,8}){1,3}")
+                .prop_map(|path| PackageVersion::Path(path.map(PathBuf::from))),
+            url.prop_map(PackageVersion::DownloadUrl),
+            Just(PackageVersion::Unknown),
+        ]
+    }
+
+    fn any_package() -> impl Strategy<Value = Package> {
+        (package_type(), package_version()).prop_flat_map(|(package_type, version)| {
+            package_name(package_type).prop_map(move |name| Package {
+                name,
+                version: version.clone(),
+                package_type,
+            })
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn purl_roundtrip(package in any_package()) {
+            let purl = package.to_purl().unwrap();
+            prop_assert_eq!(&Package::from_purl(&purl).unwrap(), &package);
+
+            // Ensure the serialized representation is lossless too.
+            let parsed = GenericPurl::<String>::from_str(&purl.to_string()).unwrap();
+            prop_assert_eq!(Package::from_purl(&parsed).unwrap(), package);
+        }
+    }
+}
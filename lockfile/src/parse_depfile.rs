@@ -1,4 +1,5 @@
 //! Parse generic dependency files.
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::{Path, PathBuf};
 
 #[cfg(feature = "generator")]
@@ -7,7 +8,9 @@ use anyhow::Context;
 use phylum_types::types::package::PackageDescriptor;
 use serde::{Deserialize, Serialize};
 
-use crate::{LockfileFormat, Package, PackageVersion, Parse, ThirdPartyVersion};
+#[cfg(feature = "generator")]
+use crate::Parse;
+use crate::{LockfileFormat, Package, PackageVersion, ThirdPartyVersion, Workspace};
 
 /// Lockfile parsing error.
 #[derive(thiserror::Error, Debug)]
@@ -18,6 +21,9 @@ pub enum ParseError {
     /// Dependency file is a manifest, but file type was not provided.
     #[error("Parsing {0:?} requires a type to be specified")]
     UnknownManifestFormat(String),
+    /// Lockfile generation exceeded its time or resource limits.
+    #[error(transparent)]
+    GenerationLimitExceeded(anyhow::Error),
     #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
@@ -28,6 +34,17 @@ pub struct ParsedLockfile {
     pub path: String,
     pub packages: Vec<PackageDescriptor>,
     pub format: LockfileFormat,
+    /// Information about how the lockfile was generated.
+    ///
+    /// This is only present for lockfiles generated from a manifest.
+    #[cfg(feature = "generator")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<lockfile_generator::Provenance>,
+    /// Dependencies of each workspace member.
+    ///
+    /// This is only present for lockfiles of workspaces with multiple members.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub workspace_members: Vec<ParsedWorkspaceMember>,
 }
 
 impl ParsedLockfile {
@@ -36,7 +53,98 @@ impl ParsedLockfile {
         format: LockfileFormat,
         packages: Vec<PackageDescriptor>,
     ) -> Self {
-        Self { path: path.into(), packages, format }
+        Self {
+            path: path.into(),
+            packages,
+            format,
+            #[cfg(feature = "generator")]
+            provenance: None,
+            workspace_members: Vec::new(),
+        }
+    }
+
+    /// Create a lockfile from its packages and workspace members.
+    fn from_workspace(
+        path: impl Into<String>,
+        format: LockfileFormat,
+        workspace: Workspace,
+    ) -> Self {
+        let mut parsed = Self::new(path, format, filter_packages(workspace.packages));
+
+        // Lockfiles without workspaces only have a root member.
+        if workspace.members.len() > 1 {
+            parsed.workspace_members = workspace
+                .members
+                .into_iter()
+                .map(|member| ParsedWorkspaceMember {
+                    name: member.name,
+                    path: member.path,
+                    packages: filter_packages(member.dependencies),
+                })
+                .collect();
+        }
+
+        parsed
+    }
+}
+
+/// Workspace member with all its dependencies.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ParsedWorkspaceMember {
+    /// Package name, if available.
+    pub name: Option<String>,
+    /// Member directory, relative to the workspace root.
+    pub path: String,
+    /// Packages required by this member.
+    pub packages: Vec<PackageDescriptor>,
+}
+
+/// Lockfile generation settings.
+#[derive(Clone, Debug)]
+pub struct Generation {
+    /// Path of the manifest on the filesystem.
+    pub manifest_path: PathBuf,
+    /// Generate the lockfile in a scratch copy of the project, leaving the
+    /// project directory untouched.
+    pub out_of_tree: bool,
+    /// Directory for scratch copies shared with other generations.
+    ///
+    /// Each project is copied to a new temporary directory if this is `None`.
+    pub scratch_dir: Option<PathBuf>,
+    /// Limits, offline mode and cancellation of the generation process.
+    #[cfg(feature = "generator")]
+    pub options: lockfile_generator::Options,
+}
+
+impl Generation {
+    pub fn new(manifest_path: impl Into<PathBuf>) -> Self {
+        Self {
+            manifest_path: manifest_path.into(),
+            out_of_tree: false,
+            scratch_dir: None,
+            #[cfg(feature = "generator")]
+            options: lockfile_generator::Options::default(),
+        }
+    }
+
+    /// Generate the lockfile in a scratch copy of the project.
+    pub fn out_of_tree(mut self, out_of_tree: bool) -> Self {
+        self.out_of_tree = out_of_tree;
+        self
+    }
+
+    /// Share scratch copies with other generations using the same directory.
+    pub fn scratch_dir(mut self, scratch_dir: Option<PathBuf>) -> Self {
+        self.scratch_dir = scratch_dir;
+        self
+    }
+
+    /// Run the generation process with these limits, offline mode and
+    /// cancellation.
+    #[cfg(feature = "generator")]
+    pub fn options(mut self, options: lockfile_generator::Options) -> Self {
+        self.options = options;
+        self
     }
 }
 
@@ -44,7 +152,7 @@ impl ParsedLockfile {
 ///
 /// The `path` argument is used for format identification and display purposes.
 ///
-/// The `_generation_path` must point to the manifest on the filesystem if
+/// The `_generation` settings must point to the manifest on the filesystem if
 /// lockfile generation should be performed. Use `None` to disable lockfile
 /// generation.
 ///
@@ -55,7 +163,7 @@ pub fn parse_depfile(
     contents: &str,
     path: impl Into<String>,
     format: Option<LockfileFormat>,
-    _generation_path: Option<PathBuf>,
+    _generation: Option<Generation>,
 ) -> Result<ParsedLockfile, ParseError> {
     // Try to determine the dependency file format.
     let path = path.into();
@@ -79,10 +187,10 @@ pub fn parse_depfile(
     let mut lockfile_error = None;
     if maybe_lockfile || !maybe_manifest {
         // Parse lockfile content.
-        let packages = parse_lockfile_content(contents, parser);
+        let workspace = parse_lockfile_content(contents, format);
 
-        match packages {
-            Ok(packages) => return Ok(ParsedLockfile::new(path, format, packages)),
+        match workspace {
+            Ok(workspace) => return Ok(ParsedLockfile::from_workspace(path, format, workspace)),
             // Store error on failure.
             Err(err) => lockfile_error = Some(err),
         }
@@ -93,10 +201,13 @@ pub fn parse_depfile(
     // point to not be a valid/parseable lockfile but may parse as a manifest with
     // a non-standard name.
     #[cfg(feature = "generator")]
-    if let Some(generation_path) = _generation_path.filter(|_| !maybe_lockfile || maybe_manifest) {
+    if let Some(generation) = _generation.filter(|_| !maybe_lockfile || maybe_manifest) {
         if parser.generator().is_some() {
-            match generate_lockfile(&generation_path, &path, format, parser) {
+            match generate_lockfile(&generation, &path, format, parser) {
                 Ok(depfile) => return Ok(depfile),
+                Err(err) if is_limit_exceeded(&err) => {
+                    return Err(ParseError::GenerationLimitExceeded(err))
+                },
                 // Discard errors for unknown files.
                 // The error from the lockfile parser can be used instead.
                 Err(_) if !maybe_manifest => {},
@@ -108,22 +219,94 @@ pub fn parse_depfile(
     // Return the original lockfile parsing error.
     match lockfile_error {
         // Report parsing errors only for lockfiles.
-        Some(err) if !maybe_manifest => Err(err),
+        Some(err) if !maybe_manifest => Err(err.into()),
         _ => Err(ParseError::ManifestWithoutGeneration(path)),
     }
 }
 
+/// Parse an installed environment.
+///
+/// The `path` must point to the installed environment's directory on the
+/// filesystem, like `node_modules` or `site-packages`.
+///
+/// The `display_path` is used for format identification and display purposes.
+pub fn parse_installed(
+    path: &Path,
+    display_path: impl Into<String>,
+    format: Option<LockfileFormat>,
+) -> Result<ParsedLockfile, ParseError> {
+    let display_path = display_path.into();
+    let format = format.or_else(|| crate::get_path_format(path));
+
+    // Find the parser for the installed environment.
+    let (format, parser) = match format.and_then(|f| Some((f, f.installed_parser()?))) {
+        Some((format, parser)) => (format, parser),
+        None => return Err(ParseError::UnknownManifestFormat(display_path)),
+    };
+
+    let installed = parser
+        .parse_installed(path)
+        .with_context(|| format!("Failed to parse installed {format} packages"))?;
+
+    // Deduplicate packages installed in multiple locations.
+    let packages: BTreeSet<_> = installed.into_iter().map(|installed| installed.package).collect();
+
+    Ok(ParsedLockfile::new(display_path, format, filter_packages(packages)))
+}
+
+/// Parse the dependency metadata embedded in a binary.
+///
+/// The `display_path` is used for display purposes only, the format is
+/// identified based on the binary's content.
+///
+/// Packages of nested archives are returned as separate dependency files, with
+/// their path formatted as `<DISPLAY_PATH>!/<NESTED_PATH>`.
+pub fn parse_binary(
+    data: &[u8],
+    display_path: impl Into<String>,
+    format: Option<LockfileFormat>,
+) -> Result<Vec<ParsedLockfile>, ParseError> {
+    let display_path = display_path.into();
+
+    // Identify the binary format based on its content.
+    let format = format.or_else(|| {
+        LockfileFormat::iter()
+            .find(|format| format.binary_parser().is_some_and(|parser| parser.is_binary(data)))
+    });
+    let (format, parser) = match format.and_then(|f| Some((f, f.binary_parser()?))) {
+        Some((format, parser)) => (format, parser),
+        None => return Err(ParseError::UnknownManifestFormat(display_path)),
+    };
+
+    let embedded = parser.parse_binary(data).context("Failed to parse binary")?;
+
+    // Group packages by their nested archive, always reporting the binary itself.
+    let mut origins = BTreeMap::<_, BTreeSet<_>>::new();
+    origins.insert(String::new(), BTreeSet::new());
+    for embedded in embedded {
+        origins.entry(embedded.origin).or_default().insert(embedded.package);
+    }
+
+    let depfiles = origins.into_iter().map(|(origin, packages)| {
+        let path = match origin.is_empty() {
+            true => display_path.clone(),
+            false => format!("{display_path}!/{origin}"),
+        };
+        ParsedLockfile::new(path, format, filter_packages(packages))
+    });
+
+    Ok(depfiles.collect())
+}
+
 /// Attempt to get packages from an unknown lockfile type
 fn try_get_packages(path: impl Into<String>, contents: &str) -> Result<ParsedLockfile, ParseError> {
     let path = path.into();
     for format in LockfileFormat::iter() {
-        let parser = format.parser();
-        if let Some(packages) = parser.parse(contents).ok().filter(|pkgs| !pkgs.is_empty()) {
+        let workspace = parse_lockfile_content(contents, format).ok();
+        if let Some(workspace) = workspace.filter(|workspace| !workspace.packages.is_empty()) {
             log::info!("Identified lockfile type: {format}");
 
-            let packages = filter_packages(packages);
-
-            return Ok(ParsedLockfile::new(path, format, packages));
+            return Ok(ParsedLockfile::from_workspace(path, format, workspace));
         }
     }
 
@@ -133,7 +316,7 @@ fn try_get_packages(path: impl Into<String>, contents: &str) -> Result<ParsedLoc
 /// Generate a lockfile from a manifest path.
 #[cfg(feature = "generator")]
 fn generate_lockfile(
-    generation_path: &Path,
+    generation: &Generation,
     display_path: &str,
     format: LockfileFormat,
     parser: &dyn Parse,
@@ -147,31 +330,54 @@ fn generate_lockfile(
     eprintln!("Generating lockfile for manifest {display_path:?} using {format:?}…");
 
     // Generate a new lockfile.
-    let canonical_path = generation_path.canonicalize()?;
-    let generated_lockfile = generator.generate_lockfile(&canonical_path).context(
+    let canonical_path = generation.manifest_path.canonicalize()?;
+    let options = &generation.options;
+    let generated_lockfile = if generation.out_of_tree {
+        let scratch_dir = generation.scratch_dir.as_deref();
+        generator.generate_lockfile_out_of_tree(&canonical_path, scratch_dir, options)
+    } else {
+        generator.generate_lockfile(&canonical_path, options)
+    };
+    let generated_lockfile = generated_lockfile.context(
         "Lockfile generation failed! For details, see: \
          https://docs.phylum.io/cli/lockfile_generation",
     )?;
 
     // Parse the generated lockfile.
-    let packages = parse_lockfile_content(&generated_lockfile, parser)?;
+    let workspace = parse_lockfile_content(&generated_lockfile.contents, format)?;
 
-    Ok(ParsedLockfile::new(display_path, format, packages))
+    let mut parsed_lockfile = ParsedLockfile::from_workspace(display_path, format, workspace);
+    parsed_lockfile.provenance = Some(generated_lockfile.provenance);
+    Ok(parsed_lockfile)
+}
+
+/// Check if lockfile generation failed due to its time or resource limits.
+#[cfg(feature = "generator")]
+fn is_limit_exceeded(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<lockfile_generator::Error>()
+        .is_some_and(lockfile_generator::Error::is_limit_exceeded)
 }
 
 /// Attempt to parse a lockfile.
-fn parse_lockfile_content(
-    content: &str,
-    parser: &dyn Parse,
-) -> Result<Vec<PackageDescriptor>, ParseError> {
-    let packages = parser.parse(content).context("Failed to parse lockfile")?;
-    Ok(filter_packages(packages))
+///
+/// Workspace members are parsed in the same pass for formats supporting them.
+fn parse_lockfile_content(content: &str, format: LockfileFormat) -> anyhow::Result<Workspace> {
+    let workspace = match format.workspace_parser() {
+        Some(parser) => parser.parse_workspace(content),
+        None => format
+            .parser()
+            .parse(content)
+            .map(|packages| Workspace { packages, members: Vec::new() }),
+    };
+    workspace.context("Failed to parse lockfile")
 }
 
 /// Filter packages for submission.
-fn filter_packages(mut packages: Vec<Package>) -> Vec<PackageDescriptor> {
+pub(crate) fn filter_packages(
+    packages: impl IntoIterator<Item = Package>,
+) -> Vec<PackageDescriptor> {
     packages
-        .drain(..)
+        .into_iter()
         .filter_map(|package| {
             // Check if package should be submitted based on version format.
             let version = match package.version {
@@ -249,4 +455,41 @@ mod tests {
             assert_eq!(parsed.format, expected_format, "{path}");
         }
     }
+
+    #[test]
+    fn installed_environment() {
+        let path = Path::new("../tests/fixtures/installed/node_modules");
+        let parsed = parse_installed(path, "node_modules", None).unwrap();
+        assert_eq!(parsed.format, LockfileFormat::NodeModules);
+        assert_eq!(parsed.packages.len(), 4);
+
+        // Installed formats must be specified when they cannot be identified.
+        let path = Path::new("../tests/fixtures/installed/vendor");
+        let err = parse_installed(path, "vendor", None).unwrap_err();
+        assert!(matches!(err, ParseError::UnknownManifestFormat(_)));
+
+        let parsed = parse_installed(path, "vendor", Some(LockfileFormat::GoVendor)).unwrap();
+        assert_eq!(parsed.packages.len(), 2);
+    }
+
+    #[test]
+    fn workspace_members() {
+        let path = "../tests/fixtures/workspace/package-lock.json";
+        let contents = fs::read_to_string(path).unwrap();
+        let parsed = parse_depfile(&contents, path, None, None).unwrap();
+        assert_eq!(parsed.packages.len(), 5);
+
+        let members: Vec<_> = parsed
+            .workspace_members
+            .iter()
+            .map(|member| (member.path.as_str(), member.packages.len()))
+            .collect();
+        assert_eq!(members, vec![(".", 1), ("packages/a", 1), ("packages/b", 3)]);
+
+        // Lockfiles without workspaces have no members.
+        let path = "../tests/fixtures/pnpm-lock.yaml";
+        let contents = fs::read_to_string(path).unwrap();
+        let parsed = parse_depfile(&contents, path, None, None).unwrap();
+        assert!(parsed.workspace_members.is_empty());
+    }
 }
@@ -0,0 +1,48 @@
+//! JavaScript bindings for WebAssembly targets.
+//!
+//! Without filesystem access, only in-memory dependency file contents can be
+//! parsed. Directory discovery, installed environments and lockfile
+//! generation are unavailable.
+
+use serde::Serialize;
+use serde_wasm_bindgen::Serializer;
+use wasm_bindgen::prelude::*;
+
+use crate::LockfileFormat;
+
+/// Parse a dependency file's contents.
+///
+/// The `path` is used for format identification and display purposes. If
+/// `format` is omitted, it is detected from the `path`, falling back to trying
+/// all supported formats.
+///
+/// Returns the parsed lockfile with its `path`, `format` and `packages`.
+#[wasm_bindgen(js_name = parseDepfile)]
+pub fn parse_depfile(
+    contents: &str,
+    path: &str,
+    format: Option<String>,
+) -> Result<JsValue, JsError> {
+    let format = format.map(|format| parse_format(&format)).transpose()?;
+    let lockfile = crate::parse_depfile(contents, path, format, None)?;
+    Ok(lockfile.serialize(&Serializer::json_compatible())?)
+}
+
+/// Identify a dependency file's format based on its path.
+///
+/// Returns `undefined` if no supported format could be identified.
+#[wasm_bindgen(js_name = getDepfilePathFormat)]
+pub fn get_depfile_path_format(path: &str) -> Option<String> {
+    crate::get_depfile_path_format(path).map(|format| format.to_string())
+}
+
+/// Get the names of all supported dependency file formats.
+#[wasm_bindgen(js_name = lockfileFormats)]
+pub fn lockfile_formats() -> Vec<String> {
+    LockfileFormat::iter().map(|format| format.to_string()).collect()
+}
+
+/// Parse a format name, as accepted by the CLI's `--type` option.
+fn parse_format(format: &str) -> Result<LockfileFormat, JsError> {
+    format.parse().map_err(|_| JsError::new(&format!("Unsupported format {format:?}")))
+}
@@ -0,0 +1,72 @@
+//! WebAssembly binding tests.
+//!
+//! Run with `wasm-bindgen-test-runner` configured as the target's runner:
+//!
+//! ```sh
+//! CARGO_TARGET_WASM32_UNKNOWN_UNKNOWN_RUNNER=wasm-bindgen-test-runner \
+//!     cargo test -p phylum_lockfile --target wasm32-unknown-unknown --test wasm
+//! ```
+
+#![cfg(target_arch = "wasm32")]
+
+use phylum_lockfile::wasm::{get_depfile_path_format, lockfile_formats, parse_depfile};
+use phylum_lockfile::LockfileFormat;
+use serde_json::Value;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+const CARGO_LOCK: &str = include_str!("../../tests/fixtures/Cargo_v3.lock");
+
+fn parse(contents: &str, path: &str, format: Option<&str>) -> Value {
+    let Ok(lockfile) = parse_depfile(contents, path, format.map(String::from)) else {
+        panic!("failed to parse {path:?}");
+    };
+    serde_wasm_bindgen::from_value(lockfile).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn parse_detected_format() {
+    let lockfile = parse(CARGO_LOCK, "Cargo.lock", None);
+
+    assert_eq!(lockfile["path"], "Cargo.lock");
+    assert_eq!(lockfile["format"], "cargo");
+
+    let packages = lockfile["packages"].as_array().unwrap();
+    assert_eq!(packages.len(), 530);
+    assert_eq!(packages[0]["name"], "Inflector");
+    assert_eq!(packages[0]["version"], "0.11.4");
+    assert_eq!(packages[0]["type"], "cargo");
+}
+
+#[wasm_bindgen_test]
+fn parse_explicit_format() {
+    let lockfile = parse(CARGO_LOCK, "uploaded.txt", Some("cargo"));
+    assert_eq!(lockfile["format"], "cargo");
+    assert_eq!(lockfile["packages"].as_array().unwrap().len(), 530);
+}
+
+#[wasm_bindgen_test]
+fn parse_fallback_format() {
+    let lockfile = parse(CARGO_LOCK, "uploaded.txt", None);
+    assert_eq!(lockfile["format"], "cargo");
+}
+
+#[wasm_bindgen_test]
+fn parse_errors() {
+    assert!(parse_depfile(CARGO_LOCK, "Cargo.lock", Some("invalid".into())).is_err());
+    assert!(parse_depfile("invalid", "package-lock.json", None).is_err());
+}
+
+#[wasm_bindgen_test]
+fn depfile_path_format() {
+    assert_eq!(get_depfile_path_format("package-lock.json").as_deref(), Some("npm"));
+    assert_eq!(get_depfile_path_format("a/b/requirements.txt").as_deref(), Some("pip"));
+    assert_eq!(get_depfile_path_format("README.md"), None);
+}
+
+#[wasm_bindgen_test]
+fn formats() {
+    let formats = lockfile_formats();
+    assert_eq!(formats.len(), LockfileFormat::iter().count());
+    assert_eq!(formats[0], "npm");
+    assert!(formats.iter().all(|format| format.parse::<LockfileFormat>().is_ok()));
+}
@@ -4,7 +4,7 @@ use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
-use crate::{Error, Generator, Result};
+use crate::{offline, Error, Generator, Result};
 
 pub struct Bundler;
 
@@ -26,6 +26,18 @@ impl Generator for Bundler {
         "Bundler"
     }
 
+    fn environment_prefixes(&self) -> &'static [&'static str] {
+        &["BUNDLE_", "GEM_", "RUBYOPT"]
+    }
+
+    fn configure_offline(&self, command: &mut Command) {
+        command.arg("--local");
+    }
+
+    fn missing_cache_entry(&self, line: &str) -> Option<String> {
+        offline::text_between(line, "Could not find gem '", "'")
+    }
+
     fn check_prerequisites(&self, manifest_path: &Path) -> Result<()> {
         if manifest_path.file_name() != Some(OsStr::new("Gemfile")) {
             Err(Error::InvalidManifest(manifest_path.to_path_buf()))
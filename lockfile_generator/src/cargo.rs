@@ -5,7 +5,7 @@ use std::process::Command;
 
 use serde::Deserialize;
 
-use crate::{Error, Generator, Result};
+use crate::{offline, Error, Generator, Result};
 
 pub struct Cargo;
 
@@ -34,6 +34,10 @@ impl Generator for Cargo {
         Ok(workspace_root.join("Cargo.lock"))
     }
 
+    fn locates_lockfile_with_tool(&self) -> bool {
+        true
+    }
+
     fn command(&self, manifest_path: &Path) -> Command {
         let mut command = Command::new("cargo");
         command.arg("generate-lockfile").arg("--manifest-path").arg(manifest_path);
@@ -43,6 +47,20 @@ impl Generator for Cargo {
     fn tool(&self) -> &'static str {
         "Cargo"
     }
+
+    fn environment_prefixes(&self) -> &'static [&'static str] {
+        &["CARGO_", "RUSTUP_TOOLCHAIN"]
+    }
+
+    fn configure_offline(&self, command: &mut Command) {
+        command.arg("--offline");
+    }
+
+    fn missing_cache_entry(&self, line: &str) -> Option<String> {
+        offline::text_between(line, "no matching package named `", "`").or_else(|| {
+            offline::text_between(line, "failed to select a version for the requirement `", "`")
+        })
+    }
 }
 
 /// Output of `cargo locate-project`.
@@ -1,5 +1,6 @@
 //! C# .NET ecosystem.
 
+use std::env;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
@@ -24,4 +25,32 @@ impl Generator for Dotnet {
     fn tool(&self) -> &'static str {
         ".NET"
     }
+
+    fn environment_prefixes(&self) -> &'static [&'static str] {
+        &["DOTNET_", "NUGET_"]
+    }
+
+    fn configure_offline(&self, command: &mut Command) {
+        // NuGet has no offline mode, but the global packages folder can be used as
+        // the only package source.
+        if let Some(packages_dir) = global_packages_dir() {
+            command.arg("--source").arg(packages_dir);
+        }
+    }
+
+    fn missing_cache_entry(&self, line: &str) -> Option<String> {
+        let (_, package) = line.split_once("Unable to find package ")?;
+        let package = package.split(". ").next()?.trim_end_matches('.').trim();
+        (!package.is_empty()).then(|| package.into())
+    }
+}
+
+/// Get NuGet's global packages folder.
+fn global_packages_dir() -> Option<PathBuf> {
+    if let Some(packages_dir) = env::var_os("NUGET_PACKAGES") {
+        return Some(packages_dir.into());
+    }
+
+    let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"))?;
+    Some(PathBuf::from(home).join(".nuget").join("packages"))
 }
@@ -26,6 +26,37 @@ impl Generator for Go {
         "Go"
     }
 
+    fn version_command(&self, _manifest_path: &Path) -> Command {
+        let mut command = Command::new("go");
+        command.arg("version");
+        command
+    }
+
+    fn environment_prefixes(&self) -> &'static [&'static str] {
+        &[
+            "GOFLAGS",
+            "GOPROXY",
+            "GOPRIVATE",
+            "GONOPROXY",
+            "GONOSUMDB",
+            "GOSUMDB",
+            "GOTOOLCHAIN",
+            "GOINSECURE",
+            "GO111MODULE",
+        ]
+    }
+
+    fn configure_offline(&self, command: &mut Command) {
+        command.env("GOFLAGS", "-mod=mod");
+        command.env("GOPROXY", "off");
+    }
+
+    fn missing_cache_entry(&self, line: &str) -> Option<String> {
+        let line = line.strip_prefix("go: ").unwrap_or(line);
+        let (module, _) = line.split_once(": module lookup disabled by GOPROXY=off")?;
+        Some(module.trim().into())
+    }
+
     fn check_prerequisites(&self, manifest_path: &Path) -> Result<()> {
         if manifest_path.file_name() != Some(OsStr::new("go.mod")) {
             Err(Error::InvalidManifest(manifest_path.to_path_buf()))
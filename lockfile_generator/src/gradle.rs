@@ -8,7 +8,9 @@ use std::process::{Command, Stdio};
 
 use tempfile::NamedTempFile;
 
-use crate::{Error, FileRelocator, Generator, Result};
+use crate::{
+    offline, provenance, Error, FileRelocator, GeneratedLockfile, Generator, Options, Result,
+};
 
 pub struct Gradle;
 
@@ -30,14 +32,26 @@ impl Generator for Gradle {
     }
 
     fn command(&self, _manifest_path: &Path) -> Command {
-        // NOTE: We use a custom command to pass the init script.
-        unreachable!()
+        // NOTE: The init script is only created during lockfile generation.
+        gradle_command("<init script>")
     }
 
     fn tool(&self) -> &'static str {
         "Gradle"
     }
 
+    fn environment_prefixes(&self) -> &'static [&'static str] {
+        &["GRADLE_", "ORG_GRADLE_PROJECT_", "JAVA_HOME", "JAVA_TOOL_OPTIONS"]
+    }
+
+    fn configure_offline(&self, command: &mut Command) {
+        command.arg("--offline");
+    }
+
+    fn missing_cache_entry(&self, line: &str) -> Option<String> {
+        offline::text_between(line, "No cached version of ", " available for offline mode")
+    }
+
     fn check_prerequisites(&self, manifest_path: &Path) -> Result<()> {
         if manifest_path.file_name() == Some(OsStr::new("build.gradle"))
             || manifest_path.file_name() == Some(OsStr::new("build.gradle.kts"))
@@ -48,7 +62,11 @@ impl Generator for Gradle {
         }
     }
 
-    fn generate_lockfile(&self, manifest_path: &Path) -> Result<String> {
+    fn generate_lockfile(
+        &self,
+        manifest_path: &Path,
+        options: &Options,
+    ) -> Result<GeneratedLockfile> {
         self.check_prerequisites(manifest_path)?;
 
         let canonicalized = dunce::canonicalize(manifest_path)?;
@@ -69,21 +87,16 @@ impl Generator for Gradle {
         let init_path = init_file.path().to_string_lossy();
 
         // Generate lockfile at the target location.
-        let mut command = Command::new("gradle");
-        command.args(["dependencies", "--init-script", &init_path, "--write-locks"]);
+        let mut command = gradle_command(&init_path);
+        offline::configure(self, &mut command, options.offline);
         command.current_dir(project_path);
         command.stdin(Stdio::null());
-        command.stdout(Stdio::null());
 
-        // Provide better error message, including the failed program's name.
-        let output = command.output().map_err(|err| {
-            let program = format!("{:?}", command.get_program());
-            Error::ProcessCreation(program, self.tool().to_string(), err)
-        })?;
+        let (output, provenance) = provenance::run(self, &canonicalized, &mut command, options)?;
 
         // Ensure generation was successful.
         if !output.status.success() {
-            return Err(Error::NonZeroExit(output));
+            return Err(offline::exit_error(self, output, options.offline));
         }
 
         // Ensure lockfile was created.
@@ -93,11 +106,18 @@ impl Generator for Gradle {
         }
 
         // Read lockfile contents.
-        let lockfile = fs::read_to_string(&lockfile_path)?;
+        let contents = fs::read_to_string(&lockfile_path)?;
 
         // Cleanup lockfile.
         fs::remove_file(lockfile_path)?;
 
-        Ok(lockfile)
+        Ok(GeneratedLockfile { contents, provenance })
     }
 }
+
+/// Gradle command for writing lockfiles using the init script at `init_path`.
+fn gradle_command(init_path: &str) -> Command {
+    let mut command = Command::new("gradle");
+    command.args(["dependencies", "--init-script", init_path, "--write-locks"]);
+    command
+}
@@ -0,0 +1,330 @@
+//! Crash-safe journal for files relocated during lockfile generation.
+//!
+//! Every file moved out of the way by a [`FileRelocator`] is recorded in the
+//! journal directory before it is moved. If the generating process is killed
+//! before the file could be restored, the journal entry is used to restore it
+//! later.
+//!
+//! Journaling is disabled until a directory is configured with
+//! [`set_journal_dir`].
+//!
+//! [`FileRelocator`]: crate::FileRelocator
+
+use std::ffi::OsStr;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+#[cfg(unix)]
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+
+/// Directory storing the journal entries.
+static JOURNAL_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Journal entries recorded by the current process.
+///
+/// Entries are locked for as long as their file is open, which allows other
+/// processes to detect abandoned entries, even across PID namespaces.
+static ENTRIES: Mutex<Vec<Entry>> = Mutex::new(Vec::new());
+
+/// Set the directory used for journaling file relocations.
+///
+/// Returns `false` if the journal directory was already set.
+pub fn set_journal_dir(dir: impl Into<PathBuf>) -> bool {
+    JOURNAL_DIR.set(dir.into()).is_ok()
+}
+
+/// Get the directory used for journaling file relocations.
+pub fn journal_dir() -> Option<&'static Path> {
+    JOURNAL_DIR.get().map(PathBuf::as_path)
+}
+
+/// File moved to a backup location during lockfile generation.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Relocation {
+    /// Original location of the file.
+    pub original_path: PathBuf,
+    /// Backup location of the file.
+    pub backup_path: PathBuf,
+}
+
+/// Journal entry of the current process.
+struct Entry {
+    path: PathBuf,
+    relocation: Relocation,
+    /// Open entry file, holding its lock.
+    _file: File,
+}
+
+/// Restore all files relocated by processes which are no longer running.
+///
+/// Returns all restored relocations.
+pub fn recover() -> Result<Vec<Relocation>> {
+    match journal_dir() {
+        Some(dir) => restore_entries(dir, false),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Restore all journaled files, including ones of running processes.
+///
+/// Returns all restored relocations.
+pub fn recover_all() -> Result<Vec<Relocation>> {
+    match journal_dir() {
+        Some(dir) => restore_entries(dir, true),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Restore all files relocated by the current process.
+///
+/// This is intended to be called from signal handlers, before terminating
+/// the process.
+pub fn restore_process() -> Result<Vec<Relocation>> {
+    let entries = std::mem::take(&mut *ENTRIES.lock().unwrap());
+
+    let mut restored = Vec::new();
+    for entry in entries {
+        if restore(&entry.relocation)? {
+            restored.push(entry.relocation);
+        }
+        fs::remove_file(&entry.path)?;
+    }
+
+    Ok(restored)
+}
+
+/// Record a relocation in the journal.
+///
+/// The entry is persisted to disk before returning, so the relocation can
+/// safely be performed afterwards. It stays locked until it is removed with
+/// [`remove`] or the current process exits.
+///
+/// Returns the path of the journal entry.
+pub(crate) fn record(relocation: &Relocation) -> Result<Option<PathBuf>> {
+    let dir = match journal_dir() {
+        Some(dir) => dir,
+        None => return Ok(None),
+    };
+
+    let (path, file) = write_entry(dir, relocation)?;
+    let entry = Entry { path: path.clone(), relocation: relocation.clone(), _file: file };
+    ENTRIES.lock().unwrap().push(entry);
+
+    Ok(Some(path))
+}
+
+/// Remove a journal entry recorded by the current process.
+pub(crate) fn remove(path: &Path) -> Result<()> {
+    // Release the lock only after removal, so the entry is never recovered.
+    let result = fs::remove_file(path);
+    ENTRIES.lock().unwrap().retain(|entry| entry.path != path);
+    Ok(result?)
+}
+
+/// Write a new locked journal entry to `dir`.
+fn write_entry(dir: &Path, relocation: &Relocation) -> Result<(PathBuf, File)> {
+    fs::create_dir_all(dir)?;
+
+    // Write to a uniquely named temporary file first, so other processes never
+    // observe incomplete or unlocked entries.
+    let mut tmp_file =
+        tempfile::Builder::new().prefix("relocation-").suffix(".tmp").tempfile_in(dir)?;
+    try_lock(tmp_file.as_file())?;
+    tmp_file.write_all(&serde_json::to_vec(relocation)?)?;
+    tmp_file.as_file().sync_all()?;
+
+    let path = tmp_file.path().with_extension("json");
+    let file = tmp_file.persist(&path).map_err(|err| err.error)?;
+
+    Ok((path, file))
+}
+
+/// Restore all relocations in `dir`.
+///
+/// Unless `force` is set, entries which are still locked by their process are
+/// skipped.
+fn restore_entries(dir: &Path, force: bool) -> Result<Vec<Relocation>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut restored = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension() != Some(OsStr::new("json")) {
+            continue;
+        }
+
+        // Skip entries removed since listing the directory.
+        let mut file = match File::open(&path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err.into()),
+        };
+
+        // Keep the lock while restoring, so no other process restores it too.
+        if !force && !try_lock(&file)? {
+            continue;
+        }
+
+        // Entries are synced before relocating, so an invalid entry never
+        // describes a completed relocation.
+        let mut content = Vec::new();
+        file.read_to_end(&mut content)?;
+        let relocation = match serde_json::from_slice(&content) {
+            Ok(relocation) => relocation,
+            Err(_) => {
+                fs::remove_file(&path)?;
+                continue;
+            },
+        };
+
+        // Restore the backup, unless it was restored before the entry was
+        // removed.
+        if restore(&relocation)? {
+            restored.push(relocation);
+        }
+
+        fs::remove_file(&path)?;
+    }
+
+    Ok(restored)
+}
+
+/// Move a relocated file back to its original location.
+///
+/// Returns `false` if there was no backup to restore.
+pub(crate) fn restore(relocation: &Relocation) -> Result<bool> {
+    match fs::rename(&relocation.backup_path, &relocation.original_path) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Try to lock a journal entry.
+///
+/// Returns `false` if the entry is already locked. Locks are released
+/// automatically once all handles of the file are closed, which includes the
+/// termination of the process holding them.
+#[cfg(unix)]
+fn try_lock(file: &File) -> io::Result<bool> {
+    // SAFETY: The file descriptor is valid for the lifetime of `file`.
+    let result = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) };
+    if result == 0 {
+        return Ok(true);
+    }
+
+    let err = io::Error::last_os_error();
+    match err.kind() {
+        io::ErrorKind::WouldBlock => Ok(false),
+        _ => Err(err),
+    }
+}
+
+/// Try to lock a journal entry.
+///
+/// Without a reliable way to lock entries, all of them are assumed to be
+/// locked by a running process.
+#[cfg(not(unix))]
+fn try_lock(_file: &File) -> io::Result<bool> {
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn restore_unlocked_entries() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let journal = tempdir.path().join("journal");
+
+        // Relocate lockfile and write a generated lockfile in its place.
+        let original_path = tempdir.path().join("package-lock.json");
+        let backup_path = tempdir.path().join("package-lock.json.phylum_bak");
+        fs::write(&backup_path, "original").unwrap();
+        fs::write(&original_path, "generated").unwrap();
+
+        let relocation = Relocation { original_path: original_path.clone(), backup_path };
+        let (_, file) = write_entry(&journal, &relocation).unwrap();
+
+        // Ensure entries are skipped while they're locked.
+        let restored = restore_entries(&journal, false).unwrap();
+        assert!(restored.is_empty());
+        assert_eq!(fs::read_to_string(&original_path).unwrap(), "generated");
+
+        // Ensure abandoned entries are restored and removed.
+        drop(file);
+        let restored = restore_entries(&journal, false).unwrap();
+        assert_eq!(restored, [relocation]);
+        assert_eq!(fs::read_to_string(&original_path).unwrap(), "original");
+        assert_eq!(fs::read_dir(&journal).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn force_restore_locked_entries() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let journal = tempdir.path().join("journal");
+
+        let original_path = tempdir.path().join("yarn.lock");
+        let backup_path = tempdir.path().join("yarn.lock.phylum_bak");
+        fs::write(&backup_path, "original").unwrap();
+
+        let relocation = Relocation { original_path: original_path.clone(), backup_path };
+        let (_path, _file) = write_entry(&journal, &relocation).unwrap();
+
+        let restored = restore_entries(&journal, true).unwrap();
+        assert_eq!(restored, [relocation]);
+        assert_eq!(fs::read_to_string(&original_path).unwrap(), "original");
+    }
+
+    #[test]
+    fn remove_stale_entries() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let journal = tempdir.path().join("journal");
+
+        // Write entry whose backup was already restored.
+        let original_path = tempdir.path().join("Cargo.lock");
+        let backup_path = tempdir.path().join("Cargo.lock.phylum_bak");
+        fs::write(&original_path, "original").unwrap();
+        let relocation = Relocation { original_path: original_path.clone(), backup_path };
+        write_entry(&journal, &relocation).unwrap();
+
+        // Write incomplete entry.
+        fs::write(journal.join("relocation-invalid.json"), "{").unwrap();
+
+        let restored = restore_entries(&journal, true).unwrap();
+        assert!(restored.is_empty());
+        assert_eq!(fs::read_to_string(&original_path).unwrap(), "original");
+        assert_eq!(fs::read_dir(&journal).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn missing_journal() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let restored = restore_entries(&tempdir.path().join("journal"), true).unwrap();
+        assert!(restored.is_empty());
+    }
+
+    #[test]
+    fn unique_entry_names() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let relocation = Relocation {
+            original_path: tempdir.path().join("go.sum"),
+            backup_path: tempdir.path().join("go.sum.phylum_bak"),
+        };
+
+        let (first, _) = write_entry(tempdir.path(), &relocation).unwrap();
+        let (second, _) = write_entry(tempdir.path(), &relocation).unwrap();
+        assert_ne!(first, second);
+    }
+}
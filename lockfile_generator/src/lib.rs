@@ -1,25 +1,36 @@
-use std::ffi::OsString;
 use std::fmt::{self, Display, Formatter};
 #[cfg(unix)]
 use std::os::unix::process::ExitStatusExt;
 use std::path::{Path, PathBuf, StripPrefixError};
 use std::process::{Command, Output, Stdio};
 use std::string::FromUtf8Error;
+use std::time::Duration;
 use std::{fs, io};
 
+use serde::{Deserialize, Serialize};
 use serde_json::Error as JsonError;
 
+use crate::journal::Relocation;
+use crate::limits::{Cancellation, Limits, Resource};
+pub use crate::provenance::Provenance;
+use crate::scratch::ScratchCopy;
+
 pub mod bundler;
 pub mod cargo;
 pub mod dotnet;
 pub mod go;
 pub mod gradle;
+pub mod journal;
+pub mod limits;
 pub mod maven;
 pub mod npm;
+pub mod offline;
 pub mod pip;
 pub mod pipenv;
 pub mod pnpm;
 pub mod poetry;
+mod provenance;
+mod scratch;
 pub mod yarn;
 
 /// Lockfile generation.
@@ -33,6 +44,38 @@ pub trait Generator {
     /// Name of the tool used to generate the lockfile
     fn tool(&self) -> &'static str;
 
+    /// Command for querying the version of the tool.
+    ///
+    /// By default, this runs the program of [`Self::command`] with `--version`.
+    fn version_command(&self, manifest_path: &Path) -> Command {
+        let mut command = Command::new(self.command(manifest_path).get_program());
+        command.arg("--version");
+        command
+    }
+
+    /// Prefixes of environment variables affecting lockfile generation.
+    ///
+    /// Matching variables are recorded in the lockfile's [`Provenance`].
+    /// Prefixes must be uppercase and are matched case-insensitively.
+    fn environment_prefixes(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Configure the lockfile generation command to only use local package
+    /// caches.
+    ///
+    /// This is applied to the [`Self::command`] when [`Options::offline`] is
+    /// enabled. By default, the command is left unchanged.
+    fn configure_offline(&self, _command: &mut Command) {}
+
+    /// Get the cache entry which is reported as missing in a line of output.
+    ///
+    /// This is used to explain failures of [`Options::offline`] lockfile
+    /// generation.
+    fn missing_cache_entry(&self, _line: &str) -> Option<String> {
+        None
+    }
+
     /// List of files conflicting with lockfile generation.
     ///
     /// These files are temporarily renamed during lockfile generation to ensure
@@ -41,16 +84,74 @@ pub trait Generator {
         Ok(vec![self.lockfile_path(manifest_path)?])
     }
 
+    /// Root directory of the project the manifest belongs to.
+    ///
+    /// This directory must contain all files required for lockfile generation,
+    /// like the manifests of other workspace members. By default, this is the
+    /// directory of the lockfile.
+    fn project_root(&self, manifest_path: &Path) -> Result<PathBuf> {
+        let lockfile_path = self.lockfile_path(manifest_path)?;
+        lockfile_path
+            .parent()
+            .map(Path::to_path_buf)
+            .ok_or_else(|| Error::InvalidManifest(manifest_path.to_path_buf()))
+    }
+
     /// Verify that all the prerequisites for lockfile generation are met.
     fn check_prerequisites(&self, _manifest_path: &Path) -> Result<()> {
         Ok(())
     }
 
+    /// Whether [`Self::lockfile_path`] runs the tool to locate the lockfile.
+    fn locates_lockfile_with_tool(&self) -> bool {
+        false
+    }
+
+    /// Explain how the lockfile for a project would be generated.
+    ///
+    /// This never runs any command or modifies any files, since the project's
+    /// tool configuration could execute arbitrary code. So prerequisites are
+    /// not checked and lockfiles which can only be located by running the
+    /// tool are not resolved.
+    fn explain(&self, manifest_path: &Path, options: &Options) -> Result<Explanation> {
+        let canonicalized = dunce::canonicalize(manifest_path)?;
+        let working_dir = canonicalized
+            .parent()
+            .ok_or_else(|| Error::InvalidManifest(manifest_path.to_path_buf()))?
+            .to_path_buf();
+
+        let (project_root, conflicting_files) = if self.locates_lockfile_with_tool() {
+            (None, None)
+        } else {
+            // Only existing files are relocated.
+            let mut conflicting_files = self.conflicting_files(&canonicalized)?;
+            conflicting_files.retain(|path| path.exists());
+
+            (Some(self.project_root(&canonicalized)?), Some(conflicting_files))
+        };
+
+        let mut command = self.command(&canonicalized);
+        offline::configure(self, &mut command, options.offline);
+
+        Ok(Explanation {
+            tool: self.tool().into(),
+            command: provenance::command_line(&command),
+            offline: options.offline,
+            working_dir,
+            project_root,
+            conflicting_files,
+        })
+    }
+
     /// Generate the lockfile for a project.
     ///
     /// This will ignore all existing lockfiles and create a new lockfile based
     /// on the current project configuration.
-    fn generate_lockfile(&self, manifest_path: &Path) -> Result<String> {
+    fn generate_lockfile(
+        &self,
+        manifest_path: &Path,
+        options: &Options,
+    ) -> Result<GeneratedLockfile> {
         self.check_prerequisites(manifest_path)?;
 
         let canonicalized = dunce::canonicalize(manifest_path)?;
@@ -67,18 +168,15 @@ pub trait Generator {
 
         // Generate lockfile at the target location.
         let mut command = self.command(&canonicalized);
+        offline::configure(self, &mut command, options.offline);
         command.current_dir(project_path);
         command.stdin(Stdio::null());
 
-        // Provide better error message, including the failed program's name.
-        let output = command.output().map_err(|err| {
-            let program = format!("{:?}", command.get_program());
-            Error::ProcessCreation(program, self.tool().to_string(), err)
-        })?;
+        let (output, provenance) = provenance::run(self, &canonicalized, &mut command, options)?;
 
         // Ensure generation was successful.
         if !output.status.success() {
-            return Err(Error::NonZeroExit(output));
+            return Err(offline::exit_error(self, output, options.offline));
         }
 
         // Ensure lockfile was created.
@@ -88,29 +186,120 @@ pub trait Generator {
         }
 
         // Read lockfile contents.
-        let lockfile = fs::read_to_string(&lockfile_path)?;
+        let contents = fs::read_to_string(&lockfile_path)?;
 
         // Cleanup lockfile.
         fs::remove_file(lockfile_path)?;
 
-        Ok(lockfile)
+        Ok(GeneratedLockfile { contents, provenance })
     }
+
+    /// Generate the lockfile for a project in a scratch copy of the project.
+    ///
+    /// Contrary to [`Self::generate_lockfile`], this never modifies the project
+    /// directory. Everything below the [`Self::project_root`] is copied to a
+    /// temporary directory, except for common dependency or build directories
+    /// like `node_modules`. Existing lockfiles are removed from the copy.
+    ///
+    /// Copies in the `scratch_dir` are shared with all other generations using
+    /// the same directory, so every project is only copied once. Without a
+    /// `scratch_dir`, the project is copied to a new temporary directory.
+    fn generate_lockfile_out_of_tree(
+        &self,
+        manifest_path: &Path,
+        scratch_dir: Option<&Path>,
+        options: &Options,
+    ) -> Result<GeneratedLockfile> {
+        self.check_prerequisites(manifest_path)?;
+
+        let canonicalized = dunce::canonicalize(manifest_path)?;
+        let project_root = dunce::canonicalize(self.project_root(&canonicalized)?)?;
+
+        let scratch = ScratchCopy::acquire(&project_root, scratch_dir)?;
+
+        // Remove files interfering with lockfile generation from the copy.
+        for path in self.conflicting_files(&canonicalized)? {
+            let copied_path = match path.strip_prefix(&project_root) {
+                Ok(relative_path) => scratch.root().join(relative_path),
+                Err(_) => continue,
+            };
+
+            match fs::remove_file(copied_path) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+                _ => (),
+            }
+        }
+
+        // Generate the lockfile for the copied manifest.
+        let relative_manifest = canonicalized.strip_prefix(&project_root)?;
+        self.generate_lockfile(&scratch.root().join(relative_manifest), options)
+    }
+}
+
+/// Settings of lockfile generation processes.
+#[derive(Clone, Default, Debug)]
+pub struct Options {
+    /// Time and resource limits of each generation process.
+    pub limits: Limits,
+    /// Generate lockfiles exclusively from the local package caches.
+    pub offline: bool,
+    /// Cancellation of all generation processes using these options.
+    pub cancellation: Cancellation,
+}
+
+/// Lockfile created by a [`Generator`].
+#[derive(Clone, Debug)]
+pub struct GeneratedLockfile {
+    /// Contents of the generated lockfile.
+    pub contents: String,
+    /// Information about how the lockfile was generated.
+    pub provenance: Provenance,
+}
+
+/// Planned lockfile generation for a manifest.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Explanation {
+    /// Name of the tool used to generate the lockfile.
+    pub tool: String,
+    /// Command line used to generate the lockfile.
+    pub command: Vec<String>,
+    /// Whether the lockfile is generated only from local package caches.
+    pub offline: bool,
+    /// Working directory of the command.
+    pub working_dir: PathBuf,
+    /// Root directory copied for out-of-tree generation.
+    ///
+    /// This is `None` if it can only be determined by running the tool.
+    pub project_root: Option<PathBuf>,
+    /// Existing files moved out of the way during generation.
+    ///
+    /// This is `None` if they can only be determined by running the tool.
+    pub conflicting_files: Option<Vec<PathBuf>>,
 }
 
 /// Temporarily move a file to a different location.
 ///
 /// This utility moves a file to a backup location in the same directory and
 /// automatically restores it to its original location on drop.
+///
+/// Relocations are recorded in the [`journal`], allowing recovery if the
+/// process is terminated before the file could be restored.
 struct FileRelocator {
-    original_path: PathBuf,
-    backup_path: OsString,
+    relocation: Relocation,
+    journal_entry: Option<PathBuf>,
 }
 
 impl Drop for FileRelocator {
     fn drop(&mut self) {
         // We can't do anything about failure here, but the original file should stay
         // around allowing users to still resolve these issues manually.
-        let _ = fs::rename(&self.backup_path, &self.original_path);
+        //
+        // The journal entry is kept on failure, so recovery can be reattempted.
+        if journal::restore(&self.relocation).is_ok() {
+            if let Some(journal_entry) = &self.journal_entry {
+                let _ = journal::remove(journal_entry);
+            }
+        }
     }
 }
 
@@ -120,12 +309,22 @@ impl FileRelocator {
             return Ok(None);
         }
 
-        // Relocate the file.
         let mut backup_path = path.clone().into_os_string();
         backup_path.push(".phylum_bak");
-        fs::rename(&path, &backup_path)?;
+        let relocation = Relocation { original_path: path, backup_path: backup_path.into() };
 
-        Ok(Some(Self { original_path: path, backup_path }))
+        // Record the relocation before performing it.
+        let journal_entry = journal::record(&relocation)?;
+
+        // Relocate the file.
+        if let Err(err) = fs::rename(&relocation.original_path, &relocation.backup_path) {
+            if let Some(journal_entry) = journal_entry {
+                let _ = journal::remove(&journal_entry);
+            }
+            return Err(err.into());
+        }
+
+        Ok(Some(Self { relocation, journal_entry }))
     }
 }
 
@@ -140,10 +339,13 @@ pub enum Error {
     InvalidUtf8(#[from] FromUtf8Error),
     Io(#[from] io::Error),
     Json(#[from] JsonError),
+    MissingCacheEntries(Vec<String>, Output),
     NonZeroExit(Output),
     PipReportVersionMismatch(&'static str, String),
     ProcessCreation(String, String, #[source] io::Error),
+    ResourceLimit(Resource, Output),
     StripPrefix(#[from] StripPrefixError),
+    Timeout(Duration, Output),
     UnsupportedCommandVersion(&'static str, &'static str, String),
     NoLockfileGenerated,
 }
@@ -156,27 +358,19 @@ impl Display for Error {
             Self::InvalidUtf8(_) => write!(f, "utf8 parsing error"),
             Self::Io(_) => write!(f, "I/O error"),
             Self::Json(_) => write!(f, "json parsing error"),
+            Self::MissingCacheEntries(entries, output) => {
+                write!(f, "offline lockfile generation failed, missing from the package cache:")?;
+                for entry in entries {
+                    write!(f, "\n    - {entry}")?;
+                }
+                write_output(f, output)
+            },
             Self::NonZeroExit(output) => {
                 write!(f, "package manager quit unexpectedly (code: {:?}", output.status.code())?;
                 #[cfg(unix)]
                 write!(f, ", signal: {:?}", output.status.signal())?;
                 write!(f, ")")?;
-
-                if !output.stderr.is_empty() {
-                    write!(f, "\n    STDERR:")?;
-                    for line in String::from_utf8_lossy(&output.stderr).lines() {
-                        write!(f, "\n        {line}")?;
-                    }
-                }
-
-                if !output.stdout.is_empty() {
-                    write!(f, "\n    STDOUT:")?;
-                    for line in String::from_utf8_lossy(&output.stdout).lines() {
-                        write!(f, "\n        {line}")?;
-                    }
-                }
-
-                Ok(())
+                write_output(f, output)
             },
             Self::PipReportVersionMismatch(expected, version) => {
                 write!(f, "unsupported pip report version {version:?}, expected {expected:?}")
@@ -184,7 +378,15 @@ impl Display for Error {
             Self::ProcessCreation(command, tool_name, _) => {
                 write!(f, "failed to spawn command {command}: Is {tool_name} installed?")
             },
+            Self::ResourceLimit(resource, output) => {
+                write!(f, "package manager exceeded its {resource} limit")?;
+                write_output(f, output)
+            },
             Self::StripPrefix(_) => write!(f, "could not strip path prefix"),
+            Self::Timeout(duration, output) => {
+                write!(f, "package manager timed out after {}s", duration.as_secs())?;
+                write_output(f, output)
+            },
             Self::UnsupportedCommandVersion(command, expected_version, version) => write!(
                 f,
                 "unsupported {command:?} version {version:?}, expected {expected_version:?}"
@@ -193,3 +395,30 @@ impl Display for Error {
         }
     }
 }
+
+impl Error {
+    /// Check if lockfile generation was aborted due to its time or resource
+    /// limits.
+    pub fn is_limit_exceeded(&self) -> bool {
+        matches!(self, Self::ResourceLimit(..) | Self::Timeout(..))
+    }
+}
+
+/// Write the output of a failed process.
+fn write_output(f: &mut Formatter<'_>, output: &Output) -> fmt::Result {
+    if !output.stderr.is_empty() {
+        write!(f, "\n    STDERR:")?;
+        for line in String::from_utf8_lossy(&output.stderr).lines() {
+            write!(f, "\n        {line}")?;
+        }
+    }
+
+    if !output.stdout.is_empty() {
+        write!(f, "\n    STDOUT:")?;
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            write!(f, "\n        {line}")?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,675 @@
+//! Time and resource limits for lockfile generation processes.
+//!
+//! Resource limits are only supported on Unix platforms and apply to each
+//! generation process individually.
+//!
+//! The same limits are used for processes started by `phylum sandbox`, through
+//! [`status`].
+//!
+//! Running generation processes can be stopped through their
+//! [`Cancellation`].
+
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Read, Write};
+#[cfg(unix)]
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// Interval for checking if a process with a timeout has exited.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Number of bytes kept from the end of forwarded output.
+const FORWARDED_TAIL_LEN: usize = 8192;
+
+/// Lowercase error messages of common runtimes for exhausted resources.
+///
+/// Processes are checked first, since some runtimes report failed thread
+/// creation as an out of memory error.
+const EXHAUSTION_MESSAGES: [(Resource, &[&str]); 3] = [
+    (Resource::Processes, &[
+        "fork: retry",
+        "fork: resource temporarily unavailable",
+        "can't fork",
+        "cannot fork",
+        "spawn eagain",
+        "failed to create new os thread",
+        "unable to create native thread",
+    ]),
+    (Resource::OpenFiles, &["too many open files", "emfile"]),
+    (Resource::Memory, &[
+        "cannot allocate memory",
+        "memory allocation of",
+        "failed to allocate memory",
+        "out of memory",
+        "memoryerror",
+        "outofmemoryerror",
+        "std::bad_alloc",
+        "insufficient memory",
+    ]),
+];
+
+/// Cancellation of lockfile generation processes.
+///
+/// Clones share their state, so cancelling any clone cancels all processes
+/// started with one of them. Running processes are killed, while new ones fail
+/// to start.
+#[derive(Clone, Default, Debug)]
+pub struct Cancellation {
+    cancelled: Arc<AtomicBool>,
+    /// Process groups of all running generation processes.
+    groups: Arc<Mutex<Vec<u32>>>,
+}
+
+impl Cancellation {
+    /// Cancel lockfile generation.
+    ///
+    /// Running generation processes are killed immediately, together with all
+    /// processes they spawned.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+
+        for group in self.groups.lock().unwrap().iter() {
+            kill_group(*group);
+        }
+    }
+
+    /// Check if lockfile generation was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Time and resource limits of lockfile generation processes.
+#[derive(Serialize, Deserialize, Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum wall-clock time.
+    pub timeout: Option<Duration>,
+    /// Maximum CPU time.
+    pub cpu_time: Option<Duration>,
+    /// Maximum virtual memory in bytes.
+    pub memory: Option<u64>,
+    /// Maximum size of written files in bytes.
+    pub file_size: Option<u64>,
+    /// Maximum number of processes.
+    ///
+    /// This limit applies to all processes of the current user.
+    pub processes: Option<u64>,
+    /// Maximum number of open files.
+    pub open_files: Option<u64>,
+    /// Maximum combined size of STDOUT and STDERR in bytes.
+    pub output_size: Option<u64>,
+}
+
+impl Limits {
+    /// Check if no limit is configured.
+    pub fn is_unlimited(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Resource restricted by [`Limits`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Resource {
+    CpuTime,
+    Memory,
+    FileSize,
+    Processes,
+    OpenFiles,
+    OutputSize,
+}
+
+impl Display for Resource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CpuTime => write!(f, "CPU time"),
+            Self::Memory => write!(f, "memory"),
+            Self::FileSize => write!(f, "file size"),
+            Self::Processes => write!(f, "process"),
+            Self::OpenFiles => write!(f, "open files"),
+            Self::OutputSize => write!(f, "output size"),
+        }
+    }
+}
+
+/// Result of a process run with time and resource limits.
+pub enum Limited<T> {
+    /// Process exited by itself.
+    Completed(T),
+    /// Process was killed after exceeding the timeout.
+    TimedOut(T),
+    /// Process was killed after exceeding the output size limit.
+    OutputExceeded(T),
+}
+
+/// Run a command to completion, enforcing the time and resource limits.
+///
+/// STDOUT and STDERR are always captured. When the process was killed, only
+/// the output received before is returned.
+///
+/// On Unix platforms, the process is started in a new process group, so all
+/// processes it spawned are killed with it.
+pub(crate) fn output(
+    command: &mut Command,
+    limits: &Limits,
+    cancellation: &Cancellation,
+) -> io::Result<Limited<Output>> {
+    command.stdout(Stdio::piped());
+    command.stderr(Stdio::piped());
+    limit_resources(command, limits);
+    #[cfg(unix)]
+    command.process_group(0);
+
+    let mut child = spawn(command, cancellation)?;
+
+    // Read output in the background, so full pipes never block the process.
+    let output_limit = limits.output_size.map(OutputLimit::new);
+    let stdout = OutputReader::new(child.stdout.take(), None, output_limit.clone());
+    let stderr = OutputReader::new(child.stderr.take(), None, output_limit.clone());
+
+    // Register the process group, so cancellation kills it immediately.
+    cancellation.groups.lock().unwrap().push(child.id());
+    let result = wait(&mut child, limits, output_limit.as_deref(), cancellation);
+    cancellation.groups.lock().unwrap().retain(|group| *group != child.id());
+    let result = result?;
+
+    // Processes spawned by the child might still hold the pipes open, so only the
+    // output received so far is used for killed processes.
+    let output = |status, finish| {
+        let (stdout, stderr) = match finish {
+            true => (stdout.finish(), stderr.finish()),
+            false => (stdout.partial(), stderr.partial()),
+        };
+        Output { status, stdout, stderr }
+    };
+    Ok(match result {
+        Limited::Completed(status) => Limited::Completed(output(status, true)),
+        Limited::TimedOut(status) => Limited::TimedOut(output(status, false)),
+        Limited::OutputExceeded(status) => Limited::OutputExceeded(output(status, false)),
+    })
+}
+
+/// Run a command to completion, enforcing the time and resource limits.
+///
+/// STDOUT and STDERR are forwarded to the current process. They are only
+/// piped through the current process with an output size limit, or with limits
+/// that can only be detected from STDERR (see [`exceeded_resource`]).
+///
+/// The returned output contains only the end of STDERR, if it was piped.
+///
+/// The process is never cancelled. It stays in the current process group, so
+/// interactive commands keep access to the terminal, but processes it spawned
+/// are not killed with it.
+pub fn status(command: &mut Command, limits: &Limits) -> io::Result<Limited<Output>> {
+    let exhaustible =
+        limits.memory.is_some() || limits.processes.is_some() || limits.open_files.is_some();
+    if limits.output_size.is_some() {
+        command.stdout(Stdio::piped());
+    }
+    if limits.output_size.is_some() || exhaustible {
+        command.stderr(Stdio::piped());
+    }
+    limit_resources(command, limits);
+
+    let cancellation = Cancellation::default();
+    let mut child = spawn(command, &cancellation)?;
+
+    let output_limit = limits.output_size.map(OutputLimit::new);
+    let stdout = child.stdout.take().map(|stdout| {
+        OutputReader::new(Some(stdout), Some(Box::new(io::stdout())), output_limit.clone())
+    });
+    let stderr = child.stderr.take().map(|stderr| {
+        OutputReader::new(Some(stderr), Some(Box::new(io::stderr())), output_limit.clone())
+    });
+
+    let result = wait(&mut child, limits, output_limit.as_deref(), &cancellation)?;
+
+    // Forward remaining output of processes which exited by themselves.
+    let completed = matches!(result, Limited::Completed(_));
+    let stderr = match stderr {
+        Some(stderr) if completed => stderr.finish(),
+        Some(stderr) => stderr.partial(),
+        None => Vec::new(),
+    };
+    if completed {
+        stdout.map(OutputReader::finish);
+    }
+
+    let output = |status| Output { status, stdout: Vec::new(), stderr };
+    Ok(match result {
+        Limited::Completed(status) => Limited::Completed(output(status)),
+        Limited::TimedOut(status) => Limited::TimedOut(output(status)),
+        Limited::OutputExceeded(status) => Limited::OutputExceeded(output(status)),
+    })
+}
+
+/// Spawn a process, unless lockfile generation was cancelled.
+fn spawn(command: &mut Command, cancellation: &Cancellation) -> io::Result<Child> {
+    if cancellation.is_cancelled() {
+        return Err(cancelled_error());
+    }
+    command.spawn()
+}
+
+/// Wait for a process to exit, killing it once it exceeds its limits or
+/// lockfile generation is cancelled.
+fn wait(
+    child: &mut Child,
+    limits: &Limits,
+    output_limit: Option<&OutputLimit>,
+    cancellation: &Cancellation,
+) -> io::Result<Limited<ExitStatus>> {
+    let start = Instant::now();
+    loop {
+        let status = child.try_wait()?;
+
+        // Cancellation might have killed the process already.
+        if cancellation.is_cancelled() {
+            if status.is_none() {
+                kill(child)?;
+                child.wait()?;
+            }
+            return Err(cancelled_error());
+        }
+
+        if let Some(status) = status {
+            return Ok(Limited::Completed(status));
+        }
+
+        if output_limit.is_some_and(OutputLimit::is_exceeded) {
+            kill(child)?;
+            return Ok(Limited::OutputExceeded(child.wait()?));
+        }
+
+        let remaining = match limits.timeout.map(|timeout| timeout.checked_sub(start.elapsed())) {
+            Some(Some(remaining)) if !remaining.is_zero() => remaining,
+            Some(_) => {
+                kill(child)?;
+                return Ok(Limited::TimedOut(child.wait()?));
+            },
+            None => POLL_INTERVAL,
+        };
+        thread::sleep(remaining.min(POLL_INTERVAL));
+    }
+}
+
+/// Kill a process, including all processes of its process group if it leads
+/// one.
+fn kill(child: &mut Child) -> io::Result<()> {
+    if kill_group(child.id()) {
+        return Ok(());
+    }
+    child.kill()
+}
+
+/// Kill all processes of a process group.
+///
+/// Returns `false` if the process group does not exist.
+#[cfg(unix)]
+fn kill_group(group: u32) -> bool {
+    // SAFETY: `killpg` has no memory safety requirements.
+    unsafe { libc::killpg(group as libc::pid_t, libc::SIGKILL) == 0 }
+}
+
+/// Kill all processes of a process group.
+///
+/// Process groups are not supported on this platform.
+#[cfg(not(unix))]
+fn kill_group(_group: u32) -> bool {
+    false
+}
+
+/// Error for processes which were stopped by their [`Cancellation`].
+fn cancelled_error() -> io::Error {
+    io::Error::new(io::ErrorKind::Interrupted, "lockfile generation was cancelled")
+}
+
+/// Get the resource limit which caused the failure of a process.
+///
+/// Only the CPU time and file size limits terminate processes with a dedicated
+/// signal. Running out of memory, processes or open files is reported by the
+/// process itself, so these limits are only considered exceeded if the process
+/// failed with a matching error message on STDERR. Other crashes are not
+/// attributed to any limit.
+#[cfg(unix)]
+pub fn exceeded_resource(output: &Output, limits: &Limits) -> Option<Resource> {
+    match output.status.signal() {
+        Some(libc::SIGXCPU) if limits.cpu_time.is_some() => return Some(Resource::CpuTime),
+        Some(libc::SIGXFSZ) if limits.file_size.is_some() => return Some(Resource::FileSize),
+        _ => (),
+    }
+
+    if output.status.success() {
+        return None;
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr).to_lowercase();
+    EXHAUSTION_MESSAGES.into_iter().find_map(|(resource, messages)| {
+        let limited = match resource {
+            Resource::Memory => limits.memory.is_some(),
+            Resource::Processes => limits.processes.is_some(),
+            Resource::OpenFiles => limits.open_files.is_some(),
+            _ => false,
+        };
+        let reported = messages.iter().any(|message| stderr.contains(message));
+        (limited && reported).then_some(resource)
+    })
+}
+
+/// Get the resource limit which caused the failure of a process.
+#[cfg(not(unix))]
+pub fn exceeded_resource(_output: &Output, _limits: &Limits) -> Option<Resource> {
+    None
+}
+
+/// Apply resource limits to the spawned process.
+#[cfg(unix)]
+fn limit_resources(command: &mut Command, limits: &Limits) {
+    // Leave one second between soft and hard CPU limit, so the process receives
+    // `SIGXCPU` instead of `SIGKILL`.
+    let cpu_time = limits.cpu_time.map(|cpu_time| (cpu_time.as_secs().max(1), 1));
+    let rlimits = [
+        (libc::RLIMIT_CPU, cpu_time),
+        (libc::RLIMIT_AS, limits.memory.map(|memory| (memory, 0))),
+        (libc::RLIMIT_FSIZE, limits.file_size.map(|file_size| (file_size, 0))),
+        (libc::RLIMIT_NPROC, limits.processes.map(|processes| (processes, 0))),
+        (libc::RLIMIT_NOFILE, limits.open_files.map(|open_files| (open_files, 0))),
+    ];
+
+    if rlimits.iter().all(|(_, limit)| limit.is_none()) {
+        return;
+    }
+
+    // SAFETY: The closure only calls the async-signal-safe `getrlimit` and
+    // `setrlimit` functions, without allocating.
+    unsafe {
+        command.pre_exec(move || {
+            for (resource, limit) in rlimits {
+                let Some((soft, headroom)) = limit else { continue };
+
+                let mut rlimit = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+                if libc::getrlimit(resource, &mut rlimit) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+
+                // Never raise existing limits.
+                let soft = (soft as libc::rlim_t).min(rlimit.rlim_max);
+                let hard = soft.saturating_add(headroom as libc::rlim_t).min(rlimit.rlim_max);
+                let rlimit = libc::rlimit { rlim_cur: soft, rlim_max: hard };
+
+                if libc::setrlimit(resource, &rlimit) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+
+            Ok(())
+        });
+    }
+}
+
+/// Apply resource limits to the spawned process.
+///
+/// Resource limits are not supported on this platform.
+#[cfg(not(unix))]
+fn limit_resources(_command: &mut Command, _limits: &Limits) {}
+
+/// Output size limit shared between all output streams of a process.
+struct OutputLimit {
+    limit: u64,
+    received: AtomicU64,
+}
+
+impl OutputLimit {
+    fn new(limit: u64) -> Arc<Self> {
+        Arc::new(Self { limit, received: AtomicU64::new(0) })
+    }
+
+    /// Record received output.
+    ///
+    /// Returns the number of bytes which are still within the limit.
+    fn receive(&self, len: usize) -> usize {
+        let previous = self.received.fetch_add(len as u64, Ordering::Relaxed);
+        self.limit.saturating_sub(previous).min(len as u64) as usize
+    }
+
+    /// Check if more output was received than allowed.
+    fn is_exceeded(&self) -> bool {
+        self.received.load(Ordering::Relaxed) > self.limit
+    }
+}
+
+/// Background reader for the output of a process.
+///
+/// Output is either collected or forwarded to a writer, keeping only the end
+/// of forwarded output. Output exceeding the output limit is discarded.
+struct OutputReader {
+    buffer: Arc<Mutex<Vec<u8>>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl OutputReader {
+    fn new(
+        pipe: Option<impl Read + Send + 'static>,
+        mut writer: Option<Box<dyn Write + Send>>,
+        limit: Option<Arc<OutputLimit>>,
+    ) -> Self {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+
+        let thread = pipe.map(|mut pipe| {
+            let buffer = buffer.clone();
+            thread::spawn(move || {
+                let mut chunk = [0; 8192];
+                while let Ok(len @ 1..) = pipe.read(&mut chunk) {
+                    let len = limit.as_ref().map_or(len, |limit| limit.receive(len));
+                    if len == 0 {
+                        break;
+                    }
+
+                    let mut buffer = buffer.lock().unwrap();
+                    buffer.extend_from_slice(&chunk[..len]);
+
+                    if let Some(writer) = &mut writer {
+                        let _ = writer.write_all(&chunk[..len]);
+                        let _ = writer.flush();
+
+                        let excess = buffer.len().saturating_sub(FORWARDED_TAIL_LEN);
+                        buffer.drain(..excess);
+                    }
+                }
+            })
+        });
+
+        Self { buffer, thread }
+    }
+
+    /// Wait for the end of the output and return all of it.
+    fn finish(mut self) -> Vec<u8> {
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        self.partial()
+    }
+
+    /// Get all output received so far.
+    fn partial(&self) -> Vec<u8> {
+        self.buffer.lock().unwrap().clone()
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kill_after_timeout() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo started >&2; sleep 10"]);
+        let limits = Limits { timeout: Some(Duration::from_millis(500)), ..Limits::default() };
+
+        let start = Instant::now();
+        let output = output(&mut command, &limits, &Cancellation::default()).unwrap();
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        match output {
+            Limited::TimedOut(output) => assert_eq!(output.stderr, b"started\n"),
+            _ => panic!("process was not killed"),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn kill_spawned_processes() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "sleep 10 & echo $!; wait"]);
+        let limits = Limits { timeout: Some(Duration::from_millis(500)), ..Limits::default() };
+
+        let output = match output(&mut command, &limits, &Cancellation::default()).unwrap() {
+            Limited::TimedOut(output) => output,
+            _ => panic!("process was not killed"),
+        };
+
+        // Wait for the orphaned process to be reaped or left as zombie.
+        let pid = String::from_utf8(output.stdout).unwrap();
+        let stat_path = format!("/proc/{}/stat", pid.trim());
+        let is_dead = || {
+            let stat = std::fs::read_to_string(&stat_path);
+            stat.map_or(true, |stat| stat.contains(") Z ") || stat.contains(") X "))
+        };
+        let start = Instant::now();
+        while !is_dead() && start.elapsed() < Duration::from_secs(5) {
+            thread::sleep(POLL_INTERVAL);
+        }
+        assert!(is_dead());
+    }
+
+    #[test]
+    fn complete_before_timeout() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo done"]);
+        let limits = Limits { timeout: Some(Duration::from_secs(10)), ..Limits::default() };
+
+        match output(&mut command, &limits, &Cancellation::default()).unwrap() {
+            Limited::Completed(output) => assert_eq!(output.stdout, b"done\n"),
+            _ => panic!("process was killed"),
+        }
+    }
+
+    #[test]
+    fn kill_after_cancellation() {
+        let cancellation = Cancellation::default();
+        let cancel = cancellation.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(500));
+            cancel.cancel();
+        });
+
+        let mut command = Command::new("sh");
+        command.args(["-c", "sleep 10"]);
+
+        let start = Instant::now();
+        let err = output(&mut command, &Limits::default(), &cancellation).err().unwrap();
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(err.kind(), io::ErrorKind::Interrupted);
+
+        // Other generations are not affected.
+        let mut command = Command::new("true");
+        let output = output(&mut command, &Limits::default(), &Cancellation::default());
+        assert!(matches!(output, Ok(Limited::Completed(_))));
+    }
+
+    #[test]
+    fn file_size_limit() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let mut command = Command::new("sh");
+        command.args(["-c", "head -c 4096 /dev/zero > file"]);
+        command.current_dir(tempdir.path());
+        let limits = Limits { file_size: Some(1024), ..Limits::default() };
+
+        let output = match output(&mut command, &limits, &Cancellation::default()).unwrap() {
+            Limited::Completed(output) => output,
+            _ => panic!("process was killed"),
+        };
+
+        assert!(!output.status.success());
+    }
+
+    #[test]
+    fn output_size_limit() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "head -c 4096 /dev/zero; sleep 10"]);
+        let limits = Limits { output_size: Some(1024), ..Limits::default() };
+
+        let start = Instant::now();
+        let output = output(&mut command, &limits, &Cancellation::default()).unwrap();
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        match output {
+            Limited::OutputExceeded(output) => assert_eq!(output.stdout.len(), 1024),
+            _ => panic!("process was not killed"),
+        }
+    }
+
+    /// Run a shell script which must not be killed.
+    fn completed_output(script: &str, limits: &Limits) -> Output {
+        let mut command = Command::new("sh");
+        command.args(["-c", script]);
+        match output(&mut command, limits, &Cancellation::default()).unwrap() {
+            Limited::Completed(output) => output,
+            _ => panic!("process was killed"),
+        }
+    }
+
+    #[test]
+    fn crash_without_allocation_failure() {
+        let limits = Limits { memory: Some(1 << 30), ..Limits::default() };
+
+        let output = completed_output("kill -SEGV $$", &limits);
+
+        assert_eq!(output.status.signal(), Some(libc::SIGSEGV));
+        assert_eq!(exceeded_resource(&output, &limits), None);
+    }
+
+    #[test]
+    fn reported_resource_exhaustion() {
+        let limits = Limits { memory: Some(1 << 30), ..Limits::default() };
+        let script = "echo 'memory allocation of 4096 bytes failed' >&2; kill -ABRT $$";
+
+        let output = completed_output(script, &limits);
+        assert_eq!(exceeded_resource(&output, &limits), Some(Resource::Memory));
+
+        // Messages are ignored for resources without limits.
+        let output = completed_output("echo 'Too many open files' >&2; exit 1", &limits);
+        assert_eq!(exceeded_resource(&output, &limits), None);
+
+        let limits = Limits { open_files: Some(32), ..Limits::default() };
+        assert_eq!(exceeded_resource(&output, &limits), Some(Resource::OpenFiles));
+    }
+
+    #[test]
+    fn forwarded_output_tail() {
+        let mut forwarded = vec![0; 2 * FORWARDED_TAIL_LEN];
+        forwarded.extend_from_slice(b"Too many open files");
+        let pipe = io::Cursor::new(forwarded.clone());
+
+        let reader = OutputReader::new(Some(pipe), Some(Box::new(io::sink())), None);
+
+        assert_eq!(reader.finish(), forwarded[forwarded.len() - FORWARDED_TAIL_LEN..]);
+    }
+
+    #[test]
+    fn open_files_limit() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "ulimit -n"]);
+        let limits = Limits { open_files: Some(32), ..Limits::default() };
+
+        match output(&mut command, &limits, &Cancellation::default()).unwrap() {
+            Limited::Completed(output) => assert_eq!(output.stdout, b"32\n"),
+            _ => panic!("process was killed"),
+        }
+    }
+}
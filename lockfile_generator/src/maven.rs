@@ -3,7 +3,7 @@
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
-use crate::{Error, Generator, Result};
+use crate::{offline, Error, Generator, Result};
 
 pub struct Maven;
 
@@ -40,4 +40,16 @@ impl Generator for Maven {
     fn tool(&self) -> &'static str {
         "Maven"
     }
+
+    fn environment_prefixes(&self) -> &'static [&'static str] {
+        &["MAVEN_", "JAVA_HOME", "JAVA_TOOL_OPTIONS"]
+    }
+
+    fn configure_offline(&self, command: &mut Command) {
+        command.arg("--offline");
+    }
+
+    fn missing_cache_entry(&self, line: &str) -> Option<String> {
+        offline::text_between(line, "the artifact ", " has not been downloaded")
+    }
 }
@@ -8,7 +8,7 @@ use std::process::Command;
 use glob::Pattern;
 use serde::Deserialize;
 
-use crate::{Error, Generator, Result};
+use crate::{offline, Error, Generator, Result};
 
 /// Maximum upwards traversal when searching for a workspace root.
 const WORKSPACE_ROOT_RECURSION_LIMIT: usize = 16;
@@ -40,6 +40,21 @@ impl Generator for Npm {
         "npm"
     }
 
+    fn environment_prefixes(&self) -> &'static [&'static str] {
+        &["NPM_CONFIG_", "NODE_OPTIONS"]
+    }
+
+    fn configure_offline(&self, command: &mut Command) {
+        command.arg("--offline");
+    }
+
+    fn missing_cache_entry(&self, line: &str) -> Option<String> {
+        if !line.contains("only-if-cached") {
+            return None;
+        }
+        offline::text_between(line, "request to ", " failed")
+    }
+
     fn check_prerequisites(&self, manifest_path: &Path) -> Result<()> {
         if manifest_path.file_name() != Some(OsStr::new("package.json")) {
             Err(Error::InvalidManifest(manifest_path.to_path_buf()))
@@ -103,6 +118,7 @@ struct PackageJson {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Options;
 
     const NON_WORKSPACE_MANIFEST: &str = r#"{ "name": "test" }"#;
 
@@ -145,4 +161,29 @@ mod tests {
         let root = find_workspace_root(&manifest_path).unwrap();
         assert_eq!(root, tempdir.path().to_path_buf().canonicalize().unwrap());
     }
+
+    #[test]
+    fn explain_without_changes() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let root = dunce::canonicalize(tempdir.path()).unwrap();
+        let manifest = root.join("package.json");
+        let lockfile = root.join("package-lock.json");
+        fs::write(&manifest, NON_WORKSPACE_MANIFEST).unwrap();
+        fs::write(&lockfile, "{}").unwrap();
+
+        let explanation = Npm.explain(&manifest, &Options::default()).unwrap();
+
+        assert_eq!(explanation.command, [
+            "npm",
+            "install",
+            "--package-lock-only",
+            "--ignore-scripts"
+        ]);
+        assert_eq!(explanation.working_dir, root);
+        assert_eq!(explanation.project_root, Some(root.clone()));
+
+        // Ensure the existing lockfile was not relocated.
+        assert_eq!(fs::read_to_string(&lockfile).unwrap(), "{}");
+        assert_eq!(explanation.conflicting_files, Some(vec![lockfile]));
+    }
 }
@@ -0,0 +1,119 @@
+//! Offline lockfile generation.
+//!
+//! In offline mode, lockfiles are generated exclusively from the package
+//! caches of each tool. Offline mode is enabled with [`Options::offline`].
+//!
+//! [`Options::offline`]: crate::Options::offline
+
+use std::process::{Command, Output};
+
+use crate::{Error, Generator};
+
+/// Apply the generator's offline configuration to a command, if `offline` mode
+/// is enabled.
+pub(crate) fn configure<G>(generator: &G, command: &mut Command, offline: bool)
+where
+    G: Generator + ?Sized,
+{
+    if offline {
+        generator.configure_offline(command);
+    }
+}
+
+/// Get the error for a failed lockfile generation process.
+///
+/// In offline mode, the cache entries missing for lockfile generation are
+/// reported when they can be identified from the process output.
+pub(crate) fn exit_error<G>(generator: &G, output: Output, offline: bool) -> Error
+where
+    G: Generator + ?Sized,
+{
+    if !offline {
+        return Error::NonZeroExit(output);
+    }
+
+    let entries = missing_cache_entries(generator, &output);
+    if entries.is_empty() {
+        Error::NonZeroExit(output)
+    } else {
+        Error::MissingCacheEntries(entries, output)
+    }
+}
+
+/// Collect all missing cache entries reported in the output of a process.
+fn missing_cache_entries<G>(generator: &G, output: &Output) -> Vec<String>
+where
+    G: Generator + ?Sized,
+{
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let mut entries = Vec::new();
+    for line in stderr.lines().chain(stdout.lines()) {
+        if let Some(entry) = generator.missing_cache_entry(line) {
+            if !entries.contains(&entry) {
+                entries.push(entry);
+            }
+        }
+    }
+
+    entries
+}
+
+/// Get the text between the first occurrence of `start` and the following
+/// occurrence of `end` in a line.
+pub(crate) fn text_between(line: &str, start: &str, end: &str) -> Option<String> {
+    let (_, rest) = line.split_once(start)?;
+    let (text, _) = rest.split_once(end)?;
+    Some(text.trim().into()).filter(|text: &String| !text.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::ExitStatus;
+
+    use super::*;
+    use crate::cargo::Cargo;
+    use crate::gradle::Gradle;
+    use crate::npm::Npm;
+
+    fn output(stderr: &str) -> Output {
+        Output { status: ExitStatus::default(), stdout: Vec::new(), stderr: stderr.into() }
+    }
+
+    #[test]
+    fn npm_missing_entries() {
+        let output = output(
+            "npm error code ENOTCACHED\nnpm error request to https://registry.npmjs.org/left-pad \
+             failed: cache mode is 'only-if-cached' but no cached response is available.\n",
+        );
+
+        assert_eq!(missing_cache_entries(&Npm, &output), ["https://registry.npmjs.org/left-pad"]);
+    }
+
+    #[test]
+    fn cargo_missing_entries() {
+        let output = output(
+            "error: no matching package named `serde` found\nlocation searched: registry \
+             `crates-io`\nerror: no matching package named `serde` found\n",
+        );
+
+        assert_eq!(missing_cache_entries(&Cargo, &output), ["serde"]);
+    }
+
+    #[test]
+    fn gradle_missing_entries() {
+        let output = output(
+            "   > Could not resolve com.google.guava:guava:31.1-jre.\n      > No cached version \
+             of com.google.guava:guava:31.1-jre available for offline mode.\n",
+        );
+
+        assert_eq!(missing_cache_entries(&Gradle, &output), ["com.google.guava:guava:31.1-jre"]);
+    }
+
+    #[test]
+    fn unrelated_failure() {
+        let output = output("error: failed to parse manifest\n");
+        assert!(missing_cache_entries(&Cargo, &output).is_empty());
+    }
+}
@@ -6,7 +6,7 @@ use std::process::{Command, Stdio};
 
 use serde::Deserialize;
 
-use crate::{Error, Generator, Result};
+use crate::{offline, provenance, Error, GeneratedLockfile, Generator, Options, Result};
 
 pub struct Pip;
 
@@ -49,12 +49,48 @@ impl Generator for Pip {
         "pip"
     }
 
+    fn version_command(&self, _manifest_path: &Path) -> Command {
+        let mut command = Command::new("python3");
+        command.args(["-m", "pip", "--version"]);
+        command
+    }
+
+    fn environment_prefixes(&self) -> &'static [&'static str] {
+        &["PIP_", "PYTHONPATH", "VIRTUAL_ENV"]
+    }
+
+    fn configure_offline(&self, command: &mut Command) {
+        // Pip can't resolve packages from its HTTP cache, so packages must be
+        // provided by local archives using `--find-links`.
+        command.arg("--no-index");
+    }
+
+    fn missing_cache_entry(&self, line: &str) -> Option<String> {
+        missing_distribution(line)
+    }
+
+    fn conflicting_files(&self, _manifest_path: &Path) -> Result<Vec<PathBuf>> {
+        // NOTE: Pip never writes a lockfile, so no files can conflict.
+        Ok(Vec::new())
+    }
+
+    fn project_root(&self, manifest_path: &Path) -> Result<PathBuf> {
+        let project_path = manifest_path
+            .parent()
+            .ok_or_else(|| Error::InvalidManifest(manifest_path.to_path_buf()))?;
+        Ok(project_path.into())
+    }
+
     /// Generate virtual requirements.txt from dry-run output.
     ///
     /// Since the `pip --report` never writes any actual lockfile to the disk,
     /// we provide a custom method here which parses this output and transforms
     /// it into the locked requirements.txt format our lockfile parser expects.
-    fn generate_lockfile(&self, manifest_path: &Path) -> Result<String> {
+    fn generate_lockfile(
+        &self,
+        manifest_path: &Path,
+        options: &Options,
+    ) -> Result<GeneratedLockfile> {
         let canonicalized = dunce::canonicalize(manifest_path)?;
         let project_path = canonicalized
             .parent()
@@ -67,18 +103,15 @@ impl Generator for Pip {
         //
         // We still change directory here since it could impact pip's report generation.
         let mut command = self.command(&canonicalized);
+        offline::configure(self, &mut command, options.offline);
         command.current_dir(project_path);
         command.stdin(Stdio::null());
 
-        // Provide better error message, including the failed program's name.
-        let output = command.output().map_err(|err| {
-            let program = format!("{:?}", command.get_program());
-            Error::ProcessCreation(program, self.tool().to_string(), err)
-        })?;
+        let (output, provenance) = provenance::run(self, &canonicalized, &mut command, options)?;
 
         // Ensure generation was successful.
         if !output.status.success() {
-            return Err(Error::NonZeroExit(output));
+            return Err(offline::exit_error(self, output, options.offline));
         }
 
         // Parse pip install report STDOUT.
@@ -92,17 +125,17 @@ impl Generator for Pip {
         }
 
         // Create the virtual requirements.txt lockfile.
-        let mut lockfile = String::new();
+        let mut contents = String::new();
         for package in report.install {
             let name = package.metadata.name;
             if package.is_direct {
-                let _ = writeln!(lockfile, "{} @ {}", name, package.download_info.url);
+                let _ = writeln!(contents, "{} @ {}", name, package.download_info.url);
             } else {
-                let _ = writeln!(lockfile, "{}=={}", name, package.metadata.version);
+                let _ = writeln!(contents, "{}=={}", name, package.metadata.version);
             }
         }
 
-        Ok(lockfile)
+        Ok(GeneratedLockfile { contents, provenance })
     }
 }
 
@@ -144,6 +177,12 @@ fn check_pip_version(project_path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Get the requirement pip reports as unavailable in a line of output.
+pub(crate) fn missing_distribution(line: &str) -> Option<String> {
+    let (_, requirement) = line.split_once("No matching distribution found for ")?;
+    Some(requirement.trim().into()).filter(|requirement: &String| !requirement.is_empty())
+}
+
 /// Pip install report output.
 #[derive(Deserialize, Debug)]
 struct Report {
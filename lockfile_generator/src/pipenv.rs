@@ -4,7 +4,7 @@ use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
-use crate::{Error, Generator, Result};
+use crate::{pip, Error, Generator, Result};
 
 pub struct Pipenv;
 
@@ -26,6 +26,14 @@ impl Generator for Pipenv {
         "Pipenv"
     }
 
+    fn environment_prefixes(&self) -> &'static [&'static str] {
+        &["PIPENV_", "PIP_", "VIRTUAL_ENV"]
+    }
+
+    fn missing_cache_entry(&self, line: &str) -> Option<String> {
+        pip::missing_distribution(line)
+    }
+
     fn check_prerequisites(&self, manifest_path: &Path) -> Result<()> {
         if manifest_path.file_name() != Some(OsStr::new("Pipfile")) {
             Err(Error::InvalidManifest(manifest_path.to_path_buf()))
@@ -5,7 +5,7 @@ use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::{env, fs};
 
-use crate::{Error, Generator, Result};
+use crate::{offline, Error, Generator, Result};
 
 const WORKSPACE_MANIFEST_FILENAME: &str = "pnpm-workspace.yaml";
 const WORKSPACE_DIR_ENV_VAR: &str = "NPM_CONFIG_WORKSPACE_DIR";
@@ -44,6 +44,18 @@ impl Generator for Pnpm {
         "pnpm"
     }
 
+    fn environment_prefixes(&self) -> &'static [&'static str] {
+        &["NPM_CONFIG_", "PNPM_", "NODE_OPTIONS"]
+    }
+
+    fn configure_offline(&self, command: &mut Command) {
+        command.arg("--offline");
+    }
+
+    fn missing_cache_entry(&self, line: &str) -> Option<String> {
+        offline::text_between(line, "Failed to resolve ", " in package mirror")
+    }
+
     fn check_prerequisites(&self, manifest_path: &Path) -> Result<()> {
         if manifest_path.file_name() != Some(OsStr::new("package.json")) {
             Err(Error::InvalidManifest(manifest_path.to_path_buf()))
@@ -26,6 +26,10 @@ impl Generator for Poetry {
         "Poetry"
     }
 
+    fn environment_prefixes(&self) -> &'static [&'static str] {
+        &["POETRY_", "VIRTUAL_ENV"]
+    }
+
     fn check_prerequisites(&self, manifest_path: &Path) -> Result<()> {
         if manifest_path.file_name() != Some(OsStr::new("pyproject.toml")) {
             Err(Error::InvalidManifest(manifest_path.to_path_buf()))
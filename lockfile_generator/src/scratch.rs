@@ -0,0 +1,268 @@
+//! Scratch copies of projects for out-of-tree lockfile generation.
+//!
+//! Copies created in a shared scratch directory are reused by all lockfile
+//! generations for the same project, even across processes. Each copy is
+//! locked while it is in use, so generations within the same copy never run
+//! concurrently.
+
+use std::fs::{self, File, OpenOptions};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io;
+#[cfg(unix)]
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+
+use tempfile::TempDir;
+
+use crate::Result;
+
+/// Directories never copied into the scratch directory.
+///
+/// These contain VCS metadata, installed dependencies or build artifacts which
+/// are not necessary for lockfile generation.
+const EXCLUDED_DIRS: &[&str] =
+    &[".git", ".hg", ".svn", "node_modules", "target", ".venv", "__pycache__", ".gradle"];
+
+/// Scratch copy of a project, reserved for exclusive use.
+pub(crate) struct ScratchCopy {
+    /// Project root inside the copy.
+    root: PathBuf,
+    /// Lock of a copy in the shared scratch directory.
+    _lock: Option<File>,
+    /// Temporary directory of a copy which is not shared.
+    _tempdir: Option<TempDir>,
+}
+
+impl ScratchCopy {
+    /// Get a scratch copy of a canonical project root.
+    ///
+    /// With a `scratch_dir`, an existing copy of the project or any of its
+    /// parent directories is reused and new copies are kept for later
+    /// generations. Otherwise the project is copied to a new temporary
+    /// directory.
+    pub(crate) fn acquire(root: &Path, scratch_dir: Option<&Path>) -> Result<Self> {
+        let scratch_dir = match scratch_dir {
+            Some(scratch_dir) => scratch_dir,
+            None => {
+                let tempdir = tempfile::tempdir()?;
+                copy_dir(root, tempdir.path(), root, tempdir.path())?;
+                let root = tempdir.path().to_path_buf();
+                return Ok(Self { root, _lock: None, _tempdir: Some(tempdir) });
+            },
+        };
+
+        // Reuse the copy of the closest directory containing the project.
+        let copied_root = root
+            .ancestors()
+            .find(|ancestor| scratch_dir.join(copy_name(ancestor)).exists())
+            .unwrap_or(root);
+        let copy = scratch_dir.join(copy_name(copied_root));
+
+        let lock = lock(&copy)?;
+
+        // Copy the project, unless another generation already did so while we were
+        // waiting for the lock.
+        if !copy.exists() {
+            // Copy to a temporary location first, so partial copies are never reused.
+            let tempdir = tempfile::Builder::new().prefix(".copy").tempdir_in(scratch_dir)?;
+            copy_dir(copied_root, tempdir.path(), copied_root, &copy)?;
+
+            // The moved temporary directory no longer exists once it is dropped.
+            fs::rename(tempdir.path(), &copy)?;
+        }
+
+        let root = copy.join(root.strip_prefix(copied_root)?);
+        Ok(Self { root, _lock: Some(lock), _tempdir: None })
+    }
+
+    /// Project root inside the copy.
+    pub(crate) fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+/// Name of the copy of a directory in the scratch directory.
+fn copy_name(root: &Path) -> String {
+    let mut hasher = DefaultHasher::new();
+    root.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Lock a copy in the scratch directory, waiting for other users to release
+/// it.
+///
+/// Locks are released automatically once all handles of the file are closed,
+/// which includes the termination of the process holding them.
+fn lock(copy: &Path) -> io::Result<File> {
+    let mut lock_path = copy.as_os_str().to_owned();
+    lock_path.push(".lock");
+    let file = OpenOptions::new().create(true).truncate(false).write(true).open(lock_path)?;
+
+    #[cfg(unix)]
+    loop {
+        // SAFETY: The file descriptor is valid for the lifetime of `file`.
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } == 0 {
+            break;
+        }
+
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+
+    Ok(file)
+}
+
+/// Recursively copy all files from `src` to `dst`.
+///
+/// Symlinks are resolved relative to the copied directory `root`, which is
+/// going to be located at `copy`.
+fn copy_dir(src: &Path, dst: &Path, root: &Path, copy: &Path) -> Result<()> {
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let src_path = entry.path();
+        let dst_path = dst.join(entry.file_name());
+
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            let is_excluded =
+                entry.file_name().to_str().is_some_and(|name| EXCLUDED_DIRS.contains(&name));
+            if !is_excluded {
+                fs::create_dir(&dst_path)?;
+                copy_dir(&src_path, &dst_path, root, copy)?;
+            }
+        } else if file_type.is_symlink() {
+            copy_symlink(&src_path, &dst_path, root, copy)?;
+        } else {
+            fs::copy(&src_path, &dst_path)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Copy a symlink without ever linking outside of the copy.
+///
+/// Links to files in the project are pointed at the same file in the `copy`.
+/// Files outside of the project are copied instead, while links to
+/// directories outside of the project and dangling links are skipped.
+fn copy_symlink(src: &Path, dst: &Path, root: &Path, copy: &Path) -> Result<()> {
+    let target = match fs::canonicalize(src) {
+        Ok(target) => target,
+        Err(_) => return Ok(()),
+    };
+
+    match target.strip_prefix(root) {
+        Ok(relative) => link(&copy.join(relative), dst)?,
+        Err(_) if target.is_file() => {
+            fs::copy(&target, dst)?;
+        },
+        Err(_) => (),
+    }
+
+    Ok(())
+}
+
+/// Create a symlink.
+#[cfg(unix)]
+fn link(target: &Path, path: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, path)
+}
+
+/// Create a symlink.
+///
+/// Creating symlinks requires elevated privileges, so they're skipped instead.
+#[cfg(not(unix))]
+fn link(_target: &Path, _path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copy_without_excluded() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let root = tempdir.path();
+
+        fs::write(root.join("package.json"), "{}").unwrap();
+        fs::create_dir_all(root.join("packages/a/node_modules/dep")).unwrap();
+        fs::write(root.join("packages/a/package.json"), "{}").unwrap();
+        fs::write(root.join("packages/a/node_modules/dep/package.json"), "{}").unwrap();
+
+        let scratch = ScratchCopy::acquire(root, None).unwrap();
+        let scratch = scratch.root();
+
+        assert!(scratch.join("package.json").exists());
+        assert!(scratch.join("packages/a/package.json").exists());
+        assert!(!scratch.join("packages/a/node_modules").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_stay_in_copy() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let tempdir = fs::canonicalize(tempdir.path()).unwrap();
+        let root = tempdir.join("project");
+        let outside = tempdir.join("outside");
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(root.join("package.json"), "{}").unwrap();
+        fs::write(outside.join("shared.json"), "shared").unwrap();
+
+        link(&root.join("package.json"), &root.join("sub/absolute.json")).unwrap();
+        link(Path::new("../package.json"), &root.join("sub/relative.json")).unwrap();
+        link(Path::new("../outside/shared.json"), &root.join("shared.json")).unwrap();
+        link(&outside, &root.join("outside")).unwrap();
+        link(Path::new("missing"), &root.join("dangling")).unwrap();
+
+        let scratch = ScratchCopy::acquire(&root, None).unwrap();
+        let scratch = scratch.root();
+
+        // Links inside the project point into the copy.
+        for path in ["sub/absolute.json", "sub/relative.json"] {
+            let target = fs::read_link(scratch.join(path)).unwrap();
+            assert_eq!(target, scratch.join("package.json"));
+        }
+
+        // Writing through links never modifies the project.
+        fs::write(scratch.join("sub/absolute.json"), "modified").unwrap();
+        assert_eq!(fs::read_to_string(root.join("package.json")).unwrap(), "{}");
+
+        // Files outside the project are copied, everything else is skipped.
+        assert!(!scratch.join("shared.json").is_symlink());
+        fs::write(scratch.join("shared.json"), "modified").unwrap();
+        assert_eq!(fs::read_to_string(outside.join("shared.json")).unwrap(), "shared");
+        assert!(!scratch.join("outside").exists());
+        assert!(!scratch.join("dangling").is_symlink());
+    }
+
+    #[test]
+    fn shared_copies() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(tempdir.path()).unwrap();
+        let scratch_dir = tempfile::tempdir().unwrap();
+        let scratch_dir = scratch_dir.path();
+
+        fs::create_dir_all(root.join("packages/a")).unwrap();
+        fs::write(root.join("packages/a/package.json"), "{}").unwrap();
+
+        let scratch = ScratchCopy::acquire(&root, Some(scratch_dir)).unwrap();
+        let copy = scratch.root().to_path_buf();
+        drop(scratch);
+
+        // Projects inside an existing copy reuse it.
+        let member = ScratchCopy::acquire(&root.join("packages/a"), Some(scratch_dir)).unwrap();
+        assert_eq!(member.root(), copy.join("packages/a"));
+        assert!(member.root().join("package.json").exists());
+        drop(member);
+
+        let copies = fs::read_dir(scratch_dir)
+            .unwrap()
+            .filter(|entry| entry.as_ref().unwrap().file_type().unwrap().is_dir())
+            .count();
+        assert_eq!(copies, 1);
+    }
+}
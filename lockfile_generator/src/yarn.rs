@@ -4,7 +4,7 @@ use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
-use crate::{npm, Error, Generator, Result};
+use crate::{npm, offline, Error, Generator, Result};
 
 pub struct Yarn;
 
@@ -24,6 +24,19 @@ impl Generator for Yarn {
         "Yarn"
     }
 
+    fn environment_prefixes(&self) -> &'static [&'static str] {
+        &["YARN_", "NPM_CONFIG_", "NODE_OPTIONS"]
+    }
+
+    fn configure_offline(&self, command: &mut Command) {
+        command.env("YARN_ENABLE_NETWORK", "0");
+        command.env("YARN_ENABLE_OFFLINE_MODE", "1");
+    }
+
+    fn missing_cache_entry(&self, line: &str) -> Option<String> {
+        offline::text_between(line, "Error: ", ": Network access have been disabled")
+    }
+
     fn check_prerequisites(&self, manifest_path: &Path) -> Result<()> {
         if manifest_path.file_name() != Some(OsStr::new("package.json")) {
             return Err(Error::InvalidManifest(manifest_path.to_path_buf()));
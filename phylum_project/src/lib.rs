@@ -1,7 +1,10 @@
 //! Phylum project configuration handling.
 
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
 use chrono::{DateTime, Local};
 use phylum_types::types::common::ProjectId;
@@ -21,8 +24,12 @@ pub struct ProjectConfig {
     lockfile_type: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     lockfile_path: Option<String>,
-    #[serde(default, alias = "lockfiles")]
-    depfiles: Vec<DepfileConfig>,
+    #[serde(default, alias = "lockfiles", skip_serializing_if = "Option::is_none")]
+    depfiles: Option<Vec<DepfileConfig>>,
+    #[serde(default, skip_serializing_if = "DiscoveryConfig::is_empty")]
+    pub discovery: DiscoveryConfig,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sandbox_profiles: BTreeMap<String, SandboxProfile>,
     #[serde(skip)]
     root: PathBuf,
 }
@@ -37,7 +44,9 @@ impl ProjectConfig {
             created_at: Local::now(),
             lockfile_type: None,
             lockfile_path: None,
-            depfiles: Default::default(),
+            depfiles: Some(Vec::new()),
+            discovery: Default::default(),
+            sandbox_profiles: Default::default(),
             root: Default::default(),
         }
     }
@@ -45,9 +54,8 @@ impl ProjectConfig {
     /// Get all dependency files of this project.
     pub fn depfiles(&self) -> Vec<DepfileConfig> {
         // Return new config format if present.
-        if !self.depfiles.is_empty() {
-            return self
-                .depfiles
+        if let Some(depfiles) = self.depfiles.as_ref().filter(|depfiles| !depfiles.is_empty()) {
+            return depfiles
                 .iter()
                 .map(|depfile| {
                     let path = self.root.join(&depfile.path);
@@ -76,7 +84,25 @@ impl ProjectConfig {
 
     /// Update the project's dependency files.
     pub fn set_depfiles(&mut self, depfiles: Vec<DepfileConfig>) {
-        self.depfiles = depfiles;
+        self.depfiles = Some(depfiles);
+    }
+
+    /// Remove the project's dependency files, so they are discovered by
+    /// searching the project directory instead.
+    pub fn unset_depfiles(&mut self) {
+        self.depfiles = None;
+        self.lockfile_path = None;
+        self.lockfile_type = None;
+    }
+
+    /// Check if dependency files are discovered by searching the project
+    /// directory.
+    ///
+    /// This is only the case when the project does not list any dependency
+    /// files. Projects with an explicitly empty list do not search for
+    /// dependency files.
+    pub fn discovers_depfiles(&self) -> bool {
+        self.depfiles.is_none() && self.lockfile_path.is_none()
     }
 
     /// Get project's root directory.
@@ -99,6 +125,161 @@ impl DepfileConfig {
     }
 }
 
+/// Dependency file discovery settings.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiscoveryConfig {
+    /// Maximum directory depth to recurse into.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_depth: Option<MaxDepth>,
+    /// Globs selecting the paths which should be considered.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// Globs for paths which should never be considered.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    /// Dependency file types which should be detected.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub formats: Vec<String>,
+    /// Whether symbolic links should be followed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub follow_symlinks: Option<bool>,
+}
+
+impl DiscoveryConfig {
+    /// Check if no discovery settings were specified.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// Maximum directory depth of the dependency file search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "MaxDepthValue", into = "MaxDepthValue")]
+pub enum MaxDepth {
+    Limited(usize),
+    Unlimited,
+}
+
+impl MaxDepth {
+    /// Get the depth limit, if any.
+    pub fn limit(self) -> Option<usize> {
+        match self {
+            Self::Limited(depth) => Some(depth),
+            Self::Unlimited => None,
+        }
+    }
+}
+
+impl FromStr for MaxDepth {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "unlimited" => Ok(Self::Unlimited),
+            _ => s
+                .parse()
+                .map(Self::Limited)
+                .map_err(|_| format!("invalid depth {s:?}, expected a number or `unlimited`")),
+        }
+    }
+}
+
+impl Display for MaxDepth {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Limited(depth) => write!(f, "{depth}"),
+            Self::Unlimited => f.write_str("unlimited"),
+        }
+    }
+}
+
+/// Serialized representation of [`MaxDepth`].
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum MaxDepthValue {
+    Limited(usize),
+    Unlimited(String),
+}
+
+impl TryFrom<MaxDepthValue> for MaxDepth {
+    type Error = String;
+
+    fn try_from(value: MaxDepthValue) -> Result<Self, Self::Error> {
+        match value {
+            MaxDepthValue::Limited(depth) => Ok(Self::Limited(depth)),
+            MaxDepthValue::Unlimited(value) => value.parse(),
+        }
+    }
+}
+
+impl From<MaxDepth> for MaxDepthValue {
+    fn from(depth: MaxDepth) -> Self {
+        match depth {
+            MaxDepth::Limited(depth) => Self::Limited(depth),
+            MaxDepth::Unlimited => Self::Unlimited(depth.to_string()),
+        }
+    }
+}
+
+/// Additional sandbox exceptions for lockfile generation.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SandboxProfile {
+    /// Dependency file types the profile applies to.
+    ///
+    /// Profiles without any types apply to all dependency files.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub formats: Vec<String>,
+    /// Paths which can be read.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub read: Vec<String>,
+    /// Paths which can be written and read.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub write: Vec<String>,
+    /// Paths which can be executed and read.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub run: Vec<String>,
+    /// Environment variables which are accessible.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub env: Vec<String>,
+    /// Domains network access is restricted to.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub net: Vec<String>,
+}
+
+impl SandboxProfile {
+    /// Check if the profile applies to a dependency file type.
+    pub fn applies_to(&self, format: Option<&str>) -> bool {
+        self.formats.is_empty()
+            || format.is_some_and(|format| self.formats.iter().any(|f| f == format))
+    }
+
+    /// Add all exceptions of another profile.
+    pub fn merge(&mut self, other: &SandboxProfile) {
+        let merge = |target: &mut Vec<String>, source: &[String]| {
+            for value in source {
+                if !target.contains(value) {
+                    target.push(value.clone());
+                }
+            }
+        };
+
+        merge(&mut self.read, &other.read);
+        merge(&mut self.write, &other.write);
+        merge(&mut self.run, &other.run);
+        merge(&mut self.env, &other.env);
+        merge(&mut self.net, &other.net);
+    }
+
+    /// Check if the profile contains no exceptions.
+    pub fn is_empty(&self) -> bool {
+        self.read.is_empty()
+            && self.write.is_empty()
+            && self.run.is_empty()
+            && self.env.is_empty()
+            && self.net.is_empty()
+    }
+}
+
 /// Get current project configuration file's path.
 pub fn find_project_conf(
     starting_directory: impl AsRef<Path>,
@@ -217,6 +398,90 @@ mod tests {
         assert_eq!(&PathBuf::from(r"C:\home\user\project\Cargo.lock"), &depfile.path);
     }
 
+    #[test]
+    fn deserialize_discovery_config() {
+        let config = format!(
+            "id: {PROJECT_ID}\nname: {PROJECT_NAME}\ncreated_at: \
+             2024-01-01T00:00:00+00:00\ngroup_name: null\ndiscovery:\n  max_depth: 8\n  \
+             exclude:\n    - \"tests/fixtures\"\n"
+        );
+
+        let config: ProjectConfig = serde_yaml::from_str(&config).unwrap();
+
+        assert_eq!(config.discovery.max_depth, Some(MaxDepth::Limited(8)));
+        assert_eq!(config.discovery.exclude, vec![String::from("tests/fixtures")]);
+        assert!(config.discovery.include.is_empty());
+        assert_eq!(config.discovery.follow_symlinks, None);
+
+        // Empty discovery settings are omitted.
+        let config = ProjectConfig::new(PROJECT_ID, PROJECT_NAME.to_owned(), None);
+        let yaml = serde_yaml::to_string(&config).unwrap();
+        assert!(!yaml.contains("discovery"));
+    }
+
+    #[test]
+    fn unlimited_max_depth() {
+        let config = format!(
+            "id: {PROJECT_ID}\nname: {PROJECT_NAME}\ncreated_at: \
+             2024-01-01T00:00:00+00:00\ngroup_name: null\ndiscovery:\n  max_depth: unlimited\n"
+        );
+
+        let config: ProjectConfig = serde_yaml::from_str(&config).unwrap();
+        assert_eq!(config.discovery.max_depth, Some(MaxDepth::Unlimited));
+
+        let yaml = serde_yaml::to_string(&config).unwrap();
+        assert!(yaml.contains("max_depth: unlimited"));
+
+        assert!(serde_yaml::from_str::<DiscoveryConfig>("max_depth: forever").is_err());
+    }
+
+    #[test]
+    fn empty_depfiles_disable_discovery() {
+        let header = format!(
+            "id: {PROJECT_ID}\nname: {PROJECT_NAME}\ncreated_at: \
+             2024-01-01T00:00:00+00:00\ngroup_name: null\n"
+        );
+
+        let config: ProjectConfig = serde_yaml::from_str(&header).unwrap();
+        assert!(config.discovers_depfiles());
+
+        let config: ProjectConfig =
+            serde_yaml::from_str(&format!("{header}depfiles: []\n")).unwrap();
+        assert!(!config.discovers_depfiles());
+        assert!(config.depfiles().is_empty());
+
+        // New projects keep writing an explicit empty list.
+        let config = ProjectConfig::new(PROJECT_ID, PROJECT_NAME.to_owned(), None);
+        assert!(serde_yaml::to_string(&config).unwrap().contains("depfiles: []"));
+    }
+
+    #[test]
+    fn deserialize_sandbox_profiles() {
+        let config = format!(
+            "id: {PROJECT_ID}\nname: {PROJECT_NAME}\ncreated_at: \
+             2024-01-01T00:00:00+00:00\ngroup_name: null\nsandbox_profiles:\n  asdf-node:\n    \
+             formats: [npm, yarn]\n    run: [\"~/.asdf\"]\n  proxy:\n    env: [HTTPS_PROXY]\n"
+        );
+
+        let config: ProjectConfig = serde_yaml::from_str(&config).unwrap();
+
+        let node = &config.sandbox_profiles["asdf-node"];
+        assert_eq!(node.run, vec![String::from("~/.asdf")]);
+        assert!(node.applies_to(Some("yarn")));
+        assert!(!node.applies_to(Some("cargo")));
+        assert!(!node.applies_to(None));
+
+        let proxy = &config.sandbox_profiles["proxy"];
+        assert!(proxy.applies_to(Some("cargo")));
+        assert!(proxy.applies_to(None));
+
+        let mut merged = node.clone();
+        merged.merge(proxy);
+        merged.merge(node);
+        assert_eq!(merged.run, vec![String::from("~/.asdf")]);
+        assert_eq!(merged.env, vec![String::from("HTTPS_PROXY")]);
+    }
+
     #[cfg(any(unix, windows))]
     #[test]
     fn find_project_conf_can_recurse_up() {